@@ -1,6 +1,6 @@
 use seija_core::math::{Vec4, Mat4,Vec4Swizzles, Vec2};
 use crate::common::types::Thickness;
-use seija_core::Rect;
+use seija_core::{Rect, impl_reflect_enum};
 use crate::common::{Rect2D,Mesh2D,Vertex2D};
 
 #[derive(Copy,Clone,PartialEq,Eq)]
@@ -11,6 +11,8 @@ pub enum ImageFilledType {
     VerticalBottom
 }
 
+impl_reflect_enum!(ImageFilledType,HorizontalLeft,HorizontalRight,VerticalTop,VerticalBottom);
+
 impl From<u32> for ImageFilledType {
     fn from(n: u32) -> ImageFilledType {
         match n {
@@ -86,6 +88,7 @@ impl ImageGenericInfo {
 
         Mesh2D { 
             color:self.color, 
+            colors:vec![],
             points, 
             indexs 
         }
@@ -165,6 +168,7 @@ impl ImageGenericInfo {
 
         Mesh2D {
           color:self.color,
+          colors:vec![],
           points:meshes,
           indexs  
         }
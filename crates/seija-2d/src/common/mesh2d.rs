@@ -5,10 +5,18 @@ use wgpu::PrimitiveTopology;
 #[derive(Debug,Clone,Default)]
 pub struct Mesh2D {
     pub color:Vec4,
+    //每个顶点的颜色,为空时所有顶点都使用color
+    pub colors:Vec<Vec4>,
     pub points:Vec<Vertex2D>,
     pub indexs:Vec<u32>
 }
 
+impl Mesh2D {
+    pub fn vertex_color(&self,index:usize) -> Vec4 {
+        self.colors.get(index).copied().unwrap_or(self.color)
+    }
+}
+
 #[derive(Clone, Copy, Debug)]
 pub struct Vertex2D {
     pub pos:Vec3,
@@ -22,10 +30,10 @@ impl Into<Mesh> for Mesh2D {
         let mut positons:Vec<[f32;3]> = vec![];
         let mut uvs:Vec<[f32;2]> = vec![];
         let mut colors:Vec<[f32;4]> = vec![];
-        for vertex in self.points.iter() {
+        for (index,vertex) in self.points.iter().enumerate() {
            positons.push([vertex.pos.x,vertex.pos.y,vertex.pos.z]);
            uvs.push([vertex.uv.x,vertex.uv.y]);
-           colors.push(self.color.into());
+           colors.push(self.vertex_color(index).into());
         }
         
         mesh.set(MeshAttributeType::POSITION, positons);
@@ -1,8 +1,8 @@
 use bevy_ecs::prelude::Component;
-use seija_core::math::{Vec2, Vec4};
+use seija_core::{math::{Vec2, Vec4}, reflect::Reflect};
 use seija_transform::TransformMatrix;
 
-#[derive(Debug,Component,Clone)]
+#[derive(Debug,Component,Reflect,Clone)]
 #[repr(C)]
 pub struct Rect2D {
    pub width:f32,
@@ -1,4 +1,5 @@
-use seija_core::math::Vec2;
+use seija_core::math::{Vec2, Vec4};
+use seija_core::reflect::{IReflectValue, ReflectType, ReflectValue};
 
 #[derive(PartialEq,Clone,Default,Debug)]
 pub struct Thickness {
@@ -8,6 +9,18 @@ pub struct Thickness {
    pub bottom:f32
 }
 
+//作为反射字段时写成left,top,right,bottom
+impl IReflectValue for Thickness {
+    const TYPE:ReflectType = ReflectType::Vec4;
+    fn to_value(&self) -> ReflectValue { ReflectValue::Vec4(Vec4::new(self.left, self.top, self.right, self.bottom)) }
+    fn from_value(value:ReflectValue) -> Option<Self> {
+        match value {
+            ReflectValue::Vec4(v) => Some(Thickness { left:v.x,top:v.y,right:v.z,bottom:v.w }),
+            _ => None
+        }
+    }
+}
+
 impl Thickness {
    pub fn new1(num:f32) -> Self {
        Thickness { left: num, top: num, right: num, bottom: num }
@@ -0,0 +1,243 @@
+use std::{collections::HashMap, fs::File, io::{Read, Seek, SeekFrom, Write}, path::Path};
+use flate2::{Compression, read::DeflateDecoder, write::DeflateEncoder};
+use parking_lot::Mutex;
+use seija_core::{anyhow::{Result, bail}, smol_str::SmolStr};
+use crate::{errors::AssetError, source::{IAssetSource, normalize_asset_path}};
+
+//资源打包文件格式:
+//header: magic(4) version(u32) entry_count(u32)
+//index : [path_len(u16) path offset(u64) size(u64) raw_size(u64) flags(u8)] * entry_count
+//data  : 每个资源的数据,flags为1时使用deflate压缩
+pub const ARCHIVE_MAGIC:[u8;4] = *b"SPAK";
+pub const ARCHIVE_VERSION:u32 = 1;
+const FLAG_DEFLATE:u8 = 1;
+
+struct ArchiveEntry {
+    offset:u64,
+    size:u64,
+    raw_size:u64,
+    flags:u8
+}
+
+pub struct ArchiveAssetSource {
+    file:Mutex<File>,
+    entries:HashMap<SmolStr,ArchiveEntry>
+}
+
+impl ArchiveAssetSource {
+    pub fn open(path:&Path) -> Result<Self> {
+        let mut file = File::open(path)?;
+        let file_len = file.metadata()?.len();
+        let mut magic = [0u8;4];
+        file.read_exact(&mut magic)?;
+        if magic != ARCHIVE_MAGIC {
+            bail!("{:?} is not a asset archive",path);
+        }
+        let version = read_u32(&mut file)?;
+        if version != ARCHIVE_VERSION {
+            bail!("asset archive version error:{}",version);
+        }
+        let count = read_u32(&mut file)?;
+        let mut entries = HashMap::default();
+        for _ in 0..count {
+            let path_len = read_u16(&mut file)?;
+            let mut path_bytes = vec![0u8;path_len as usize];
+            file.read_exact(&mut path_bytes)?;
+            let entry_path = String::from_utf8(path_bytes)?;
+            let entry = ArchiveEntry {
+                offset:read_u64(&mut file)?,
+                size:read_u64(&mut file)?,
+                raw_size:read_u64(&mut file)?,
+                flags:read_u8(&mut file)?
+            };
+            entries.insert(SmolStr::new(entry_path), entry);
+        }
+        //索引来自文件本身,数据范围必须在索引之后并且不超过文件长度
+        let data_start = file.stream_position()?;
+        for (entry_path,entry) in entries.iter() {
+            let in_range = entry.offset >= data_start && entry.offset.checked_add(entry.size).map(|end| end <= file_len).unwrap_or(false);
+            if !in_range {
+                bail!("asset archive entry {} out of range:{}+{}",entry_path,entry.offset,entry.size);
+            }
+        }
+        Ok(ArchiveAssetSource { file:Mutex::new(file),entries })
+    }
+
+    pub fn paths(&self) -> impl Iterator<Item = &SmolStr> {
+        self.entries.keys()
+    }
+}
+
+impl IAssetSource for ArchiveAssetSource {
+    fn read(&self,path:&str) -> Result<Vec<u8>> {
+        let entry = self.entries.get(normalize_asset_path(path).as_str()).ok_or(AssetError::NotFoundAsset(path.into()))?;
+        //不按索引里的大小预先分配,文件被截断时读到的数据会变少
+        let mut bytes = vec![];
+        {
+            let mut file = self.file.lock();
+            file.seek(SeekFrom::Start(entry.offset))?;
+            (&mut *file).take(entry.size).read_to_end(&mut bytes)?;
+        }
+        if bytes.len() as u64 != entry.size {
+            bail!("asset archive entry {} is truncated",path);
+        }
+        if entry.flags & FLAG_DEFLATE != 0 {
+            let mut raw_bytes = vec![];
+            DeflateDecoder::new(bytes.as_slice()).take(entry.raw_size).read_to_end(&mut raw_bytes)?;
+            if raw_bytes.len() as u64 != entry.raw_size {
+                bail!("asset archive entry {} raw size error",path);
+            }
+            return Ok(raw_bytes);
+        }
+        Ok(bytes)
+    }
+
+    fn exists(&self,path:&str) -> bool {
+        self.entries.contains_key(normalize_asset_path(path).as_str())
+    }
+}
+
+#[derive(Default)]
+pub struct ArchiveWriter {
+    entries:Vec<(SmolStr,Vec<u8>,u64,u8)>
+}
+
+impl ArchiveWriter {
+    pub fn add(&mut self,path:&str,bytes:Vec<u8>,compress:bool) -> Result<()> {
+        let path = normalize_asset_path(path);
+        check_path_len(&path)?;
+        let raw_size = bytes.len() as u64;
+        if compress {
+            let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(&bytes)?;
+            let compressed = encoder.finish()?;
+            if compressed.len() < bytes.len() {
+                self.entries.push((path,compressed,raw_size,FLAG_DEFLATE));
+                return Ok(());
+            }
+        }
+        self.entries.push((path,bytes,raw_size,0));
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn write<W:Write>(&self,writer:&mut W) -> Result<()> {
+        let index_size:usize = self.entries.iter().map(|(path,..)| 2 + path.len() + 8 * 3 + 1).sum();
+        let mut offset = (4 + 4 + 4 + index_size) as u64;
+        for (path,..) in self.entries.iter() {
+            check_path_len(path)?;
+        }
+        writer.write_all(&ARCHIVE_MAGIC)?;
+        writer.write_all(&ARCHIVE_VERSION.to_le_bytes())?;
+        writer.write_all(&(self.entries.len() as u32).to_le_bytes())?;
+        for (path,bytes,raw_size,flags) in self.entries.iter() {
+            writer.write_all(&(path.len() as u16).to_le_bytes())?;
+            writer.write_all(path.as_bytes())?;
+            writer.write_all(&offset.to_le_bytes())?;
+            writer.write_all(&(bytes.len() as u64).to_le_bytes())?;
+            writer.write_all(&raw_size.to_le_bytes())?;
+            writer.write_all(&[*flags])?;
+            offset += bytes.len() as u64;
+        }
+        for (_,bytes,..) in self.entries.iter() {
+            writer.write_all(bytes)?;
+        }
+        Ok(())
+    }
+}
+
+//索引中路径长度用u16保存
+fn check_path_len(path:&str) -> Result<()> {
+    if path.len() > u16::MAX as usize {
+        bail!("asset archive path too long:{} bytes",path.len());
+    }
+    Ok(())
+}
+
+fn read_u8<R:Read>(reader:&mut R) -> Result<u8> {
+    let mut buf = [0u8;1];
+    reader.read_exact(&mut buf)?;
+    Ok(buf[0])
+}
+
+fn read_u16<R:Read>(reader:&mut R) -> Result<u16> {
+    let mut buf = [0u8;2];
+    reader.read_exact(&mut buf)?;
+    Ok(u16::from_le_bytes(buf))
+}
+
+fn read_u32<R:Read>(reader:&mut R) -> Result<u32> {
+    let mut buf = [0u8;4];
+    reader.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_u64<R:Read>(reader:&mut R) -> Result<u64> {
+    let mut buf = [0u8;8];
+    reader.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn archive_round_trip() {
+        let mut writer = ArchiveWriter::default();
+        let text = "hello seija ".repeat(64);
+        writer.add("ui/a.txt", text.as_bytes().to_vec(), true).unwrap();
+        writer.add("/tex/b.bin", vec![1,2,3], true).unwrap();
+        let nanos = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_nanos();
+        let dir = std::env::temp_dir().join(format!("seija_archive_{}_{}",std::process::id(),nanos));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("round_trip.pak");
+        let mut file = File::create(&path).unwrap();
+        writer.write(&mut file).unwrap();
+        drop(file);
+
+        let archive = ArchiveAssetSource::open(&path).unwrap();
+        assert_eq!(archive.read("ui/a.txt").unwrap(), text.as_bytes());
+        assert_eq!(archive.read("tex/../tex/b.bin").unwrap(), vec![1,2,3]);
+        assert!(!archive.exists("none.txt"));
+        drop(archive);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn bad_entry_range() {
+        let mut writer = ArchiveWriter::default();
+        writer.add("a.bin", vec![1,2,3,4], false).unwrap();
+        let mut bytes = vec![];
+        writer.write(&mut bytes).unwrap();
+        let nanos = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_nanos();
+        let dir = std::env::temp_dir().join(format!("seija_archive_bad_{}_{}",std::process::id(),nanos));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("bad.pak");
+        //size改成很大的值
+        let size_pos = 4 + 4 + 4 + 2 + "a.bin".len() + 8;
+        bytes[size_pos..size_pos + 8].copy_from_slice(&u64::MAX.to_le_bytes());
+        std::fs::write(&path, &bytes).unwrap();
+        assert!(ArchiveAssetSource::open(&path).is_err());
+        //数据被截断
+        bytes[size_pos..size_pos + 8].copy_from_slice(&4u64.to_le_bytes());
+        std::fs::write(&path, &bytes[..bytes.len() - 1]).unwrap();
+        assert!(ArchiveAssetSource::open(&path).is_err());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn long_path() {
+        let mut writer = ArchiveWriter::default();
+        let path = "a/".repeat(u16::MAX as usize);
+        assert!(writer.add(&path, vec![1], false).is_err());
+        assert!(writer.is_empty());
+    }
+}
@@ -19,9 +19,18 @@ impl<T> Asset for T where T: TypeUuid + AssetDynamic + TypeUuidDynamic {}
 impl<T> AssetDynamic for T where T: Send + Sync + 'static + TypeUuidDynamic {}
 
 
-pub trait AssetLoaderParams:DowncastSync {}
+pub trait AssetLoaderParams:DowncastSync + CloneLoaderParams {}
 impl_downcast!(AssetLoaderParams);
 
+//重新加载时需要复制一份加载参数
+pub trait CloneLoaderParams {
+    fn clone_params(&self) -> Box<dyn AssetLoaderParams>;
+}
+
+impl<T> CloneLoaderParams for T where T:AssetLoaderParams + Clone {
+    fn clone_params(&self) -> Box<dyn AssetLoaderParams> { Box::new(self.clone()) }
+}
+
 
 #[derive(PartialEq, Eq)]
 pub enum AsyncLoadMode {
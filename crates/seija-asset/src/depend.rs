@@ -0,0 +1,176 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use seija_core::smol_str::SmolStr;
+use crate::HandleId;
+
+//资源之间的依赖关系图
+#[derive(Default)]
+pub(crate) struct AssetDependGraph {
+    depends:HashMap<HandleId,HashSet<HandleId>>,
+    dependents:HashMap<HandleId,HashSet<HandleId>>,
+    //同步加载时资源的HandleId要在加载完成后才能确定,先按路径记录
+    pending:HashMap<SmolStr,HashSet<HandleId>>
+}
+
+impl AssetDependGraph {
+    pub fn add(&mut self,id:HandleId,depend:HandleId) {
+        if id == depend { return; }
+        self.depends.entry(id).or_default().insert(depend);
+        self.dependents.entry(depend).or_default().insert(id);
+    }
+
+    pub fn add_pending(&mut self,path:&str,depend:HandleId) {
+        self.pending.entry(SmolStr::new(path)).or_default().insert(depend);
+    }
+
+    pub fn resolve_pending(&mut self,path:&str,id:HandleId) {
+        if let Some(depends) = self.pending.remove(path) {
+            for depend in depends {
+                self.add(id, depend);
+            }
+        }
+    }
+
+    pub fn remove_pending(&mut self,path:&str) {
+        self.pending.remove(path);
+    }
+
+    pub fn clear_depends(&mut self,id:&HandleId) {
+        if let Some(depends) = self.depends.remove(id) {
+            for depend in depends.iter() {
+                if let Some(set) = self.dependents.get_mut(depend) {
+                    set.remove(id);
+                    if set.is_empty() { self.dependents.remove(depend); }
+                }
+            }
+        }
+    }
+
+    pub fn remove(&mut self,id:&HandleId) {
+        self.clear_depends(id);
+        if let Some(dependents) = self.dependents.remove(id) {
+            for dependent in dependents.iter() {
+                if let Some(set) = self.depends.get_mut(dependent) {
+                    set.remove(id);
+                    if set.is_empty() { self.depends.remove(dependent); }
+                }
+            }
+        }
+    }
+
+    pub fn depends(&self,id:&HandleId) -> Vec<HandleId> {
+        self.depends.get(id).map(|v| v.iter().cloned().collect()).unwrap_or_default()
+    }
+
+    pub fn dependents(&self,id:&HandleId) -> Vec<HandleId> {
+        self.dependents.get(id).map(|v| v.iter().cloned().collect()).unwrap_or_default()
+    }
+
+    //递归获取所有依赖,不包含自身
+    pub fn all_depends(&self,id:&HandleId) -> Vec<HandleId> {
+        let mut visited:HashSet<HandleId> = HashSet::default();
+        let mut ret = vec![];
+        let mut queue:VecDeque<HandleId> = VecDeque::default();
+        queue.push_back(*id);
+        visited.insert(*id);
+        while let Some(cur) = queue.pop_front() {
+            if let Some(depends) = self.depends.get(&cur) {
+                for depend in depends.iter() {
+                    if visited.insert(*depend) {
+                        ret.push(*depend);
+                        queue.push_back(*depend);
+                    }
+                }
+            }
+        }
+        ret
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+    use bevy_ecs::prelude::World;
+    use downcast_rs::DowncastSync;
+    use seija_core::{TypeUuid, anyhow::{Result, anyhow}, smol_str::SmolStr};
+    use uuid::Uuid;
+    use crate::{AssetServer, AssetDynamic, AssetLoaderParams, HandleId, HandleUntyped, IAssetLoader};
+
+    #[derive(TypeUuid)]
+    #[uuid = "5c1e2a40-7d6b-4f3e-9a52-0b8c6d7e1f21"]
+    struct TestAsset;
+
+    struct TestLoader;
+
+    #[async_trait]
+    impl IAssetLoader for TestLoader {
+        fn typ(&self) -> Uuid { TestAsset::TYPE_UUID }
+        fn add_to_asset(&self,_world:&mut World,_res:Box<dyn AssetDynamic>) -> Result<HandleUntyped> { Err(anyhow!("test")) }
+        fn sync_load(&self,_:&mut World,_:&str,_:&AssetServer,_:Option<Box<dyn AssetLoaderParams>>) -> Result<Box<dyn AssetDynamic>> {
+            Err(anyhow!("test"))
+        }
+        async fn async_load(&self,_:AssetServer,_:SmolStr,_:Option<Box<dyn DowncastSync>>,_:Option<Box<dyn AssetLoaderParams>>) -> Result<Box<dyn AssetDynamic>> {
+            Err(anyhow!("test"))
+        }
+    }
+
+    fn new_server(paths:&[&str]) -> (AssetServer,Vec<HandleId>) {
+        let server = AssetServer::new(std::env::temp_dir());
+        server.register_loader::<TestAsset,TestLoader>(TestLoader);
+        let ids = paths.iter().map(|path| {
+            let id = HandleId::random::<TestAsset>();
+            server.set_asset(path, id);
+            id
+        }).collect();
+        (server,ids)
+    }
+
+    fn request_paths(server:&AssetServer) -> Vec<SmolStr> {
+        server.inner.request_list.write().drain(..).map(|v| v.0).collect()
+    }
+
+    #[test]
+    fn cascade_reload() {
+        //a依赖b,b依赖c
+        let (server,ids) = new_server(&["a.txt","b.txt","c.txt"]);
+        server.add_dependency("a.txt", ids[1]);
+        server.add_dependency("b.txt", ids[2]);
+        assert!(server.is_loaded_with_dependencies(ids[0]));
+
+        server.reload("c.txt").unwrap();
+        assert_eq!(request_paths(&server), vec![SmolStr::new("c.txt")]);
+        server.on_load_finish("c.txt", ids[2]);
+        assert_eq!(request_paths(&server), vec![SmolStr::new("b.txt")]);
+        //重新加载会清除旧的依赖,由Loader重新添加
+        server.add_dependency("b.txt", ids[2]);
+        server.on_load_finish("b.txt", ids[1]);
+        assert_eq!(request_paths(&server), vec![SmolStr::new("a.txt")]);
+        server.on_load_finish("a.txt", ids[0]);
+        assert!(request_paths(&server).is_empty());
+    }
+
+    #[test]
+    fn cascade_unload() {
+        let (server,ids) = new_server(&["a.txt","b.txt","c.txt"]);
+        server.add_dependency("a.txt", ids[1]);
+        server.add_dependency("b.txt", ids[2]);
+        assert_eq!(server.inner.depends.read().all_depends(&ids[0]).len(), 2);
+
+        server.on_assets_freed(vec![ids[1]]);
+        assert!(server.dependencies(ids[0]).is_empty());
+        assert!(server.dependents(ids[2]).is_empty());
+        assert!(server.get_path(ids[1]).is_none());
+
+        //没有加载信息的依赖视为未加载
+        server.add_dependency("a.txt", ids[1]);
+        assert!(!server.is_loaded_with_dependencies(ids[0]));
+    }
+
+    #[test]
+    fn sync_fail_clear_pending() {
+        let (server,ids) = new_server(&["b.txt"]);
+        server.add_dependency("a.txt", ids[0]);
+        let mut world = World::default();
+        assert!(server.load_sync::<TestAsset>(&mut world, "a.txt", None).is_err());
+        assert!(server.inner.depends.read().pending.is_empty());
+    }
+}
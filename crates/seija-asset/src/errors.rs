@@ -1,11 +1,15 @@
 use thiserror::Error;
 
-#[derive(Debug,Error)]
+#[derive(Debug,Clone,Error)]
 pub enum AssetError {
     #[error("not found loader")]
     NotFoundLoader,
     #[error("type cast error")]
     TypeCastError,
     #[error("type cast error")]
-    NotFoundAssets
+    NotFoundAssets,
+    #[error("not found asset:{0}")]
+    NotFoundAsset(String),
+    #[error("load asset fail:{0}")]
+    LoadFail(String)
 }
@@ -1,10 +1,10 @@
-use std::{ffi::CStr, path::PathBuf};
+use std::{ffi::CStr, path::PathBuf, time::Duration};
 use crate::{uuid_to_u64,uuid_from_u64};
 use bevy_ecs::world::World;
 use seija_app::App;
 use seija_core::ResultExt;
 use uuid::Uuid;
-use crate::{AssetModule, AssetServer, HandleId, HandleUntyped};
+use crate::{AssetModule, AssetServer, HandleId, HandleUntyped, RetentionCode, LoadStateCode};
 
 #[no_mangle]
 pub unsafe extern "C" fn asset_add_module(app_ptr:&mut App,path:*mut i8) {
@@ -68,6 +68,13 @@ pub unsafe extern "C" fn asset_load_sync(world:&mut World,path:*const i8,ta:u64,
     }
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn asset_get_load_state(world:&mut World,id:u64,ta:u64,tb:u64) -> LoadStateCode {
+    let uuid = uuid_from_u64(ta,tb);
+    let server = world.get_resource::<AssetServer>().unwrap();
+    server.get_load_state(HandleId::new(uuid,id)).code()
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn asset_unload(world:&mut World,id:u64,ta:u64,tb:u64)  {
     let uuid = uuid_from_u64(ta,tb);
@@ -76,6 +83,46 @@ pub unsafe extern "C" fn asset_unload(world:&mut World,id:u64,ta:u64,tb:u64)  {
     server.unload(handle_id);
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn asset_watch_for_changes(world:&mut World,interval_ms:u64) {
+    let server = world.get_resource::<AssetServer>().unwrap();
+    server.watch_for_changes(Duration::from_millis(interval_ms));
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn asset_stop_watch(world:&mut World) {
+    let server = world.get_resource::<AssetServer>().unwrap();
+    server.stop_watch();
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn asset_set_process_cache(world:&mut World,path:*const i8) {
+    let path_str = CStr::from_ptr(path).to_str().log_err().unwrap_or_default();
+    let server = world.get_resource::<AssetServer>().unwrap();
+    server.set_process_cache_dir(PathBuf::from(path_str));
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn asset_set_retention(world:&mut World,ta:u64,tb:u64,typ:u8,value:f32) -> bool {
+    let uuid = uuid_from_u64(ta,tb);
+    let code = match RetentionCode::from_u8(typ) {
+        Some(code) => code,
+        None => {
+            log::error!("asset_set_retention unknown retention:{}",typ);
+            return false;
+        }
+    };
+    let server = world.get_resource::<AssetServer>().unwrap();
+    server.inner.life_cycle.set_retention(&uuid, code.to_policy(value));
+    true
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn asset_trim_memory(world:&mut World) {
+    let server = world.get_resource::<AssetServer>().unwrap();
+    server.trim_memory();
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn string_to_uuid(str:*const i8,ta:&mut u64,tb:&mut u64) -> bool {
     let str = std::ffi::CStr::from_ptr(str).to_str().unwrap_or_default();
@@ -13,11 +13,19 @@ mod assets;
 pub mod errors;
 mod lifecycle;
 mod loading_queue;
+mod watcher;
+mod depend;
+mod source;
+mod archive;
+mod process;
 pub use asset::*;
 pub use handle::{HandleId,HandleUntyped,Handle};
 pub use assets::{Assets,AssetEvent};
-pub use server::{AssetServer,AssetRequest,AssetInfo};
-pub use lifecycle::{RefEvent,LifecycleEvent};
+pub use server::{AssetServer,AssetRequest,AssetInfo,LoadState,LoadStateCode};
+pub use lifecycle::{RefEvent,LifecycleEvent,RetentionPolicy,RetentionCode};
+pub use source::{IAssetSource,DirAssetSource,MemoryAssetSource,normalize_asset_path};
+pub use process::IAssetProcessor;
+pub use archive::{ArchiveAssetSource,ArchiveWriter,ARCHIVE_MAGIC,ARCHIVE_VERSION};
 use seija_core::bevy_ecs::change_detection::Mut;
 pub use downcast_rs;
 pub use async_trait;
@@ -48,6 +56,7 @@ impl IModule for AssetModule {
 pub trait AddAsset {
     fn add_asset<T>(&mut self)  where T: Asset;
     fn add_asset_loader<T:Asset,F:IAssetLoader + Default>(&mut self,);
+    fn set_asset_retention<T:Asset>(&mut self,policy:RetentionPolicy);
 }
 
 impl AddAsset for App {
@@ -68,13 +77,19 @@ impl AddAsset for App {
         let asset_server = self.world.get_resource::<AssetServer>().unwrap();
         asset_server.register_loader::<T,F>(Default::default());
     }
+
+    fn set_asset_retention<T:Asset>(&mut self,policy:RetentionPolicy) {
+        let asset_server = self.world.get_resource::<AssetServer>().unwrap();
+        asset_server.set_retention::<T>(policy);
+    }
 }
 
 
 fn update_asset_system(world:&mut World) {
     world.resource_scope(|w:&mut World,mut loading_queue:Mut<AssetLoadingQueue>| {
        if let Some(server) = w.get_resource::<AssetServer>() {
-          server.inner.life_cycle.free_unused_assets();
+          server.free_unused_assets();
+          server.check_file_changes();
           let mut req_list = server.inner.request_list.write();
           if req_list.len() > 0 {
             let mut new_req_list:Vec<_> = req_list.drain(..).collect();
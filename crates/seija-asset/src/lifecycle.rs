@@ -1,4 +1,4 @@
-use std::{sync::Arc, collections::HashMap};
+use std::{sync::{Arc, atomic::{AtomicU64, Ordering}}, collections::HashMap, time::Instant};
 use parking_lot::RwLock;
 use seija_core::smol;
 use seija_core::smol::channel::{Sender, Receiver, TryRecvError};
@@ -49,27 +49,96 @@ pub enum LifecycleEvent {
     Free(HandleId),
 }
 
+//最后一个强引用Handle释放后资源的保留策略
+#[derive(Debug,Clone,Copy,PartialEq,Default)]
+pub enum RetentionPolicy {
+    #[default]
+    Immediate,
+    KeepSeconds(f32),
+    KeepFrames(u64),
+    UntilMemoryPressure
+}
+
+//导出给FFI的保留策略类型
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+#[repr(u8)]
+pub enum RetentionCode {
+    Immediate = 0,
+    KeepSeconds = 1,
+    KeepFrames = 2,
+    UntilMemoryPressure = 3
+}
+
+impl RetentionCode {
+    pub fn from_u8(code:u8) -> Option<RetentionCode> {
+        match code {
+            0 => Some(RetentionCode::Immediate),
+            1 => Some(RetentionCode::KeepSeconds),
+            2 => Some(RetentionCode::KeepFrames),
+            3 => Some(RetentionCode::UntilMemoryPressure),
+            _ => None
+        }
+    }
+
+    pub fn to_policy(self,value:f32) -> RetentionPolicy {
+        match self {
+            RetentionCode::Immediate => RetentionPolicy::Immediate,
+            RetentionCode::KeepSeconds => RetentionPolicy::KeepSeconds(value),
+            RetentionCode::KeepFrames => RetentionPolicy::KeepFrames(value as u64),
+            RetentionCode::UntilMemoryPressure => RetentionPolicy::UntilMemoryPressure
+        }
+    }
+}
+
+struct RetainedAsset {
+    policy:RetentionPolicy,
+    time:Instant,
+    frame:u64
+}
+
+impl RetainedAsset {
+    fn is_expired(&self,frame:u64) -> bool {
+        match self.policy {
+            RetentionPolicy::Immediate => true,
+            RetentionPolicy::KeepSeconds(secs) => self.time.elapsed().as_secs_f32() >= secs,
+            RetentionPolicy::KeepFrames(count) => frame - self.frame >= count,
+            RetentionPolicy::UntilMemoryPressure => false
+        }
+    }
+}
+
 #[derive(Default)]
 pub(crate) struct AssetLifeCycle {
     pub ref_counter: AssetRefCounter,
     pub lifecycle_events: RwLock<HashMap<Uuid, LifecycleEventChannel>>,
+    retentions:RwLock<HashMap<Uuid,RetentionPolicy>>,
+    retained:RwLock<HashMap<HandleId,RetainedAsset>>,
+    frame:AtomicU64
 }
 
 impl AssetLifeCycle {
     pub fn register(&self,typ_id:&Uuid) {
         self.lifecycle_events
             .write()
-            .insert(typ_id.clone(), LifecycleEventChannel::default());
+            .insert(*typ_id, LifecycleEventChannel::default());
     }
 
     pub fn sender(&self) -> Sender<RefEvent> {
         self.ref_counter.channel.sender.clone()
     }
 
+    pub fn set_retention(&self,typ_id:&Uuid,policy:RetentionPolicy) {
+        self.retentions.write().insert(*typ_id, policy);
+    }
 
-    pub fn free_unused_assets(&self) {
+    pub fn next_frame(&self) {
+        self.frame.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn free_unused_assets(&self) -> Vec<HandleId> {
         let ref_receiver = &self.ref_counter.channel.receiver;
         let mut ref_map = self.ref_counter.ref_counts.write();
+        let mut retained = self.retained.write();
         let mut free_list: Vec<HandleId> = Vec::new();
         loop {
             let ref_event = match ref_receiver.try_recv() {
@@ -80,6 +149,7 @@ impl AssetLifeCycle {
             match ref_event {
                 RefEvent::Increment(id) => {
                     *ref_map.entry(id).or_insert(0) += 1;
+                    retained.remove(&id);
                 }
                 RefEvent::Decrement(id) => {
                     let entry = ref_map.entry(id).or_insert(0);
@@ -91,15 +161,127 @@ impl AssetLifeCycle {
             }
         }
 
-        if !free_list.is_empty() {
-            let lifecycle_events = self.lifecycle_events.read();
-            for id in free_list {
-                if ref_map.get(&id).cloned().unwrap_or(0) == 0 {
-                    if let Some(channel) = lifecycle_events.get(id.typ()) {
-                        channel.sender.try_send(LifecycleEvent::Free(id)).unwrap();
-                    }
-                }
+        let frame = self.frame.load(Ordering::Relaxed);
+        let retentions = self.retentions.read();
+        for id in free_list {
+            if ref_map.get(&id).cloned().unwrap_or(0) == 0 {
+                let policy = retentions.get(id.typ()).cloned().unwrap_or_default();
+                retained.insert(id, RetainedAsset { policy, time:Instant::now(), frame });
             }
         }
+        let expired:Vec<HandleId> = retained.iter().filter(|(_,v)| v.is_expired(frame)).map(|(k,_)| *k).collect();
+        self.free_list(expired, &mut ref_map, &mut retained)
+    }
+
+    //释放所有被保留的资源,在内存不足时调用
+    pub fn free_retained_assets(&self) -> Vec<HandleId> {
+        let mut ref_map = self.ref_counter.ref_counts.write();
+        let mut retained = self.retained.write();
+        let all:Vec<HandleId> = retained.keys().cloned().collect();
+        self.free_list(all, &mut ref_map, &mut retained)
     }
-}
\ No newline at end of file
+
+    fn free_list(&self,list:Vec<HandleId>,ref_map:&mut HashMap<HandleId,usize>,retained:&mut HashMap<HandleId,RetainedAsset>) -> Vec<HandleId> {
+        let mut freed:Vec<HandleId> = Vec::new();
+        if list.is_empty() { return freed; }
+        let lifecycle_events = self.lifecycle_events.read();
+        for id in list {
+            retained.remove(&id);
+            ref_map.remove(&id);
+            if let Some(channel) = lifecycle_events.get(id.typ()) {
+                channel.sender.try_send(LifecycleEvent::Free(id)).unwrap();
+                freed.push(id);
+            }
+        }
+        freed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use seija_core::smol::channel::Receiver;
+    use super::*;
+
+    const TYP:Uuid = Uuid::from_u128(0x2a6e_8c1d_4b7f_4e0a_9d35_6f1c_b0e2_7a48);
+
+    fn life_cycle(policy:RetentionPolicy) -> (AssetLifeCycle,Receiver<LifecycleEvent>) {
+        let life_cycle = AssetLifeCycle::default();
+        life_cycle.register(&TYP);
+        life_cycle.set_retention(&TYP, policy);
+        let receiver = life_cycle.lifecycle_events.read().get(&TYP).unwrap().receiver.clone();
+        (life_cycle,receiver)
+    }
+
+    fn acquire(life_cycle:&AssetLifeCycle,id:HandleId) {
+        life_cycle.sender().try_send(RefEvent::Increment(id)).unwrap();
+    }
+
+    fn release(life_cycle:&AssetLifeCycle,id:HandleId) {
+        life_cycle.sender().try_send(RefEvent::Decrement(id)).unwrap();
+    }
+
+    fn update(life_cycle:&AssetLifeCycle) -> Vec<HandleId> {
+        life_cycle.next_frame();
+        life_cycle.free_unused_assets()
+    }
+
+    #[test]
+    fn keep_frames() {
+        let (life_cycle,receiver) = life_cycle(RetentionPolicy::KeepFrames(2));
+        let id = HandleId::new(TYP, 1);
+        acquire(&life_cycle, id);
+        assert!(update(&life_cycle).is_empty());
+        release(&life_cycle, id);
+        assert!(update(&life_cycle).is_empty());
+        assert!(update(&life_cycle).is_empty());
+        assert_eq!(update(&life_cycle), vec![id]);
+        assert!(matches!(receiver.try_recv(), Ok(LifecycleEvent::Free(free_id)) if free_id == id));
+    }
+
+    #[test]
+    fn keep_seconds() {
+        let (life_cycle,_receiver) = life_cycle(RetentionPolicy::KeepSeconds(0.05));
+        let id = HandleId::new(TYP, 1);
+        acquire(&life_cycle, id);
+        release(&life_cycle, id);
+        assert!(update(&life_cycle).is_empty());
+        std::thread::sleep(std::time::Duration::from_millis(60));
+        assert_eq!(update(&life_cycle), vec![id]);
+    }
+
+    #[test]
+    fn until_memory_pressure() {
+        let (life_cycle,_receiver) = life_cycle(RetentionPolicy::UntilMemoryPressure);
+        let id = HandleId::new(TYP, 1);
+        acquire(&life_cycle, id);
+        release(&life_cycle, id);
+        for _ in 0..10 {
+            assert!(update(&life_cycle).is_empty());
+        }
+        assert_eq!(life_cycle.free_retained_assets(), vec![id]);
+        assert!(life_cycle.free_retained_assets().is_empty());
+    }
+
+    #[test]
+    fn reacquire_cancel_retention() {
+        let (life_cycle,_receiver) = life_cycle(RetentionPolicy::KeepFrames(1));
+        let id = HandleId::new(TYP, 1);
+        acquire(&life_cycle, id);
+        release(&life_cycle, id);
+        assert!(update(&life_cycle).is_empty());
+        acquire(&life_cycle, id);
+        for _ in 0..3 {
+            assert!(update(&life_cycle).is_empty());
+        }
+        assert!(life_cycle.free_retained_assets().is_empty());
+        release(&life_cycle, id);
+        assert!(update(&life_cycle).is_empty());
+        assert_eq!(update(&life_cycle), vec![id]);
+    }
+
+    #[test]
+    fn retention_code() {
+        assert_eq!(RetentionCode::from_u8(2).map(|code| code.to_policy(3.0)), Some(RetentionPolicy::KeepFrames(3)));
+        assert_eq!(RetentionCode::from_u8(4), None);
+    }
+}
@@ -5,6 +5,7 @@ use downcast_rs::DowncastSync;
 use seija_core::smol::Task;
 use seija_core::smol_str::SmolStr;
 use seija_core::{smol,anyhow::Result};
+use crate::errors::AssetError;
 use crate::{AssetDynamic, HandleId, AssetLoaderParams, IAssetLoader, AsyncLoadMode};
 use crate::server::AssetServer;
 
@@ -17,6 +18,7 @@ pub(crate) struct LoadContext {
     pub(crate) load_task:Option<Task<Result<Box<dyn AssetDynamic>>>>,
     pub is_finish:bool,
     pub is_fail:bool,
+    pub error:Option<AssetError>,
     pub params:Option<Box<dyn AssetLoaderParams>>
 }
 
@@ -31,6 +33,7 @@ impl LoadContext {
             load_task:None,
             is_finish:false,
             is_fail:false,
+            error:None,
             params
         }
     }
@@ -84,6 +87,7 @@ impl AssetLoadingQueue {
                     },
                     Err(err) => {
                         log::error!("async touch error:{:?}",err);
+                        load_ctx.error = Some(AssetError::LoadFail(err.to_string()));
                         load_ctx.is_fail = true;
                         continue;
                     },
@@ -108,11 +112,13 @@ impl AssetLoadingQueue {
                        
                         if let Some(server) = world.get_resource::<AssetServer>() {
                             server.add_dyn_asset(&load_ctx.uri,&load_ctx.loader.typ(),load_ctx.hid, dyn_asset);
+                            server.on_load_finish(&load_ctx.uri,load_ctx.hid);
                         }
                         load_ctx.is_finish = true;
                     },
                     Err(err) => {
                         log::error!("load asset error path:{:?} err:{:?}",load_ctx.uri.as_str(),err);
+                        load_ctx.error = Some(AssetError::LoadFail(err.to_string()));
                         load_ctx.is_fail = true;
                     },
                 }
@@ -124,9 +130,8 @@ impl AssetLoadingQueue {
                 
             } else if load_ctx.is_fail {
                 let server = world.get_resource::<AssetServer>().unwrap();
-                if let Some(info) = server.get_asset(&load_ctx.uri) {
-                    info.set_fail();
-                }
+                let err = load_ctx.error.take().unwrap_or(AssetError::LoadFail(load_ctx.uri.to_string()));
+                server.on_load_fail(&load_ctx.uri,load_ctx.hid,err);
                 self.loadings.remove(count as usize);
             }
             count -= 1;
@@ -0,0 +1,170 @@
+use std::{path::{Path, PathBuf}, time::UNIX_EPOCH};
+use seija_core::anyhow::Result;
+
+//资源预处理器,把源文件处理成运行时可以直接使用的数据,结果按源文件hash缓存
+pub trait IAssetProcessor: Send + Sync + 'static {
+    //处理器版本,修改处理结果的格式后需要增加版本使旧的缓存失效
+    fn version(&self) -> u32 { 1 }
+    fn process(&self,path:&str,bytes:&[u8]) -> Result<Vec<u8>>;
+}
+
+//源文件的修改时间和大小,没有变化时直接使用上次的hash,不需要读取源文件
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub(crate) struct FileStamp {
+    pub modified:u64,
+    pub size:u64
+}
+
+impl FileStamp {
+    pub fn from_path(path:&Path) -> Option<FileStamp> {
+        let meta = std::fs::metadata(path).ok()?;
+        let modified = meta.modified().ok()?.duration_since(UNIX_EPOCH).ok()?.as_nanos() as u64;
+        Some(FileStamp { modified,size:meta.len() })
+    }
+}
+
+pub(crate) struct AssetProcessCache {
+    dir:PathBuf
+}
+
+impl AssetProcessCache {
+    pub fn new(dir:PathBuf) -> Self {
+        AssetProcessCache { dir }
+    }
+
+    pub fn key(path:&str,version:u32,bytes:&[u8]) -> u64 {
+        let mut hash = fnv1a(FNV_OFFSET, path.as_bytes());
+        hash = fnv1a(hash, &version.to_le_bytes());
+        fnv1a(hash, bytes)
+    }
+
+    fn cache_path(&self,key:u64) -> PathBuf {
+        self.dir.join(format!("{:016x}.bin",key))
+    }
+
+    fn stamp_path(&self,path:&str,version:u32) -> PathBuf {
+        let hash = fnv1a(fnv1a(FNV_OFFSET, path.as_bytes()), &version.to_le_bytes());
+        self.dir.join(format!("{:016x}.stamp",hash))
+    }
+
+    pub fn get(&self,key:u64) -> Option<Vec<u8>> {
+        std::fs::read(self.cache_path(key)).ok()
+    }
+
+    pub fn put(&self,key:u64,bytes:&[u8]) -> Result<()> {
+        std::fs::create_dir_all(&self.dir)?;
+        write_file(&self.cache_path(key), bytes)
+    }
+
+    //stamp文件记录 modified(u64) size(u64) key(u64)
+    pub fn get_stamp(&self,path:&str,version:u32,stamp:&FileStamp) -> Option<u64> {
+        let bytes = std::fs::read(self.stamp_path(path, version)).ok()?;
+        if bytes.len() != 24 { return None; }
+        let read_u64 = |start:usize| {
+            let mut buf = [0u8;8];
+            buf.copy_from_slice(&bytes[start..start + 8]);
+            u64::from_le_bytes(buf)
+        };
+        if read_u64(0) != stamp.modified || read_u64(8) != stamp.size {
+            return None;
+        }
+        Some(read_u64(16))
+    }
+
+    pub fn put_stamp(&self,path:&str,version:u32,stamp:&FileStamp,key:u64) -> Result<()> {
+        std::fs::create_dir_all(&self.dir)?;
+        let mut bytes = Vec::with_capacity(24);
+        bytes.extend_from_slice(&stamp.modified.to_le_bytes());
+        bytes.extend_from_slice(&stamp.size.to_le_bytes());
+        bytes.extend_from_slice(&key.to_le_bytes());
+        write_file(&self.stamp_path(path, version), &bytes)
+    }
+
+    //修改时间和大小都没有变化时直接读取缓存
+    pub fn get_stamped(&self,path:&str,version:u32,stamp:&FileStamp) -> Option<Vec<u8>> {
+        self.get_stamp(path, version, stamp).and_then(|key| self.get(key))
+    }
+}
+
+//先写临时文件再改名,避免读到写了一半的缓存
+fn write_file(path:&Path,bytes:&[u8]) -> Result<()> {
+    let tmp_path = path.with_extension("tmp");
+    std::fs::write(&tmp_path, bytes)?;
+    std::fs::rename(tmp_path, path)?;
+    Ok(())
+}
+
+const FNV_OFFSET:u64 = 0xcbf29ce484222325;
+const FNV_PRIME:u64 = 0x100000001b3;
+
+fn fnv1a(mut hash:u64,bytes:&[u8]) -> u64 {
+    for byte in bytes {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(FNV_PRIME);
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, atomic::{AtomicUsize, Ordering}};
+    use seija_core::anyhow::Result;
+    use crate::AssetServer;
+    use super::*;
+
+    fn temp_dir(name:&str) -> PathBuf {
+        let nanos = std::time::SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos();
+        let dir = std::env::temp_dir().join(format!("seija_{}_{}_{}",name,std::process::id(),nanos));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    struct CountProcessor(Arc<AtomicUsize>);
+
+    impl IAssetProcessor for CountProcessor {
+        fn process(&self,_path:&str,bytes:&[u8]) -> Result<Vec<u8>> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            Ok(bytes.iter().rev().cloned().collect())
+        }
+    }
+
+    #[test]
+    fn cache_hit_miss() {
+        let dir = temp_dir("process_cache");
+        let cache = AssetProcessCache::new(dir.clone());
+        let key = AssetProcessCache::key("a.png", 1, &[1,2,3]);
+        assert!(cache.get(key).is_none());
+        cache.put(key, &[3,2,1]).unwrap();
+        assert_eq!(cache.get(key), Some(vec![3,2,1]));
+        assert_ne!(key, AssetProcessCache::key("a.png", 1, &[1,2,4]));
+        assert_ne!(key, AssetProcessCache::key("a.png", 2, &[1,2,3]));
+
+        let stamp = FileStamp { modified:100,size:3 };
+        cache.put_stamp("a.png", 1, &stamp, key).unwrap();
+        assert_eq!(cache.get_stamped("a.png", 1, &stamp), Some(vec![3,2,1]));
+        assert!(cache.get_stamp("a.png", 1, &FileStamp { modified:101,size:3 }).is_none());
+        assert!(cache.get_stamp("a.png", 1, &FileStamp { modified:100,size:4 }).is_none());
+        assert!(cache.get_stamp("a.png", 2, &stamp).is_none());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn server_invalidation() {
+        let dir = temp_dir("process_server");
+        std::fs::write(dir.join("a.txt"), b"abc").unwrap();
+        let server = AssetServer::new(dir.clone());
+        let count = Arc::new(AtomicUsize::new(0));
+        server.register_processor("txt", CountProcessor(count.clone()));
+        server.set_process_cache_dir(dir.join("cache"));
+
+        assert_eq!(server.read_processed("a.txt").unwrap(), Some(b"cba".to_vec()));
+        assert_eq!(server.read_processed("a.txt").unwrap(), Some(b"cba".to_vec()));
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+
+        std::fs::write(dir.join("a.txt"), b"abcd").unwrap();
+        assert_eq!(server.read_processed("a.txt").unwrap(), Some(b"dcba".to_vec()));
+        assert_eq!(count.load(Ordering::SeqCst), 2);
+        assert!(server.read_processed("b.bin").unwrap().is_none());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -1,56 +1,114 @@
 use crate::{
-    Asset, Assets, lifecycle::AssetLifeCycle, HandleId, RefEvent,
+    Asset, Assets, lifecycle::{AssetLifeCycle, RetentionPolicy}, HandleId, RefEvent,
     AssetDynamic, Handle, errors::AssetError, HandleUntyped, LifecycleEvent, AssetLoaderParams, IAssetLoader,
 };
 use bevy_ecs::{prelude::{Res, World}, system::Resource};
-use parking_lot::RwLock;
+use parking_lot::{RwLock, Mutex};
 use parking_lot_core::SpinWait;
 use relative_path::RelativePath;
-use seija_core::{anyhow::{Result,anyhow}, smol_str::SmolStr, smol::channel::Sender};
+use seija_core::{anyhow::Result, smol_str::SmolStr, smol, smol::channel::Sender};
 use uuid::Uuid;
 use std::{
     path::{PathBuf},
-    sync::{Arc, atomic::{AtomicU8,Ordering}}, collections::{HashMap, VecDeque}, future::Future, task::{Poll}};
+    sync::Arc, collections::{HashMap, HashSet, VecDeque}, future::Future, task::{Poll, Waker}, time::Duration};
+use crate::{watcher::AssetWatcher, depend::AssetDependGraph, source::{IAssetSource, DirAssetSource}, archive::ArchiveAssetSource, process::{IAssetProcessor, AssetProcessCache, FileStamp}};
 
 
+#[derive(Debug,Clone)]
+pub enum LoadState {
+    NotLoaded,
+    Loading,
+    Loaded,
+    Failed(AssetError)
+}
+
+//导出给FFI的加载状态,不带错误信息
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+#[repr(u8)]
+pub enum LoadStateCode {
+    NotLoaded = 0,
+    Loading = 1,
+    Loaded = 2,
+    Failed = 3
+}
+
+impl LoadState {
+    pub fn code(&self) -> LoadStateCode {
+        match self {
+            LoadState::NotLoaded => LoadStateCode::NotLoaded,
+            LoadState::Loading => LoadStateCode::Loading,
+            LoadState::Loaded => LoadStateCode::Loaded,
+            LoadState::Failed(_) => LoadStateCode::Failed
+        }
+    }
+
+    pub fn to_u8(&self) -> u8 {
+        self.code() as u8
+    }
+}
+
+struct AssetInfoState {
+    state:LoadState,
+    wakers:Vec<Waker>
+}
+
 pub struct AssetInfo {
     handle_id:HandleId,
-    state:AtomicU8,
+    state:Mutex<AssetInfoState>,
     sender:Sender<RefEvent>,
+    params:Mutex<Option<Box<dyn AssetLoaderParams>>>
 }
 
 pub struct ArcAssetInfo(pub Arc<AssetInfo>);
 
 impl AssetInfo {
-    //#[warn(dead_code)]
-    //pub(crate) fn new<T:Asset>(sender:Sender<RefEvent>) -> Self {
-    //    let id = HandleId::random::<T>();
-    //    AssetInfo { handle_id:id, state: AtomicU8::new(0),sender,waker:Default::default() }
-    //}
-
     pub(crate) fn new_untyped(typ:&Uuid,sender:Sender<RefEvent>) -> Self {
         let id = HandleId::new(typ.clone(), rand::random());
-        AssetInfo { handle_id:id, state: AtomicU8::new(0),sender}
+        AssetInfo::new_id(id, sender)
     }
 
     pub(crate) fn new_id(id:HandleId,sender:Sender<RefEvent>) -> Self {
-        AssetInfo { handle_id: id, state: AtomicU8::new(0),sender }
+        let state = AssetInfoState { state:LoadState::Loading,wakers:vec![] };
+        AssetInfo { handle_id: id, state: Mutex::new(state),sender,params:Mutex::new(None) }
+    }
+
+    pub(crate) fn set_params(&self,params:Option<&dyn AssetLoaderParams>) {
+        *self.params.lock() = params.map(|v| v.clone_params());
+    }
+
+    pub(crate) fn clone_params(&self) -> Option<Box<dyn AssetLoaderParams>> {
+        self.params.lock().as_ref().map(|v| v.clone_params())
+    }
+
+    fn set_state(&self,new_state:LoadState) {
+        let wakers = {
+            let mut state = self.state.lock();
+            state.state = new_state;
+            std::mem::take(&mut state.wakers)
+        };
+        for waker in wakers {
+            waker.wake();
+        }
     }
 
     pub(crate) fn set_finish(&self) {
-        self.state.store(1, Ordering::SeqCst);
+        self.set_state(LoadState::Loaded);
     }
 
-    pub(crate) fn set_fail(&self) {
-        self.state.store(2, Ordering::SeqCst);
+    pub(crate) fn set_fail(&self,err:AssetError) {
+        self.set_state(LoadState::Failed(err));
     }
 
     pub(crate) fn is_finish(&self) -> bool {
-        self.state.load(Ordering::SeqCst) == 1
+        matches!(self.state.lock().state,LoadState::Loaded)
     }
 
     pub(crate) fn is_fail(&self) -> bool {
-        self.state.load(Ordering::SeqCst) == 2
+        matches!(self.state.lock().state,LoadState::Failed(_))
+    }
+
+    pub fn load_state(&self) -> LoadState {
+        self.state.lock().state.clone()
     }
 
     pub fn make_handle(&self) -> HandleUntyped {
@@ -75,6 +133,10 @@ impl AssetRequest {
         self.asset.0.is_finish()
     }
 
+    pub fn load_state(&self) -> LoadState {
+        self.asset.0.load_state()
+    }
+
     pub fn make_handle(&self) -> HandleUntyped {
         self.asset.0.make_handle()
     }
@@ -84,7 +146,9 @@ impl AssetRequest {
         self.asset.0.make_weak_handle()
     }
 
-    pub async fn wait_id(self) -> Option<HandleId> { self.asset.await }
+    pub async fn wait_result(self) -> std::result::Result<HandleId,AssetError> { self.asset.await }
+
+    pub async fn wait_id(self) -> Option<HandleId> { self.wait_result().await.ok() }
 
     pub async fn wait_handle(self) -> Option<HandleUntyped> {
         let sender = self.asset.0.sender.clone();
@@ -97,21 +161,18 @@ impl AssetRequest {
 }
 
 impl Future for ArcAssetInfo {
-    type Output = Option<HandleId>;
+    type Output = std::result::Result<HandleId,AssetError>;
     fn poll(self: std::pin::Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> std::task::Poll<Self::Output> {
-        //let waker = cx.waker().clone();
-        //{
-        //    let mut waker_lock = self.0.waker.lock().unwrap();
-        //    *waker_lock = Some(waker);
-        //};
-       
-        cx.waker().clone().wake();
-        if self.0.is_finish() {
-            Poll::Ready(Some(self.0.handle_id))
-        } else if self.0.is_fail() {
-            Poll::Ready(None)
-        } else {
-            Poll::Pending
+        let mut state = self.0.state.lock();
+        match &state.state {
+            LoadState::Loaded => Poll::Ready(Ok(self.0.handle_id)),
+            LoadState::Failed(err) => Poll::Ready(Err(err.clone())),
+            _ => {
+                if !state.wakers.iter().any(|w| w.will_wake(cx.waker())) {
+                    state.wakers.push(cx.waker().clone());
+                }
+                Poll::Pending
+            }
         }
     }
 }
@@ -125,25 +186,52 @@ pub struct AssetServer {
 
 pub struct AssetServerInner {
     pub root_path: PathBuf,
+    source:RwLock<Arc<dyn IAssetSource>>,
     pub(crate) life_cycle:AssetLifeCycle,
     assets:RwLock<HashMap<SmolStr,Arc<AssetInfo>>>,
+    paths:RwLock<HashMap<HandleId,SmolStr>>,
+    pub(crate) depends:RwLock<AssetDependGraph>,
+    reloading:RwLock<HashSet<HandleId>>,
     loaders:RwLock<HashMap<Uuid,Arc<dyn IAssetLoader>>>,
+    watcher:RwLock<Option<AssetWatcher>>,
+    processors:RwLock<HashMap<SmolStr,Arc<dyn IAssetProcessor>>>,
+    process_cache:RwLock<Option<Arc<AssetProcessCache>>>,
     pub(crate) request_list:Arc<RwLock<VecDeque<(SmolStr,HandleId,Option<Box<dyn AssetLoaderParams>>,Arc<dyn IAssetLoader>)>>>
 }
 
 impl AssetServer {
+    //root_path是打包文件但打开失败时直接panic,需要处理错误时用try_new
     pub fn new(root_path: PathBuf) -> AssetServer {
+        match Self::try_new(root_path) {
+            Ok(server) => server,
+            Err(err) => panic!("open asset archive error:{:?}",err)
+        }
+    }
+
+    pub fn try_new(root_path: PathBuf) -> Result<AssetServer> {
         log::info!("init asset server:{:?}", root_path.as_path());
-        
-        AssetServer {
+        //root_path是文件时作为资源打包文件读取
+        let source:Arc<dyn IAssetSource> = if root_path.is_file() {
+            Arc::new(ArchiveAssetSource::open(&root_path)?)
+        } else {
+            Arc::new(DirAssetSource::new(root_path.clone()))
+        };
+        Ok(AssetServer {
             inner: Arc::new(AssetServerInner {
                 root_path,
+                source:RwLock::new(source),
                 life_cycle:Default::default(),
                 assets:RwLock::new(HashMap::default()),
+                paths:Default::default(),
+                depends:Default::default(),
+                reloading:Default::default(),
                 loaders:Default::default(),
+                watcher:RwLock::new(None),
+                processors:Default::default(),
+                process_cache:RwLock::new(None),
                 request_list:Default::default()
             }),
-        }
+        })
     }
 
     pub fn register_type<T: Asset>(&self) -> Assets<T> {
@@ -159,22 +247,157 @@ impl AssetServer {
         Ok(RelativePath::from_path(path)?.to_logical_path(&self.inner.root_path))
     }
 
+    pub fn set_source(&self,source:Arc<dyn IAssetSource>) {
+        *self.inner.source.write() = source;
+    }
+
+    pub fn source(&self) -> Arc<dyn IAssetSource> {
+        self.inner.source.read().clone()
+    }
+
+    pub fn exists(&self,path:&str) -> bool {
+        self.source().exists(path)
+    }
+
+    pub fn read_bytes(&self,path:&str) -> Result<Vec<u8>> {
+        self.source().read(path)
+    }
+
+    pub fn read_string(&self,path:&str) -> Result<String> {
+        Ok(String::from_utf8(self.read_bytes(path)?)?)
+    }
+
+    pub async fn read_bytes_async(&self,path:&str) -> Result<Vec<u8>> {
+        let source = self.source();
+        let path = SmolStr::new(path);
+        smol::unblock(move || source.read(path.as_str())).await
+    }
+
+    pub async fn read_string_async(&self,path:&str) -> Result<String> {
+        Ok(String::from_utf8(self.read_bytes_async(path).await?)?)
+    }
+
+    //按扩展名注册预处理器,例如"png"
+    pub fn register_processor<P:IAssetProcessor>(&self,ext:&str,processor:P) {
+        self.inner.processors.write().insert(SmolStr::new(ext.to_lowercase()), Arc::new(processor));
+    }
+
+    //设置预处理结果的缓存目录,设置后Loader才会使用预处理的数据
+    pub fn set_process_cache_dir(&self,dir:PathBuf) {
+        log::info!("asset process cache:{:?}",dir.as_path());
+        *self.inner.process_cache.write() = Some(Arc::new(AssetProcessCache::new(dir)));
+    }
+
+    fn get_processor(&self,path:&str) -> Option<Arc<dyn IAssetProcessor>> {
+        let ext = path.rsplit('.').next()?.to_lowercase();
+        self.inner.processors.read().get(ext.as_str()).cloned()
+    }
+
+    //读取预处理后的数据,没有对应的处理器或者没有开启缓存时返回None
+    pub fn read_processed(&self,path:&str) -> Result<Option<Vec<u8>>> {
+        let cache = match self.inner.process_cache.read().clone() {
+            Some(cache) => cache,
+            None => return Ok(None)
+        };
+        let processor = match self.get_processor(path) {
+            Some(processor) => processor,
+            None => return Ok(None)
+        };
+        let stamp = self.source().local_path(path).and_then(|full_path| FileStamp::from_path(&full_path));
+        if let Some(cached) = stamp.and_then(|stamp| cache.get_stamped(path, processor.version(), &stamp)) {
+            return Ok(Some(cached));
+        }
+        let bytes = self.read_bytes(path)?;
+        process_with_cache(&cache, processor.as_ref(), path, &bytes, stamp).map(Some)
+    }
+
+    pub async fn read_processed_async(&self,path:&str) -> Result<Option<Vec<u8>>> {
+        let cache = match self.inner.process_cache.read().clone() {
+            Some(cache) => cache,
+            None => return Ok(None)
+        };
+        let processor = match self.get_processor(path) {
+            Some(processor) => processor,
+            None => return Ok(None)
+        };
+        let source = self.source();
+        let (stamp_cache,stamp_path,version) = (cache.clone(),SmolStr::new(path),processor.version());
+        let (stamp,cached) = smol::unblock(move || {
+            let stamp = source.local_path(stamp_path.as_str()).and_then(|full_path| FileStamp::from_path(&full_path));
+            let cached = stamp.and_then(|stamp| stamp_cache.get_stamped(stamp_path.as_str(), version, &stamp));
+            (stamp,cached)
+        }).await;
+        if cached.is_some() {
+            return Ok(cached);
+        }
+        let bytes = self.read_bytes_async(path).await?;
+        let path = SmolStr::new(path);
+        smol::unblock(move || process_with_cache(&cache, processor.as_ref(), path.as_str(), &bytes, stamp)).await.map(Some)
+    }
+
     pub fn set_asset(&self,path:&str,id:HandleId) {
        let asset_info = AssetInfo::new_id(id,self.inner.life_cycle.sender());
-       self.inner.assets.write().insert(SmolStr::new(path), Arc::new(asset_info));
+       asset_info.set_finish();
+       self.insert_info(path, Arc::new(asset_info));
+    }
+
+    fn insert_info(&self,path:&str,info:Arc<AssetInfo>) {
+        self.inner.paths.write().insert(info.handle_id, SmolStr::new(path));
+        self.inner.assets.write().insert(SmolStr::new(path), info);
     }
 
     pub fn get_asset(&self,path:&str) -> Option<Arc<AssetInfo>> {
         self.inner.assets.read().get(path).cloned()
     }
 
+    pub fn get_path(&self,id:HandleId) -> Option<SmolStr> {
+        self.inner.paths.read().get(&id).cloned()
+    }
+
+    //记录path对应的资源依赖depend,在Loader中加载子资源时调用
+    pub fn add_dependency(&self,path:&str,depend:HandleId) {
+        let info = self.get_asset(path);
+        let mut graph = self.inner.depends.write();
+        match info {
+            Some(info) if !info.is_fail() => graph.add(info.handle_id, depend),
+            _ => graph.add_pending(path, depend)
+        }
+    }
+
+    pub fn get_load_state(&self,id:HandleId) -> LoadState {
+        match self.get_path(id).and_then(|path| self.get_asset(path.as_str())) {
+            Some(info) if info.handle_id == id => info.load_state(),
+            _ => LoadState::NotLoaded
+        }
+    }
+
+    pub fn dependencies(&self,id:HandleId) -> Vec<HandleId> {
+        self.inner.depends.read().depends(&id)
+    }
+
+    pub fn dependents(&self,id:HandleId) -> Vec<HandleId> {
+        self.inner.depends.read().dependents(&id)
+    }
+
+    //资源自身和递归的所有依赖是否都已加载完成
+    pub fn is_loaded_with_dependencies(&self,id:HandleId) -> bool {
+        let all_depends = self.inner.depends.read().all_depends(&id);
+        std::iter::once(id).chain(all_depends).all(|hid| {
+            //没有加载信息的依赖已经被释放或者不是通过AssetServer加载的,视为未加载
+            match self.get_path(hid).and_then(|path| self.get_asset(path.as_str())) {
+                Some(info) => info.handle_id == hid && info.is_finish(),
+                None => false
+            }
+        })
+    }
+
     pub fn add_dyn_asset(&self,path:&str,typ:&Uuid,hid:HandleId,asset:Box<dyn AssetDynamic>) {
         let read_info = self.inner.assets.read().get(path).cloned();
         let info = if let Some(info) = read_info {
             info
         } else {
             let info = Arc::new(AssetInfo::new_id(hid, self.inner.life_cycle.sender()));
-            self.inner.assets.write().insert(SmolStr::new(path), info.clone());
+            self.insert_info(path, info.clone());
             info
         };
         let events = self.inner.life_cycle.lifecycle_events.write();
@@ -196,28 +419,35 @@ impl AssetServer {
     pub fn load_sync_untyped(&self,world:&mut World,typ:&Uuid,path:&str,params:Option<Box<dyn AssetLoaderParams>>) -> Result<HandleUntyped> {
         let info = self.inner.assets.read().get(path).cloned();
         if let Some(info) = info {
-            if info.is_finish() {
-                return Ok(info.make_handle());
-            }
             if !info.is_fail() {
                 let mut wait:SpinWait = Default::default();
                 loop {
-                    if info.is_finish() {
-                        return Ok(info.make_handle());
-                    } else if info.is_fail() {
-                        return Err(anyhow!("load fail"));
+                    match info.load_state() {
+                        LoadState::Loaded => return Ok(info.make_handle()),
+                        LoadState::Failed(err) => return Err(err.into()),
+                        _ => { wait.spin(); }
                     }
-                    wait.spin();
                 }
             }
         }
 
         let loader = self.inner.loaders.read().get(&typ).ok_or(AssetError::NotFoundLoader)?.clone();
-        let load_asset = loader.sync_load(world,path,self,params)?;
-        let handle = loader.add_to_asset(world, load_asset)?;
+        let saved_params = params.as_deref().map(|v| v.clone_params());
+        let load_ret = loader.sync_load(world,path,self,params).and_then(|asset| loader.add_to_asset(world, asset));
+        let handle = match load_ret {
+            Ok(handle) => handle,
+            Err(err) => {
+                //加载失败时清除Loader中已经记录的依赖
+                self.inner.depends.write().remove_pending(path);
+                return Err(err);
+            }
+        };
         let info = Arc::new( AssetInfo::new_id(handle.id, self.inner.life_cycle.sender()));
+        info.set_params(saved_params.as_deref());
         info.set_finish();
-        self.inner.assets.write().insert(SmolStr::new(path), info);
+        self.insert_info(path, info);
+        self.inner.depends.write().resolve_pending(path, handle.id);
+        self.track_file(path);
         Ok(handle)
     }
 
@@ -235,7 +465,8 @@ impl AssetServer {
         }
        
         let asset_info = Arc::new(AssetInfo::new_untyped(typ,self.inner.life_cycle.sender()));
-        self.inner.assets.write().insert(path.into(), asset_info.clone());
+        asset_info.set_params(params.as_deref());
+        self.insert_info(path, asset_info.clone());
         log::info!("load_async_untyped:{}",path);
 
         let loader = self.inner.loaders.read().get(typ).ok_or(AssetError::NotFoundLoader)?.clone();
@@ -243,6 +474,82 @@ impl AssetServer {
         Ok(AssetRequest::new(asset_info))
     }
 
+    //开启资源热重载,定时检查已加载资源文件的修改时间,修改后重新加载并发送AssetEvent::Modified
+    pub fn watch_for_changes(&self,interval:Duration) {
+        let mut watcher = AssetWatcher::new(interval);
+        for (path,info) in self.inner.assets.read().iter() {
+            if info.is_finish() {
+                if let Some(full_path) = self.source().local_path(path.as_str()) {
+                    watcher.track(path.as_str(), full_path);
+                }
+            }
+        }
+        *self.inner.watcher.write() = Some(watcher);
+    }
+
+    pub fn stop_watch(&self) {
+        *self.inner.watcher.write() = None;
+    }
+
+    pub fn is_watching(&self) -> bool {
+        self.inner.watcher.read().is_some()
+    }
+
+    pub(crate) fn on_load_finish(&self,path:&str,id:HandleId) {
+        self.track_file(path);
+        if self.inner.reloading.write().remove(&id) {
+            for dependent in self.dependents(id) {
+                if let Some(dep_path) = self.get_path(dependent) {
+                    if let Err(err) = self.reload(dep_path.as_str()) {
+                        log::error!("reload dependent asset {} error:{:?}",dep_path.as_str(),err);
+                    }
+                }
+            }
+        }
+    }
+
+    pub(crate) fn on_load_fail(&self,path:&str,id:HandleId,err:AssetError) {
+        //重新加载失败时保留旧的资源
+        if self.inner.reloading.write().remove(&id) {
+            return;
+        }
+        self.inner.depends.write().clear_depends(&id);
+        if let Some(info) = self.get_asset(path) {
+            info.set_fail(err);
+        }
+    }
+
+    pub(crate) fn track_file(&self,path:&str) {
+        if let Some(watcher) = self.inner.watcher.write().as_mut() {
+            if let Some(full_path) = self.source().local_path(path) {
+                watcher.track(path, full_path);
+            }
+        }
+    }
+
+    pub(crate) fn check_file_changes(&self) {
+        let changed = match self.inner.watcher.write().as_mut() {
+            Some(watcher) => watcher.poll_changed(),
+            None => return
+        };
+        for path in changed {
+            if let Err(err) = self.reload(path.as_str()) {
+                log::error!("reload asset {} error:{:?}",path.as_str(),err);
+            }
+        }
+    }
+
+    //使用同一个HandleId和第一次加载时的参数重新加载资源
+    pub fn reload(&self,path:&str) -> Result<()> {
+        let info = self.get_asset(path).ok_or(AssetError::NotFoundAsset(path.into()))?;
+        let loader = self.inner.loaders.read().get(info.handle_id.typ()).ok_or(AssetError::NotFoundLoader)?.clone();
+        log::info!("reload asset:{}",path);
+        self.inner.depends.write().clear_depends(&info.handle_id);
+        self.inner.reloading.write().insert(info.handle_id);
+        self.inner.request_list.write().push_back((SmolStr::new(path),info.handle_id,info.clone_params(),loader));
+        Ok(())
+    }
+
     pub fn get_ref_sender(&self) -> Sender<RefEvent> {
         self.inner.life_cycle.sender()
     }
@@ -251,10 +558,115 @@ impl AssetServer {
         let sender = self.inner.life_cycle.sender();
         let _ = sender.try_send(RefEvent::Decrement(id));
     }
+
+    pub fn set_retention<T:Asset>(&self,policy:RetentionPolicy) {
+        self.inner.life_cycle.set_retention(&T::TYPE_UUID, policy);
+    }
+
+    //内存不足时释放所有被保留的无引用资源
+    pub fn trim_memory(&self) {
+        let free_list = self.inner.life_cycle.free_retained_assets();
+        self.on_assets_freed(free_list);
+    }
+
+    //释放引用计数为0的资源,资源值中持有的子资源Handle释放后会在之后的帧中级联释放
+    pub(crate) fn free_unused_assets(&self) {
+        let free_list = self.inner.life_cycle.free_unused_assets();
+        self.on_assets_freed(free_list);
+    }
+
+    pub(crate) fn on_assets_freed(&self,free_list:Vec<HandleId>) {
+        if free_list.is_empty() { return; }
+        let mut graph = self.inner.depends.write();
+        for id in free_list.iter() {
+            graph.remove(id);
+            if let Some(path) = self.inner.paths.write().remove(id) {
+                let mut assets = self.inner.assets.write();
+                if assets.get(&path).map(|info| info.handle_id == *id).unwrap_or(false) {
+                    assets.remove(&path);
+                    if let Some(watcher) = self.inner.watcher.write().as_mut() {
+                        watcher.untrack(path.as_str());
+                    }
+                }
+            }
+        }
+    }
 }
 
 
 
+//stamp不为空时记录源文件的修改时间和大小,下次没有变化时不需要再hash源文件
+fn process_with_cache(cache:&AssetProcessCache,processor:&dyn IAssetProcessor,path:&str,bytes:&[u8],stamp:Option<FileStamp>) -> Result<Vec<u8>> {
+    let key = AssetProcessCache::key(path, processor.version(), bytes);
+    let processed = match cache.get(key) {
+        Some(cached) => cached,
+        None => {
+            log::info!("process asset:{}",path);
+            let processed = processor.process(path, bytes)?;
+            if let Err(err) = cache.put(key, &processed) {
+                log::error!("write asset process cache {} error:{:?}",path,err);
+            }
+            processed
+        }
+    };
+    if let Some(stamp) = stamp {
+        if let Err(err) = cache.put_stamp(path, processor.version(), &stamp, key) {
+            log::error!("write asset process stamp {} error:{:?}",path,err);
+        }
+    }
+    Ok(processed)
+}
+
 pub fn free_unused_assets_system(asset_server: Res<AssetServer>) {
-    asset_server.inner.life_cycle.free_unused_assets();
+    asset_server.inner.life_cycle.next_frame();
+    asset_server.free_unused_assets();
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::{Arc, atomic::{AtomicUsize, Ordering}}, task::{Context, Poll, Wake, Waker}, future::Future};
+    use seija_core::smol;
+    use uuid::Uuid;
+    use crate::errors::AssetError;
+    use super::{AssetInfo, AssetRequest};
+
+    #[derive(Default)]
+    struct CountWaker(AtomicUsize);
+
+    impl Wake for CountWaker {
+        fn wake(self: Arc<Self>) { self.0.fetch_add(1, Ordering::SeqCst); }
+    }
+
+    fn new_request() -> (Arc<AssetInfo>,AssetRequest) {
+        let (sender,_) = smol::channel::unbounded();
+        let info = Arc::new(AssetInfo::new_untyped(&Uuid::nil(), sender));
+        (info.clone(),AssetRequest::new(info))
+    }
+
+    #[test]
+    fn wake_on_loaded() {
+        let (info,request) = new_request();
+        let counter = Arc::new(CountWaker::default());
+        let waker = Waker::from(counter.clone());
+        let mut cx = Context::from_waker(&waker);
+        let mut fut = Box::pin(request.wait_result());
+        assert!(fut.as_mut().poll(&mut cx).is_pending());
+        assert_eq!(counter.0.load(Ordering::SeqCst), 0);
+        info.set_finish();
+        assert_eq!(counter.0.load(Ordering::SeqCst), 1);
+        assert!(matches!(fut.as_mut().poll(&mut cx), Poll::Ready(Ok(id)) if id == info.handle_id));
+    }
+
+    #[test]
+    fn wake_on_failed() {
+        let (info,request) = new_request();
+        let counter = Arc::new(CountWaker::default());
+        let waker = Waker::from(counter.clone());
+        let mut cx = Context::from_waker(&waker);
+        let mut fut = Box::pin(request.wait_id());
+        assert!(fut.as_mut().poll(&mut cx).is_pending());
+        info.set_fail(AssetError::LoadFail("test".into()));
+        assert_eq!(counter.0.load(Ordering::SeqCst), 1);
+        assert!(matches!(fut.as_mut().poll(&mut cx), Poll::Ready(None)));
+    }
 }
@@ -0,0 +1,72 @@
+use std::{path::{PathBuf, Path}, collections::HashMap};
+use parking_lot::RwLock;
+use relative_path::RelativePath;
+use seija_core::{anyhow::Result, smol_str::SmolStr};
+use crate::errors::AssetError;
+
+//资源的读取来源,可以是目录,打包文件或者内存
+pub trait IAssetSource: Send + Sync + 'static {
+    fn read(&self,path:&str) -> Result<Vec<u8>>;
+    fn exists(&self,path:&str) -> bool;
+    //资源在磁盘上的文件路径,不是单独文件的资源返回None
+    fn local_path(&self,_path:&str) -> Option<PathBuf> { None }
+}
+
+pub fn normalize_asset_path(path:&str) -> SmolStr {
+    SmolStr::new(RelativePath::new(path.trim_start_matches('/')).normalize().as_str())
+}
+
+pub struct DirAssetSource {
+    root:PathBuf
+}
+
+impl DirAssetSource {
+    pub fn new(root:PathBuf) -> Self {
+        DirAssetSource { root }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+}
+
+impl IAssetSource for DirAssetSource {
+    fn read(&self,path:&str) -> Result<Vec<u8>> {
+        let full_path = RelativePath::new(path).to_logical_path(&self.root);
+        Ok(std::fs::read(full_path)?)
+    }
+
+    fn exists(&self,path:&str) -> bool {
+        RelativePath::new(path).to_logical_path(&self.root).is_file()
+    }
+
+    fn local_path(&self,path:&str) -> Option<PathBuf> {
+        Some(RelativePath::new(path).to_logical_path(&self.root))
+    }
+}
+
+#[derive(Default)]
+pub struct MemoryAssetSource {
+    files:RwLock<HashMap<SmolStr,Vec<u8>>>
+}
+
+impl MemoryAssetSource {
+    pub fn insert(&self,path:&str,bytes:Vec<u8>) {
+        self.files.write().insert(normalize_asset_path(path), bytes);
+    }
+
+    pub fn remove(&self,path:&str) -> Option<Vec<u8>> {
+        self.files.write().remove(normalize_asset_path(path).as_str())
+    }
+}
+
+impl IAssetSource for MemoryAssetSource {
+    fn read(&self,path:&str) -> Result<Vec<u8>> {
+        let bytes = self.files.read().get(normalize_asset_path(path).as_str()).cloned();
+        Ok(bytes.ok_or(AssetError::NotFoundAsset(path.into()))?)
+    }
+
+    fn exists(&self,path:&str) -> bool {
+        self.files.read().contains_key(normalize_asset_path(path).as_str())
+    }
+}
@@ -0,0 +1,46 @@
+use std::{collections::HashMap, path::{PathBuf, Path}, time::{SystemTime, Duration, Instant}};
+use seija_core::smol_str::SmolStr;
+
+//轮询资源文件的修改时间,用于资源热重载
+pub(crate) struct AssetWatcher {
+    interval:Duration,
+    last_check:Instant,
+    files:HashMap<SmolStr,(PathBuf,SystemTime)>
+}
+
+impl AssetWatcher {
+    pub fn new(interval:Duration) -> Self {
+        AssetWatcher { interval, last_check:Instant::now(), files:HashMap::default() }
+    }
+
+    pub fn track(&mut self,path:&str,full_path:PathBuf) {
+        if let Some(time) = modified_time(&full_path) {
+            self.files.insert(SmolStr::new(path), (full_path,time));
+        }
+    }
+
+    pub fn untrack(&mut self,path:&str) {
+        self.files.remove(path);
+    }
+
+    pub fn poll_changed(&mut self) -> Vec<SmolStr> {
+        let mut changed = vec![];
+        if self.last_check.elapsed() < self.interval {
+            return changed;
+        }
+        self.last_check = Instant::now();
+        for (path,(full_path,last_time)) in self.files.iter_mut() {
+            if let Some(time) = modified_time(full_path) {
+                if time != *last_time {
+                    *last_time = time;
+                    changed.push(path.clone());
+                }
+            }
+        }
+        changed
+    }
+}
+
+fn modified_time(path:&Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
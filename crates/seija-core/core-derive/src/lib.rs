@@ -32,4 +32,104 @@ pub fn type_uuid_derive(input: proc_macro::TokenStream) -> proc_macro::TokenStre
         }
     };
     gen.into()
-}
\ No newline at end of file
+}
+
+//#[derive(Reflect)] 为具名字段的结构体生成按名字读写字段的代码
+//#[reflect(skip)] 跳过字段, #[reflect(name = "xxx")] 修改字段名
+//结构体上的#[reflect(on_set = "xxx")] 在设置字段后调用self.xxx(),用来更新由字段计算出的缓存
+#[proc_macro_derive(Reflect, attributes(reflect))]
+pub fn reflect_derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let ast: DeriveInput = syn::parse(input).unwrap();
+    let name = &ast.ident;
+    let type_name = name.to_string();
+    let mut on_set = None;
+    for meta in ast.attrs.iter().filter(|attr| attr.path.is_ident("reflect")).filter_map(|attr| attr.parse_meta().ok()) {
+        let list = match meta {
+            Meta::List(list) => list,
+            _ => panic!("reflect attribute on struct must take the form `#[reflect(on_set = \"xxx\")]`"),
+        };
+        for nested in list.nested.iter() {
+            match nested {
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("on_set") => {
+                    match &nv.lit {
+                        Lit::Str(s) => on_set = Some(Ident::new(&s.value(), s.span())),
+                        _ => panic!("reflect on_set must be a string"),
+                    }
+                },
+                _ => panic!("unknown reflect attribute"),
+            }
+        }
+    }
+    let on_set = on_set.map(|method| quote! { self.#method(); }).unwrap_or_default();
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+    let fields = match &ast.data {
+        Data::Struct(DataStruct { fields:Fields::Named(fields), .. }) => &fields.named,
+        _ => panic!("Reflect can only be derived for structs with named fields"),
+    };
+    let mut idents = vec![];
+    let mut names = vec![];
+    let mut types = vec![];
+    for field in fields.iter() {
+        let mut field_name = field.ident.as_ref().unwrap().to_string();
+        let mut skip = false;
+        for meta in field.attrs.iter().filter(|attr| attr.path.is_ident("reflect")).filter_map(|attr| attr.parse_meta().ok()) {
+            let list = match meta {
+                Meta::List(list) => list,
+                _ => panic!("reflect attribute must take the form `#[reflect(skip)]` or `#[reflect(name = \"xxx\")]`"),
+            };
+            for nested in list.nested.iter() {
+                match nested {
+                    NestedMeta::Meta(Meta::Path(path)) if path.is_ident("skip") => skip = true,
+                    NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("name") => {
+                        match &nv.lit {
+                            Lit::Str(s) => field_name = s.value(),
+                            _ => panic!("reflect name must be a string"),
+                        }
+                    },
+                    _ => panic!("unknown reflect attribute"),
+                }
+            }
+        }
+        if skip { continue; }
+        idents.push(field.ident.clone().unwrap());
+        names.push(field_name);
+        types.push(field.ty.clone());
+    }
+    let gen = quote! {
+        impl #impl_generics seija_core::reflect::ReflectStatic for #name #ty_generics #where_clause {
+            const TYPE_NAME:&'static str = #type_name;
+            const FIELDS:&'static [seija_core::reflect::FieldInfo] = &[
+                #( seija_core::reflect::FieldInfo { name:#names,typ:<#types as seija_core::reflect::IReflectValue>::TYPE } ),*
+            ];
+        }
+
+        impl #impl_generics seija_core::reflect::Reflect for #name #ty_generics #where_clause {
+            fn type_name(&self) -> &'static str { #type_name }
+
+            fn fields(&self) -> &'static [seija_core::reflect::FieldInfo] {
+                <Self as seija_core::reflect::ReflectStatic>::FIELDS
+            }
+
+            fn get_field(&self,name:&str) -> Option<seija_core::reflect::ReflectValue> {
+                match name {
+                    #( #names => Some(seija_core::reflect::IReflectValue::to_value(&self.#idents)), )*
+                    _ => None
+                }
+            }
+
+            fn set_field(&mut self,name:&str,value:seija_core::reflect::ReflectValue) -> seija_core::anyhow::Result<()> {
+                let typ = value.typ();
+                match name {
+                    #( #names => {
+                        self.#idents = <#types as seija_core::reflect::IReflectValue>::from_value(value)
+                                       .ok_or_else(|| seija_core::reflect::field_type_error(#type_name,name,typ))?;
+                        #on_set
+                        Ok(())
+                    }, )*
+                    _ => Err(seija_core::reflect::not_found_field_error(#type_name,name))
+                }
+            }
+        }
+    };
+    gen.into()
+}
@@ -4,7 +4,7 @@ use bevy_ecs::{prelude::World};
 use log::Level;
 use seija_app::App;
 use seija_app::ecs::prelude::*;
-use crate::{CoreModule, time::Time, CoreStage, StartupStage, FrameDirty};
+use crate::{CoreModule, time::Time, CoreStage, StartupStage, FrameDirty, reflect::ReflectRegistry, ResultExt};
 
 #[no_mangle]
 pub unsafe extern "C" fn core_add_module(app_ptr:*mut u8) {
@@ -34,6 +34,14 @@ pub unsafe extern "C" fn core_time_get_delta_seconds(time_ptr:* const u8) -> f32
     time_ref.delta_seconds()
 }
 
+//delta小于等于0时恢复使用真实时间
+#[no_mangle]
+pub unsafe extern "C" fn core_world_set_fixed_delta(world:&mut World,delta:f32) {
+    if let Some(mut time) = world.get_resource_mut::<Time>() {
+        time.set_fixed_delta(if delta > 0f32 { Some(delta) } else { None });
+    }
+}
+
 
 #[no_mangle]
 pub unsafe extern "C" fn init_log(level:*const c_char) {
@@ -55,6 +63,38 @@ pub unsafe extern "C" fn is_frame_dirty(world: &mut World,eid:u64,frame:u64,inde
     }
 }
 
+//按组件名和字段名设置反射组件的字段,value使用模板中的字符串格式
+#[no_mangle]
+pub unsafe extern "C" fn core_reflect_set_field(world: &mut World,eid:u64,typ:*const c_char,field:*const c_char,value:*const c_char) -> bool {
+    let typ = std::ffi::CStr::from_ptr(typ).to_str().unwrap_or_default();
+    let field = std::ffi::CStr::from_ptr(field).to_str().unwrap_or_default();
+    let value = std::ffi::CStr::from_ptr(value).to_str().unwrap_or_default();
+    let info = match world.get_resource::<ReflectRegistry>().and_then(|r| r.get_info(typ)) {
+        Some(info) => info,
+        None => { log::error!("not found reflect component {}",typ); return false; }
+    };
+    info.set_field_str(world, Entity::from_bits(eid), field, value).log_err().is_ok()
+}
+
+//把字段的字符串值写入out,返回字符串的字节长度,out_len不够时只返回长度,找不到字段返回-1
+#[no_mangle]
+pub unsafe extern "C" fn core_reflect_get_field(world: &World,eid:u64,typ:*const c_char,field:*const c_char,out:*mut u8,out_len:usize) -> i32 {
+    let typ = std::ffi::CStr::from_ptr(typ).to_str().unwrap_or_default();
+    let field = std::ffi::CStr::from_ptr(field).to_str().unwrap_or_default();
+    let value = world.get_resource::<ReflectRegistry>().and_then(|r| r.get_info(typ))
+                     .and_then(|info| info.get_field(world, Entity::from_bits(eid), field));
+    match value {
+        Some(value) => {
+            let str_value = value.to_string();
+            if !out.is_null() && str_value.len() <= out_len {
+                std::ptr::copy_nonoverlapping(str_value.as_ptr(), out, str_value.len());
+            }
+            str_value.len() as i32
+        },
+        None => -1
+    }
+}
+
 type WorldFN = extern fn(world:*mut World);
 
 #[derive(Resource)]
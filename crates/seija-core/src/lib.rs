@@ -3,6 +3,7 @@ use bevy_ecs::{prelude::*};
 use bevy_ecs::schedule::{StageLabel, ShouldRun};
 use seija_app::{IModule,App};
 use time::{Time};
+use reflect::{ReflectRegistry, Reflect, ReflectStatic};
 use std::fmt::Debug;
 use std::sync::atomic::{AtomicU64,Ordering, AtomicU32};
 pub mod info;
@@ -11,6 +12,10 @@ pub mod time;
 pub mod window;
 pub mod type_uuid;
 pub mod ffi;
+pub mod reflect;
+//测试中#[derive(Reflect)]生成的seija_core路径指向本crate
+#[cfg(test)]
+extern crate self as seija_core;
 pub use type_uuid::{TypeUuid,TypeUuidDynamic};
 pub use uuid;
 pub use bevy_ecs;
@@ -46,6 +51,7 @@ impl IModule for CoreModule {
     fn init(&mut self, app:&mut App) {
         self.add_core_stages(app);
         app.init_resource::<Time>();
+        app.world.init_resource::<ReflectRegistry>();
         app.add_system(CoreStage::First, time::time_system);
         app.add_system(CoreStage::Last, World::clear_trackers.at_end());
     }
@@ -72,6 +78,7 @@ impl CoreModule {
 
 pub trait AddCore {
     fn add_event<T:Send + Sync + 'static>(&mut self);
+    fn register_reflect<T:Reflect + ReflectStatic + Component>(&mut self);
 }
 
 impl AddCore for App {
@@ -79,6 +86,10 @@ impl AddCore for App {
         self.add_resource(Events::<T>::default());
         self.add_system(CoreStage::First, Events::<T>::update_system);
     }
+
+    fn register_reflect<T:Reflect + ReflectStatic + Component>(&mut self) {
+        self.world.get_resource_or_insert_with(ReflectRegistry::default).register::<T>();
+    }
 }
 
 macro_rules! idgen {
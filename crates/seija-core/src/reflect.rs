@@ -0,0 +1,363 @@
+use std::collections::HashMap;
+use anyhow::{Result, anyhow, bail};
+use bevy_ecs::{prelude::{Component, Entity, World}, system::Resource};
+use glam::{Vec2, Vec3, Vec4, Quat, EulerRot};
+use smol_str::SmolStr;
+pub use core_derive::Reflect;
+
+#[derive(Debug,Clone,Copy,PartialEq, Eq)]
+pub enum ReflectType {
+    Bool,
+    I32,
+    U32,
+    F32,
+    Vec2,
+    Vec3,
+    Vec4,
+    Quat,
+    String
+}
+
+#[derive(Debug,Clone,PartialEq)]
+pub enum ReflectValue {
+    Bool(bool),
+    I32(i32),
+    U32(u32),
+    F32(f32),
+    Vec2(Vec2),
+    Vec3(Vec3),
+    Vec4(Vec4),
+    Quat(Quat),
+    String(SmolStr)
+}
+
+impl ReflectValue {
+    pub fn typ(&self) -> ReflectType {
+        match self {
+            ReflectValue::Bool(_) => ReflectType::Bool,
+            ReflectValue::I32(_) => ReflectType::I32,
+            ReflectValue::U32(_) => ReflectType::U32,
+            ReflectValue::F32(_) => ReflectType::F32,
+            ReflectValue::Vec2(_) => ReflectType::Vec2,
+            ReflectValue::Vec3(_) => ReflectType::Vec3,
+            ReflectValue::Vec4(_) => ReflectType::Vec4,
+            ReflectValue::Quat(_) => ReflectType::Quat,
+            ReflectValue::String(_) => ReflectType::String,
+        }
+    }
+
+    //按模板中的字符串格式解析,向量用逗号分隔,Quat写成YXZ顺序的欧拉角(角度)
+    pub fn parse(typ:ReflectType,s:&str) -> Result<ReflectValue> {
+        let value = match typ {
+            ReflectType::Bool => ReflectValue::Bool(s.trim().parse()?),
+            ReflectType::I32 => ReflectValue::I32(s.trim().parse()?),
+            ReflectType::U32 => ReflectValue::U32(s.trim().parse()?),
+            ReflectType::F32 => ReflectValue::F32(s.trim().parse()?),
+            ReflectType::Vec2 => {
+                let v = parse_floats::<2>(s)?;
+                ReflectValue::Vec2(Vec2::new(v[0], v[1]))
+            },
+            ReflectType::Vec3 => {
+                let v = parse_floats::<3>(s)?;
+                ReflectValue::Vec3(Vec3::new(v[0], v[1], v[2]))
+            },
+            ReflectType::Vec4 => {
+                let v = parse_floats::<4>(s)?;
+                ReflectValue::Vec4(Vec4::new(v[0], v[1], v[2], v[3]))
+            },
+            ReflectType::Quat => {
+                let v = parse_floats::<3>(s)?;
+                ReflectValue::Quat(Quat::from_euler(EulerRot::YXZ, v[1].to_radians(), v[0].to_radians(), v[2].to_radians()))
+            },
+            ReflectType::String => ReflectValue::String(s.into()),
+        };
+        Ok(value)
+    }
+}
+
+impl std::fmt::Display for ReflectValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReflectValue::Bool(v) => write!(f,"{}",v),
+            ReflectValue::I32(v) => write!(f,"{}",v),
+            ReflectValue::U32(v) => write!(f,"{}",v),
+            ReflectValue::F32(v) => write!(f,"{}",v),
+            ReflectValue::Vec2(v) => write!(f,"{},{}",v.x,v.y),
+            ReflectValue::Vec3(v) => write!(f,"{},{},{}",v.x,v.y,v.z),
+            ReflectValue::Vec4(v) => write!(f,"{},{},{},{}",v.x,v.y,v.z,v.w),
+            ReflectValue::Quat(v) => {
+                let (y,x,z) = v.to_euler(EulerRot::YXZ);
+                write!(f,"{},{},{}",x.to_degrees(),y.to_degrees(),z.to_degrees())
+            },
+            ReflectValue::String(v) => write!(f,"{}",v),
+        }
+    }
+}
+
+fn parse_floats<const N:usize>(s:&str) -> Result<[f32;N]> {
+    let mut arr = [0f32;N];
+    let mut iter = s.split(',');
+    for item in arr.iter_mut() {
+        *item = iter.next().ok_or(anyhow!("need {} numbers:{}",N,s))?.trim().parse()?;
+    }
+    Ok(arr)
+}
+
+//可以作为反射字段的类型
+pub trait IReflectValue: Sized {
+    const TYPE:ReflectType;
+    fn to_value(&self) -> ReflectValue;
+    fn from_value(value:ReflectValue) -> Option<Self>;
+}
+
+macro_rules! impl_reflect_value {
+    ($typ:ty,$name:ident) => {
+        impl IReflectValue for $typ {
+            const TYPE:ReflectType = ReflectType::$name;
+            fn to_value(&self) -> ReflectValue { ReflectValue::$name(Clone::clone(self)) }
+            fn from_value(value:ReflectValue) -> Option<Self> {
+                match value {
+                    ReflectValue::$name(v) => Some(v),
+                    _ => None
+                }
+            }
+        }
+    };
+}
+
+impl_reflect_value!(bool,Bool);
+impl_reflect_value!(i32,I32);
+impl_reflect_value!(u32,U32);
+impl_reflect_value!(f32,F32);
+impl_reflect_value!(Vec2,Vec2);
+impl_reflect_value!(Vec3,Vec3);
+impl_reflect_value!(Vec4,Vec4);
+impl_reflect_value!(Quat,Quat);
+impl_reflect_value!(SmolStr,String);
+
+impl IReflectValue for usize {
+    const TYPE:ReflectType = ReflectType::U32;
+    fn to_value(&self) -> ReflectValue { ReflectValue::U32(*self as u32) }
+    fn from_value(value:ReflectValue) -> Option<Self> {
+        match value {
+            ReflectValue::U32(v) => Some(v as usize),
+            _ => None
+        }
+    }
+}
+
+//没有数据的枚举作为反射字段,在模板中写成变体名
+#[macro_export]
+macro_rules! impl_reflect_enum {
+    ($typ:ident,$($variant:ident),+) => {
+        impl $crate::reflect::IReflectValue for $typ {
+            const TYPE:$crate::reflect::ReflectType = $crate::reflect::ReflectType::String;
+            fn to_value(&self) -> $crate::reflect::ReflectValue {
+                let name = match self { $($typ::$variant => stringify!($variant)),+ };
+                $crate::reflect::ReflectValue::String(name.into())
+            }
+            fn from_value(value:$crate::reflect::ReflectValue) -> Option<Self> {
+                match value {
+                    $crate::reflect::ReflectValue::String(name) => match name.as_str() {
+                        $(stringify!($variant) => Some($typ::$variant),)+
+                        _ => None
+                    },
+                    _ => None
+                }
+            }
+        }
+    };
+}
+
+impl IReflectValue for String {
+    const TYPE:ReflectType = ReflectType::String;
+    fn to_value(&self) -> ReflectValue { ReflectValue::String(self.as_str().into()) }
+    fn from_value(value:ReflectValue) -> Option<Self> {
+        match value {
+            ReflectValue::String(v) => Some(v.to_string()),
+            _ => None
+        }
+    }
+}
+
+#[derive(Debug,Clone,Copy)]
+pub struct FieldInfo {
+    pub name:&'static str,
+    pub typ:ReflectType
+}
+
+//由#[derive(Reflect)]生成,按名字读写字段,字段类型需要实现IReflectValue
+pub trait Reflect: Send + Sync + 'static {
+    fn type_name(&self) -> &'static str;
+    fn fields(&self) -> &'static [FieldInfo];
+    fn get_field(&self,name:&str) -> Option<ReflectValue>;
+    fn set_field(&mut self,name:&str,value:ReflectValue) -> Result<()>;
+
+    fn field_info(&self,name:&str) -> Option<&'static FieldInfo> {
+        self.fields().iter().find(|f| f.name == name)
+    }
+
+    fn set_field_str(&mut self,name:&str,value:&str) -> Result<()> {
+        let info = self.field_info(name).ok_or_else(|| not_found_field_error(self.type_name(), name))?;
+        self.set_field(name, ReflectValue::parse(info.typ, value)?)
+    }
+}
+
+pub trait ReflectStatic {
+    const TYPE_NAME:&'static str;
+    const FIELDS:&'static [FieldInfo];
+}
+
+pub fn field_type_error(type_name:&str,field:&str,typ:ReflectType) -> anyhow::Error {
+    anyhow!("{}.{} type error:{:?}",type_name,field,typ)
+}
+
+pub fn not_found_field_error(type_name:&str,field:&str) -> anyhow::Error {
+    anyhow!("{} not found field {}",type_name,field)
+}
+
+#[derive(Clone,Copy)]
+pub struct ReflectComponentInfo {
+    pub fields:&'static [FieldInfo],
+    get:fn(&World,Entity,&str) -> Option<ReflectValue>,
+    set:fn(&mut World,Entity,&str,ReflectValue) -> Result<()>
+}
+
+impl ReflectComponentInfo {
+    pub fn get_field(&self,world:&World,entity:Entity,field:&str) -> Option<ReflectValue> {
+        (self.get)(world,entity,field)
+    }
+
+    pub fn set_field(&self,world:&mut World,entity:Entity,field:&str,value:ReflectValue) -> Result<()> {
+        (self.set)(world,entity,field,value)
+    }
+
+    pub fn set_field_str(&self,world:&mut World,entity:Entity,field:&str,value:&str) -> Result<()> {
+        let info = self.fields.iter().find(|f| f.name == field).ok_or_else(|| anyhow!("not found field {}",field))?;
+        (self.set)(world,entity,field,ReflectValue::parse(info.typ, value)?)
+    }
+}
+
+//注册过的反射组件,通过组件名和字段名读写entity上的组件
+#[derive(Default,Resource)]
+pub struct ReflectRegistry {
+    components:HashMap<SmolStr,ReflectComponentInfo>
+}
+
+impl ReflectRegistry {
+    pub fn register<T:Reflect + ReflectStatic + Component>(&mut self) {
+        let info = ReflectComponentInfo { fields:T::FIELDS,get:get_component_field::<T>,set:set_component_field::<T> };
+        self.components.insert(T::TYPE_NAME.into(), info);
+    }
+
+    //返回的info可以复制出来,在修改World时不需要持有ReflectRegistry
+    pub fn get_info(&self,type_name:&str) -> Option<ReflectComponentInfo> {
+        self.components.get(type_name).copied()
+    }
+}
+
+fn get_component_field<T:Reflect + Component>(world:&World,entity:Entity,field:&str) -> Option<ReflectValue> {
+    world.get::<T>(entity)?.get_field(field)
+}
+
+fn set_component_field<T:Reflect + Component>(world:&mut World,entity:Entity,field:&str,value:ReflectValue) -> Result<()> {
+    match world.get_mut::<T>(entity) {
+        Some(mut component) => component.set_field(field, value),
+        None => bail!("{:?} not found component {}",entity,std::any::type_name::<T>())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_ecs::prelude::{Component, World};
+    use glam::{Vec3, Quat};
+    use smol_str::SmolStr;
+    use super::*;
+
+    #[derive(Reflect,Component,Default)]
+    struct TestComp {
+        speed:f32,
+        #[reflect(name = "isOpen")]
+        is_open:bool,
+        offset:Vec3,
+        rotation:Quat,
+        label:SmolStr,
+        #[reflect(skip)]
+        #[allow(dead_code)]
+        cache:Vec<u8>
+    }
+
+    #[test]
+    fn derive_fields() {
+        let names:Vec<&str> = TestComp::FIELDS.iter().map(|f| f.name).collect();
+        assert_eq!(names, vec!["speed","isOpen","offset","rotation","label"]);
+        assert_eq!(TestComp::TYPE_NAME, "TestComp");
+
+        let mut comp = TestComp::default();
+        comp.set_field_str("speed", " 2.5").unwrap();
+        comp.set_field_str("isOpen", "true").unwrap();
+        comp.set_field_str("offset", "1, 2,3").unwrap();
+        comp.set_field_str("rotation", "0,90,0").unwrap();
+        comp.set_field_str("label", "abc").unwrap();
+        assert_eq!(comp.get_field("speed"), Some(ReflectValue::F32(2.5)));
+        assert_eq!(comp.get_field("isOpen"), Some(ReflectValue::Bool(true)));
+        assert_eq!(comp.get_field("offset"), Some(ReflectValue::Vec3(Vec3::new(1.0, 2.0, 3.0))));
+        assert_eq!(comp.get_field("label").unwrap().to_string(), "abc");
+        assert!(comp.rotation.abs_diff_eq(Quat::from_rotation_y(90f32.to_radians()), 0.0001));
+
+        assert!(comp.get_field("is_open").is_none());
+        assert!(comp.get_field("cache").is_none());
+        assert!(comp.set_field_str("cache", "1").is_err());
+        assert!(comp.set_field_str("speed", "abc").is_err());
+        assert!(comp.set_field_str("offset", "1,2").is_err());
+        assert!(comp.set_field("speed", ReflectValue::Bool(false)).is_err());
+        assert_eq!(comp.speed, 2.5);
+    }
+
+    #[derive(Debug,Clone,Copy,PartialEq,Default)]
+    enum TestMode {
+        #[default]
+        Idle,
+        Run
+    }
+    crate::impl_reflect_enum!(TestMode,Idle,Run);
+
+    #[derive(Reflect,Default)]
+    #[reflect(on_set = "on_changed")]
+    struct TestEnumComp {
+        mode:TestMode,
+        count:usize,
+        #[reflect(skip)]
+        changes:u32
+    }
+
+    impl TestEnumComp {
+        fn on_changed(&mut self) { self.changes += 1; }
+    }
+
+    #[test]
+    fn derive_enum_usize() {
+        let mut comp = TestEnumComp::default();
+        comp.set_field_str("mode", "Run").unwrap();
+        comp.set_field_str("count", "3").unwrap();
+        assert_eq!((comp.mode,comp.count), (TestMode::Run,3));
+        assert_eq!(comp.get_field("mode").unwrap().to_string(), "Run");
+        assert!(comp.set_field_str("mode", "Walk").is_err());
+        assert_eq!(comp.mode, TestMode::Run);
+        assert_eq!(comp.changes, 2);
+    }
+
+    #[test]
+    fn registry_world() {
+        let mut registry = ReflectRegistry::default();
+        registry.register::<TestComp>();
+        let mut world = World::new();
+        let entity = world.spawn(TestComp::default()).id();
+        let info = registry.get_info("TestComp").unwrap();
+        info.set_field_str(&mut world, entity, "speed", "3").unwrap();
+        assert_eq!(info.get_field(&world, entity, "speed"), Some(ReflectValue::F32(3.0)));
+        let empty = world.spawn_empty().id();
+        assert!(info.set_field_str(&mut world, empty, "speed", "3").is_err());
+        assert!(registry.get_info("Other").is_none());
+    }
+}
@@ -8,6 +8,8 @@ pub struct Time {
     last_update: Instant,
     delta: Duration,
     startup: Instant,
+    //设置后每帧使用固定的时间间隔,用于无头运行和回放
+    fixed_delta:Option<Duration>
 }
 
 impl Default for Time {
@@ -18,7 +20,8 @@ impl Default for Time {
             delta: Duration::from_secs(0),
             startup: now,
             delta_seconds: 0f32,
-            frame:0u64
+            frame:0u64,
+            fixed_delta:None
         }
     }
 }
@@ -43,9 +46,17 @@ impl Time {
         self.delta_seconds
     }
 
+    pub fn fixed_delta(&self) -> Option<f32> {
+        self.fixed_delta.map(|v| v.as_secs_f32())
+    }
+
+    pub fn set_fixed_delta(&mut self,delta:Option<f32>) {
+        self.fixed_delta = delta.map(Duration::from_secs_f32);
+    }
+
     pub fn update(&mut self) {
         let now = Instant::now();
-        self.delta = now - self.last_update;
+        self.delta = self.fixed_delta.unwrap_or(now - self.last_update);
         self.delta_seconds = self.delta.as_secs_f32();
         self.frame += 1;
         self.last_update = now;
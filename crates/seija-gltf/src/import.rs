@@ -1,4 +1,6 @@
 use std::path::Path;
+use relative_path::RelativePath;
+use seija_asset::AssetServer;
 use seija_core::{anyhow::{Result,anyhow}};
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub(crate) enum Scheme<'a> {
@@ -38,11 +40,11 @@ impl<'a> Scheme<'a> {
         }
     }
 
-    pub(crate) fn read(uri: &str,base_path:&Path) -> Result<Vec<u8>> {
+    pub(crate) fn read(uri: &str,server:&AssetServer,base_dir:&RelativePath) -> Result<Vec<u8>> {
         match Scheme::parse(uri) {
             Scheme::Data(_, base64) => base64::decode(&base64).map_err(|e|anyhow!(e)),
             Scheme::File(path) => read_to_end(path),
-            Scheme::Relative  => read_to_end(base_path.join(uri)),
+            Scheme::Relative  => server.read_bytes(base_dir.join_normalized(uri).as_str()),
             Scheme::Unsupported => Err(anyhow!(gltf::Error::UnsupportedScheme)),
         }
     }
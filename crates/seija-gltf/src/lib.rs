@@ -1,4 +1,5 @@
 mod import;
+mod process;
 pub mod asset;
 pub mod loader;
 
@@ -8,7 +9,8 @@ pub use gltf;
 
 use loader::GLTFLoader;
 use seija_app::{IModule, App};
-use seija_asset::{Handle, AddAsset};
+use seija_asset::{Handle, AddAsset, AssetServer};
+use process::GltfProcessor;
 
 use seija_render::{material::{Material}, shadow::Shadow};
 use seija_transform::{Transform,events::EntityCommandsEx};
@@ -19,6 +21,10 @@ impl IModule for GLTFModule {
     fn init(&mut self,app:&mut App) {
         app.add_asset::<GltfAsset>();
         app.add_asset_loader::<GltfAsset,GLTFLoader>();
+        if let Some(server) = app.world.get_resource::<AssetServer>() {
+            server.register_processor("gltf", GltfProcessor);
+            server.register_processor("glb", GltfProcessor);
+        }
     }
 }
 
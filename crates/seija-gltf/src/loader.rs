@@ -1,5 +1,5 @@
-use std::{sync::Arc, collections::HashMap, fmt::Debug, path::{Path}};
-use crate::{import::Scheme, asset::GltfAsset};
+use std::{sync::Arc, collections::HashMap, fmt::Debug};
+use crate::{import::Scheme, asset::GltfAsset, process::INTERLEAVED_EXTRAS};
 use bevy_ecs::prelude::World;
 use glam::{Mat4, Vec3, Vec4};
 use gltf::{Document, Node, animation::{Channel, Property, Interpolation}, Gltf};
@@ -15,7 +15,7 @@ use seija_skeleton3d::{
     Skeleton, AnimationSet, Skin, offine::{
         raw_skeleton::{RawSkeleton, RawJoint}, 
         skeleton_builder::SkeletonBuilder, 
-        raw_animation::{ RawAnimation, RawJointTrack, RawTranslationKey, RawScaleKey, RawRotationKey},
+        raw_animation::{ RawAnimation, RawJointTrack, RawTranslationKey, RawScaleKey, RawRotationKey, RawEventKey, parse_event_extras, parse_event_file},
         animation_builder::AnimationBuilder}, Animation
 };
 use seija_transform::{Transform, TransformMatrix};
@@ -30,11 +30,13 @@ impl IAssetLoader for GLTFLoader {
         add_to_asset_type::<GltfAsset>(world, res)
     }
     fn sync_load(&self,w:&mut World,path:&str,server:&AssetServer,_:Option<Box<dyn AssetLoaderParams>>) -> Result<Box<dyn AssetDynamic>> {
-        let full_path = server.full_path(path)?;
-        let bytes = std::fs::read(&full_path)?;
+        let bytes = match server.read_processed(path)? {
+            Some(bytes) => bytes,
+            None => server.read_bytes(path)?
+        };
         let mut gltf_data = Gltf::from_slice(&bytes)?;
-        let full_base_path = full_path.parent().context(1)?;
-        let buffers = import_buffer_data(&mut gltf_data,full_base_path)?;
+        let base_dir = RelativePath::new(path).parent().context(1)?;
+        let buffers = import_buffer_data(&mut gltf_data,server,base_dir)?;
         let textures = sync_load_textures(w,&gltf_data,&buffers,path)?;
 
         let materials = load_materials(&gltf_data,&textures);
@@ -55,7 +57,8 @@ impl IAssetLoader for GLTFLoader {
               }
            }
            
-           let anim_set = load_animations(&gltf_data,&buffers, &take_skeleton)?;
+           let events = server.read_string(&event_file_path(path)).ok().map(|s| read_event_file(path,&s)).unwrap_or_default();
+           let anim_set = load_animations(&gltf_data,&buffers, &take_skeleton,&events)?;
            if let Some(mut assets)  = w.get_resource_mut::<Assets<AnimationSet>>() {
                 let h_anim = assets.add(anim_set);
                 server.set_asset(&format!("{}#animset",path), h_anim.id);
@@ -70,7 +73,7 @@ impl IAssetLoader for GLTFLoader {
         }
         
 
-        
+        add_gltf_dependencies(server,path,&textures,&skeleton,&anims,&skins);
         Ok(Box::new(GltfAsset {
             scenes,
             meshs,
@@ -87,15 +90,15 @@ impl IAssetLoader for GLTFLoader {
                         _:Option<Box<dyn seija_asset::downcast_rs::DowncastSync>>,
                         _:Option<Box<dyn AssetLoaderParams>>) -> Result<Box<dyn AssetDynamic>> {
        
-       let full_path = server.full_path(path.as_str())?;
-      
-      
-       let bytes = smol::fs::read(&full_path).await?;
+       let bytes = match server.read_processed_async(path.as_str()).await? {
+           Some(bytes) => bytes,
+           None => server.read_bytes_async(path.as_str()).await?
+       };
        
        let mut gltf_data = Gltf::from_slice(&bytes)?;
       
-       let full_base_path = full_path.parent().context(1)?;
-       let buffers = import_buffer_data(&mut gltf_data,full_base_path)?;
+       let base_dir = RelativePath::new(path.as_str()).parent().context(1)?;
+       let buffers = import_buffer_data(&mut gltf_data,&server,base_dir)?;
        
        let mut track_textures = vec![];
        let textures = load_textures(&server, path.as_str(),&gltf_data, &buffers,&mut track_textures).await?;
@@ -116,7 +119,8 @@ impl IAssetLoader for GLTFLoader {
        if let Some(skeleton) = _skeleton.as_ref() {
            skins = load_skin(&gltf_data, &buffers,&skeleton).map(|v| server.create_asset(v,&format!("{}#skin",path)));
            
-           let anim_set = load_animations(&gltf_data,&buffers, &skeleton)?;
+           let events = server.read_string_async(&event_file_path(path.as_str())).await.ok().map(|s| read_event_file(path.as_str(),&s)).unwrap_or_default();
+           let anim_set = load_animations(&gltf_data,&buffers, &skeleton,&events)?;
           
            anims = Some(server.create_asset(anim_set,&format!("{}#animset",path)));
        }
@@ -125,7 +129,7 @@ impl IAssetLoader for GLTFLoader {
        for track in track_textures.drain(..) {
           let _ = track.wait_id().await;
        }
-      
+       add_gltf_dependencies(&server,path.as_str(),&textures,&skeleton,&anims,&skins);
        Ok(Box::new(GltfAsset {
         scenes,
         meshs,
@@ -139,12 +143,12 @@ impl IAssetLoader for GLTFLoader {
     }
 }
 
-fn import_buffer_data(data:&mut gltf::Gltf,full_base_path:&Path) -> Result<Vec<gltf::buffer::Data>> {
+fn import_buffer_data(data:&mut gltf::Gltf,server:&AssetServer,base_dir:&RelativePath) -> Result<Vec<gltf::buffer::Data>> {
     let mut buffers:Vec<gltf::buffer::Data> = Vec::new();
     for buffer in data.document.buffers() {
         let mut bytes = match buffer.source() {
             gltf::buffer::Source::Bin => {  data.blob.take().ok_or(gltf::Error::MissingBlob)? },
-            gltf::buffer::Source::Uri(uri) => { crate::import::Scheme::read(uri,full_base_path)? }
+            gltf::buffer::Source::Uri(uri) => { crate::import::Scheme::read(uri,server,base_dir)? }
         };
         if bytes.len() < buffer.length() {
             bail!(gltf::Error::BufferLength {
@@ -162,6 +166,16 @@ fn import_buffer_data(data:&mut gltf::Gltf,full_base_path:&Path) -> Result<Vec<g
     Ok(buffers)
 }
 
+fn add_gltf_dependencies(server:&AssetServer,path:&str,textures:&Vec<Handle<Texture>>,
+                         skeleton:&Option<Handle<Skeleton>>,anims:&Option<Handle<AnimationSet>>,skins:&Option<Handle<Skin>>) {
+    for h_texture in textures.iter() {
+        server.add_dependency(path, h_texture.id);
+    }
+    if let Some(h) = skeleton { server.add_dependency(path, h.id); }
+    if let Some(h) = anims { server.add_dependency(path, h.id); }
+    if let Some(h) = skins { server.add_dependency(path, h.id); }
+}
+
 fn sync_load_textures(world:&mut World,gltf_data:&gltf::Gltf,buffers:&Vec<gltf::buffer::Data>,path:&str) -> Result<Vec<Handle<Texture>>> {
     let mut textures:Vec<Handle<Texture>> = vec![];
     for (index,json_texture) in gltf_data.textures().enumerate() {
@@ -327,52 +341,10 @@ fn load_meshs(path:&str,server:&AssetServer,gltf:&gltf::Gltf,buffers:&Vec<gltf::
         let mut primitives:Vec<GltfPrimitive> = vec![];
         for (primitive_index,primitive)  in mesh.primitives().enumerate() {
             
-            //dbg!(&primitive);
-            let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
-            let primitive_topology = get_primitive_topology(primitive.mode())?;
-            let mut mesh = Mesh::new(primitive_topology);
-            if let Some(verts) = reader.read_positions().map(|iter| VertexAttributeValues::Float3(iter.collect())) {
-                mesh.set(MeshAttributeType::POSITION, verts);
-            }
-            if let Some(normals) = reader.read_normals().map(|iter| VertexAttributeValues::Float3(iter.collect())) {
-                mesh.set(MeshAttributeType::NORMAL, normals);
-            }
-
-            if let Some(uvs) = reader.read_tex_coords(0).map(|iter| VertexAttributeValues::Float2(iter.into_f32().collect())) {
-                mesh.set(MeshAttributeType::UV0, uvs);
-            }
-
-            if let Some(uvs2) = reader.read_tex_coords(1).map(|iter| VertexAttributeValues::Float2(iter.into_f32().collect())) {
-                mesh.set(MeshAttributeType::UV1, uvs2);
-            }
-
-            if let Some(tangents) = reader.read_tangents().map(|iter| VertexAttributeValues::Float4(iter.collect())) {
-                mesh.set(MeshAttributeType::TANGENT, tangents);
-            }
-
-            if let Some(colors) = reader.read_colors(0).map(|iter| VertexAttributeValues::Float4(iter.into_rgba_f32().collect())) {
-                mesh.set(MeshAttributeType::COLOR, colors);
-            }
-            
-            if let Some(joint0) = reader.read_joints(0).map(|iter|VertexAttributeValues::UInt16X4(iter.into_u16().collect())) {
-                mesh.set(MeshAttributeType::JOINTS, joint0);
-            }
-
-            if let Some(weights) = reader.read_weights(0).map(|iter|VertexAttributeValues::Float4(iter.into_f32().collect())) {
-                mesh.set(MeshAttributeType::WEIGHTS, weights);
-            }
-
-
-            if let Some(indices) = reader.read_indices() {
-                mesh.set_indices(Some(Indices::U32(indices.into_u32().collect())));
+            let mesh = match read_interleaved_mesh(gltf,&primitive,buffers) {
+                Some(mesh) => mesh,
+                None => read_primitive_mesh(&primitive,|buffer| Some(&buffers[buffer.index()]))?
             };
-
-            let bounding_box =  primitive.bounding_box();
-            let aabb = AABB3::new(Vec3::from(bounding_box.min), Vec3::from(bounding_box.max));
-          
-            mesh.aabb = Some(aabb);
-
-            mesh.build();
             let mesh_path =format!("{}#mesh.{}.{}",path,mesh_index,primitive_index);
             //log::error!("create:{}",mesh_path.as_str());
             let mesh_handle = server.create_asset(mesh,&mesh_path);
@@ -387,6 +359,90 @@ fn load_meshs(path:&str,server:&AssetServer,gltf:&gltf::Gltf,buffers:&Vec<gltf::
     Ok(meshs)
 }
 
+//按glTF的顶点属性构建Mesh,预处理器也用它生成交错好的顶点数据
+pub(crate) fn read_primitive_mesh<'a,'s,F>(primitive:&'a gltf::Primitive<'a>,get_buffer:F) -> Result<Mesh>
+    where F:Clone + Fn(gltf::Buffer<'a>) -> Option<&'s [u8]> {
+    let reader = primitive.reader(get_buffer);
+    let primitive_topology = get_primitive_topology(primitive.mode())?;
+    let mut mesh = Mesh::new(primitive_topology);
+    if let Some(verts) = reader.read_positions().map(|iter| VertexAttributeValues::Float3(iter.collect())) {
+        mesh.set(MeshAttributeType::POSITION, verts);
+    }
+    if let Some(normals) = reader.read_normals().map(|iter| VertexAttributeValues::Float3(iter.collect())) {
+        mesh.set(MeshAttributeType::NORMAL, normals);
+    }
+
+    if let Some(uvs) = reader.read_tex_coords(0).map(|iter| VertexAttributeValues::Float2(iter.into_f32().collect())) {
+        mesh.set(MeshAttributeType::UV0, uvs);
+    }
+
+    if let Some(uvs2) = reader.read_tex_coords(1).map(|iter| VertexAttributeValues::Float2(iter.into_f32().collect())) {
+        mesh.set(MeshAttributeType::UV1, uvs2);
+    }
+
+    if let Some(tangents) = reader.read_tangents().map(|iter| VertexAttributeValues::Float4(iter.collect())) {
+        mesh.set(MeshAttributeType::TANGENT, tangents);
+    }
+
+    if let Some(colors) = reader.read_colors(0).map(|iter| VertexAttributeValues::Float4(iter.into_rgba_f32().collect())) {
+        mesh.set(MeshAttributeType::COLOR, colors);
+    }
+    
+    if let Some(joint0) = reader.read_joints(0).map(|iter|VertexAttributeValues::UInt16X4(iter.into_u16().collect())) {
+        mesh.set(MeshAttributeType::JOINTS, joint0);
+    }
+
+    if let Some(weights) = reader.read_weights(0).map(|iter|VertexAttributeValues::Float4(iter.into_f32().collect())) {
+        mesh.set(MeshAttributeType::WEIGHTS, weights);
+    }
+
+
+    if let Some(indices) = reader.read_indices() {
+        mesh.set_indices(Some(Indices::U32(indices.into_u32().collect())));
+    };
+
+    let bounding_box =  primitive.bounding_box();
+    mesh.aabb = Some(AABB3::new(Vec3::from(bounding_box.min), Vec3::from(bounding_box.max)));
+    mesh.build();
+    Ok(mesh)
+}
+
+//和read_primitive_mesh写入的属性格式一致
+pub(crate) fn attr_format(typ:MeshAttributeType) -> Option<wgpu::VertexFormat> {
+    match typ {
+        MeshAttributeType::POSITION | MeshAttributeType::NORMAL => Some(wgpu::VertexFormat::Float32x3),
+        MeshAttributeType::UV0 | MeshAttributeType::UV1 => Some(wgpu::VertexFormat::Float32x2),
+        MeshAttributeType::TANGENT | MeshAttributeType::COLOR | MeshAttributeType::WEIGHTS => Some(wgpu::VertexFormat::Float32x4),
+        MeshAttributeType::JOINTS => Some(wgpu::VertexFormat::Uint16x4),
+        _ => None
+    }
+}
+
+//预处理器写在primitive extras里的交错顶点数据,没有或者数据不对时返回None按原始属性读取
+fn read_interleaved_mesh(gltf:&gltf::Gltf,primitive:&gltf::Primitive,buffers:&Vec<gltf::buffer::Data>) -> Option<Mesh> {
+    let extras:serde_json::Value = serde_json::from_str(primitive.extras().as_ref()?.get()).ok()?;
+    let info = extras.get(INTERLEAVED_EXTRAS)?;
+    let view_bytes = |key:&str| -> Option<&[u8]> {
+        let view = gltf.views().nth(info.get(key)?.as_u64()? as usize)?;
+        buffers.get(view.buffer().index())?.get(view.offset()..view.offset() + view.length())
+    };
+    let mut layout = vec![];
+    for bits in info.get("attrs")?.as_array()? {
+        let typ = MeshAttributeType::from_bits(bits.as_u64()? as usize)?;
+        layout.push((typ,attr_format(typ)?));
+    }
+    let topology = get_primitive_topology(primitive.mode()).ok()?;
+    let mut mesh = Mesh::from_interleaved(topology, layout, view_bytes("vertexView")?.to_vec())?;
+    if info.get("indexView").is_some() {
+        let indices = view_bytes("indexView")?.chunks_exact(4).map(|b| u32::from_le_bytes([b[0],b[1],b[2],b[3]])).collect();
+        mesh.set_indices(Some(Indices::U32(indices)));
+    }
+    let bounding_box = primitive.bounding_box();
+    mesh.aabb = Some(AABB3::new(Vec3::from(bounding_box.min), Vec3::from(bounding_box.max)));
+    mesh.build();
+    Some(mesh)
+}
+
 fn load_nodes(gltf:&gltf::Gltf) -> Result<Vec<GltfNode>> {
     let mut nodes:Vec<GltfNode> = vec![];
     for node in gltf.nodes() {
@@ -660,16 +716,34 @@ pub fn load_skin(gltf:&gltf::Gltf,buffers:&Vec<gltf::buffer::Data>,skeleton:&Ske
     Some(Skin::new(mat4s))
 }
 
-pub fn load_animations(data:&gltf::Gltf,buffers:&Vec<gltf::buffer::Data>,skeleton:&Skeleton) -> Result<AnimationSet> {
+//动画事件的外部配置文件,和gltf文件同名,例如hero.gltf对应hero.events.json
+fn event_file_path(path:&str) -> String {
+    RelativePath::new(path).with_extension("events.json").to_string()
+}
+
+fn read_event_file(path:&str,json:&str) -> HashMap<String,Vec<RawEventKey>> {
+    parse_event_file(json).unwrap_or_else(|err| {
+        log::error!("load animation events {} error:{:?}",path,err);
+        HashMap::default()
+    })
+}
+
+pub fn load_animations(data:&gltf::Gltf,buffers:&Vec<gltf::buffer::Data>,skeleton:&Skeleton,events:&HashMap<String,Vec<RawEventKey>>) -> Result<AnimationSet> {
     let mut anim_set = AnimationSet::default();
     for gltf_anim in data.animations() {
-       let animation = import_animation(buffers,&gltf_anim, skeleton)?;
+       let animation = import_animation(buffers,&gltf_anim, skeleton,events)?;
        anim_set.add(animation);
     }
     Ok(anim_set)
 }
 
-fn import_animation(buffers:&Vec<gltf::buffer::Data>,animation:&gltf::Animation,skeleton:&Skeleton) -> Result<Animation> {
+fn import_animation(buffers:&Vec<gltf::buffer::Data>,animation:&gltf::Animation,skeleton:&Skeleton,events:&HashMap<String,Vec<RawEventKey>>) -> Result<Animation> {
+    let raw_animation = import_raw_animation(buffers, animation, skeleton, events)?;
+    Ok(AnimationBuilder::build(&raw_animation))
+}
+
+//按骨骼的关节顺序采样动画轨道,导出工具在这之后做关键帧精简
+pub fn import_raw_animation(buffers:&Vec<gltf::buffer::Data>,animation:&gltf::Animation,skeleton:&Skeleton,events:&HashMap<String,Vec<RawEventKey>>) -> Result<RawAnimation> {
     let mut raw_animation = RawAnimation::default();
     raw_animation.name = animation.name().unwrap_or("none").to_string();
     raw_animation.duration = 0f32;
@@ -709,8 +783,17 @@ fn import_animation(buffers:&Vec<gltf::buffer::Data>,animation:&gltf::Animation,
         }
         raw_animation.tracks.push(new_track);
     }
-    let animation = AnimationBuilder::build(&raw_animation);
-    Ok(animation)
+    //事件可以写在glTF动画的extras里,也可以写在外部配置文件里
+    if let Some(extras) = animation.extras() {
+        match parse_event_extras(extras.get()) {
+            Ok(keys) => raw_animation.events.extend(keys),
+            Err(err) => log::warn!("animation {} extras error:{:?}",raw_animation.name,err)
+        }
+    }
+    if let Some(keys) = events.get(&raw_animation.name) {
+        raw_animation.events.extend(keys.iter().cloned());
+    }
+    Ok(raw_animation)
 }
 
 fn sample_animation_channel(buffers:&Vec<gltf::buffer::Data>,duration:&mut f32,channel:&Channel,track:&mut RawJointTrack,rate:f32) -> Result<()> {
@@ -790,4 +873,53 @@ fn sample_line_channel<T,E:Clone>(output:&[u8],len:usize,timestamps:&[f32],keys:
     for index in 0..key_values.len() {
         keys.push(f(timestamps[index],key_values[index].clone()));
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use seija_skeleton3d::Skeleton;
+    use seija_transform::TransformMatrix;
+    use super::{event_file_path, read_event_file, load_animations};
+
+    //一个关节,Run动画2秒,extras里有一个事件
+    fn anim_gltf() -> (gltf::Gltf,Vec<gltf::buffer::Data>) {
+        let mut bin:Vec<u8> = vec![];
+        for f in [0f32,2f32, 0f32,0f32,0f32, 1f32,0f32,0f32] { bin.extend_from_slice(&f.to_le_bytes()); }
+        let json = serde_json::json!({
+            "asset":{"version":"2.0"},
+            "nodes":[{"name":"root"}],
+            "buffers":[{"byteLength":bin.len(),"uri":format!("data:application/octet-stream;base64,{}",base64::encode(&bin))}],
+            "bufferViews":[{"buffer":0,"byteOffset":0,"byteLength":8,"byteStride":4},{"buffer":0,"byteOffset":8,"byteLength":24,"byteStride":12}],
+            "accessors":[{"bufferView":0,"componentType":5126,"count":2,"type":"SCALAR","min":[0.0],"max":[2.0]},
+                         {"bufferView":1,"componentType":5126,"count":2,"type":"VEC3"}],
+            "animations":[{"name":"Run","channels":[{"sampler":0,"target":{"node":0,"path":"translation"}}],
+                           "samplers":[{"input":0,"output":1}],
+                           "extras":{"events":[{"time":0.5,"name":"footstep"}]}}]
+        });
+        let data = gltf::Gltf::from_slice(json.to_string().as_bytes()).unwrap();
+        let buffers = gltf::import_buffers(&data, None, None).unwrap();
+        (data,buffers)
+    }
+
+    #[test]
+    fn animation_events() {
+        let (data,buffers) = anim_gltf();
+        let mut skeleton = Skeleton::new(1);
+        skeleton.joint_parents = vec![None];
+        skeleton.joint_rest_poses = vec![TransformMatrix::default()];
+        skeleton.joint_names = vec![Some("root".into())];
+
+        assert_eq!(event_file_path("models/hero.gltf"), "models/hero.events.json");
+        let events = read_event_file("models/hero.events.json", r#"{"Run":[{"time":1.5,"name":"land"}],"Idle":[{"time":0.1,"name":"blink"}]}"#);
+        let anim_set = load_animations(&data, &buffers, &skeleton, &events).unwrap();
+        let anim = anim_set.get_index(0).unwrap();
+        let names:Vec<(&str,f32)> = anim.events().iter().map(|e| (e.name.as_str(),e.ratio)).collect();
+        //extras和外部文件的事件合并后按ratio排序
+        assert_eq!(names, vec![("footstep",0.25f32),("land",0.75f32)]);
+
+        //外部文件格式错误时只使用extras里的事件
+        let events = read_event_file("models/hero.events.json", "[1,2]");
+        assert!(events.is_empty());
+        let anim_set = load_animations(&data, &buffers, &skeleton, &events).unwrap();
+        assert_eq!(anim_set.get_index(0).unwrap().events().len(), 1);
+    }
+}
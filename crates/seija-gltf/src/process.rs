@@ -0,0 +1,150 @@
+use seija_asset::IAssetProcessor;
+use seija_core::anyhow::{Result, anyhow};
+use serde_json::{Value, json};
+use crate::{import::Scheme, loader::read_primitive_mesh};
+
+const GLB_MAGIC:[u8;4] = *b"glTF";
+const GLB_VERSION:u32 = 2;
+const CHUNK_JSON:u32 = 0x4E4F534A;
+const CHUNK_BIN:u32 = 0x004E4942;
+pub(crate) const INTERLEAVED_EXTRAS:&str = "seija_interleaved";
+
+//把.gltf中base64编码的buffer解码合并成glb,运行时不需要再解码base64
+//BIN里的primitive预先生成交错好的顶点数据,Loader直接作为顶点buffer使用
+//引用外部文件的buffer和图片保持不变,仍然按原路径加载
+#[derive(Default)]
+pub(crate) struct GltfProcessor;
+
+impl IAssetProcessor for GltfProcessor {
+    fn version(&self) -> u32 { 2 }
+
+    fn process(&self,path:&str,bytes:&[u8]) -> Result<Vec<u8>> {
+        let (mut root,mut bin) = if bytes.starts_with(&GLB_MAGIC) {
+            let glb = gltf::Glb::from_slice(bytes)?;
+            let root:Value = serde_json::from_slice(&glb.json).map_err(|err| anyhow!("gltf json error {}:{}",path,err))?;
+            (root,glb.bin.map(|bin| bin.into_owned()).unwrap_or_default())
+        } else {
+            let mut root:Value = serde_json::from_slice(bytes).map_err(|err| anyhow!("gltf json error {}:{}",path,err))?;
+            let bin = embed_data_buffers(&mut root)?;
+            (root,bin)
+        };
+        interleave_meshes(&mut root, &mut bin)?;
+        Ok(write_glb(&serde_json::to_vec(&root)?, &bin))
+    }
+}
+
+//所有属性和索引都在BIN里的primitive,把交错顶点和u32索引追加到BIN,bufferView序号写在primitive的extras里
+//原来的accessor保留,皮肤和动画以及没有缓存时的加载仍然使用它们
+fn interleave_meshes(root:&mut Value,bin:&mut Vec<u8>) -> Result<()> {
+    if bin.is_empty() {
+        return Ok(());
+    }
+    let document = gltf::Document::from_json(serde_json::from_value(root.clone())?)?;
+    if !matches!(document.buffers().next().map(|buffer| buffer.source()),Some(gltf::buffer::Source::Bin)) {
+        return Ok(());
+    }
+    let mut baked = vec![];
+    for mesh in document.meshes() {
+        for primitive in mesh.primitives() {
+            let in_bin = primitive.attributes().all(|(_,accessor)| accessor_in_bin(&accessor))
+                         && primitive.indices().map(|accessor| accessor_in_bin(&accessor)).unwrap_or(true);
+            if !in_bin { continue; }
+            let data:&[u8] = bin;
+            let mesh_data = match read_primitive_mesh(&primitive, |_| Some(data)) {
+                Ok(mesh_data) => mesh_data,
+                Err(_) => continue
+            };
+            let attrs:Vec<usize> = mesh_data.mesh_attr_types().iter().map(|typ| typ.bits()).collect();
+            baked.push((mesh.index(),primitive.index(),attrs,mesh_data.get_vertex_buffer_data(),mesh_data.get_index_buffer_bytes()));
+        }
+    }
+    for (mesh_index,primitive_index,attrs,verts,indices) in baked {
+        let extras = root["meshes"][mesh_index]["primitives"][primitive_index].get("extras");
+        if extras.map(|extras| !extras.is_object()).unwrap_or(false) {
+            continue;
+        }
+        let mut info = json!({ "vertexView":push_view(root, bin, &verts),"attrs":attrs });
+        if let Some(indices) = indices {
+            info["indexView"] = json!(push_view(root, bin, &indices));
+        }
+        root["meshes"][mesh_index]["primitives"][primitive_index]["extras"][INTERLEAVED_EXTRAS] = info;
+    }
+    root["buffers"][0]["byteLength"] = json!(bin.len());
+    Ok(())
+}
+
+fn accessor_in_bin(accessor:&gltf::Accessor) -> bool {
+    accessor.sparse().is_none() && accessor.view().map(|view| matches!(view.buffer().source(),gltf::buffer::Source::Bin)).unwrap_or(false)
+}
+
+//数据4字节对齐追加到BIN,返回新bufferView的序号
+fn push_view(root:&mut Value,bin:&mut Vec<u8>,bytes:&[u8]) -> usize {
+    while bin.len() % 4 != 0 { bin.push(0); }
+    let view = json!({ "buffer":0,"byteOffset":bin.len(),"byteLength":bytes.len() });
+    bin.extend_from_slice(bytes);
+    if !root["bufferViews"].is_array() {
+        root["bufferViews"] = json!([]);
+    }
+    let views = root["bufferViews"].as_array_mut().unwrap();
+    views.push(view);
+    views.len() - 1
+}
+
+//data uri的buffer合并为第0个buffer,bufferView的buffer和byteOffset重新映射
+fn embed_data_buffers(root:&mut Value) -> Result<Vec<u8>> {
+    let mut bin:Vec<u8> = vec![];
+    let mut new_buffers:Vec<Value> = vec![Value::Null];
+    let mut remap:Vec<(usize,u64)> = vec![];
+    for buffer in root.get("buffers").and_then(Value::as_array).cloned().unwrap_or_default() {
+        let data = match buffer.get("uri").and_then(Value::as_str).map(Scheme::parse) {
+            Some(Scheme::Data(_, base64)) => Some(base64::decode(base64)?),
+            _ => None
+        };
+        if let Some(data) = data {
+            let offset = bin.len() as u64;
+            bin.extend_from_slice(&data);
+            while bin.len() % 4 != 0 { bin.push(0); }
+            remap.push((0,offset));
+        } else {
+            remap.push((new_buffers.len(),0));
+            new_buffers.push(buffer);
+        }
+    }
+    if bin.is_empty() {
+        return Ok(bin);
+    }
+    new_buffers[0] = json!({ "byteLength":bin.len() });
+    root["buffers"] = Value::Array(new_buffers);
+    if let Some(views) = root.get_mut("bufferViews").and_then(Value::as_array_mut) {
+        for view in views.iter_mut() {
+            let index = view.get("buffer").and_then(Value::as_u64).ok_or(anyhow!("gltf bufferView without buffer"))? as usize;
+            let (new_index,offset) = *remap.get(index).ok_or(anyhow!("gltf bufferView buffer index error:{}",index))?;
+            let view_offset = view.get("byteOffset").and_then(Value::as_u64).unwrap_or(0);
+            view["buffer"] = json!(new_index);
+            view["byteOffset"] = json!(view_offset + offset);
+        }
+    }
+    Ok(bin)
+}
+
+//glb: header(magic version length) + JSON chunk + 可选的BIN chunk,chunk长度4字节对齐
+fn write_glb(json:&[u8],bin:&[u8]) -> Vec<u8> {
+    let json_len = (json.len() + 3) & !3;
+    let bin_len = (bin.len() + 3) & !3;
+    let total = 12 + 8 + json_len + if bin.is_empty() { 0 } else { 8 + bin_len };
+    let mut bytes = Vec::with_capacity(total);
+    bytes.extend_from_slice(&GLB_MAGIC);
+    bytes.extend_from_slice(&GLB_VERSION.to_le_bytes());
+    bytes.extend_from_slice(&(total as u32).to_le_bytes());
+    bytes.extend_from_slice(&(json_len as u32).to_le_bytes());
+    bytes.extend_from_slice(&CHUNK_JSON.to_le_bytes());
+    bytes.extend_from_slice(json);
+    bytes.resize(20 + json_len, b' ');
+    if !bin.is_empty() {
+        bytes.extend_from_slice(&(bin_len as u32).to_le_bytes());
+        bytes.extend_from_slice(&CHUNK_BIN.to_le_bytes());
+        bytes.extend_from_slice(bin);
+        bytes.resize(total, 0);
+    }
+    bytes
+}
@@ -0,0 +1,703 @@
+use std::collections::HashMap;
+use bevy_ecs::system::Resource;
+use glam::Vec2;
+use quick_xml::events::{BytesStart, Event};
+use seija_core::anyhow::{Result, anyhow, bail};
+use crate::{Input, keycode::KeyCode, event::{MouseButton, GamepadButton, GamepadAxis}};
+
+//可以绑定到动作上的按钮输入
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum InputSource {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    WheelUp,
+    WheelDown,
+    //任意一个手柄的按钮
+    Gamepad(GamepadButton)
+}
+
+impl InputSource {
+    //配置文件里的名字:键盘用KeyCode的名字,鼠标是MouseLeft/MouseRight/MouseMiddle/Mouse{n},手柄是Pad加GamepadButton的名字
+    pub fn from_name(name:&str) -> Option<InputSource> {
+        match name {
+            "MouseLeft" => Some(InputSource::Mouse(MouseButton::Left)),
+            "MouseRight" => Some(InputSource::Mouse(MouseButton::Right)),
+            "MouseMiddle" => Some(InputSource::Mouse(MouseButton::Middle)),
+            "WheelUp" => Some(InputSource::WheelUp),
+            "WheelDown" => Some(InputSource::WheelDown),
+            _ => {
+                if let Some(n) = name.strip_prefix("Mouse").and_then(|n| n.parse::<u32>().ok()) {
+                    return Some(InputSource::Mouse(MouseButton::Other(n)));
+                }
+                if let Some(pad_name) = name.strip_prefix("Pad") {
+                    return GamepadButton::ALL.iter().find(|b| format!("{:?}",b) == pad_name).map(|b| InputSource::Gamepad(*b));
+                }
+                KeyCode::from_name(name).map(InputSource::Key)
+            }
+        }
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            InputSource::Key(key) => format!("{:?}",key),
+            InputSource::Mouse(MouseButton::Left) => "MouseLeft".into(),
+            InputSource::Mouse(MouseButton::Right) => "MouseRight".into(),
+            InputSource::Mouse(MouseButton::Middle) => "MouseMiddle".into(),
+            InputSource::Mouse(MouseButton::Other(n)) => format!("Mouse{}",n),
+            InputSource::WheelUp => "WheelUp".into(),
+            InputSource::WheelDown => "WheelDown".into(),
+            InputSource::Gamepad(button) => format!("Pad{:?}",button)
+        }
+    }
+
+    fn is_pressed(&self,input:&Input) -> bool {
+        match self {
+            InputSource::Key(key) => input.get_key(*key),
+            InputSource::Mouse(btn) => input.get_mouse(*btn),
+            InputSource::Gamepad(button) => input.any_gamepad_button(*button),
+            _ => self.is_down(input)
+        }
+    }
+
+    fn is_down(&self,input:&Input) -> bool {
+        match self {
+            InputSource::Key(key) => input.get_key_down(*key),
+            InputSource::Mouse(btn) => input.get_mouse_down(*btn),
+            InputSource::WheelUp => input.get_mouse_wheel().map(|v| v.y > 0f32).unwrap_or(false),
+            InputSource::WheelDown => input.get_mouse_wheel().map(|v| v.y < 0f32).unwrap_or(false),
+            InputSource::Gamepad(button) => input.any_gamepad_button_down(*button)
+        }
+    }
+}
+
+//buttons全部按下时触发,多个时是组合键,按下顺序不限
+//modifiers需要在buttons按下之前就按住,比如Ctrl+S
+//exact为true时不能按住绑定以外的修饰键,这样S不会在按Ctrl+S时触发
+#[derive(Debug,Clone,PartialEq,Default)]
+pub struct ButtonBinding {
+    pub buttons:Vec<InputSource>,
+    pub modifiers:Vec<KeyCode>,
+    pub exact:bool
+}
+
+const MODIFIER_KEYS:[KeyCode;8] = [KeyCode::LControl,KeyCode::RControl,KeyCode::LShift,KeyCode::RShift,
+                                   KeyCode::LAlt,KeyCode::RAlt,KeyCode::LWin,KeyCode::RWin];
+
+impl ButtonBinding {
+    pub fn new(source:InputSource) -> Self {
+        ButtonBinding { buttons:vec![source],modifiers:vec![],exact:false }
+    }
+
+    pub fn with_modifier(mut self,key:KeyCode) -> Self {
+        self.modifiers.push(key);
+        self
+    }
+
+    pub fn with_exact(mut self,exact:bool) -> Self {
+        self.exact = exact;
+        self
+    }
+
+    fn is_held(&self,input:&Input) -> bool {
+        !self.buttons.is_empty() && self.modifiers.iter().all(|k| input.get_key(*k)) && self.buttons.iter().all(|b| b.is_pressed(input))
+        && (!self.exact || !self.has_extra_modifier(input))
+    }
+
+    fn has_extra_modifier(&self,input:&Input) -> bool {
+        MODIFIER_KEYS.iter().any(|k| input.get_key(*k) && !self.modifiers.contains(k) && !self.buttons.contains(&InputSource::Key(*k)))
+    }
+}
+
+#[derive(Debug,Clone,PartialEq)]
+pub enum AxisBinding {
+    //两个按钮组成的轴,按住positive为1,按住negative为-1
+    Buttons { negative:InputSource,positive:InputSource },
+    //鼠标本帧的移动距离,y轴向上为正
+    MouseX,
+    MouseY,
+    WheelX,
+    WheelY,
+    Gamepad(GamepadAxis)
+}
+
+impl AxisBinding {
+    //配置文件里的属性,和read_axis_binding对应
+    fn xml_attrs(&self) -> String {
+        match self {
+            AxisBinding::Buttons { negative, positive } => format!("negative=\"{}\" positive=\"{}\"",negative.name(),positive.name()),
+            AxisBinding::MouseX => "source=\"MouseX\"".into(),
+            AxisBinding::MouseY => "source=\"MouseY\"".into(),
+            AxisBinding::WheelX => "source=\"WheelX\"".into(),
+            AxisBinding::WheelY => "source=\"WheelY\"".into(),
+            AxisBinding::Gamepad(axis) => format!("source=\"Pad{:?}\"",axis)
+        }
+    }
+
+    fn value(&self,input:&Input) -> f32 {
+        match self {
+            AxisBinding::Buttons { negative, positive } => {
+                let mut value = 0f32;
+                if positive.is_pressed(input) { value += 1f32; }
+                if negative.is_pressed(input) { value -= 1f32; }
+                value
+            },
+            AxisBinding::MouseX => input.mouse_move_delta.x,
+            AxisBinding::MouseY => input.mouse_move_delta.y,
+            AxisBinding::WheelX => input.get_mouse_wheel().map(|v| v.x).unwrap_or(0f32),
+            AxisBinding::WheelY => input.get_mouse_wheel().map(|v| v.y).unwrap_or(0f32),
+            AxisBinding::Gamepad(axis) => input.any_gamepad_axis(*axis)
+        }
+    }
+}
+
+#[derive(Debug,Default)]
+pub struct ButtonAction {
+    pub bindings:Vec<ButtonBinding>,
+    actives:Vec<bool>,
+    is_pressed:bool,
+    is_just_pressed:bool,
+    is_just_released:bool
+}
+
+impl ButtonAction {
+    pub fn new(bindings:Vec<ButtonBinding>) -> Self {
+        ButtonAction { bindings,..Default::default() }
+    }
+
+    fn update(&mut self,input:&Input) {
+        self.actives.resize(self.bindings.len(), false);
+        for (binding,active) in self.bindings.iter().zip(self.actives.iter_mut()) {
+            if !binding.is_held(input) {
+                *active = false;
+            } else if !*active {
+                //只有本帧按下了buttons中的一个才开始触发,先按住按钮再按修饰键不算
+                *active = binding.buttons.iter().any(|b| b.is_down(input));
+            }
+        }
+        let is_pressed = self.actives.iter().any(|v| *v);
+        self.is_just_pressed = is_pressed && !self.is_pressed;
+        self.is_just_released = !is_pressed && self.is_pressed;
+        self.is_pressed = is_pressed;
+    }
+}
+
+//多个绑定时取绝对值最大的那个,|value|小于deadzone时为0,[-1,1]范围内的值去掉死区后重新映射到[0,1]
+#[derive(Debug,Clone)]
+pub struct AxisAction {
+    pub bindings:Vec<AxisBinding>,
+    pub deadzone:f32,
+    pub scale:f32,
+    value:f32
+}
+
+impl AxisAction {
+    pub fn new(bindings:Vec<AxisBinding>) -> Self {
+        AxisAction { bindings,deadzone:0f32,scale:1f32,value:0f32 }
+    }
+
+    fn update(&mut self,input:&Input) {
+        let raw = self.bindings.iter().map(|b| b.value(input)).fold(0f32, |a,b| if b.abs() > a.abs() { b } else { a });
+        self.value = apply_deadzone(raw, self.deadzone) * self.scale;
+    }
+}
+
+//normalize时长度限制在1以内,避免按键斜向移动比单向更快
+#[derive(Debug,Clone)]
+pub struct Axis2DAction {
+    pub x:AxisAction,
+    pub y:AxisAction,
+    pub deadzone:f32,
+    pub scale:f32,
+    pub normalize:bool,
+    value:Vec2
+}
+
+impl Axis2DAction {
+    pub fn new(x:Vec<AxisBinding>,y:Vec<AxisBinding>) -> Self {
+        Axis2DAction { x:AxisAction::new(x),y:AxisAction::new(y),deadzone:0f32,scale:1f32,normalize:true,value:Vec2::ZERO }
+    }
+
+    fn update(&mut self,input:&Input) {
+        self.x.update(input);
+        self.y.update(input);
+        let mut value = Vec2::new(self.x.value, self.y.value);
+        let length = value.length();
+        if length < self.deadzone {
+            value = Vec2::ZERO;
+        } else if length <= 1f32 && self.deadzone > 0f32 {
+            value *= apply_deadzone(length, self.deadzone) / length;
+        }
+        if self.normalize {
+            value = value.clamp_length_max(1f32);
+        }
+        self.value = value * self.scale;
+    }
+}
+
+fn apply_deadzone(value:f32,deadzone:f32) -> f32 {
+    let abs = value.abs();
+    if abs < deadzone || abs == 0f32 {
+        0f32
+    } else if abs <= 1f32 && deadzone > 0f32 {
+        value.signum() * (abs - deadzone) / (1f32 - deadzone)
+    } else {
+        value
+    }
+}
+
+//等待玩家按下新按键来替换绑定
+#[derive(Debug,Clone,PartialEq)]
+pub struct RebindRequest {
+    pub action:String,
+    pub index:usize
+}
+
+//按名字查询的输入动作,由action_system每帧根据Input更新
+#[derive(Resource,Default)]
+pub struct InputMap {
+    buttons:HashMap<String,ButtonAction>,
+    axes:HashMap<String,AxisAction>,
+    axes_2d:HashMap<String,Axis2DAction>,
+    rebind:Option<RebindRequest>
+}
+
+impl InputMap {
+    pub fn add_button(&mut self,name:&str,bindings:Vec<ButtonBinding>) {
+        self.buttons.insert(name.into(), ButtonAction::new(bindings));
+    }
+
+    pub fn add_axis(&mut self,name:&str,axis:AxisAction) {
+        self.axes.insert(name.into(), axis);
+    }
+
+    pub fn add_axis_2d(&mut self,name:&str,axis:Axis2DAction) {
+        self.axes_2d.insert(name.into(), axis);
+    }
+
+    pub fn get_button(&self,name:&str) -> Option<&ButtonAction> { self.buttons.get(name) }
+
+    pub fn get_button_mut(&mut self,name:&str) -> Option<&mut ButtonAction> { self.buttons.get_mut(name) }
+
+    pub fn get_axis_mut(&mut self,name:&str) -> Option<&mut AxisAction> { self.axes.get_mut(name) }
+
+    pub fn get_axis_2d_mut(&mut self,name:&str) -> Option<&mut Axis2DAction> { self.axes_2d.get_mut(name) }
+
+    pub fn is_pressed(&self,name:&str) -> bool {
+        self.buttons.get(name).map(|v| v.is_pressed).unwrap_or(false)
+    }
+
+    pub fn is_just_pressed(&self,name:&str) -> bool {
+        self.buttons.get(name).map(|v| v.is_just_pressed).unwrap_or(false)
+    }
+
+    pub fn is_just_released(&self,name:&str) -> bool {
+        self.buttons.get(name).map(|v| v.is_just_released).unwrap_or(false)
+    }
+
+    pub fn axis(&self,name:&str) -> f32 {
+        self.axes.get(name).map(|v| v.value).unwrap_or(0f32)
+    }
+
+    pub fn axis_2d(&self,name:&str) -> Vec2 {
+        self.axes_2d.get(name).map(|v| v.value).unwrap_or(Vec2::ZERO)
+    }
+
+    //替换按钮动作的第index个绑定,index超出时追加
+    pub fn rebind(&mut self,name:&str,index:usize,binding:ButtonBinding) -> bool {
+        match self.buttons.get_mut(name) {
+            Some(action) => {
+                if index < action.bindings.len() {
+                    action.bindings[index] = binding;
+                } else {
+                    action.bindings.push(binding);
+                }
+                action.actives.clear();
+                true
+            },
+            None => false
+        }
+    }
+
+    //下一个按下的按键或鼠标按钮会成为新的绑定,Escape取消
+    pub fn start_rebind(&mut self,name:&str,index:usize) -> bool {
+        if !self.buttons.contains_key(name) { return false; }
+        self.rebind = Some(RebindRequest { action:name.into(),index });
+        true
+    }
+
+    pub fn cancel_rebind(&mut self) {
+        self.rebind = None;
+    }
+
+    pub fn rebinding(&self) -> Option<&RebindRequest> {
+        self.rebind.as_ref()
+    }
+
+    pub fn update(&mut self,input:&Input) {
+        if let Some(request) = self.rebind.clone() {
+            if input.get_key_down(KeyCode::Escape) {
+                self.rebind = None;
+            } else {
+                if let Some(source) = rebind_source(input) {
+                    //新绑定保留原来的修饰键和exact设置
+                    let mut binding = ButtonBinding::new(source);
+                    if let Some(old) = self.buttons.get(&request.action).and_then(|a| a.bindings.get(request.index)) {
+                        binding.modifiers = old.modifiers.clone();
+                        binding.exact = old.exact;
+                    }
+                    self.rebind(&request.action, request.index, binding);
+                    self.rebind = None;
+                }
+            }
+            //等待绑定的这帧不触发任何动作
+            for action in self.buttons.values_mut() {
+                action.is_just_pressed = false;
+            }
+            return;
+        }
+        for action in self.buttons.values_mut() {
+            action.update(input);
+        }
+        for axis in self.axes.values_mut() {
+            axis.update(input);
+        }
+        for axis in self.axes_2d.values_mut() {
+            axis.update(input);
+        }
+    }
+
+    /*
+    <InputMap>
+        <Button name="save"><Bind input="S" modifiers="LControl" /></Button>
+        <Button name="down"><Bind input="S" exact="true" /></Button>
+        <Button name="dash"><Bind input="LShift+Space" /></Button>
+        <Axis name="zoom" deadzone="0" scale="1"><Bind source="WheelY" /></Axis>
+        <Axis2D name="move" deadzone="0.1">
+            <X negative="A" positive="D" />
+            <X source="PadLeftStickX" />
+            <Y negative="S" positive="W" />
+            <Y source="PadLeftStickY" />
+        </Axis2D>
+    </InputMap>
+    */
+    pub fn load_xml(&mut self,xml_string:&str) -> Result<()> {
+        let mut xml_reader = quick_xml::Reader::from_str(xml_string);
+        xml_reader.trim_text(true);
+        let mut buf = Vec::new();
+        let mut state = XmlReadState::default();
+        loop {
+            match xml_reader.read_event(&mut buf) {
+                Ok(Event::Start(ref e)) => state.start(e)?,
+                Ok(Event::Empty(ref e)) => {
+                    state.start(e)?;
+                    state.end(e.name(), self);
+                },
+                Ok(Event::End(ref e)) => state.end(e.name(), self),
+                Ok(Event::Eof) => break,
+                Err(e) => bail!(e),
+                _ => {}
+            }
+            buf.clear();
+        }
+        Ok(())
+    }
+
+    pub fn to_xml(&self) -> String {
+        let mut xml = String::from("<InputMap>\n");
+        for name in sorted_keys(&self.buttons) {
+            xml.push_str(&format!("    <Button name=\"{}\">",escape_attr(name)));
+            for binding in self.buttons[name].bindings.iter() {
+                let input = binding.buttons.iter().map(|b| b.name()).collect::<Vec<_>>().join("+");
+                xml.push_str(&format!("<Bind input=\"{}\"",input));
+                if !binding.modifiers.is_empty() {
+                    let modifiers = binding.modifiers.iter().map(|k| format!("{:?}",k)).collect::<Vec<_>>().join("+");
+                    xml.push_str(&format!(" modifiers=\"{}\"",modifiers));
+                }
+                if binding.exact {
+                    xml.push_str(" exact=\"true\"");
+                }
+                xml.push_str(" />");
+            }
+            xml.push_str("</Button>\n");
+        }
+        for name in sorted_keys(&self.axes) {
+            let axis = &self.axes[name];
+            xml.push_str(&format!("    <Axis name=\"{}\" deadzone=\"{}\" scale=\"{}\">",escape_attr(name),axis.deadzone,axis.scale));
+            for binding in axis.bindings.iter() {
+                xml.push_str(&format!("<Bind {} />",binding.xml_attrs()));
+            }
+            xml.push_str("</Axis>\n");
+        }
+        for name in sorted_keys(&self.axes_2d) {
+            let axis = &self.axes_2d[name];
+            xml.push_str(&format!("    <Axis2D name=\"{}\" deadzone=\"{}\" scale=\"{}\" normalize=\"{}\">",escape_attr(name),axis.deadzone,axis.scale,axis.normalize));
+            for binding in axis.x.bindings.iter() {
+                xml.push_str(&format!("<X {} />",binding.xml_attrs()));
+            }
+            for binding in axis.y.bindings.iter() {
+                xml.push_str(&format!("<Y {} />",binding.xml_attrs()));
+            }
+            xml.push_str("</Axis2D>\n");
+        }
+        xml.push_str("</InputMap>");
+        xml
+    }
+}
+
+//同一帧按下多个时按编号取最小的,修饰键不作为新绑定的按键
+fn rebind_source(input:&Input) -> Option<InputSource> {
+    let key = input.frame_keydown.iter().filter(|k| !MODIFIER_KEYS.contains(k)).min_by_key(|k| **k as u32);
+    if let Some(key) = key {
+        return Some(InputSource::Key(*key));
+    }
+    let mouse = input.frame_mousedown.iter().min_by_key(|b| Into::<u32>::into(**b));
+    if let Some(mouse) = mouse {
+        return Some(InputSource::Mouse(*mouse));
+    }
+    input.gamepads.iter().flat_map(|(id,pad)| pad.frame_down.iter().map(move |b| (*id,*b)))
+                  .min_by_key(|(id,b)| (*id,*b as u32)).map(|(_,b)| InputSource::Gamepad(b))
+}
+
+//属性值中的&,<,>和引号需要转义
+fn escape_attr(value:&str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c)
+        }
+    }
+    escaped
+}
+
+fn sorted_keys<T>(map:&HashMap<String,T>) -> Vec<&String> {
+    let mut names:Vec<&String> = map.keys().collect();
+    names.sort();
+    names
+}
+
+#[derive(Default)]
+struct XmlReadState {
+    button:Option<(String,Vec<ButtonBinding>)>,
+    axis:Option<(String,AxisAction)>,
+    axis_2d:Option<(String,Axis2DAction)>
+}
+
+impl XmlReadState {
+    fn start(&mut self,e:&BytesStart) -> Result<()> {
+        let attrs = read_attrs(e)?;
+        let get = |name:&str| attrs.get(name).map(|v| v.as_str());
+        match e.name() {
+            b"Button" => {
+                let name = get("name").ok_or_else(|| anyhow!("Button miss name"))?;
+                self.button = Some((name.into(),vec![]));
+            },
+            b"Axis" => {
+                let name = get("name").ok_or_else(|| anyhow!("Axis miss name"))?;
+                let mut axis = AxisAction::new(vec![]);
+                if let Some(v) = get("deadzone") { axis.deadzone = v.parse()?; }
+                if let Some(v) = get("scale") { axis.scale = v.parse()?; }
+                self.axis = Some((name.into(),axis));
+            },
+            b"Axis2D" => {
+                let name = get("name").ok_or_else(|| anyhow!("Axis2D miss name"))?;
+                let mut axis = Axis2DAction::new(vec![], vec![]);
+                if let Some(v) = get("deadzone") { axis.deadzone = v.parse()?; }
+                if let Some(v) = get("scale") { axis.scale = v.parse()?; }
+                if let Some(v) = get("normalize") { axis.normalize = v.parse()?; }
+                self.axis_2d = Some((name.into(),axis));
+            },
+            b"Bind" => {
+                if let Some((_,bindings)) = self.button.as_mut() {
+                    bindings.push(read_button_binding(&attrs)?);
+                } else if let Some((_,axis)) = self.axis.as_mut() {
+                    axis.bindings.push(read_axis_binding(&attrs)?);
+                }
+            },
+            b"X" => {
+                if let Some((_,axis)) = self.axis_2d.as_mut() {
+                    axis.x.bindings.push(read_axis_binding(&attrs)?);
+                }
+            },
+            b"Y" => {
+                if let Some((_,axis)) = self.axis_2d.as_mut() {
+                    axis.y.bindings.push(read_axis_binding(&attrs)?);
+                }
+            },
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn end(&mut self,name:&[u8],map:&mut InputMap) {
+        match name {
+            b"Button" => {
+                if let Some((name,bindings)) = self.button.take() {
+                    map.add_button(&name, bindings);
+                }
+            },
+            b"Axis" => {
+                if let Some((name,axis)) = self.axis.take() {
+                    map.add_axis(&name, axis);
+                }
+            },
+            b"Axis2D" => {
+                if let Some((name,axis)) = self.axis_2d.take() {
+                    map.add_axis_2d(&name, axis);
+                }
+            },
+            _ => {}
+        }
+    }
+}
+
+fn read_attrs(e:&BytesStart) -> Result<HashMap<String,String>> {
+    let mut attrs = HashMap::default();
+    for attr in e.attributes() {
+        let attr = attr?;
+        attrs.insert(std::str::from_utf8(attr.key)?.to_string(), std::str::from_utf8(&attr.unescaped_value()?)?.to_string());
+    }
+    Ok(attrs)
+}
+
+fn read_source(name:&str) -> Result<InputSource> {
+    InputSource::from_name(name.trim()).ok_or_else(|| anyhow!("unknown input {}",name))
+}
+
+fn read_button_binding(attrs:&HashMap<String,String>) -> Result<ButtonBinding> {
+    let input = attrs.get("input").ok_or_else(|| anyhow!("Bind miss input"))?;
+    let mut binding = ButtonBinding::default();
+    for name in input.split('+') {
+        binding.buttons.push(read_source(name)?);
+    }
+    if let Some(modifiers) = attrs.get("modifiers") {
+        for name in modifiers.split('+') {
+            binding.modifiers.push(KeyCode::from_name(name.trim()).ok_or_else(|| anyhow!("unknown key {}",name))?);
+        }
+    }
+    if let Some(exact) = attrs.get("exact") {
+        binding.exact = exact.parse()?;
+    }
+    Ok(binding)
+}
+
+fn read_axis_binding(attrs:&HashMap<String,String>) -> Result<AxisBinding> {
+    if let Some(source) = attrs.get("source") {
+        return match source.as_str() {
+            "MouseX" => Ok(AxisBinding::MouseX),
+            "MouseY" => Ok(AxisBinding::MouseY),
+            "WheelX" => Ok(AxisBinding::WheelX),
+            "WheelY" => Ok(AxisBinding::WheelY),
+            _ => {
+                let axis = source.strip_prefix("Pad").and_then(|name| GamepadAxis::ALL.iter().find(|a| format!("{:?}",a) == name));
+                axis.map(|a| AxisBinding::Gamepad(*a)).ok_or_else(|| anyhow!("unknown axis source {}",source))
+            }
+        };
+    }
+    match (attrs.get("negative"),attrs.get("positive")) {
+        (Some(negative),Some(positive)) => Ok(AxisBinding::Buttons { negative:read_source(negative)?,positive:read_source(positive)? }),
+        _ => Err(anyhow!("axis Bind miss source or negative/positive"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec2;
+    use crate::{Input, keycode::KeyCode, event::GamepadAxis};
+    use super::{InputMap, ButtonBinding, InputSource, AxisBinding};
+
+    fn press(input:&mut Input,key:KeyCode) {
+        input.frame_keydown.insert(key);
+        input.key_pressing.insert(key);
+    }
+
+    const XML:&str = r#"
+    <InputMap>
+        <Button name="save"><Bind input="S" modifiers="LControl" /></Button>
+        <Button name="down"><Bind input="S" exact="true" /></Button>
+        <Button name="dash"><Bind input="LShift+Space" /></Button>
+        <Axis name="zoom" scale="2"><Bind source="WheelY" /></Axis>
+        <Axis2D name="move" deadzone="0.1">
+            <X negative="A" positive="D" />
+            <X source="PadLeftStickX" />
+            <Y negative="S" positive="W" />
+        </Axis2D>
+    </InputMap>"#;
+
+    #[test]
+    fn modifier_and_chord() {
+        let mut map = InputMap::default();
+        map.load_xml(XML).unwrap();
+        let mut input = Input::default();
+        press(&mut input, KeyCode::S);
+        map.update(&input);
+        assert!(!map.is_pressed("save"));
+        assert!(map.is_just_pressed("down"));
+        input.clear();
+        //先按S再按Ctrl不触发,exact的绑定在按住其他修饰键时松开
+        press(&mut input, KeyCode::LControl);
+        map.update(&input);
+        assert!(!map.is_pressed("save"));
+        assert!(map.is_just_released("down"));
+        input.clear();
+        input.key_pressing.remove(&KeyCode::S);
+        press(&mut input, KeyCode::S);
+        map.update(&input);
+        assert!(map.is_just_pressed("save"));
+        assert!(!map.is_pressed("down"));
+
+        press(&mut input, KeyCode::Space);
+        map.update(&input);
+        assert!(!map.is_pressed("dash"));
+        input.clear();
+        press(&mut input, KeyCode::LShift);
+        map.update(&input);
+        assert!(map.is_just_pressed("dash"));
+        input.key_pressing.remove(&KeyCode::Space);
+        map.update(&input);
+        assert!(map.is_just_released("dash"));
+    }
+
+    #[test]
+    fn axis_and_rebind() {
+        let mut map = InputMap::default();
+        map.load_xml(XML).unwrap();
+        let mut input = Input::default();
+        press(&mut input, KeyCode::D);
+        press(&mut input, KeyCode::W);
+        input.frame_mouse_wheel = Some(Vec2::new(0f32, -2f32));
+        map.update(&input);
+        assert!((map.axis_2d("move").length() - 1f32).abs() < 0.0001f32);
+        assert_eq!(map.axis("zoom"), -4f32);
+        input.clear();
+
+        //修饰键不会成为新绑定,原来的修饰键保留,同时按下的按键取编号最小的
+        assert!(map.start_rebind("save", 0));
+        press(&mut input, KeyCode::LShift);
+        map.update(&input);
+        assert!(map.rebinding().is_some());
+        press(&mut input, KeyCode::F5);
+        press(&mut input, KeyCode::A);
+        map.update(&input);
+        assert!(map.rebinding().is_none());
+        assert_eq!(map.get_button("save").unwrap().bindings[0], ButtonBinding::new(InputSource::Key(KeyCode::A)).with_modifier(KeyCode::LControl));
+        map.add_button("say \"hi\" & <bye>", vec![ButtonBinding::new(InputSource::Key(KeyCode::H))]);
+        let mut loaded = InputMap::default();
+        loaded.load_xml(&map.to_xml()).unwrap();
+        assert_eq!(loaded.get_button("save").unwrap().bindings, map.get_button("save").unwrap().bindings);
+        assert_eq!(loaded.get_button("dash").unwrap().bindings, map.get_button("dash").unwrap().bindings);
+        assert_eq!(loaded.get_button("down").unwrap().bindings, map.get_button("down").unwrap().bindings);
+        assert!(loaded.get_button("say \"hi\" & <bye>").is_some());
+        let zoom = loaded.get_axis_mut("zoom").unwrap();
+        assert_eq!(zoom.scale, 2f32);
+        assert_eq!(zoom.bindings, vec![AxisBinding::WheelY]);
+        let move_axis = loaded.get_axis_2d_mut("move").unwrap();
+        assert_eq!(move_axis.deadzone, 0.1f32);
+        assert_eq!(move_axis.x.bindings, vec![AxisBinding::Buttons { negative:InputSource::Key(KeyCode::A),positive:InputSource::Key(KeyCode::D) },
+                                              AxisBinding::Gamepad(GamepadAxis::LeftStickX)]);
+        assert_eq!(move_axis.y.bindings.len(), 1);
+    }
+}
@@ -1,10 +1,11 @@
+use num_enum::FromPrimitive;
 use glam::Vec2;
 
 use crate::keycode::KeyCode;
 
 
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct KeyboardInput {
     pub scan_code: u32,
     pub key_code:KeyCode,
@@ -17,7 +18,7 @@ pub enum InputState {
     Released,
     
 }
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct MouseInput {
     pub button:MouseButton,
     pub state:InputState
@@ -28,7 +29,26 @@ pub struct MouseWheelInput {
     pub delta:Vec2
 }
 
-#[derive(Debug,Clone)]
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+#[repr(u8)]
+pub enum TouchPhase {
+    Started,
+    Moved,
+    //本帧没有变化
+    Stationary,
+    Ended,
+    Cancelled
+}
+
+//触摸点事件,位置和鼠标一样是窗口坐标
+#[derive(Debug,Clone,PartialEq)]
+pub struct TouchInput {
+    pub id:u64,
+    pub phase:TouchPhase,
+    pub position:Vec2
+}
+
+#[derive(Debug,Clone,PartialEq)]
 pub enum ImeEvent {
     ReceivedCharacter(char),
     Commit(String)
@@ -64,4 +84,75 @@ impl Into<u32> for MouseButton {
             MouseButton::Other(n) => n,
         }
     }
-}
\ No newline at end of file
+}
+//按Xbox布局命名,South是A键
+#[derive(Debug,Hash,PartialEq,Eq,Clone,Copy,FromPrimitive)]
+#[repr(u32)]
+pub enum GamepadButton {
+    South,
+    East,
+    North,
+    West,
+    LeftBumper,
+    RightBumper,
+    Select,
+    Start,
+    Mode,
+    LeftThumb,
+    RightThumb,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+    #[num_enum(default)]
+    Unknown
+}
+
+impl GamepadButton {
+    pub const ALL:[GamepadButton;15] = [GamepadButton::South,GamepadButton::East,GamepadButton::North,GamepadButton::West,
+                                        GamepadButton::LeftBumper,GamepadButton::RightBumper,GamepadButton::Select,GamepadButton::Start,
+                                        GamepadButton::Mode,GamepadButton::LeftThumb,GamepadButton::RightThumb,GamepadButton::DPadUp,
+                                        GamepadButton::DPadDown,GamepadButton::DPadLeft,GamepadButton::DPadRight];
+}
+
+//摇杆范围[-1,1],y轴向上为正,扳机范围[0,1]
+#[derive(Debug,Hash,PartialEq,Eq,Clone,Copy,FromPrimitive)]
+#[repr(u32)]
+pub enum GamepadAxis {
+    LeftStickX,
+    LeftStickY,
+    RightStickX,
+    RightStickY,
+    LeftTrigger,
+    RightTrigger,
+    #[num_enum(default)]
+    Unknown
+}
+
+impl GamepadAxis {
+    pub const ALL:[GamepadAxis;6] = [GamepadAxis::LeftStickX,GamepadAxis::LeftStickY,GamepadAxis::RightStickX,
+                                     GamepadAxis::RightStickY,GamepadAxis::LeftTrigger,GamepadAxis::RightTrigger];
+}
+
+#[derive(Debug,Clone,PartialEq)]
+pub enum GamepadEventKind {
+    Connected(String),
+    Disconnected,
+    Button(GamepadButton,InputState),
+    Axis(GamepadAxis,f32)
+}
+
+#[derive(Debug,Clone,PartialEq)]
+pub struct GamepadEvent {
+    pub gamepad:u32,
+    pub kind:GamepadEventKind
+}
+
+//手柄震动,strong是低频大马达,weak是高频小马达,范围[0,1],duration单位秒
+#[derive(Debug,Clone,PartialEq)]
+pub struct GamepadRumble {
+    pub gamepad:u32,
+    pub strong:f32,
+    pub weak:f32,
+    pub duration:f32
+}
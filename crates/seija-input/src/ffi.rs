@@ -1,6 +1,9 @@
+use std::ffi::{c_char, CStr};
 use glam::Vec2;
+use num_enum::FromPrimitive;
 use seija_app::{App, ecs::world::World};
-use crate::{InputModule, Input, keycode::KeyCode};
+use seija_core::ResultExt;
+use crate::{InputModule, Input, keycode::KeyCode, action::InputMap, event::{GamepadButton, GamepadAxis, GamepadRumble, GamepadEvent, GamepadEventKind, InputState}, gamepad::{GamepadBackendRes, FakeGamepadBackend}};
 
 #[no_mangle]
 pub unsafe extern "C" fn input_add_module(app_ptr:*mut App) {
@@ -37,3 +40,150 @@ pub unsafe extern "C" fn input_get_mouse_down(input:*const Input,mouse_btn:u32)
 pub unsafe extern "C" fn input_get_mouse_up(input:*const Input,mouse_btn:u32) -> bool {
     (&*input).get_mouse_up(mouse_btn.into())
 }
+
+#[no_mangle]
+pub unsafe extern "C" fn input_get_touch_count(input:*const Input) -> u32 {
+    (&*input).touches.len() as u32
+}
+
+//index按触摸开始的顺序,包含本帧结束的触摸点
+#[no_mangle]
+pub unsafe extern "C" fn input_get_touch(input:*const Input,index:u32,id:&mut u64,phase:&mut u8,position:&mut Vec2) -> bool {
+    if let Some(touch) = (&*input).touches.get(index as usize) {
+        *id = touch.id;
+        *phase = touch.phase as u8;
+        *position = touch.position;
+        return true;
+    }
+    false
+}
+
+//把已连接手柄的id写入out,返回手柄数量
+#[no_mangle]
+pub unsafe extern "C" fn input_get_gamepads(input:*const Input,out:*mut u32,out_len:usize) -> u32 {
+    let mut count = 0;
+    for (id,_) in (&*input).gamepads() {
+        if !out.is_null() && count < out_len {
+            *out.add(count) = id;
+        }
+        count += 1;
+    }
+    count as u32
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn input_get_gamepad_button(input:*const Input,gamepad:u32,button:u32) -> bool {
+    (&*input).get_gamepad_button(gamepad, GamepadButton::from_primitive(button))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn input_get_gamepad_button_down(input:*const Input,gamepad:u32,button:u32) -> bool {
+    (&*input).get_gamepad_button_down(gamepad, GamepadButton::from_primitive(button))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn input_get_gamepad_button_up(input:*const Input,gamepad:u32,button:u32) -> bool {
+    (&*input).get_gamepad_button_up(gamepad, GamepadButton::from_primitive(button))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn input_get_gamepad_axis(input:*const Input,gamepad:u32,axis:u32) -> f32 {
+    (&*input).get_gamepad_axis(gamepad, GamepadAxis::from_primitive(axis))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn input_gamepad_rumble(world:*mut World,gamepad:u32,strong:f32,weak:f32,duration:f32) {
+    (&mut *world).send_event(GamepadRumble { gamepad,strong,weak,duration });
+}
+
+//无头运行时使用假手柄,之后用input_fake_gamepad_push注入事件
+#[no_mangle]
+pub unsafe extern "C" fn input_use_fake_gamepad(world:*mut World) {
+    let backend = FakeGamepadBackend::default();
+    (&mut *world).insert_non_send_resource(GamepadBackendRes::new(backend.clone()));
+    (&mut *world).insert_resource(backend);
+}
+
+//typ 0:连接 1:断开 2:按下 3:松开 4:轴,code是按钮或轴,name只在连接时使用
+#[no_mangle]
+pub unsafe extern "C" fn input_fake_gamepad_push(world:*mut World,gamepad:u32,typ:u8,code:u32,value:f32,name:*const c_char) -> bool {
+    let backend = match (&*world).get_resource::<FakeGamepadBackend>() {
+        Some(v) => v,
+        None => return false
+    };
+    let kind = match typ {
+        0 => GamepadEventKind::Connected(if name.is_null() { String::default() } else { CStr::from_ptr(name).to_string_lossy().to_string() }),
+        1 => GamepadEventKind::Disconnected,
+        2 => GamepadEventKind::Button(GamepadButton::from_primitive(code), InputState::Pressed),
+        3 => GamepadEventKind::Button(GamepadButton::from_primitive(code), InputState::Released),
+        4 => GamepadEventKind::Axis(GamepadAxis::from_primitive(code), value),
+        _ => return false
+    };
+    backend.push(GamepadEvent { gamepad,kind });
+    true
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn input_world_get_input_map(world:*mut World) -> *mut InputMap {
+    if let Some(mut input_map) = (&mut *world).get_resource_mut::<InputMap>() {
+        input_map.as_mut() as *mut InputMap
+    } else {
+        std::ptr::null_mut()
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn input_map_load_xml(input_map:&mut InputMap,xml:*const c_char) -> bool {
+    let xml = CStr::from_ptr(xml).to_str().unwrap_or_default();
+    input_map.load_xml(xml).log_err().is_ok()
+}
+
+//把动作绑定的xml写入out,返回字节长度,out_len不够时只返回长度
+#[no_mangle]
+pub unsafe extern "C" fn input_map_to_xml(input_map:&InputMap,out:*mut u8,out_len:usize) -> i32 {
+    let xml = input_map.to_xml();
+    if !out.is_null() && xml.len() <= out_len {
+        std::ptr::copy_nonoverlapping(xml.as_ptr(), out, xml.len());
+    }
+    xml.len() as i32
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn input_map_is_pressed(input_map:&InputMap,name:*const c_char) -> bool {
+    input_map.is_pressed(CStr::from_ptr(name).to_str().unwrap_or_default())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn input_map_is_just_pressed(input_map:&InputMap,name:*const c_char) -> bool {
+    input_map.is_just_pressed(CStr::from_ptr(name).to_str().unwrap_or_default())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn input_map_is_just_released(input_map:&InputMap,name:*const c_char) -> bool {
+    input_map.is_just_released(CStr::from_ptr(name).to_str().unwrap_or_default())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn input_map_axis(input_map:&InputMap,name:*const c_char) -> f32 {
+    input_map.axis(CStr::from_ptr(name).to_str().unwrap_or_default())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn input_map_axis_2d(input_map:&InputMap,name:*const c_char,out:&mut Vec2) {
+    *out = input_map.axis_2d(CStr::from_ptr(name).to_str().unwrap_or_default());
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn input_map_start_rebind(input_map:&mut InputMap,name:*const c_char,index:u32) -> bool {
+    input_map.start_rebind(CStr::from_ptr(name).to_str().unwrap_or_default(), index as usize)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn input_map_cancel_rebind(input_map:&mut InputMap) {
+    input_map.cancel_rebind();
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn input_map_is_rebinding(input_map:&InputMap) -> bool {
+    input_map.rebinding().is_some()
+}
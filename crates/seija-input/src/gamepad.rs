@@ -0,0 +1,219 @@
+use std::{collections::{HashMap, HashSet}, sync::{Arc, Mutex}};
+use bevy_ecs::{event::{EventReader, EventWriter}, system::{NonSendMut, Resource}};
+use crate::event::{GamepadButton, GamepadAxis, GamepadEvent, GamepadRumble};
+
+#[derive(Debug,Default)]
+pub struct GamepadState {
+    pub name:String,
+    pub(crate) pressing:HashSet<GamepadButton>,
+    pub(crate) frame_down:HashSet<GamepadButton>,
+    pub(crate) frame_up:HashSet<GamepadButton>,
+    pub(crate) axes:HashMap<GamepadAxis,f32>
+}
+
+impl GamepadState {
+    pub fn get_button(&self,button:GamepadButton) -> bool {
+        self.pressing.contains(&button)
+    }
+
+    pub fn get_button_down(&self,button:GamepadButton) -> bool {
+        self.frame_down.contains(&button)
+    }
+
+    pub fn get_button_up(&self,button:GamepadButton) -> bool {
+        self.frame_up.contains(&button)
+    }
+
+    pub fn get_axis(&self,axis:GamepadAxis) -> f32 {
+        self.axes.get(&axis).copied().unwrap_or(0f32)
+    }
+}
+
+//手柄的平台实现,只在主线程上调用
+pub trait GamepadBackend {
+    //取出上次调用后的所有手柄事件
+    fn poll(&mut self) -> Vec<GamepadEvent>;
+    fn rumble(&mut self,rumble:&GamepadRumble);
+}
+
+pub struct GamepadBackendRes(pub Box<dyn GamepadBackend>);
+
+impl GamepadBackendRes {
+    pub fn new(backend:impl GamepadBackend + 'static) -> Self {
+        GamepadBackendRes(Box::new(backend))
+    }
+}
+
+#[derive(Default)]
+struct FakeGamepadData {
+    events:Vec<GamepadEvent>,
+    rumbles:Vec<GamepadRumble>
+}
+
+//没有真实手柄时使用,clone出来的句柄共享同一份数据,可以在外部注入事件和检查震动请求
+#[derive(Default,Clone,Resource)]
+pub struct FakeGamepadBackend {
+    data:Arc<Mutex<FakeGamepadData>>
+}
+
+impl FakeGamepadBackend {
+    pub fn push(&self,event:GamepadEvent) {
+        self.data.lock().unwrap().events.push(event);
+    }
+
+    pub fn take_rumbles(&self) -> Vec<GamepadRumble> {
+        std::mem::take(&mut self.data.lock().unwrap().rumbles)
+    }
+}
+
+impl GamepadBackend for FakeGamepadBackend {
+    fn poll(&mut self) -> Vec<GamepadEvent> {
+        std::mem::take(&mut self.data.lock().unwrap().events)
+    }
+
+    fn rumble(&mut self,rumble:&GamepadRumble) {
+        self.data.lock().unwrap().rumbles.push(rumble.clone());
+    }
+}
+
+pub(crate) fn gamepad_backend_system(backend:Option<NonSendMut<GamepadBackendRes>>,mut gamepad_events:EventWriter<GamepadEvent>,mut rumbles:EventReader<GamepadRumble>) {
+    let mut backend = match backend {
+        Some(v) => v,
+        None => return
+    };
+    for rumble in rumbles.iter() {
+        backend.0.rumble(rumble);
+    }
+    for ev in backend.0.poll() {
+        gamepad_events.send(ev);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use seija_app::App;
+    use seija_core::CoreModule;
+    use crate::{InputModule, Input, action::{InputMap, InputSource, ButtonBinding, AxisAction, AxisBinding}};
+    use crate::event::{GamepadEvent, GamepadEventKind, GamepadButton, GamepadAxis, GamepadRumble, InputState};
+    use super::{FakeGamepadBackend, GamepadBackendRes};
+
+    fn pad_event(kind:GamepadEventKind) -> GamepadEvent {
+        GamepadEvent { gamepad:1,kind }
+    }
+
+    fn new_app() -> (App,FakeGamepadBackend) {
+        let mut app = App::new();
+        app.add_module(CoreModule);
+        app.add_module(InputModule);
+        let backend = FakeGamepadBackend::default();
+        app.world.insert_non_send_resource(GamepadBackendRes::new(backend.clone()));
+        app.world.resource_mut::<InputMap>().add_button("jump", vec![ButtonBinding::new(InputSource::Gamepad(GamepadButton::South))]);
+        (app,backend)
+    }
+
+    #[test]
+    fn fake_backend() {
+        let (mut app,backend) = new_app();
+
+        backend.push(pad_event(GamepadEventKind::Connected("fake".into())));
+        backend.push(pad_event(GamepadEventKind::Button(GamepadButton::South, InputState::Pressed)));
+        backend.push(pad_event(GamepadEventKind::Axis(GamepadAxis::LeftStickX, 0.5f32)));
+        app.update();
+        {
+            let input = app.world.resource::<Input>();
+            assert_eq!(input.get_gamepad(1).map(|v| v.name.as_str()), Some("fake"));
+            assert!(input.get_gamepad_button(1, GamepadButton::South));
+            assert_eq!(input.get_gamepad_axis(1, GamepadAxis::LeftStickX), 0.5f32);
+            assert!(app.world.resource::<InputMap>().is_just_pressed("jump"));
+        }
+
+        app.world.send_event(GamepadRumble { gamepad:1,strong:1f32,weak:0f32,duration:0.2f32 });
+        backend.push(pad_event(GamepadEventKind::Button(GamepadButton::South, InputState::Released)));
+        app.update();
+        {
+            let input = app.world.resource::<Input>();
+            assert!(!input.get_gamepad_button(1, GamepadButton::South));
+            assert!(app.world.resource::<InputMap>().is_just_released("jump"));
+        }
+        assert_eq!(backend.take_rumbles().len(), 1);
+
+        backend.push(pad_event(GamepadEventKind::Disconnected));
+        app.update();
+        assert!(app.world.resource::<Input>().get_gamepad(1).is_none());
+    }
+
+    #[test]
+    fn button_transitions() {
+        let (mut app,backend) = new_app();
+        backend.push(pad_event(GamepadEventKind::Connected("fake".into())));
+        //重复的按下事件不会再次触发
+        backend.push(pad_event(GamepadEventKind::Button(GamepadButton::South, InputState::Pressed)));
+        backend.push(pad_event(GamepadEventKind::Button(GamepadButton::South, InputState::Pressed)));
+        app.update();
+        assert!(app.world.resource::<InputMap>().is_just_pressed("jump"));
+
+        //Input的down和up在帧末清除,按住时只有第一帧是just_pressed
+        app.update();
+        let input = app.world.resource::<Input>();
+        assert!(input.get_gamepad_button(1, GamepadButton::South));
+        assert!(!input.get_gamepad_button_down(1, GamepadButton::South));
+        let map = app.world.resource::<InputMap>();
+        assert!(map.is_pressed("jump") && !map.is_just_pressed("jump"));
+
+        backend.push(pad_event(GamepadEventKind::Button(GamepadButton::South, InputState::Released)));
+        app.update();
+        assert!(!app.world.resource::<Input>().get_gamepad_button(1, GamepadButton::South));
+        assert!(app.world.resource::<InputMap>().is_just_released("jump"));
+
+        app.update();
+        let map = app.world.resource::<InputMap>();
+        assert!(!map.is_pressed("jump") && !map.is_just_released("jump"));
+
+        //同一帧按下又松开,动作不会卡在按下状态
+        backend.push(pad_event(GamepadEventKind::Button(GamepadButton::South, InputState::Pressed)));
+        backend.push(pad_event(GamepadEventKind::Button(GamepadButton::South, InputState::Released)));
+        app.update();
+        assert!(!app.world.resource::<Input>().get_gamepad_button(1, GamepadButton::South));
+        app.update();
+        assert!(!app.world.resource::<InputMap>().is_pressed("jump"));
+    }
+
+    #[test]
+    fn axis_deadzone() {
+        let (mut app,backend) = new_app();
+        let mut look = AxisAction::new(vec![AxisBinding::Gamepad(GamepadAxis::RightStickX)]);
+        look.deadzone = 0.2f32;
+        app.world.resource_mut::<InputMap>().add_axis("look", look);
+        backend.push(pad_event(GamepadEventKind::Connected("fake".into())));
+        backend.push(pad_event(GamepadEventKind::Axis(GamepadAxis::RightStickX, 0.1f32)));
+        app.update();
+        assert_eq!(app.world.resource::<Input>().get_gamepad_axis(1, GamepadAxis::RightStickX), 0.1f32);
+        assert_eq!(app.world.resource::<InputMap>().axis("look"), 0f32);
+
+        //死区外的值重新映射到0到1
+        backend.push(pad_event(GamepadEventKind::Axis(GamepadAxis::RightStickX, 0.6f32)));
+        app.update();
+        assert!((app.world.resource::<InputMap>().axis("look") - 0.5f32).abs() < 0.0001f32);
+        backend.push(pad_event(GamepadEventKind::Axis(GamepadAxis::RightStickX, -1f32)));
+        app.update();
+        assert_eq!(app.world.resource::<InputMap>().axis("look"), -1f32);
+    }
+
+    #[test]
+    fn rumble() {
+        let (mut app,backend) = new_app();
+        backend.push(pad_event(GamepadEventKind::Connected("fake".into())));
+        app.update();
+        assert!(backend.take_rumbles().is_empty());
+
+        let strong = GamepadRumble { gamepad:1,strong:1f32,weak:0f32,duration:0.2f32 };
+        let weak = GamepadRumble { gamepad:1,strong:0f32,weak:0.5f32,duration:1f32 };
+        app.world.send_event(strong.clone());
+        app.world.send_event(weak.clone());
+        app.update();
+        assert_eq!(backend.take_rumbles(), vec![strong,weak]);
+        //每个震动请求只发给后端一次
+        app.update();
+        assert!(backend.take_rumbles().is_empty());
+    }
+}
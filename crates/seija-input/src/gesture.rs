@@ -0,0 +1,261 @@
+use std::collections::HashMap;
+use bevy_ecs::system::Resource;
+use glam::Vec2;
+use crate::{Input, event::TouchPhase};
+
+//识别出的手势,位置都是窗口坐标
+#[derive(Debug,Clone,PartialEq)]
+pub enum GestureEvent {
+    Tap(Vec2),
+    //第二次点击时在Tap之后发送
+    DoubleTap(Vec2),
+    LongPress(Vec2),
+    Swipe { start:Vec2,delta:Vec2,velocity:Vec2 },
+    //两指缩放,scale是相对上一帧的比例
+    Pinch { center:Vec2,scale:f32 },
+    //两指旋转,angle是相对上一帧转过的弧度
+    Rotate { center:Vec2,angle:f32 }
+}
+
+impl GestureEvent {
+    pub fn position(&self) -> Vec2 {
+        match self {
+            GestureEvent::Tap(pos) => *pos,
+            GestureEvent::DoubleTap(pos) => *pos,
+            GestureEvent::LongPress(pos) => *pos,
+            GestureEvent::Swipe { start, .. } => *start,
+            GestureEvent::Pinch { center, .. } => *center,
+            GestureEvent::Rotate { center, .. } => *center
+        }
+    }
+}
+
+struct TouchTrack {
+    start_time:f32,
+    max_distance:f32,
+    is_long_pressed:bool,
+    //有过多指触摸的不再识别单指手势
+    is_multi:bool
+}
+
+struct TwoFingerState {
+    ids:(u64,u64),
+    distance:f32,
+    angle:f32
+}
+
+#[derive(Resource)]
+pub struct GestureRecognizer {
+    pub tap_max_time:f32,
+    //移动超过这个距离就不再算点击和长按
+    pub tap_max_distance:f32,
+    pub double_tap_interval:f32,
+    pub long_press_time:f32,
+    pub swipe_min_distance:f32,
+    pub swipe_min_velocity:f32,
+    time:f32,
+    tracks:HashMap<u64,TouchTrack>,
+    last_tap:Option<(f32,Vec2)>,
+    two_finger:Option<TwoFingerState>
+}
+
+impl Default for GestureRecognizer {
+    fn default() -> Self {
+        GestureRecognizer {
+            tap_max_time:0.3f32,
+            tap_max_distance:10f32,
+            double_tap_interval:0.3f32,
+            long_press_time:0.5f32,
+            swipe_min_distance:50f32,
+            swipe_min_velocity:200f32,
+            time:0f32,
+            tracks:HashMap::default(),
+            last_tap:None,
+            two_finger:None
+        }
+    }
+}
+
+impl GestureRecognizer {
+    pub fn update(&mut self,input:&Input,dt:f32) -> Vec<GestureEvent> {
+        self.time += dt;
+        let mut events = vec![];
+        let active_count = input.touch_count();
+        for touch in input.touches() {
+            let time = self.time;
+            let track = self.tracks.entry(touch.id).or_insert_with(|| TouchTrack {
+                start_time:time,
+                max_distance:0f32,
+                is_long_pressed:false,
+                is_multi:false
+            });
+            if active_count > 1 {
+                track.is_multi = true;
+            }
+            track.max_distance = track.max_distance.max(touch.position.distance(touch.start_position));
+            let duration = self.time - track.start_time;
+            let is_still = track.max_distance <= self.tap_max_distance;
+            match touch.phase {
+                TouchPhase::Ended => {
+                    let track = self.tracks.remove(&touch.id).unwrap();
+                    if track.is_multi || track.is_long_pressed { continue; }
+                    if is_still {
+                        if duration <= self.tap_max_time {
+                            events.push(GestureEvent::Tap(touch.position));
+                            match self.last_tap {
+                                Some((tap_time,tap_pos)) if self.time - tap_time <= self.double_tap_interval
+                                                         && tap_pos.distance(touch.position) <= self.tap_max_distance * 2f32 => {
+                                    events.push(GestureEvent::DoubleTap(touch.position));
+                                    self.last_tap = None;
+                                },
+                                _ => self.last_tap = Some((self.time,touch.position))
+                            }
+                        }
+                    } else {
+                        let delta = touch.position - touch.start_position;
+                        let velocity = delta / duration.max(f32::EPSILON);
+                        if delta.length() >= self.swipe_min_distance && velocity.length() >= self.swipe_min_velocity {
+                            events.push(GestureEvent::Swipe { start:touch.start_position,delta,velocity });
+                        }
+                    }
+                },
+                TouchPhase::Cancelled => {
+                    self.tracks.remove(&touch.id);
+                },
+                _ => {
+                    if !track.is_multi && !track.is_long_pressed && is_still && duration >= self.long_press_time {
+                        track.is_long_pressed = true;
+                        events.push(GestureEvent::LongPress(touch.position));
+                    }
+                }
+            }
+        }
+        self.update_two_finger(input, &mut events);
+        events
+    }
+
+    //取最早按下的两个触摸点识别缩放和旋转
+    fn update_two_finger(&mut self,input:&Input,events:&mut Vec<GestureEvent>) {
+        let mut actives = input.touches().filter(|t| t.is_active());
+        let (a,b) = match (actives.next(),actives.next()) {
+            (Some(a),Some(b)) => (a,b),
+            _ => {
+                self.two_finger = None;
+                return;
+            }
+        };
+        let offset = b.position - a.position;
+        let center = (a.position + b.position) * 0.5f32;
+        let distance = offset.length();
+        let angle = offset.y.atan2(offset.x);
+        if let Some(state) = self.two_finger.as_ref().filter(|s| s.ids == (a.id,b.id)) {
+            if state.distance > 0f32 && (distance - state.distance).abs() > f32::EPSILON {
+                events.push(GestureEvent::Pinch { center,scale:distance / state.distance });
+            }
+            let mut delta = angle - state.angle;
+            if delta > std::f32::consts::PI { delta -= std::f32::consts::TAU; }
+            if delta < -std::f32::consts::PI { delta += std::f32::consts::TAU; }
+            if delta.abs() > f32::EPSILON {
+                events.push(GestureEvent::Rotate { center,angle:delta });
+            }
+        }
+        self.two_finger = Some(TwoFingerState { ids:(a.id,b.id),distance,angle });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec2;
+    use crate::{Input, event::{TouchInput, TouchPhase}};
+    use super::{GestureRecognizer, GestureEvent};
+
+    fn touch(input:&mut Input,id:u64,phase:TouchPhase,x:f32,y:f32) {
+        input.on_touch(&TouchInput { id,phase,position:Vec2::new(x, y) });
+    }
+
+    fn frame(input:&mut Input,recognizer:&mut GestureRecognizer,dt:f32) -> Vec<GestureEvent> {
+        let events = recognizer.update(input, dt);
+        input.clear();
+        events
+    }
+
+    #[test]
+    fn touch_emulate_mouse() {
+        let mut input = Input::default();
+        touch(&mut input, 1, TouchPhase::Started, 10f32, 20f32);
+        touch(&mut input, 2, TouchPhase::Started, 50f32, 20f32);
+        assert_eq!(input.touch_count(), 2);
+        assert!(input.get_mouse_down(crate::event::MouseButton::Left));
+        assert_eq!(input.mouse_position, Vec2::new(10f32, 20f32));
+        input.clear();
+        touch(&mut input, 2, TouchPhase::Moved, 60f32, 20f32);
+        assert_eq!(input.mouse_position, Vec2::new(10f32, 20f32));
+        touch(&mut input, 1, TouchPhase::Ended, 12f32, 20f32);
+        assert!(input.get_mouse_up(crate::event::MouseButton::Left));
+        input.clear();
+        assert_eq!(input.touch_count(), 1);
+        assert_eq!(input.get_touch(2).map(|t| t.phase), Some(TouchPhase::Stationary));
+    }
+
+    #[test]
+    fn tap_and_double_tap() {
+        let mut input = Input::default();
+        let mut recognizer = GestureRecognizer::default();
+        touch(&mut input, 1, TouchPhase::Started, 100f32, 100f32);
+        assert!(frame(&mut input, &mut recognizer, 0.016f32).is_empty());
+        touch(&mut input, 1, TouchPhase::Ended, 102f32, 100f32);
+        assert_eq!(frame(&mut input, &mut recognizer, 0.1f32), vec![GestureEvent::Tap(Vec2::new(102f32, 100f32))]);
+
+        touch(&mut input, 2, TouchPhase::Started, 101f32, 101f32);
+        frame(&mut input, &mut recognizer, 0.1f32);
+        touch(&mut input, 2, TouchPhase::Ended, 101f32, 101f32);
+        let events = frame(&mut input, &mut recognizer, 0.05f32);
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[1], GestureEvent::DoubleTap(Vec2::new(101f32, 101f32)));
+    }
+
+    #[test]
+    fn long_press_and_swipe() {
+        let mut input = Input::default();
+        let mut recognizer = GestureRecognizer::default();
+        touch(&mut input, 1, TouchPhase::Started, 0f32, 0f32);
+        frame(&mut input, &mut recognizer, 0f32);
+        assert!(frame(&mut input, &mut recognizer, 0.3f32).is_empty());
+        assert_eq!(frame(&mut input, &mut recognizer, 0.3f32), vec![GestureEvent::LongPress(Vec2::ZERO)]);
+        touch(&mut input, 1, TouchPhase::Ended, 0f32, 0f32);
+        assert!(frame(&mut input, &mut recognizer, 0.1f32).is_empty());
+
+        touch(&mut input, 2, TouchPhase::Started, 0f32, 0f32);
+        frame(&mut input, &mut recognizer, 0f32);
+        touch(&mut input, 2, TouchPhase::Moved, 100f32, 0f32);
+        frame(&mut input, &mut recognizer, 0.1f32);
+        touch(&mut input, 2, TouchPhase::Ended, 200f32, 0f32);
+        let events = frame(&mut input, &mut recognizer, 0.1f32);
+        match events.as_slice() {
+            [GestureEvent::Swipe { delta, .. }] => assert_eq!(*delta, Vec2::new(200f32, 0f32)),
+            _ => panic!("{:?}", events)
+        }
+    }
+
+    #[test]
+    fn pinch_and_rotate() {
+        let mut input = Input::default();
+        let mut recognizer = GestureRecognizer::default();
+        touch(&mut input, 1, TouchPhase::Started, -10f32, 0f32);
+        touch(&mut input, 2, TouchPhase::Started, 10f32, 0f32);
+        assert!(frame(&mut input, &mut recognizer, 0.016f32).is_empty());
+        touch(&mut input, 1, TouchPhase::Moved, -20f32, 0f32);
+        touch(&mut input, 2, TouchPhase::Moved, 20f32, 0f32);
+        assert_eq!(frame(&mut input, &mut recognizer, 0.016f32), vec![GestureEvent::Pinch { center:Vec2::ZERO,scale:2f32 }]);
+        touch(&mut input, 1, TouchPhase::Moved, 0f32, -20f32);
+        touch(&mut input, 2, TouchPhase::Moved, 0f32, 20f32);
+        match frame(&mut input, &mut recognizer, 0.016f32).as_slice() {
+            [GestureEvent::Rotate { angle, .. }] => assert!((angle - std::f32::consts::FRAC_PI_2).abs() < 0.0001f32),
+            events => panic!("{:?}", events)
+        }
+        //多指触摸结束后不会产生点击
+        touch(&mut input, 1, TouchPhase::Ended, 0f32, -20f32);
+        touch(&mut input, 2, TouchPhase::Ended, 0f32, 20f32);
+        assert!(frame(&mut input, &mut recognizer, 0.016f32).is_empty());
+    }
+}
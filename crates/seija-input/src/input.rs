@@ -1,8 +1,24 @@
-use std::collections::HashSet;
+use std::collections::{HashSet, HashMap};
 use bevy_ecs::system::Resource;
 use glam::Vec2;
 
-use crate::{keycode::KeyCode, event::MouseButton};
+use crate::{keycode::KeyCode, event::{MouseButton, TouchInput, TouchPhase, GamepadEvent, GamepadEventKind, GamepadButton, GamepadAxis, InputState}, gamepad::GamepadState};
+
+#[derive(Debug,Clone)]
+pub struct Touch {
+    pub id:u64,
+    pub phase:TouchPhase,
+    pub position:Vec2,
+    pub start_position:Vec2,
+    //上一帧结束时的位置
+    pub last_position:Vec2
+}
+
+impl Touch {
+    pub fn is_active(&self) -> bool {
+        self.phase != TouchPhase::Ended && self.phase != TouchPhase::Cancelled
+    }
+}
 
 #[repr(C)]
 #[derive(Default,Resource)]
@@ -17,8 +33,18 @@ pub struct Input {
     pub(crate) frame_keydown:HashSet<KeyCode>,
     pub(crate) frame_keyup:HashSet<KeyCode>,
 
+    pub(crate) mouse_pressing:HashSet<MouseButton>,
     pub(crate) frame_mousedown:HashSet<MouseButton>,
     pub(crate) frame_mouseup:HashSet<MouseButton>,
+
+    //按开始顺序排列,本帧结束的触摸点在clear时移除
+    pub(crate) touches:Vec<Touch>,
+    //模拟鼠标左键的触摸点
+    pub(crate) mouse_touch:Option<u64>,
+    //关闭后触摸不再模拟鼠标
+    pub disable_touch_mouse:bool,
+
+    pub(crate) gamepads:HashMap<u32,GamepadState>
 }
 
 impl Input {
@@ -42,6 +68,10 @@ impl Input {
         self.frame_mouseup.len() > 0
     }
 
+    pub fn get_mouse(&self,mouse:MouseButton) -> bool {
+        self.mouse_pressing.contains(&mouse)
+    }
+
     pub fn get_mouse_down(&self,mouse:MouseButton) -> bool {
         self.frame_mousedown.contains(&mouse)
     }
@@ -63,7 +93,134 @@ impl Input {
     }
 
 
+    pub fn touches(&self) -> impl Iterator<Item = &Touch> {
+        self.touches.iter()
+    }
+
+    pub fn get_touch(&self,id:u64) -> Option<&Touch> {
+        self.touches.iter().find(|t| t.id == id)
+    }
+
+    pub fn touch_count(&self) -> usize {
+        self.touches.iter().filter(|t| t.is_active()).count()
+    }
+
+    pub fn on_touch(&mut self,ev:&TouchInput) {
+        match ev.phase {
+            TouchPhase::Started => {
+                self.touches.retain(|t| t.id != ev.id);
+                self.touches.push(Touch { id:ev.id,phase:TouchPhase::Started,position:ev.position,start_position:ev.position,last_position:ev.position });
+                if self.mouse_touch.is_none() && !self.disable_touch_mouse {
+                    self.mouse_touch = Some(ev.id);
+                    self.mouse_position = ev.position;
+                    self.is_mouse_move = true;
+                    self.frame_mousedown.insert(MouseButton::Left);
+                    self.mouse_pressing.insert(MouseButton::Left);
+                }
+            },
+            phase => {
+                if let Some(touch) = self.touches.iter_mut().find(|t| t.id == ev.id) {
+                    touch.position = ev.position;
+                    //同一帧开始又移动的还算作开始
+                    if phase != TouchPhase::Moved || touch.phase != TouchPhase::Started {
+                        touch.phase = phase;
+                    }
+                } else {
+                    return;
+                }
+                if self.mouse_touch == Some(ev.id) {
+                    self.mouse_position = ev.position;
+                    self.is_mouse_move = true;
+                    if phase != TouchPhase::Moved {
+                        self.frame_mouseup.insert(MouseButton::Left);
+                        self.mouse_pressing.remove(&MouseButton::Left);
+                        self.mouse_touch = None;
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn gamepads(&self) -> impl Iterator<Item = (u32,&GamepadState)> {
+        self.gamepads.iter().map(|(id,state)| (*id,state))
+    }
+
+    pub fn get_gamepad(&self,id:u32) -> Option<&GamepadState> {
+        self.gamepads.get(&id)
+    }
+
+    pub fn get_gamepad_button(&self,id:u32,button:GamepadButton) -> bool {
+        self.gamepads.get(&id).map(|v| v.get_button(button)).unwrap_or(false)
+    }
+
+    pub fn get_gamepad_button_down(&self,id:u32,button:GamepadButton) -> bool {
+        self.gamepads.get(&id).map(|v| v.get_button_down(button)).unwrap_or(false)
+    }
+
+    pub fn get_gamepad_button_up(&self,id:u32,button:GamepadButton) -> bool {
+        self.gamepads.get(&id).map(|v| v.get_button_up(button)).unwrap_or(false)
+    }
+
+    pub fn get_gamepad_axis(&self,id:u32,axis:GamepadAxis) -> f32 {
+        self.gamepads.get(&id).map(|v| v.get_axis(axis)).unwrap_or(0f32)
+    }
+
+    //任意一个手柄,用于不区分玩家的情况
+    pub fn any_gamepad_button(&self,button:GamepadButton) -> bool {
+        self.gamepads.values().any(|v| v.get_button(button))
+    }
+
+    pub fn any_gamepad_button_down(&self,button:GamepadButton) -> bool {
+        self.gamepads.values().any(|v| v.get_button_down(button))
+    }
+
+    //绝对值最大的那个手柄的值
+    pub fn any_gamepad_axis(&self,axis:GamepadAxis) -> f32 {
+        self.gamepads.values().map(|v| v.get_axis(axis)).fold(0f32, |a,b| if b.abs() > a.abs() { b } else { a })
+    }
+
+    pub fn on_gamepad(&mut self,ev:&GamepadEvent) {
+        match &ev.kind {
+            GamepadEventKind::Connected(name) => {
+                let state = self.gamepads.entry(ev.gamepad).or_default();
+                state.name = name.clone();
+            },
+            GamepadEventKind::Disconnected => {
+                self.gamepads.remove(&ev.gamepad);
+            },
+            GamepadEventKind::Button(button,input_state) => {
+                let state = self.gamepads.entry(ev.gamepad).or_default();
+                match input_state {
+                    InputState::Pressed => {
+                        if state.pressing.insert(*button) {
+                            state.frame_down.insert(*button);
+                        }
+                    },
+                    InputState::Released => {
+                        if state.pressing.remove(button) {
+                            state.frame_up.insert(*button);
+                        }
+                    }
+                }
+            },
+            GamepadEventKind::Axis(axis,value) => {
+                self.gamepads.entry(ev.gamepad).or_default().axes.insert(*axis, *value);
+            }
+        }
+    }
+
     pub(crate) fn clear(&mut self) {
+        for state in self.gamepads.values_mut() {
+            state.frame_down.clear();
+            state.frame_up.clear();
+        }
+
+        self.touches.retain(|t| t.is_active());
+        for touch in self.touches.iter_mut() {
+            touch.phase = TouchPhase::Stationary;
+            touch.last_position = touch.position;
+        }
+
         self.frame_keydown.clear();
         self.frame_keyup.clear();
 
@@ -203,4 +203,9 @@ pub enum KeyCode {
 
 impl KeyCode {
     pub unsafe fn from_u32(n:u32) -> KeyCode {  KeyCode::from_unchecked(n) }
+
+    //按枚举名查找,用于读取配置文件
+    pub fn from_name(name:&str) -> Option<KeyCode> {
+        (0..=KeyCode::Unknow as u32).map(|n| unsafe { KeyCode::from_u32(n) }).find(|k| format!("{:?}",k) == name)
+    }
 }
\ No newline at end of file
@@ -1,11 +1,16 @@
-use event::{KeyboardInput, InputState, MouseInput, MouseWheelInput};
+use event::{KeyboardInput, InputState, MouseInput, MouseWheelInput, TouchInput, GamepadEvent};
 
-use seija_app::{IModule, App, ecs::{world::World, system::ResMut, prelude::EventReader}};
-use seija_core::{AddCore, CoreStage};
+use action::InputMap;
+use gesture::{GestureRecognizer, GestureEvent};
+use seija_app::{IModule, App, ecs::{world::World, system::{Res, ResMut}, prelude::{EventReader, EventWriter}, schedule::IntoSystemDescriptor}};
+use seija_core::{AddCore, CoreStage, time::Time};
 pub mod keycode;
 pub mod event;
+pub mod gesture;
+pub mod action;
+pub mod gamepad;
 mod input;
-pub use input::{Input};
+pub use input::{Input, Touch};
 pub mod ffi;
 pub struct InputModule;
 
@@ -15,8 +20,17 @@ impl IModule for InputModule {
         app.add_event::<event::KeyboardInput>();
         app.add_event::<event::MouseInput>();
         app.add_event::<event::MouseWheelInput>();
+        app.add_event::<event::TouchInput>();
+        app.init_resource::<GestureRecognizer>();
+        app.add_event::<GestureEvent>();
+        app.init_resource::<InputMap>();
+        app.add_event::<GamepadEvent>();
+        app.add_event::<event::GamepadRumble>();
         
+        app.add_system(CoreStage::PreUpdate, gamepad::gamepad_backend_system.before(input_system));
         app.add_system(CoreStage::PreUpdate, input_system);
+        app.add_system(CoreStage::PreUpdate, gesture_system.after(input_system));
+        app.add_system(CoreStage::PreUpdate, action_system.after(input_system));
         app.add_system(CoreStage::Last, clear_input);
     }
 
@@ -27,7 +41,9 @@ impl IModule for InputModule {
 
 fn input_system(mut input:ResMut<Input>,mut key_inputs:EventReader<KeyboardInput>,
                                         mut mouse_inputs:EventReader<MouseInput>,
-                                        mut mouse_wheel_inputs:EventReader<MouseWheelInput>) {
+                                        mut mouse_wheel_inputs:EventReader<MouseWheelInput>,
+                                        mut touch_inputs:EventReader<TouchInput>,
+                                        mut gamepad_inputs:EventReader<GamepadEvent>) {
     for key in key_inputs.iter() {
         match key.state {
             InputState::Pressed => {
@@ -47,9 +63,11 @@ fn input_system(mut input:ResMut<Input>,mut key_inputs:EventReader<KeyboardInput
         match mouse.state {
             InputState::Pressed =>  {
                 input.frame_mousedown.insert(mouse.button);
+                input.mouse_pressing.insert(mouse.button);
             },
             InputState::Released => {
                 input.frame_mouseup.insert(mouse.button);
+                input.mouse_pressing.remove(&mouse.button);
             }
         }
     }
@@ -58,10 +76,28 @@ fn input_system(mut input:ResMut<Input>,mut key_inputs:EventReader<KeyboardInput
         input.frame_mouse_wheel = Some(mouse_wheel.delta);
     }
 
+    for touch in touch_inputs.iter() {
+        input.on_touch(touch);
+    }
+
+    for gamepad in gamepad_inputs.iter() {
+        input.on_gamepad(gamepad);
+    }
+
     input.mouse_move_delta = input.mouse_position - input.last_frame_pos;
     input.mouse_move_delta.y = -input.mouse_move_delta.y;
 }
 
+fn gesture_system(input:Res<Input>,time:Res<Time>,mut recognizer:ResMut<GestureRecognizer>,mut gestures:EventWriter<GestureEvent>) {
+    for gesture in recognizer.update(&input, time.delta_seconds()) {
+        gestures.send(gesture);
+    }
+}
+
+fn action_system(input:Res<Input>,mut input_map:ResMut<InputMap>) {
+    input_map.update(&input);
+}
+
 fn clear_input(mut input:ResMut<Input>) {
     input.clear();
 }
\ No newline at end of file
@@ -3,7 +3,7 @@ use lite_clojure_eval::{Variable, GcRefCell};
 use seija_render::{dsl_frp::{IUpdateNode, FRPSystem}, RenderContext, UniformIndex};
 use anyhow::{Result,anyhow};
 
-use crate::{PBRCameraInfo};
+use crate::{PBRCameraInfo, Exposure};
 pub struct PBRCameraNode {
     pub ubo_name:String,
     name_index:UniformIndex,
@@ -35,10 +35,11 @@ impl IUpdateNode for PBRCameraNode {
     }
 
     fn prepare(&mut self,world:&mut World,ctx:&mut RenderContext,_:&mut FRPSystem) -> Result<()> {
-        let mut cameras = world.query_filtered::<(Entity,&PBRCameraInfo),Or<(Changed<PBRCameraInfo>,Added<PBRCameraInfo>)>>();
-        for (e,ex_info) in cameras.iter(world) {
+        let mut cameras = world.query_filtered::<(Entity,&PBRCameraInfo,Option<&Exposure>),Or<(Changed<PBRCameraInfo>,Added<PBRCameraInfo>,Changed<Exposure>)>>();
+        for (e,ex_info,exposure) in cameras.iter(world) {
+            let exposure = exposure.unwrap_or(&ex_info.exposure);
             ctx.ubo_ctx.set_buffer(&self.name_index, Some(e),|buffer| {
-                buffer.buffer.write_bytes(self.exposure_index, ex_info.exposure.exposure_self());
+                buffer.buffer.write_bytes(self.exposure_index, exposure.exposure_self());
             })
         }
         Ok(())
@@ -1,7 +1,13 @@
+use bevy_ecs::prelude::Component;
+use seija_core::reflect::Reflect;
+
+//相机上挂了Exposure组件时使用组件的曝光参数代替PBRCameraInfo.exposure
+#[derive(Reflect,Component,Clone,Copy,Debug)]
 pub struct Exposure {
     //光圈
     pub aperture:f32,
     //快门
+    #[reflect(name = "shutterSpeed")]
     pub shutter_speed:f32,
     //ISO
     pub sensitivity:f32,
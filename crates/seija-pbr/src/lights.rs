@@ -1,5 +1,6 @@
 use bevy_ecs::{prelude::Component, system::Resource};
 use glam::{f32, Vec2,  Vec3};
+use seija_core::{impl_reflect_enum, reflect::Reflect};
 
 #[derive(PartialEq, Eq)]
 pub enum PBRLightType {
@@ -19,6 +20,15 @@ impl PBRLightType {
             PBRLightType::FocusedSpot => { 1 },
         }
     }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PBRLightType::Directional => "Directional",
+            PBRLightType::Point => "Point",
+            PBRLightType::Spot => "Spot",
+            PBRLightType::FocusedSpot => "FocusedSpot",
+        }
+    }
 }
 
 impl TryFrom<&str> for PBRLightType {
@@ -39,30 +49,67 @@ impl Default for PBRLightType {
     fn default() -> Self { PBRLightType::Directional }
 }
 
-#[derive(Default,Component)]
+impl_reflect_enum!(PBRLightType,Directional,Point,Spot,FocusedSpot);
+
+//通过反射设置字段后重新计算下划线开头的缓存
+#[derive(Component,Reflect)]
+#[reflect(on_set = "update_params")]
 pub struct PBRLight {
+    #[reflect(name = "mainLight")]
     pub main_light:bool,
+    #[reflect(name = "type")]
     typ: PBRLightType,
     pub color: Vec3,
     //点光源聚光灯是辐射通量/光通量,平行光是辐射照度/光照度，
     intensity: f32,
     //发光强度，坎德拉
+    #[reflect(name = "falloff")]
     falloff_radius: f32,
+    #[reflect(name = "inner")]
     inner_angle: f32,
+    #[reflect(name = "outer")]
     outer_angle: f32,
 
+    #[reflect(skip)]
     _cos_outer_squared: f32,
+    #[reflect(skip)]
     _luminous_intensity: f32,
+    #[reflect(skip)]
     _scale_offset: Vec2,
+    #[reflect(skip)]
     _squared_fall_offinv: f32,
 }
 
+impl Default for PBRLight {
+    fn default() -> Self {
+        let mut light = PBRLight {
+            main_light:false,
+            typ:PBRLightType::Directional,
+            color:Vec3::ONE,
+            intensity:100000f32,
+            falloff_radius:10f32,
+            inner_angle:45f32,
+            outer_angle:50f32,
+            _cos_outer_squared:0f32,
+            _luminous_intensity:0f32,
+            _scale_offset:Vec2::ZERO,
+            _squared_fall_offinv:0f32
+        };
+        light.update_params();
+        light
+    }
+}
+
 impl PBRLight {
 
     pub fn get_type(&self) -> &PBRLightType {
         &self.typ
     }
 
+    pub fn get_intensity(&self) -> f32 {
+        self.intensity
+    }
+
     pub fn get_luminous_intensity(&self) -> f32 {
         self._luminous_intensity
     }
@@ -70,6 +117,10 @@ impl PBRLight {
     pub fn get_falloff(&self) -> f32 {
         self.falloff_radius
     }
+    pub fn get_spot_cone(&self) -> (f32,f32) {
+        (self.inner_angle,self.outer_angle)
+    }
+
     pub fn get_squared_fall_offinv(&self) -> f32 {
         self._squared_fall_offinv
     }
@@ -153,6 +204,12 @@ impl PBRLight {
         self.calc_spot();
     }
 
+    fn update_params(&mut self) {
+        self.calc_falloff();
+        self.calc_spot();
+        self.calc_intensity();
+    }
+
     fn calc_spot(&mut self) {
         let mut inner_clamped = self
             .inner_angle
@@ -1,59 +1,30 @@
 use relative_path::RelativePath;
-use seija_asset::AssetServer;
-use seija_core::{bevy_ecs::{system::{CommandQueue, Insert}, prelude::Entity}, anyhow::{Result,anyhow, bail}, smol_str::SmolStr, uuid::Uuid, TypeUuid, math::Vec3};
+use seija_asset::{AssetServer, Handle};
+use seija_core::{bevy_ecs::{system::{CommandQueue, Insert}, prelude::Entity, world::World}, anyhow::{Result,anyhow}, smol_str::SmolStr, uuid::Uuid, TypeUuid};
 use seija_gltf::asset::GltfAsset;
-use seija_pbr::{PBRCameraInfo, lights::{PBRLight, PBRLightType}};
-use seija_render::{camera::camera::{Camera, Perspective, Projection}, resource::Mesh, material::Material};
+use seija_pbr::{PBRCameraInfo, Exposure, lights::PBRLight};
+use seija_render::{camera::camera::Camera, resource::Mesh, material::Material};
 use seija_template::{TComponent,ITComponentOpt,AddTComponent};
 use seija_app::App;
+mod ui;
+pub use ui::add_ui_templates;
 
+//Mesh和Material是资源句柄,需要解析资源路径,其他组件通过反射读写
 pub fn add_render_templates(app:&mut App) {
-    app.add_tcomponent_opt("Camera", TComponentCameraOpt);
     app.add_tcomponent_opt("Mesh", TComponentMeshOpt);
     app.add_tcomponent_opt("PBRCameraInfo", TComponentPBRCameraInfoOpt);
     app.add_tcomponent_opt("Material", TComponentMaterialOpt);
-    app.add_tcomponent_opt("PBRLight", TComponentLightOpt);
+    app.add_tcomponent_reflect::<Camera>();
+    app.add_tcomponent_reflect::<PBRLight>();
+    app.add_tcomponent_reflect::<Exposure>();
 }
 
-pub(crate) struct TComponentCameraOpt;
-
-impl ITComponentOpt for TComponentCameraOpt {
-    fn search_assets(&self, _: &mut TComponent,_:&RelativePath) -> Result<Vec<(Uuid,SmolStr)>> {
-       Ok(vec![])
-    }
-
-    fn create_component(&self,_:&AssetServer, component: &TComponent,queue:&mut CommandQueue,entity:Entity)-> Result<()> {
-        let mut camera = Camera::default();
-        let camera_type = component.attrs.get("type").map(|v| v.as_str()).unwrap_or("Perspective");
-        if let Some(cull_str) = component.attrs.get("cull").map(|v| v.as_str()) {
-            camera.cull_type = cull_str.parse()?;
-        }
-        if let Some(is_hdr_str) = component.attrs.get("isHDR").map(|v| v.as_str()) {
-            camera.is_hdr = is_hdr_str.parse()?;
-        }
-        match camera_type {
-            "Perspective" =>  {
-                let mut per = Perspective::default();
-                if let Some(fov) = component.attrs.get("fov") {
-                    per.fov = fov.parse::<f32>()?.to_radians();
-                }
-                if let Some(near) = component.attrs.get("near") {
-                    per.near = near.parse::<f32>()?;
-                }
-                if let Some(far) = component.attrs.get("far") {
-                    per.far = far.parse::<f32>()?;
-                }
-                if let Some(aspect) = component.attrs.get("aspect") {
-                    per.aspect_ratio = aspect.parse::<f32>()?;
-                }
-                camera.projection = Projection::Perspective(per);
-            },
-            "Orthographic" => {}
-            _ => { bail!("error camera type:{}",camera_type) },
-        }
-        let insert = Insert {entity,bundle:camera};
-        queue.push(insert);
-        Ok(())
+//模板中以/开头的资源路径相对资源根目录,其他的相对模板所在目录
+pub(crate) fn res_asset_path(res_path:&str,template_path:&RelativePath) -> SmolStr {
+    if res_path.starts_with('/') {
+        res_path.trim_start_matches('/').into()
+    } else {
+        template_path.join_normalized(res_path).as_str().into()
     }
 }
 
@@ -66,7 +37,11 @@ impl ITComponentOpt for TComponentMeshOpt {
                 let split_names = res_path.split(".gltf").collect::<Vec<_>>();
                 let path = split_names[0];
                 let file_path = format!("{}.gltf",path);
-                let asset_path = template_path.join_normalized(file_path.as_str());
+                let asset_path = if file_path.starts_with('/') {
+                    RelativePath::new(file_path.trim_start_matches('/')).normalize()
+                } else {
+                    template_path.join_normalized(file_path.as_str())
+                };
                 let mesh_path = format!("{}{}",asset_path.as_str(),split_names[1]) ;
                 component.rt_attrs.insert("res".into(),mesh_path.into());
                 return Ok(vec![(GltfAsset::TYPE_UUID,asset_path.as_str().into())]);
@@ -87,6 +62,29 @@ impl ITComponentOpt for TComponentMeshOpt {
         }
         Err(anyhow!("Mesh need res"))
     }
+
+    fn serialize_component(&self,world:&World,server:&AssetServer,entity:Entity) -> Result<Option<TComponent>> {
+        let h_mesh = match world.get::<Handle<Mesh>>(entity) {
+            Some(v) => v,
+            None => return Ok(None)
+        };
+        //代码里创建的mesh没有资源路径,无法写入模板
+        let path = match server.get_path(h_mesh.id) {
+            Some(path) => path,
+            None => {
+                log::warn!("skip serialize mesh without asset path:{:?}",h_mesh.id);
+                return Ok(None);
+            }
+        };
+        let mut component = TComponent::new("Mesh".into());
+        //gltf中的mesh使用相对资源根目录的路径,内置mesh直接写名字
+        if path.contains(".gltf") {
+            component.attrs.insert("res".into(), format!("/{}",path).into());
+        } else {
+            component.attrs.insert("res".into(), path);
+        }
+        Ok(Some(component))
+    }
 }
 
 pub(crate) struct TComponentPBRCameraInfoOpt;
@@ -97,6 +95,14 @@ impl ITComponentOpt for TComponentPBRCameraInfoOpt {
         queue.push(Insert {entity,bundle:info });
         Ok(())
     }
+
+    //曝光参数由Exposure组件写出
+    fn serialize_component(&self,world:&World,_:&AssetServer,entity:Entity) -> Result<Option<TComponent>> {
+        if world.get::<PBRCameraInfo>(entity).is_none() {
+            return Ok(None);
+        }
+        Ok(Some(TComponent::new("PBRCameraInfo".into())))
+    }
 }
 
 pub(crate) struct TComponentMaterialOpt;
@@ -104,16 +110,9 @@ pub(crate) struct TComponentMaterialOpt;
 impl ITComponentOpt for TComponentMaterialOpt {
     fn search_assets(&self, component: &mut TComponent,template_path:&RelativePath) -> Result<Vec<(Uuid,SmolStr)>> {
         if let Some(res_path) = component.attrs.get("res") {
-            if res_path.starts_with('/') {
-                let asset_path = res_path.trim_start_matches('/');
-                component.rt_attrs.insert("res".into(), asset_path.into());
-                return Ok(vec![(Material::TYPE_UUID,asset_path.into())]);
-            } else {
-                let asset_path = template_path.join_normalized(res_path.as_str());
-                component.rt_attrs.insert("res".into(), asset_path.as_str().into());
-                return Ok(vec![(Material::TYPE_UUID,asset_path.as_str().into())]);
-            }
-            
+            let asset_path = res_asset_path(res_path, template_path);
+            component.rt_attrs.insert("res".into(), asset_path.clone());
+            return Ok(vec![(Material::TYPE_UUID,asset_path)]);
         }
         Ok(vec![])
     }
@@ -127,32 +126,83 @@ impl ITComponentOpt for TComponentMaterialOpt {
         }
         Ok(())
     }
-}
-
 
-pub(crate) struct TComponentLightOpt;
-
-impl ITComponentOpt for TComponentLightOpt {
-    fn create_component(&self,_:&AssetServer, component: &TComponent,queue:&mut CommandQueue,entity:Entity)-> Result<()> {
-        let light_type = component.attrs.get("type").map(|v| v.as_str()).unwrap_or("Directional");
-        let typ = PBRLightType::try_from(light_type).map_err(|_| anyhow!("PBRLight Type"))?;
-        let intensity:f32 = component.attrs.get("intensity").map(|v| v.as_str()).unwrap_or("100000").parse()?;
-        let color = component.read_v3("color").unwrap_or(Vec3::ONE);
-        
-        let light = match typ {
-            PBRLightType::Directional => { PBRLight::directional(color, intensity) },
-            PBRLightType::Point => {
-                let falloff = component.read_float("falloff", 10f32);
-                PBRLight::point(color, intensity, falloff)
-            },
-            PBRLightType::Spot | PBRLightType::FocusedSpot => {
-                let falloff = component.read_float("falloff", 10f32);
-                let inner = component.read_float("inner", 45f32);
-                let outer = component.read_float("outer", 50f32);
-                PBRLight::spot(color, intensity, falloff, inner, outer, typ == PBRLightType::Spot)
+    fn serialize_component(&self,world:&World,server:&AssetServer,entity:Entity) -> Result<Option<TComponent>> {
+        let h_material = match world.get::<Handle<Material>>(entity) {
+            Some(v) => v,
+            None => return Ok(None)
+        };
+        let path = match server.get_path(h_material.id) {
+            Some(path) => path,
+            None => {
+                log::warn!("skip serialize material without asset path:{:?}",h_material.id);
+                return Ok(None);
             }
         };
-        queue.push(Insert {entity,bundle:light });
-        Ok(())
+        let mut component = TComponent::new("Material".into());
+        component.attrs.insert("res".into(), format!("/{}",path).into());
+        Ok(Some(component))
+    }
+}
+
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use seija_app::App;
+    use seija_asset::{AssetModule, HandleId};
+    use seija_core::{CoreModule, bevy_ecs::system::CommandQueue, math::Vec3};
+    use seija_pbr::lights::PBRLightType;
+    use seija_render::camera::camera::{Orthographic, Projection};
+    use seija_template::{TComponentManager, TemplateModule, read_tmpl_entity, serialize_entity_xml};
+    use super::*;
+
+    pub(crate) fn new_app() -> App {
+        let mut app = App::new();
+        app.add_module(CoreModule);
+        app.add_module(AssetModule(std::env::temp_dir()));
+        app.add_module(TemplateModule);
+        add_render_templates(&mut app);
+        add_ui_templates(&mut app);
+        app.start();
+        app
     }
-}
\ No newline at end of file
+
+    //写出实体再按模板创建到新的实体上
+    pub(crate) fn reload_entity(world:&mut World,entity:Entity) -> Entity {
+        let xml = serialize_entity_xml(world, entity).unwrap();
+        let tentity = read_tmpl_entity(&xml).unwrap();
+        let mgr = world.get_resource::<TComponentManager>().unwrap().clone();
+        let server = world.get_resource::<AssetServer>().unwrap().clone();
+        let loaded = world.spawn_empty().id();
+        let mut queue = CommandQueue::default();
+        for tcomponent in tentity.components.iter() {
+            mgr.create(tcomponent, &server, &mut queue, loaded).unwrap();
+        }
+        queue.apply(world);
+        loaded
+    }
+
+    #[test]
+    fn save_load_round_trip() {
+        let mut app = new_app();
+        let world = &mut app.world;
+        let ortho = Orthographic { left:-2.0,right:2.0,bottom:-1.5,top:1.5,near:0.1,far:50.0 };
+        let light = PBRLight::spot(Vec3::new(1.0, 0.5, 0.25), 2000.0, 8.0, 30.0, 40.0, false);
+        let h_mesh = Handle::<Mesh>::weak(HandleId::random::<Mesh>());
+        let entity = world.spawn((Camera::from_2d(ortho),light,h_mesh)).id();
+        let xml = serialize_entity_xml(world, entity).unwrap();
+        assert!(!xml.contains("<Mesh"));
+        assert!(!xml.contains("fov="));
+
+        let loaded = reload_entity(world, entity);
+        match &world.get::<Camera>(loaded).unwrap().projection {
+            Projection::Ortho(v) => assert_eq!((v.left,v.right,v.bottom,v.top,v.near,v.far), (-2.0,2.0,-1.5,1.5,0.1,50.0)),
+            _ => panic!("camera projection error")
+        }
+        let light = world.get::<PBRLight>(loaded).unwrap();
+        assert!(*light.get_type() == PBRLightType::Spot);
+        assert_eq!(light.get_spot_cone(), (30.0,40.0));
+        assert_eq!(light.get_intensity(), 2000.0);
+        assert_eq!(light.color, Vec3::new(1.0, 0.5, 0.25));
+    }
+}
@@ -0,0 +1,224 @@
+use relative_path::RelativePath;
+use seija_asset::{Asset, AssetServer, Handle};
+use seija_core::{bevy_ecs::{system::{CommandQueue, Insert}, prelude::Entity, world::World}, anyhow::{Result,anyhow,bail}, smol_str::SmolStr, uuid::Uuid, TypeUuid, math::{Vec2, Vec4}};
+use seija_2d::common::{ImageGenericInfo, ImageType, ImageFilledType};
+use seija_template::{TComponent,ITComponentOpt,AddTComponent};
+use seija_app::App;
+use seija_ui::{Rect2D, types::AnchorAlign, text::{Text, Font, LineMode, TextEffect}};
+use seija_ui::components::{sprite::Sprite, canvas::Canvas, ui_canvas::UICanvas, input::Input, button::Button, toggle::Toggle,
+                           slider::Slider, scroll_view::ScrollView, list_view::ListView, focus::Focusable};
+use seija_ui::layout::{types::{LayoutElement, FreeLayoutItem}, comps::FlexItem};
+use spritesheet::SpriteSheet;
+use crate::res_asset_path;
+
+//Sprite和Text引用资源,需要解析资源路径,其他组件通过反射读写
+//UI组件之间的entity引用(ScrollView.content_entity等)不会写入模板,实例化后需要在代码里设置
+pub fn add_ui_templates(app:&mut App) {
+    app.add_tcomponent_reflect::<Rect2D>();
+    app.add_tcomponent_reflect::<UICanvas>();
+    app.add_tcomponent_reflect::<Canvas>();
+    app.add_tcomponent_reflect::<FreeLayoutItem>();
+    app.add_tcomponent_reflect::<Input>();
+    app.add_tcomponent_reflect::<Button>();
+    app.add_tcomponent_reflect::<Toggle>();
+    app.add_tcomponent_reflect::<Slider>();
+    app.add_tcomponent_reflect::<ScrollView>();
+    app.add_tcomponent_reflect::<ListView>();
+    app.add_tcomponent_reflect::<Focusable>();
+    app.add_tcomponent_reflect::<LayoutElement>();
+    app.add_tcomponent_reflect::<FlexItem>();
+    app.add_tcomponent_opt("Sprite", TComponentSpriteOpt);
+    app.add_tcomponent_opt("Text", TComponentTextOpt);
+}
+
+//把资源属性解析成资源路径放入rt_attrs
+fn search_res(component:&mut TComponent,template_path:&RelativePath,attrs:&[(&str,Uuid)]) -> Vec<(Uuid,SmolStr)> {
+    let mut assets = vec![];
+    for (name,typ) in attrs.iter() {
+        if let Some(res_path) = component.attrs.get(*name) {
+            let asset_path = res_asset_path(res_path, template_path);
+            component.rt_attrs.insert((*name).into(), asset_path.clone());
+            assets.push((*typ,asset_path));
+        }
+    }
+    assets
+}
+
+fn read_res<T:Asset>(server:&AssetServer,component:&TComponent,name:&str) -> Result<Option<Handle<T>>> {
+    match component.rt_attrs.get(name) {
+        Some(res_path) => {
+            let info = server.get_asset(res_path).ok_or(anyhow!("not found {}.{} res:{}",component.typ,name,res_path))?;
+            Ok(Some(info.make_handle().typed::<T>()))
+        },
+        None => Ok(None)
+    }
+}
+
+fn write_res<T:Asset>(server:&AssetServer,component:&mut TComponent,name:&str,handle:Option<&Handle<T>>) {
+    if let Some(handle) = handle {
+        match server.get_path(handle.id) {
+            Some(path) => { component.attrs.insert(name.into(), format!("/{}",path).into()); },
+            None => log::warn!("skip serialize {}.{} without asset path:{:?}",component.typ,name,handle.id)
+        }
+    }
+}
+
+pub(crate) struct TComponentSpriteOpt;
+
+impl ITComponentOpt for TComponentSpriteOpt {
+    fn search_assets(&self, component: &mut TComponent,template_path:&RelativePath) -> Result<Vec<(Uuid,SmolStr)>> {
+        Ok(search_res(component, template_path, &[("atlas",SpriteSheet::TYPE_UUID)]))
+    }
+
+    fn create_component(&self,server:&AssetServer, component: &TComponent,queue:&mut CommandQueue,entity:Entity)-> Result<()> {
+        let atlas = read_res::<SpriteSheet>(server, component, "atlas")?;
+        let typ_name = component.attrs.get("type").map(|v| v.as_str()).unwrap_or("Simple");
+        let typ = match typ_name {
+            "Simple" => ImageType::Simple,
+            "Sliced" => ImageType::Sliced(component.read_value("border")?.unwrap_or_default()),
+            "Filled" => {
+                let fill_type = component.read_value("fillType")?.unwrap_or(ImageFilledType::HorizontalLeft);
+                ImageType::Filled(fill_type, component.read_value("fillAmount")?.unwrap_or(1f32))
+            },
+            "Tiled" => ImageType::Tiled,
+            _ => bail!("error sprite type:{}",typ_name)
+        };
+        let color = component.read_value("color")?.unwrap_or(Vec4::ONE);
+        let sprite_index = component.read_value("sprite")?.unwrap_or(0usize);
+        queue.push(Insert {entity,bundle:Sprite { info:ImageGenericInfo { typ,color },atlas,sprite_index } });
+        Ok(())
+    }
+
+    fn serialize_component(&self,world:&World,server:&AssetServer,entity:Entity) -> Result<Option<TComponent>> {
+        let sprite = match world.get::<Sprite>(entity) {
+            Some(v) => v,
+            None => return Ok(None)
+        };
+        let mut component = TComponent::new("Sprite".into());
+        write_res(server, &mut component, "atlas", sprite.atlas.as_ref());
+        component.write_value("sprite", &sprite.sprite_index);
+        component.write_value("color", &sprite.info.color);
+        match &sprite.info.typ {
+            ImageType::Simple => { component.attrs.insert("type".into(), "Simple".into()); },
+            ImageType::Sliced(border) => {
+                component.attrs.insert("type".into(), "Sliced".into());
+                component.write_value("border", border);
+            },
+            ImageType::Filled(fill_type,amount) => {
+                component.attrs.insert("type".into(), "Filled".into());
+                component.write_value("fillType", fill_type);
+                component.write_value("fillAmount", amount);
+            },
+            ImageType::Tiled => { component.attrs.insert("type".into(), "Tiled".into()); }
+        }
+        Ok(Some(component))
+    }
+}
+
+pub(crate) struct TComponentTextOpt;
+
+impl ITComponentOpt for TComponentTextOpt {
+    fn search_assets(&self, component: &mut TComponent,template_path:&RelativePath) -> Result<Vec<(Uuid,SmolStr)>> {
+        Ok(search_res(component, template_path, &[("font",Font::TYPE_UUID),("boldFont",Font::TYPE_UUID),("spriteSheet",SpriteSheet::TYPE_UUID)]))
+    }
+
+    fn create_component(&self,server:&AssetServer, component: &TComponent,queue:&mut CommandQueue,entity:Entity)-> Result<()> {
+        let effect = TextEffect {
+            outline_width:component.read_value("outlineWidth")?.unwrap_or(0f32),
+            outline_color:component.read_value("outlineColor")?.unwrap_or(Vec4::ZERO),
+            glow_width:component.read_value("glowWidth")?.unwrap_or(0f32),
+            glow_color:component.read_value("glowColor")?.unwrap_or(Vec4::ZERO),
+            shadow_offset:component.read_value("shadowOffset")?.unwrap_or(Vec2::ZERO),
+            shadow_softness:component.read_value("shadowSoftness")?.unwrap_or(0f32),
+            shadow_color:component.read_value("shadowColor")?.unwrap_or(Vec4::ZERO)
+        };
+        let text = Text {
+            color:component.read_value("color")?.unwrap_or(Vec4::ONE),
+            anchor:component.read_value("anchor")?.unwrap_or(AnchorAlign::Center),
+            line_mode:component.read_value("lineMode")?.unwrap_or(LineMode::Single),
+            is_auto_size:component.read_value("autoSize")?.unwrap_or(true),
+            font_size:component.read_value("fontSize")?.unwrap_or(24),
+            font:read_res(server, component, "font")?,
+            text:component.read_value("text")?.unwrap_or_default(),
+            rich:component.read_value("rich")?.unwrap_or(false),
+            bold_font:read_res(server, component, "boldFont")?,
+            sprite_sheet:read_res(server, component, "spriteSheet")?,
+            sdf:component.read_value("sdf")?.unwrap_or(false),
+            effect,
+            world_space:component.read_value("worldSpace")?.unwrap_or(false)
+        };
+        queue.push(Insert {entity,bundle:text });
+        Ok(())
+    }
+
+    fn serialize_component(&self,world:&World,server:&AssetServer,entity:Entity) -> Result<Option<TComponent>> {
+        let text = match world.get::<Text>(entity) {
+            Some(v) => v,
+            None => return Ok(None)
+        };
+        let mut component = TComponent::new("Text".into());
+        component.write_value("text", &text.text);
+        component.write_value("fontSize", &text.font_size);
+        component.write_value("color", &text.color);
+        component.write_value("anchor", &text.anchor);
+        component.write_value("lineMode", &text.line_mode);
+        component.write_value("autoSize", &text.is_auto_size);
+        component.write_value("rich", &text.rich);
+        component.write_value("worldSpace", &text.world_space);
+        write_res(server, &mut component, "font", text.font.as_ref());
+        write_res(server, &mut component, "boldFont", text.bold_font.as_ref());
+        write_res(server, &mut component, "spriteSheet", text.sprite_sheet.as_ref());
+        component.write_value("sdf", &text.sdf);
+        //效果参数只在sdf文字上生效
+        if text.sdf {
+            let effect = &text.effect;
+            component.write_value("outlineWidth", &effect.outline_width);
+            component.write_value("outlineColor", &effect.outline_color);
+            component.write_value("glowWidth", &effect.glow_width);
+            component.write_value("glowColor", &effect.glow_color);
+            component.write_value("shadowOffset", &effect.shadow_offset);
+            component.write_value("shadowSoftness", &effect.shadow_softness);
+            component.write_value("shadowColor", &effect.shadow_color);
+        }
+        Ok(Some(component))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use seija_ui::Thickness;
+    use seija_ui::layout::{types::{TypeElement, SizeValue}, comps::{FlexLayout, FlexDirection, FlexBasis}};
+    use seija_template::serialize_entity_xml;
+    use crate::tests::{new_app, reload_entity};
+    use super::*;
+
+    #[test]
+    fn layout_sprite_round_trip() {
+        let mut app = new_app();
+        let world = &mut app.world;
+        let mut layout = LayoutElement::create_flex(FlexLayout { direction:FlexDirection::Column,..Default::default() });
+        layout.common.margin = Thickness { left:1.0,top:2.0,right:3.0,bottom:4.0 };
+        layout.common.ui_size.width = SizeValue::PixelFromRect;
+        layout.common.ui_size.height = SizeValue::Pixel(20.0);
+        let item = FlexItem { grow:2.0,basis:FlexBasis { length:50.0,is_relative:true },..Default::default() };
+        let sprite = Sprite::sliced(3, None, Thickness::new1(2.0), Vec4::new(1.0, 0.5, 0.5, 1.0));
+        let entity = world.spawn((layout,item,sprite)).id();
+        let xml = serialize_entity_xml(world, entity).unwrap();
+        assert!(xml.contains("direction=\"Column\""));
+        assert!(xml.contains("basis=\"50%\""));
+
+        let loaded = reload_entity(world, entity);
+        let layout = world.get::<LayoutElement>(loaded).unwrap();
+        assert!(matches!(&layout.typ_elem, TypeElement::Flex(flex) if flex.direction == FlexDirection::Column));
+        assert_eq!(layout.common.margin, Thickness { left:1.0,top:2.0,right:3.0,bottom:4.0 });
+        assert!(matches!(layout.common.ui_size.width, SizeValue::PixelFromRect));
+        assert_eq!(layout.common.ui_size.height.get_pixel(), 20.0);
+        let item = world.get::<FlexItem>(loaded).unwrap();
+        assert_eq!(item.grow, 2.0);
+        assert!(item.basis.is_relative && item.basis.length == 50.0);
+        let sprite = world.get::<Sprite>(loaded).unwrap();
+        assert_eq!(sprite.sprite_index, 3);
+        assert_eq!(sprite.info.color, Vec4::new(1.0, 0.5, 0.5, 1.0));
+        assert!(matches!(&sprite.info.typ, ImageType::Sliced(border) if *border == Thickness::new1(2.0)));
+    }
+}
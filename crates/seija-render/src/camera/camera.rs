@@ -1,6 +1,9 @@
 use bevy_ecs::prelude::Component;
 use glam::Mat4;
 use seija_asset::Handle;
+use seija_core::anyhow::{Result, bail};
+use seija_core::impl_reflect_enum;
+use seija_core::reflect::{Reflect, ReflectStatic, ReflectValue, ReflectType, IReflectValue, FieldInfo, field_type_error, not_found_field_error};
 use smol_str::SmolStr;
 use crate::resource::Texture;
 
@@ -11,6 +14,8 @@ pub enum SortType {
     Z
 }
 
+impl_reflect_enum!(SortType,Distance,Z);
+
 #[derive(Component)]
 #[repr(C)]
 pub struct Camera {
@@ -181,3 +186,104 @@ impl Perspective {
         //Mat4::perspective_infinite_reverse_rh(self.fov, self.aspect_ratio, self.near)
     }
 }
+
+//投影参数展开成Camera的字段,type切换投影类型,fov使用角度
+//模板按字段顺序设置,type需要放在第一个
+impl ReflectStatic for Camera {
+    const TYPE_NAME:&'static str = "Camera";
+    const FIELDS:&'static [FieldInfo] = &[
+        FieldInfo { name:"type",typ:ReflectType::String },
+        FieldInfo { name:"order",typ:ReflectType::I32 },
+        FieldInfo { name:"layer",typ:ReflectType::I32 },
+        FieldInfo { name:"cull",typ:ReflectType::I32 },
+        FieldInfo { name:"isHDR",typ:ReflectType::Bool },
+        FieldInfo { name:"sortType",typ:ReflectType::String },
+        FieldInfo { name:"path",typ:ReflectType::String },
+        FieldInfo { name:"fov",typ:ReflectType::F32 },
+        FieldInfo { name:"aspect",typ:ReflectType::F32 },
+        FieldInfo { name:"left",typ:ReflectType::F32 },
+        FieldInfo { name:"right",typ:ReflectType::F32 },
+        FieldInfo { name:"bottom",typ:ReflectType::F32 },
+        FieldInfo { name:"top",typ:ReflectType::F32 },
+        FieldInfo { name:"near",typ:ReflectType::F32 },
+        FieldInfo { name:"far",typ:ReflectType::F32 },
+    ];
+}
+
+fn set_value<T:IReflectValue>(field:&mut T,name:&str,value:ReflectValue) -> Result<()> {
+    let typ = value.typ();
+    *field = T::from_value(value).ok_or_else(|| field_type_error(Camera::TYPE_NAME, name, typ))?;
+    Ok(())
+}
+
+impl Reflect for Camera {
+    fn type_name(&self) -> &'static str { Self::TYPE_NAME }
+
+    fn fields(&self) -> &'static [FieldInfo] { Self::FIELDS }
+
+    //不属于当前投影类型的字段返回None
+    fn get_field(&self,name:&str) -> Option<ReflectValue> {
+        let value = match (name,&self.projection) {
+            ("type",Projection::Perspective(_)) => ReflectValue::String("Perspective".into()),
+            ("type",Projection::Ortho(_)) => ReflectValue::String("Orthographic".into()),
+            ("order",_) => self.order.to_value(),
+            ("layer",_) => self.layer.to_value(),
+            ("cull",_) => self.cull_type.to_value(),
+            ("isHDR",_) => self.is_hdr.to_value(),
+            ("sortType",_) => self.sort_type.to_value(),
+            ("path",_) => self.path.to_value(),
+            ("fov",Projection::Perspective(per)) => per.fov.to_degrees().to_value(),
+            ("aspect",Projection::Perspective(per)) => per.aspect_ratio.to_value(),
+            ("near",Projection::Perspective(per)) => per.near.to_value(),
+            ("far",Projection::Perspective(per)) => per.far.to_value(),
+            ("left",Projection::Ortho(ortho)) => ortho.left.to_value(),
+            ("right",Projection::Ortho(ortho)) => ortho.right.to_value(),
+            ("bottom",Projection::Ortho(ortho)) => ortho.bottom.to_value(),
+            ("top",Projection::Ortho(ortho)) => ortho.top.to_value(),
+            ("near",Projection::Ortho(ortho)) => ortho.near.to_value(),
+            ("far",Projection::Ortho(ortho)) => ortho.far.to_value(),
+            _ => return None
+        };
+        Some(value)
+    }
+
+    fn set_field(&mut self,name:&str,value:ReflectValue) -> Result<()> {
+        match (name,&mut self.projection) {
+            ("type",projection) => {
+                let mut typ = SmolStr::default();
+                set_value(&mut typ, name, value)?;
+                let is_perspective = matches!(projection,Projection::Perspective(_));
+                match typ.as_str() {
+                    "Perspective" if !is_perspective => *projection = Projection::Perspective(Perspective::default()),
+                    "Orthographic" if is_perspective => *projection = Projection::Ortho(Orthographic::default()),
+                    "Perspective" | "Orthographic" => {},
+                    _ => bail!("error camera type:{}",typ)
+                }
+                Ok(())
+            },
+            ("order",_) => set_value(&mut self.order, name, value),
+            ("layer",_) => set_value(&mut self.layer, name, value),
+            ("cull",_) => set_value(&mut self.cull_type, name, value),
+            ("isHDR",_) => set_value(&mut self.is_hdr, name, value),
+            ("sortType",_) => set_value(&mut self.sort_type, name, value),
+            ("path",_) => set_value(&mut self.path, name, value),
+            ("fov",Projection::Perspective(per)) => {
+                let mut fov = 0f32;
+                set_value(&mut fov, name, value)?;
+                per.fov = fov.to_radians();
+                Ok(())
+            },
+            ("aspect",Projection::Perspective(per)) => set_value(&mut per.aspect_ratio, name, value),
+            ("near",Projection::Perspective(per)) => set_value(&mut per.near, name, value),
+            ("far",Projection::Perspective(per)) => set_value(&mut per.far, name, value),
+            ("left",Projection::Ortho(ortho)) => set_value(&mut ortho.left, name, value),
+            ("right",Projection::Ortho(ortho)) => set_value(&mut ortho.right, name, value),
+            ("bottom",Projection::Ortho(ortho)) => set_value(&mut ortho.bottom, name, value),
+            ("top",Projection::Ortho(ortho)) => set_value(&mut ortho.top, name, value),
+            ("near",Projection::Ortho(ortho)) => set_value(&mut ortho.near, name, value),
+            ("far",Projection::Ortho(ortho)) => set_value(&mut ortho.far, name, value),
+            ("fov" | "aspect" | "left" | "right" | "bottom" | "top",_) => bail!("Camera.{} not used by current projection",name),
+            _ => Err(not_found_field_error(Self::TYPE_NAME, name))
+        }
+    }
+}
@@ -4,8 +4,8 @@ use anyhow::Context;
 use bevy_ecs::prelude::World;
 use lite_clojure_eval::EvalRT;
 use relative_path::RelativePath;
-use seija_asset::{AssetServer,async_trait::async_trait ,AssetLoaderParams, AssetDynamic, Assets, downcast_rs::*,IAssetLoader, AsyncLoadMode, HandleUntyped, add_to_asset_type, this_asset_path};
-use seija_core::{anyhow::{Result,anyhow},smol, bytes::AsBytes, OptionExt};
+use seija_asset::{AssetServer,IAssetProcessor,async_trait::async_trait ,AssetLoaderParams, AssetDynamic, Assets, downcast_rs::*,IAssetLoader, AsyncLoadMode, HandleUntyped, add_to_asset_type, this_asset_path};
+use seija_core::{anyhow::{Result,anyhow}, bytes::AsBytes, OptionExt};
 use seija_core::TypeUuid;
 use serde_json::Value;
 use smol_str::SmolStr;
@@ -13,7 +13,7 @@ use smol_str::SmolStr;
 
 use crate::{MemUniformInfo,material::Material, UniformType, RawUniformInfo, resource::Texture};
 
-use super::{read_material_def, material_def::MaterialDefineAsset, MaterialDef};
+use super::{read_material_def, material_def::{MaterialDefineAsset, eval_material_def, material_def_from_value}, MaterialDef};
 
 
 #[derive(Default)]
@@ -28,17 +28,21 @@ impl IAssetLoader for MaterialDefineLoader {
     }
 
     fn sync_load(&self,_:&mut World,path:&str,server:&AssetServer,_:Option<Box<dyn AssetLoaderParams>>) -> Result<Box<dyn AssetDynamic>> {
-        let full_path = server.full_path(path)?;
-        log::info!("loader material define full_path: {:?}",&full_path);
-        let code_string = std::fs::read_to_string(full_path)?;
+        log::info!("loader material define: {}",path);
+        if let Some(processed) = server.read_processed(path)? {
+            return load_processed_material_def(&processed);
+        }
+        let code_string = server.read_string(path)?;
         load_material_def(&code_string)
     }
 
     async fn async_load(&self,server:AssetServer,path:SmolStr,
                         _:Option<Box<dyn DowncastSync>>,
                         _:Option<Box<dyn AssetLoaderParams>>) -> Result<Box<dyn AssetDynamic>> {
-        let full_path = server.full_path(&path)?;
-        let code_string = smol::fs::read_to_string(full_path).await?;
+        if let Some(processed) = server.read_processed_async(&path).await? {
+            return load_processed_material_def(&processed);
+        }
+        let code_string = server.read_string_async(&path).await?;
         load_material_def(&code_string)
     }
 }
@@ -52,6 +56,26 @@ fn load_material_def(code_string:&str) -> Result<Box<dyn AssetDynamic>> {
     Ok(Box::new(asset))
 }
 
+fn load_processed_material_def(bytes:&[u8]) -> Result<Box<dyn AssetDynamic>> {
+    let value:Value = serde_json::from_slice(bytes)?;
+    let define = material_def_from_value(&value, false)?;
+    Ok(Box::new(MaterialDefineAsset { define:Arc::new(define) }))
+}
+
+//材质定义脚本执行后的json结果缓存起来,运行时不需要再启动Clojure VM
+#[derive(Default)]
+pub(crate) struct MaterialDefineProcessor;
+
+impl IAssetProcessor for MaterialDefineProcessor {
+    fn process(&self,path:&str,bytes:&[u8]) -> Result<Vec<u8>> {
+        let code_string = std::str::from_utf8(bytes)?;
+        let mut vm = EvalRT::new();
+        let value = eval_material_def(&mut vm, code_string).map_err(|err| anyhow!("material define {}:{}",path,err))?;
+        material_def_from_value(&value, false).map_err(|err| anyhow!("material define {}:{}",path,err))?;
+        Ok(serde_json::to_vec(&value)?)
+    }
+}
+
 
 struct MaterialTouch {
     json:Value,
@@ -75,32 +99,32 @@ impl IAssetLoader for MaterialLoader {
         let file_path = RelativePath::new(path);
         let cur_dir = file_path.parent().get()?;
         
-        let full_path = server.full_path(path)?;
-        log::info!("loader material full_path: {:?}",&full_path);
-        let bytes = std::fs::read(full_path)?;
+        log::info!("loader material: {}",path);
+        let bytes = server.read_bytes(path)?;
         let json_value:Value = serde_json::from_slice(&bytes)?;
         let json_map = json_value.as_object().get()?;
         let material_def_path = json_map.get("material").and_then(Value::as_str).get()?;
         let asset_material_def_path = this_asset_path(cur_dir, material_def_path);
         
         let h_def = server.load_sync::<MaterialDefineAsset>(w, asset_material_def_path.as_str(), None)?;
+        server.add_dependency(path, h_def.id);
         let defs = w.get_resource::<Assets<MaterialDefineAsset>>().get()?;
         let def_asset = defs.get(&h_def.id).get()?;
         let mut material = Material::from_def(def_asset.define.clone(), &server).get()?;
         let json_props = json_map.get("props").get()?;
         set_material_props(&mut material,json_props)?;
-        set_material_textures_sync(w,&mut material,json_props,&server,cur_dir)?;
+        set_material_textures_sync(w,&mut material,json_props,&server,path,cur_dir)?;
         Ok(Box::new(material))
     }
 
     async fn async_touch(&self, server:AssetServer, path:SmolStr) -> Result<Box<dyn DowncastSync>> {
-        let full_path = server.full_path(&path)?;
-        let bytes = std::fs::read(full_path)?;
+        let bytes = server.read_bytes_async(&path).await?;
         let json:Value = serde_json::from_slice(&bytes)?;
         let json_map = json.as_object().context(0)?;
         let material_def_path = json_map.get("material").and_then(Value::as_str).context(1)?;
         let req = server.load_async::<MaterialDefineAsset>(material_def_path,None)?;
         let define_handle = req.wait_handle().await.ok_or(anyhow!("load material define error"))?;
+        server.add_dependency(path.as_str(), define_handle.id);
         let touch = MaterialTouch { json,define_handle,rc_define:None };
         let ret:Box<dyn DowncastSync> = Box::new(touch);
         Ok(ret)  
@@ -118,7 +142,7 @@ impl IAssetLoader for MaterialLoader {
         None
     }
 
-    async fn async_load(&self,server:AssetServer,_:SmolStr,
+    async fn async_load(&self,server:AssetServer,path:SmolStr,
         touch:Option<Box<dyn DowncastSync>>,
                         _:Option<Box<dyn AssetLoaderParams>>) -> Result<Box<dyn AssetDynamic>> {
        let touch = touch.ok_or(anyhow!("touch error"))?.into_any()
@@ -131,12 +155,12 @@ impl IAssetLoader for MaterialLoader {
         let json_map = touch.json.as_object().context(0)?;
         let json_props = json_map.get("props").context(6)?;
         set_material_props(&mut material,json_props)?;
-        set_material_textures(&mut material,json_props,&server).await?;
+        set_material_textures(&mut material,json_props,&server,path.as_str()).await?;
         let ret:Box<dyn AssetDynamic> = Box::new(material);
         Ok(ret)
     }
 }
-async fn set_material_textures(material:&mut Material,value:&Value,server:&AssetServer) -> Result<()> {
+async fn set_material_textures(material:&mut Material,value:&Value,server:&AssetServer,path:&str) -> Result<()> {
     let props = value.as_object().context(1)?;
     let define = material.def.clone();
     for (k,v) in props.iter() {
@@ -144,13 +168,14 @@ async fn set_material_textures(material:&mut Material,value:&Value,server:&Asset
             let texture_path = v.as_str().context(2)?;
             let req = server.load_async::<Texture>(texture_path, None)?;
             let h_tex = req.wait_handle().await.context(3)?;
+            server.add_dependency(path, h_tex.id);
             material.texture_props.set(k, h_tex.typed());
         }
     }
     Ok(())
 }
 
-fn set_material_textures_sync(world:&mut World,material:&mut Material,value:&Value,server:&AssetServer,cur_dir:&RelativePath) -> Result<()> {
+fn set_material_textures_sync(world:&mut World,material:&mut Material,value:&Value,server:&AssetServer,path:&str,cur_dir:&RelativePath) -> Result<()> {
     let props = value.as_object().get()?;
     let define = material.def.clone();
     for (k,v) in props.iter() {
@@ -158,6 +183,7 @@ fn set_material_textures_sync(world:&mut World,material:&mut Material,value:&Val
             let texture_path = v.as_str().get()?;
             let asset_texture_path = this_asset_path(cur_dir, texture_path);
             let handle = server.load_sync::<Texture>(world,asset_texture_path.as_str(), None)?;
+            server.add_dependency(path, handle.id);
             material.texture_props.set(k, handle);
         }
     }
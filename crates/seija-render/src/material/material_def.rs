@@ -67,7 +67,16 @@ impl Default for PassDef {
 
 
 pub fn read_material_def(vm:&mut EvalRT,file_string:&str,read_slot:bool) -> Result<MaterialDef,MaterialDefReadError>  {
-    let value:Value = vm.eval_string(String::default(), file_string).ok_or(MaterialDefReadError::LanguageError)?.into();
+    let value = eval_material_def(vm, file_string)?;
+    material_def_from_value(&value, read_slot)
+}
+
+//执行材质定义脚本得到json,预处理器会缓存这个结果
+pub fn eval_material_def(vm:&mut EvalRT,file_string:&str) -> Result<Value,MaterialDefReadError> {
+    Ok(vm.eval_string(String::default(), file_string).ok_or(MaterialDefReadError::LanguageError)?.into())
+}
+
+pub fn material_def_from_value(value:&Value,read_slot:bool) -> Result<MaterialDef,MaterialDefReadError> {
     let value_object = value.as_object().ok_or(MaterialDefReadError::FormatError)?;
     
     //name
@@ -8,12 +8,12 @@ pub mod errors;
 pub use material::{Material};
 pub use material_def::{MaterialDef,read_material_def,PassDef,ShaderInfoDef,MaterialDefineAsset};
 use seija_app::App;
-use seija_asset::{AddAsset};
+use seija_asset::{AddAsset, AssetServer};
 pub use types::{RenderOrder,Cull,ZTest,RenderPath,STextureDescriptor};
 pub use texture_prop_def::{TexturePropDef,TexturePropInfo};
 pub use system::{MaterialSystem};
 
-use self::{loader::{MaterialDefineLoader, MaterialLoader}};
+use self::{loader::{MaterialDefineLoader, MaterialLoader, MaterialDefineProcessor}};
 
 
 pub(crate) fn init_material(app:&mut App) {
@@ -22,4 +22,7 @@ pub(crate) fn init_material(app:&mut App) {
     app.add_asset::<Material>();
     app.add_asset_loader::<MaterialDefineAsset,MaterialDefineLoader>();
     app.add_asset_loader::<Material,MaterialLoader>();
+    if let Some(server) = app.world.get_resource::<AssetServer>() {
+        server.register_processor("clj", MaterialDefineProcessor);
+    }
 }
\ No newline at end of file
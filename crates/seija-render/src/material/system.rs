@@ -75,6 +75,16 @@ impl MaterialSystem {
                             let bind_group = material_define.add_material(res, handle.id,&clone_layout);
                             mat.bind_group = Some(bind_group);
                         }
+                        AssetEvent::Modified { handle } => {
+                            for define in self.datas.values_mut() {
+                                define.remove_material(&handle.id);
+                            }
+                            if let Some(mat) = materials.get_mut(&handle.id) {
+                                let material_define = self.check_get_material_define(mat,res);
+                                let bind_group = material_define.add_material(res, handle.id,&clone_layout);
+                                mat.bind_group = Some(bind_group);
+                            }
+                        }
                         AssetEvent::Removed { handle,value } => {
                             let mat = value;
                             if let Some(define) = self.datas.get_mut(&mat.def.name) {
@@ -63,7 +63,8 @@ impl CubeMapBuilder {
             width:fst.width,
             height:fst.height,
             format:fst.format,
-            data:all_bytes
+            data:all_bytes,
+            mips:vec![]
         };
         let mut desc = TextureDescInfo::default();
         desc.desc.size.depth_or_array_layers = 6;
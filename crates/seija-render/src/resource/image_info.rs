@@ -9,7 +9,124 @@ pub struct ImageInfo {
     pub width:u32,
     pub height:u32,
     pub format:TextureFormat,
-    pub data:Vec<u8>
+    pub data:Vec<u8>,
+    //第1级开始的mipmap数据,为空时只有data一级
+    pub mips:Vec<Vec<u8>>
+}
+
+const IMAGE_INFO_MAGIC:[u8;4] = *b"SIMG";
+const IMAGE_FORMATS:[TextureFormat;6] = [
+    TextureFormat::R8Unorm,
+    TextureFormat::Rgba8Unorm,
+    TextureFormat::Rgba32Float,
+    TextureFormat::R16Uint,
+    TextureFormat::Rg16Uint,
+    TextureFormat::Rgba16Uint
+];
+
+impl ImageInfo {
+    pub fn mip_level_count(&self) -> u32 {
+        self.mips.len() as u32 + 1
+    }
+
+    pub fn mip_size(&self,level:u32) -> (u32,u32) {
+        ((self.width >> level).max(1),(self.height >> level).max(1))
+    }
+
+    pub fn mip_data(&self,level:u32) -> &[u8] {
+        if level == 0 { &self.data } else { &self.mips[level as usize - 1] }
+    }
+
+    //用2x2盒式滤波生成完整的mipmap链,只支持8位和32位浮点的格式
+    pub fn generate_mips(&mut self) -> bool {
+        let channels = match self.format {
+            TextureFormat::R8Unorm => 1,
+            TextureFormat::Rgba8Unorm | TextureFormat::Bgra8Unorm => 4,
+            TextureFormat::Rgba32Float => 4,
+            _ => return false
+        };
+        self.mips.clear();
+        let mut level = 1;
+        while self.width >> (level - 1) > 1 || self.height >> (level - 1) > 1 {
+            let (src_w,src_h) = self.mip_size(level - 1);
+            let (dst_w,dst_h) = self.mip_size(level);
+            let src = self.mip_data(level - 1);
+            let mip = if self.format == TextureFormat::Rgba32Float {
+                //data不保证4字节对齐,不能直接cast
+                let src:Vec<f32> = src.chunks_exact(4).map(|v| f32::from_ne_bytes([v[0],v[1],v[2],v[3]])).collect();
+                let dst = downsample(&src, src_w, src_h, dst_w, dst_h, channels, |a,b,c,d| (a + b + c + d) * 0.25);
+                cast_slice(&dst).to_vec()
+            } else {
+                downsample(src, src_w, src_h, dst_w, dst_h, channels, |a,b,c,d| ((a as u32 + b as u32 + c as u32 + d as u32 + 2) / 4) as u8)
+            };
+            self.mips.push(mip);
+            level += 1;
+        }
+        true
+    }
+
+    //序列化为预处理缓存使用的格式 magic width height format mip_count data mips
+    pub fn to_bytes(&self) -> Option<Vec<u8>> {
+        let format_index = IMAGE_FORMATS.iter().position(|f| *f == self.format)? as u32;
+        let mips_len:usize = self.mips.iter().map(Vec::len).sum();
+        let mut bytes = Vec::with_capacity(20 + self.data.len() + mips_len);
+        bytes.extend_from_slice(&IMAGE_INFO_MAGIC);
+        bytes.extend_from_slice(&self.width.to_le_bytes());
+        bytes.extend_from_slice(&self.height.to_le_bytes());
+        bytes.extend_from_slice(&format_index.to_le_bytes());
+        bytes.extend_from_slice(&(self.mips.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&self.data);
+        for mip in self.mips.iter() {
+            bytes.extend_from_slice(mip);
+        }
+        Some(bytes)
+    }
+
+    pub fn from_bytes(bytes:&[u8]) -> Option<ImageInfo> {
+        if bytes.len() < 20 || bytes[0..4] != IMAGE_INFO_MAGIC {
+            return None;
+        }
+        let read_u32 = |start:usize| u32::from_le_bytes([bytes[start],bytes[start + 1],bytes[start + 2],bytes[start + 3]]);
+        let (width,height) = (read_u32(4),read_u32(8));
+        let format = *IMAGE_FORMATS.get(read_u32(12) as usize)?;
+        let mip_count = read_u32(16);
+        if width == 0 || height == 0 || mip_count >= 32 {
+            return None;
+        }
+        //每一级的数据长度都要和宽高格式对应,避免上传纹理时越界
+        let block_size = format.describe().block_size as usize;
+        let mut level_sizes = Vec::with_capacity(mip_count as usize + 1);
+        for level in 0..=mip_count {
+            let (w,h) = ((width >> level).max(1) as usize,(height >> level).max(1) as usize);
+            level_sizes.push(w.checked_mul(h)?.checked_mul(block_size)?);
+        }
+        let total = level_sizes.iter().try_fold(20usize, |acc,size| acc.checked_add(*size))?;
+        if total != bytes.len() {
+            return None;
+        }
+        let mut offset = 20;
+        let mut levels = level_sizes.iter().map(|size| {
+            let level = bytes[offset..offset + size].to_vec();
+            offset += size;
+            level
+        });
+        let data = levels.next()?;
+        Some(ImageInfo { width, height, format, data, mips:levels.collect() })
+    }
+}
+
+fn downsample<T:Copy>(src:&[T],src_w:u32,src_h:u32,dst_w:u32,dst_h:u32,channels:usize,avg:impl Fn(T,T,T,T) -> T) -> Vec<T> {
+    let mut dst = Vec::with_capacity(dst_w as usize * dst_h as usize * channels);
+    let at = |x:u32,y:u32,c:usize| src[(y.min(src_h - 1) * src_w + x.min(src_w - 1)) as usize * channels + c];
+    for y in 0..dst_h {
+        for x in 0..dst_w {
+            let (sx,sy) = (x * 2,y * 2);
+            for c in 0..channels {
+                dst.push(avg(at(sx,sy,c),at(sx + 1,sy,c),at(sx,sy + 1,c),at(sx + 1,sy + 1,c)));
+            }
+        }
+    }
+    dst
 }
 
 
@@ -128,7 +245,7 @@ pub fn read_image_info(dyn_image:image::DynamicImage) -> ImageInfo {
         _ => {panic!("unsupport texture") }  
     }
 
-    ImageInfo {width,height,format,data }
+    ImageInfo {width,height,format,data,mips:vec![] }
 }
 
 
@@ -139,5 +256,29 @@ pub fn color_image_info(color:[u8;4],size:usize) -> ImageInfo {
         data.extend_from_slice(&color);
     }
     
-    ImageInfo { width: size as u32, height: size as u32, format: wgpu::TextureFormat::Bgra8Unorm, data }
-}
\ No newline at end of file
+    ImageInfo { width: size as u32, height: size as u32, format: wgpu::TextureFormat::Bgra8Unorm, data, mips:vec![] }
+}
+
+#[cfg(test)]
+mod tests {
+    use wgpu::TextureFormat;
+    use super::ImageInfo;
+
+    #[test]
+    fn mips_round_trip() {
+        let mut info = ImageInfo { width:4,height:2,format:TextureFormat::R8Unorm,data:vec![0,4,8,12,16,20,24,28],mips:vec![] };
+        assert!(info.generate_mips());
+        assert_eq!(info.mip_level_count(), 3);
+        assert_eq!(info.mips[0], vec![10,18]);
+        assert_eq!(info.mips[1], vec![14]);
+
+        let bytes = info.to_bytes().unwrap();
+        let read = ImageInfo::from_bytes(&bytes).unwrap();
+        assert_eq!(read.data, info.data);
+        assert_eq!(read.mips, info.mips);
+        assert!(ImageInfo::from_bytes(&bytes[..bytes.len() - 1]).is_none());
+        let mut more = bytes.clone();
+        more.push(0);
+        assert!(ImageInfo::from_bytes(&more).is_none());
+    }
+}
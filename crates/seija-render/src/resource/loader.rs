@@ -1,13 +1,14 @@
 use bevy_ecs::prelude::World;
-use seija_asset::{IAssetLoader, HandleUntyped, add_to_asset_type};
+use relative_path::RelativePath;
+use seija_asset::{IAssetLoader, IAssetProcessor, HandleUntyped, add_to_asset_type, this_asset_path};
 use seija_asset::async_trait::async_trait;
 use serde_json::Value;
 use smol_str::SmolStr;
 use seija_asset::{AssetLoaderParams, AssetServer, AssetDynamic, downcast_rs::DowncastSync};
 use seija_core::TypeUuid;
 use seija_core::anyhow::{Result,anyhow};
-use crate::resource::Texture;
-use seija_core::smol;
+use seija_core::OptionExt;
+use crate::resource::{Texture, TextureType};
 use super::{TextureDescInfo, ImageInfo, read_image_info};
 
 #[derive(Default)]
@@ -20,21 +21,24 @@ impl IAssetLoader for TextureLoader {
     }
     fn sync_load(&self,_:&mut World,path:&str,server:&AssetServer,params:Option<Box<dyn AssetLoaderParams>>) -> Result<Box<dyn AssetDynamic>> {
         if path.ends_with(".json") {
-            let json_path = server.full_path(path)?;
-            let json_bytes = std::fs::read(json_path.as_path())?;
+            let json_dir = RelativePath::new(path).parent().get()?;
+            let json_bytes = server.read_bytes(path)?;
             let cube_json:Value = serde_json::from_slice(&json_bytes)?;
             let path_list = read_cube_json_path(cube_json)?;
             let mut image_bytes:Vec<Vec<u8>> = vec![];
             for path in path_list.iter() {
-               let image_path = json_path.parent().unwrap().join(path.as_str());
-               let bytes = std::fs::read(image_path)?;
+               let image_path = this_asset_path(json_dir, path.as_str());
+               let bytes = server.read_bytes(image_path.as_str())?;
                image_bytes.push(bytes);
             }
             let texture = make_cube_map(image_bytes)?;
             return Ok(Box::new(texture));
         }
-        let full_path = server.full_path(path)?;
-        let bytes = std::fs::read(full_path)?;
+        if let Some(processed) = server.read_processed(path)? {
+            let info = ImageInfo::from_bytes(&processed).ok_or(anyhow!("processed texture error:{}",path))?;
+            return Ok(Box::new(Texture::from_image_info(info, read_desc(params))));
+        }
+        let bytes = server.read_bytes(path)?;
         let texture = Texture::from_image_bytes(&bytes, read_desc(params))?;
         Ok(Box::new(texture))
     }
@@ -43,26 +47,50 @@ impl IAssetLoader for TextureLoader {
                         _:Option<Box<dyn DowncastSync>>,
                         params:Option<Box<dyn AssetLoaderParams>>) -> Result<Box<dyn AssetDynamic>> {
         if path.ends_with(".json") {
-            let json_path = server.full_path(path.as_str())?;
-            let json_bytes = smol::fs::read(json_path.as_path()).await?;
+            let json_dir = RelativePath::new(path.as_str()).parent().get()?;
+            let json_bytes = server.read_bytes_async(path.as_str()).await?;
             let cube_json:Value = serde_json::from_slice(&json_bytes)?;
             let path_list = read_cube_json_path(cube_json)?;
             let mut image_bytes:Vec<Vec<u8>> = vec![];
             for path in path_list.iter() {
-               let image_path = json_path.parent().unwrap().join(path.as_str());
-               let bytes = smol::fs::read(image_path).await?;
+               let image_path = this_asset_path(json_dir, path.as_str());
+               let bytes = server.read_bytes_async(image_path.as_str()).await?;
                image_bytes.push(bytes);
             }
             let texture = make_cube_map(image_bytes)?;
             return Ok(Box::new(texture));
         }
-        let full_path = server.full_path(path.as_str())?;
-        let bytes = smol::fs::read(full_path).await?;
+        if let Some(processed) = server.read_processed_async(path.as_str()).await? {
+            let info = ImageInfo::from_bytes(&processed).ok_or(anyhow!("processed texture error:{}",path))?;
+            return Ok(Box::new(Texture::from_image_info(info, read_desc(params))));
+        }
+        let bytes = server.read_bytes_async(path.as_str()).await?;
         let texture = Texture::from_image_bytes(&bytes, read_desc(params))?;
         Ok(Box::new(texture))
     }
 }
 
+//把图片预先解码为ImageInfo并生成mipmap,运行时跳过图片解码和HDR转换
+#[derive(Default)]
+pub(crate) struct TextureProcessor;
+
+impl IAssetProcessor for TextureProcessor {
+    fn version(&self) -> u32 { 2 }
+
+    fn process(&self,path:&str,bytes:&[u8]) -> Result<Vec<u8>> {
+        let mut texture = Texture::from_image_bytes(bytes, TextureDescInfo::default())?;
+        match &mut texture.texture {
+            TextureType::Image(info) => {
+                if !info.generate_mips() {
+                    log::warn!("texture format {:?} not support mipmap:{}",info.format,path);
+                }
+                info.to_bytes().ok_or(anyhow!("unsupport texture format:{}",path))
+            },
+            _ => Err(anyhow!("not image texture:{}",path))
+        }
+    }
+}
+
 impl AssetLoaderParams for TextureDescInfo {}
 
 fn read_desc(params:Option<Box<dyn AssetLoaderParams>>) -> TextureDescInfo {
@@ -102,7 +130,8 @@ fn make_cube_map(images:Vec<Vec<u8>>) -> Result<Texture> {
         width:fst.width,
         height:fst.height,
         format:fst.format,
-        data:all_bytes
+        data:all_bytes,
+        mips:vec![]
     };
     let mut desc = TextureDescInfo::default();
     desc.desc.size.depth_or_array_layers = 6;
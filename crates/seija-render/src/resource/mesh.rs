@@ -220,7 +220,15 @@ pub struct Mesh {
     values:Vec<Option<VertexAttributeValues>>,
     indices:Option<Indices>,
     attrs:Vec<VertexAttribute>,
-    array_stride:u64
+    array_stride:u64,
+    interleaved:Option<InterleavedVertices>
+}
+
+//已经交错好的顶点数据,layout按顺序描述一个顶点里的属性
+#[derive(Debug)]
+struct InterleavedVertices {
+    layout:Vec<(MeshAttributeType,VertexFormat)>,
+    bytes:Vec<u8>
 }
 
 impl Hash for Mesh {
@@ -250,8 +258,20 @@ impl Mesh {
             values,
             indices:None,
             attrs:vec![],
-            array_stride:0
+            array_stride:0,
+            interleaved:None
+        }
+    }
+
+    //使用预处理好的交错顶点数据创建Mesh,数据长度不是顶点大小的整数倍时返回None
+    pub fn from_interleaved(typ:PrimitiveTopology,layout:Vec<(MeshAttributeType,VertexFormat)>,bytes:Vec<u8>) -> Option<Mesh> {
+        let vert_size:u64 = layout.iter().map(|(_,format)| format.size()).sum();
+        if vert_size == 0 || bytes.len() as u64 % vert_size != 0 {
+            return None;
         }
+        let mut mesh = Mesh::new(typ);
+        mesh.interleaved = Some(InterleavedVertices { layout,bytes });
+        Some(mesh)
     }
     pub fn typ(&self) -> PrimitiveTopology {
         self.typ
@@ -283,6 +303,19 @@ impl Mesh {
     pub fn build(&mut self) {
         let mut attributes:Vec<VertexAttribute> = Vec::new();
         let mut accumulated_offset = 0;
+        if let Some(interleaved) = self.interleaved.as_ref() {
+            for (typ,format) in interleaved.layout.iter() {
+                attributes.push(VertexAttribute {
+                    format:*format,
+                    shader_location:typ.bits as u32,
+                    offset:accumulated_offset
+                });
+                accumulated_offset += format.size();
+            }
+            self.attrs = attributes;
+            self.array_stride = accumulated_offset;
+            return;
+        }
         let mut location:u32 = 0;
         for value in self.values.iter() {
             if let Some(value) = value {
@@ -340,10 +373,17 @@ impl Mesh {
     }
 
     pub fn count_vertices(&self) -> usize { 
+        if let Some(interleaved) = self.interleaved.as_ref() {
+            let vert_size:u64 = interleaved.layout.iter().map(|(_,format)| format.size()).sum();
+            return interleaved.bytes.len() / vert_size as usize;
+        }
         self.values.first().map(|v| v.as_ref().unwrap().len()).unwrap_or(0) 
     }
 
     pub fn get_vertex_buffer_data(&self) -> Vec<u8> {
+        if let Some(interleaved) = self.interleaved.as_ref() {
+            return interleaved.bytes.clone();
+        }
         let mut vert_size:usize = 0;
         for value in self.values.iter() {
             if let Some(value) = value {
@@ -370,6 +410,9 @@ impl Mesh {
     }
 
     pub fn mesh_attr_types(&self) -> Vec<MeshAttributeType> {
+        if let Some(interleaved) = self.interleaved.as_ref() {
+            return interleaved.layout.iter().map(|(typ,_)| *typ).collect();
+        }
         let mut attr_types = vec![];
         for idx in 0..self.values.len() {
             if self.values[idx].is_some() {
@@ -392,7 +435,11 @@ pub fn update_mesh_system(world:&mut World,mesh_reader:&mut ManualEventReader<As
                     AssetEvent::Created { ref handle } =>  {
                         changed_meshes.insert(handle.clone_weak());
                     }
-                    AssetEvent::Modified { .. } => { }
+                    AssetEvent::Modified { ref handle } => {
+                        remove_resource(&handle.id,0,ctx);
+                        remove_resource(&handle.id,1,ctx);
+                        changed_meshes.insert(handle.clone_weak());
+                    }
                     AssetEvent::Removed { ref handle,.. } =>  { 
                         changed_meshes.remove(handle);
                         remove_resource(&handle.id,0,ctx);
@@ -12,13 +12,18 @@ pub use mesh::{Mesh,update_mesh_system,VertexAttributeValues,Indices,MeshAttribu
 pub use  resource::{RenderResources,RenderResourceId,BufferId,TextureId,SamplerId};
 
 use seija_app::{App};
-use seija_asset::{AddAsset};
+use seija_asset::{AddAsset, AssetServer};
 
-use self::loader::TextureLoader;
+use self::loader::{TextureLoader, TextureProcessor};
 
 
 pub(crate) fn init_resource(app:&mut App) {
     app.add_asset::<Mesh>();
     app.add_asset::<Texture>();
     app.add_asset_loader::<Texture,TextureLoader>();
+    if let Some(server) = app.world.get_resource::<AssetServer>() {
+        for ext in ["png","jpg","jpeg","hdr","tga","bmp"] {
+            server.register_processor(ext, TextureProcessor);
+        }
+    }
 }
\ No newline at end of file
@@ -245,6 +245,12 @@ impl RenderResources {
         );
     }
 
+    pub fn remove_sampler(&mut self,id:&RenderResourceId) {
+        if let RenderResourceId::Sampler(sampler_id) = id {
+            self.samplers.remove(sampler_id);
+        }
+    }
+
     pub fn remove_texture(&mut self,id:&RenderResourceId) {
         if let RenderResourceId::TextureView(tex_id) = id {
             self.textures.remove(tex_id);
@@ -275,34 +281,43 @@ impl RenderResources {
     pub fn fill_texture(&mut self,texture:&Texture,texture_id:&TextureId,command:&mut wgpu::CommandEncoder) {
         if let TextureType::Image(image_info) = &texture.texture {
             let desc = &texture.desc().desc;
-            let width = desc.size.width as usize;
-            let aligned_width = Self::get_aligned_texture_size(width);
-            let format_size:usize = desc.format.describe().block_size as usize;
-           
-            let mut aligned_data = vec![0;format_size * 
-                                                  aligned_width * 
-                                                  desc.size.height as usize * 
-                                                  desc.size.depth_or_array_layers as usize];
-    
-            image_info.data.chunks_exact(format_size * width)
-                        .enumerate()
-                        .for_each(|(index, row)| {
-                                    let offset = index * aligned_width * format_size;
-                                    aligned_data[offset..(offset + width * format_size)]
-                                        .copy_from_slice(row);
-                                  });
-    
-            let texture_buffer = self.create_buffer_with_data(wgpu::BufferUsages::COPY_SRC,&aligned_data);
-            self.copy_buffer_to_texture(command, 
-                           texture_buffer, 
-                           0, 
-                     NonZeroU32::new((format_size * aligned_width) as u32).unwrap(), 
-                           texture_id,
-                            wgpu::Origin3d::default(),
-                         0, desc.size,
-                        if desc.size.depth_or_array_layers > 1 { Some(NonZeroU32::new(desc.size.height).unwrap()) } else { None })
+            let layers = desc.size.depth_or_array_layers;
+            let level_count = if layers > 1 { 1 } else { image_info.mip_level_count().min(desc.mip_level_count) };
+            for level in 0..level_count {
+                let (width,height) = if level == 0 { (desc.size.width,desc.size.height) } else { image_info.mip_size(level) };
+                let size = wgpu::Extent3d { width,height,depth_or_array_layers:layers };
+                self.fill_texture_level(desc.format, image_info.mip_data(level), size, level, texture_id, command);
+            }
         }
-        
+    }
+
+    fn fill_texture_level(&mut self,format:wgpu::TextureFormat,data:&[u8],size:wgpu::Extent3d,level:u32,texture_id:&TextureId,command:&mut wgpu::CommandEncoder) {
+        let width = size.width as usize;
+        let aligned_width = Self::get_aligned_texture_size(width);
+        let format_size:usize = format.describe().block_size as usize;
+       
+        let mut aligned_data = vec![0;format_size * 
+                                              aligned_width * 
+                                              size.height as usize * 
+                                              size.depth_or_array_layers as usize];
+
+        data.chunks_exact(format_size * width)
+                    .enumerate()
+                    .for_each(|(index, row)| {
+                                let offset = index * aligned_width * format_size;
+                                aligned_data[offset..(offset + width * format_size)]
+                                    .copy_from_slice(row);
+                              });
+
+        let texture_buffer = self.create_buffer_with_data(wgpu::BufferUsages::COPY_SRC,&aligned_data);
+        self.copy_buffer_to_texture(command, 
+                       texture_buffer, 
+                       0, 
+                 NonZeroU32::new((format_size * aligned_width) as u32).unwrap(), 
+                       texture_id,
+                        wgpu::Origin3d::default(),
+                     level, size,
+                    if size.depth_or_array_layers > 1 { Some(NonZeroU32::new(size.height).unwrap()) } else { None })
     }
 
     pub fn is_texture_ready(&self,texture:&Handle<Texture>) -> bool {
@@ -1,7 +1,7 @@
 use std::{collections::HashSet};
 use bevy_ecs::prelude::World;
 use image::{ImageError, ImageFormat};
-use seija_asset::{Assets, AssetEvent, Handle};
+use seija_asset::{Assets, AssetEvent, Handle, HandleId};
 use uuid::Uuid;
 use seija_core::{TypeUuid, IDGenU32, OptionExt};
 use bevy_ecs::event::{ManualEventReader, Events};
@@ -44,7 +44,7 @@ impl Texture {
         Texture {texture,desc }
     }
 
-    pub fn from_image_bytes(bytes:&[u8],mut desc:TextureDescInfo) -> Result<Texture,ImageError> {
+    pub fn from_image_bytes(bytes:&[u8],desc:TextureDescInfo) -> Result<Texture,ImageError> {
         let guess_format = image::guess_format(bytes)?;
         let info = if guess_format == ImageFormat::Hdr {
             let format: TextureFormat = TextureFormat::Rgba32Float;
@@ -59,20 +59,24 @@ impl Texture {
                 rgba_data.extend_from_slice(&rgb.0[2].to_ne_bytes());
                 rgba_data.extend_from_slice(&alpha.to_ne_bytes());
             }
-           ImageInfo {width:info.width,height:info.height,format,data:rgba_data }
+           ImageInfo {width:info.width,height:info.height,format,data:rgba_data,mips:vec![] }
         } else {
             let dyn_image = image::load_from_memory(bytes)?;
             read_image_info(dyn_image)
         };
-        
+        Ok(Texture::from_image_info(info, desc))
+    }
+
+    pub fn from_image_info(info:ImageInfo,mut desc:TextureDescInfo) -> Texture {
         desc.desc.size.width = info.width;
         desc.desc.size.height = info.height;
         desc.desc.size.depth_or_array_layers = 1;
         desc.desc.dimension = wgpu::TextureDimension::D2;
         desc.desc.format = info.format;
+        desc.desc.mip_level_count = info.mip_level_count();
 
         let texture = TextureType::Image(info);
-        Ok(Texture {texture,desc })
+        Texture {texture,desc }
     }
 
     pub fn cast_image_data(&self) -> Option<&Vec<u8>> {
@@ -149,9 +153,13 @@ pub fn update_texture_system(world:&mut World,texture_reader:&mut ManualEventRea
             AssetEvent::Created { handle } => {
                 changed_textures.insert(handle.clone_weak());
             },
-            AssetEvent::Modified { .. } => {},
+            AssetEvent::Modified { handle } => {
+                remove_texture_resource(&handle.id, ctx);
+                changed_textures.insert(handle.clone_weak());
+            },
             AssetEvent::Removed { handle,.. } => {
                 changed_textures.remove(&handle);
+                remove_texture_resource(&handle.id, ctx);
             }
         }
     }
@@ -160,5 +168,15 @@ pub fn update_texture_system(world:&mut World,texture_reader:&mut ManualEventRea
             log::error!("upload texture error:{}",err);
         }
     }
-    //TODO 这里需要移除释放的Texture的render resource
+}
+
+fn remove_texture_resource(id:&HandleId,ctx:&mut RenderContext) {
+    if let Some(res_id) = ctx.resources.get_render_resource(id, 0).cloned() {
+        ctx.resources.remove_texture(&res_id);
+        ctx.resources.remove_render_resource(id, 0);
+    }
+    if let Some(res_id) = ctx.resources.get_render_resource(id, 1).cloned() {
+        ctx.resources.remove_sampler(&res_id);
+        ctx.resources.remove_render_resource(id, 1);
+    }
 }
\ No newline at end of file
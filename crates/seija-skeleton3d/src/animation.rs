@@ -1,17 +1,55 @@
 use glam::{Vec3, Quat};
 
-#[derive(Default,Debug)]
+use crate::compression::{compress_vec3, decompress_vec3, compress_quat, decompress_quat};
+
+//值用半精度浮点数保存
+#[derive(Default,Debug,Clone)]
 pub struct Float3Key {
    pub ratio:f32,
-   pub track:usize,
-   pub value:Vec3
+   pub track:u16,
+   pub value:[u16;3]
 }
 
-#[derive(Default,Debug)]
+impl Float3Key {
+   pub fn new(ratio:f32,track:usize,value:Vec3) -> Self {
+      Float3Key { ratio, track:track as u16, value:compress_vec3(value) }
+   }
+
+   pub fn value(&self) -> Vec3 {
+      decompress_vec3(&self.value)
+   }
+}
+
+//去掉绝对值最大的分量,其余三个分量量化成i16,largest的低两位是去掉的分量索引,第三位是它的符号
+#[derive(Debug,Clone)]
 pub struct QuaternionKey {
   pub ratio:f32,
-  pub track:usize,
-  pub value:Quat
+  pub track:u16,
+  pub largest:u8,
+  pub value:[i16;3]
+}
+
+impl QuaternionKey {
+   pub fn new(ratio:f32,track:usize,value:Quat) -> Self {
+      let (largest,value) = compress_quat(value);
+      QuaternionKey { ratio, track:track as u16, largest, value }
+   }
+
+   pub fn value(&self) -> Quat {
+      decompress_quat(self.largest, &self.value)
+   }
+}
+
+impl Default for QuaternionKey {
+   fn default() -> Self {
+      QuaternionKey::new(0f32, 0, Quat::IDENTITY)
+   }
+}
+
+#[derive(Default,Clone,Debug)]
+pub struct EventKey {
+  pub ratio:f32,
+  pub name:String
 }
 
 #[derive(Default,Debug)]
@@ -21,11 +59,31 @@ pub struct Animation {
    pub(crate) num_tracks:usize,
    pub(crate) translations_:Vec<Float3Key>,
    pub(crate) rotations_:Vec<QuaternionKey>,
-   pub(crate) scales_:Vec<Float3Key>
+   pub(crate) scales_:Vec<Float3Key>,
+   //按ratio排序
+   pub(crate) events:Vec<EventKey>
 }
 
 impl Animation {
    pub fn name(&self) -> &str {
       self.name.as_str()
    }
+
+   pub fn duration(&self) -> f32 {
+      self.duration
+   }
+
+   pub fn events(&self) -> &[EventKey] {
+      &self.events
+   }
+
+   //轨道第一个和最后一个关键帧的位移和旋转,用来计算循环时的根运动
+   pub(crate) fn track_range(&self,track:usize) -> ((Vec3,Quat),(Vec3,Quat)) {
+      let track = track as u16;
+      let start_pos = self.translations_.iter().find(|k| k.track == track).map(|k| k.value()).unwrap_or_default();
+      let start_rot = self.rotations_.iter().find(|k| k.track == track).map(|k| k.value()).unwrap_or_default();
+      let end_pos = self.translations_.iter().rev().find(|k| k.track == track).map(|k| k.value()).unwrap_or(start_pos);
+      let end_rot = self.rotations_.iter().rev().find(|k| k.track == track).map(|k| k.value()).unwrap_or(start_rot);
+      ((start_pos,start_rot),(end_pos,end_rot))
+   }
 }
\ No newline at end of file
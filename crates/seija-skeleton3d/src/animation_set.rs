@@ -19,4 +19,16 @@ impl AnimationSet {
     pub fn get_index(&self,index:usize) -> Option<&Animation> {
         self.animations.get(index)
     }
+
+    pub fn len(&self) -> usize {
+        self.animations.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.animations.is_empty()
+    }
+
+    pub fn index_of(&self,name:&str) -> Option<usize> {
+        self.names.get(name).copied()
+    }
 }
\ No newline at end of file
@@ -0,0 +1,371 @@
+use std::io::{Read, Write};
+use bevy_ecs::world::World;
+use glam::{Vec3, Quat};
+use seija_asset::{IAssetLoader, AssetDynamic, HandleUntyped, add_to_asset_type, AssetServer, AssetLoaderParams};
+use seija_asset::async_trait::async_trait;
+use seija_core::{TypeUuid, uuid::Uuid, smol_str::SmolStr};
+use seija_core::anyhow::{self, Result, bail};
+use seija_transform::TransformMatrix;
+
+use crate::{Skeleton, AnimationSet, Animation, Float3Key, QuaternionKey, EventKey};
+
+//骨骼和动画的二进制格式,小端序,关键帧直接保存量化后的值:
+//skeleton : magic(4) version(u32) joint_count(u32) [parent(i32) name rest_pose(10*f32)] * joint_count
+//animation: magic(4) version(u32) anim_count(u32) [name duration(f32) num_tracks(u32) translations rotations scales events] * anim_count
+//translations,scales: count(u32) [ratio(f32) track(u16) value(3*u16)] * count
+//rotations: count(u32) [ratio(f32) track(u16) largest(u8) value(3*i16)] * count
+//events: count(u32) [ratio(f32) name] * count
+//name: len(u16) utf8,骨骼名字长度为0表示没有名字
+//读取时数量都来自文件,不按数量预先分配内存,数据不够时在读取中报错
+pub const SKELETON_MAGIC:[u8;4] = *b"SSKL";
+pub const ANIMATION_MAGIC:[u8;4] = *b"SANM";
+pub const BINARY_VERSION:u32 = 1;
+
+pub fn write_skeleton<W:Write>(skeleton:&Skeleton,writer:&mut W) -> Result<()> {
+    writer.write_all(&SKELETON_MAGIC)?;
+    writer.write_all(&BINARY_VERSION.to_le_bytes())?;
+    writer.write_all(&(skeleton.num_joints() as u32).to_le_bytes())?;
+    for index in 0..skeleton.num_joints() {
+        let parent = skeleton.joint_parents[index].map(|v| v as i32).unwrap_or(-1);
+        writer.write_all(&parent.to_le_bytes())?;
+        write_str(writer, skeleton.joint_names[index].as_deref().unwrap_or_default())?;
+        let pose = &skeleton.joint_rest_poses[index];
+        for v in pose.position.to_array().iter().chain(pose.rotation.to_array().iter()).chain(pose.scale.to_array().iter()) {
+            writer.write_all(&v.to_le_bytes())?;
+        }
+    }
+    Ok(())
+}
+
+pub fn read_skeleton<R:Read>(reader:&mut R) -> Result<Skeleton> {
+    read_header(reader, &SKELETON_MAGIC)?;
+    let count = read_u32(reader)? as usize;
+    let mut skeleton = Skeleton::default();
+    for index in 0..count {
+        let parent = read_i32(reader)?;
+        if parent >= index as i32 {
+            bail!("skeleton joint {} parent error:{}",index,parent);
+        }
+        skeleton.joint_parents.push(if parent < 0 { None } else { Some(parent as usize) });
+        let name = read_str(reader)?;
+        skeleton.joint_names.push(if name.is_empty() { None } else { Some(name) });
+        let mut values = [0f32;10];
+        for v in values.iter_mut() {
+            *v = read_f32(reader)?;
+        }
+        skeleton.joint_rest_poses.push(TransformMatrix {
+            position:Vec3::new(values[0], values[1], values[2]),
+            rotation:Quat::from_xyzw(values[3], values[4], values[5], values[6]),
+            scale:Vec3::new(values[7], values[8], values[9])
+        });
+    }
+    Ok(skeleton)
+}
+
+pub fn write_animation_set<W:Write>(anim_set:&AnimationSet,writer:&mut W) -> Result<()> {
+    writer.write_all(&ANIMATION_MAGIC)?;
+    writer.write_all(&BINARY_VERSION.to_le_bytes())?;
+    writer.write_all(&(anim_set.len() as u32).to_le_bytes())?;
+    for anim in (0..anim_set.len()).filter_map(|i| anim_set.get_index(i)) {
+        write_str(writer, &anim.name)?;
+        writer.write_all(&anim.duration.to_le_bytes())?;
+        writer.write_all(&(anim.num_tracks as u32).to_le_bytes())?;
+        write_float3_keys(writer, &anim.translations_)?;
+        writer.write_all(&(anim.rotations_.len() as u32).to_le_bytes())?;
+        for key in anim.rotations_.iter() {
+            writer.write_all(&key.ratio.to_le_bytes())?;
+            writer.write_all(&key.track.to_le_bytes())?;
+            writer.write_all(&[key.largest])?;
+            for v in key.value.iter() {
+                writer.write_all(&v.to_le_bytes())?;
+            }
+        }
+        write_float3_keys(writer, &anim.scales_)?;
+        writer.write_all(&(anim.events.len() as u32).to_le_bytes())?;
+        for event in anim.events.iter() {
+            writer.write_all(&event.ratio.to_le_bytes())?;
+            write_str(writer, &event.name)?;
+        }
+    }
+    Ok(())
+}
+
+pub fn read_animation_set<R:Read>(reader:&mut R) -> Result<AnimationSet> {
+    read_header(reader, &ANIMATION_MAGIC)?;
+    let count = read_u32(reader)?;
+    let mut anim_set = AnimationSet::default();
+    for _ in 0..count {
+        let mut anim = Animation {
+            name:read_str(reader)?,
+            duration:read_f32(reader)?,
+            num_tracks:read_u32(reader)? as usize,
+            ..Default::default()
+        };
+        anim.translations_ = read_float3_keys(reader, anim.num_tracks)?;
+        let rotation_count = read_u32(reader)?;
+        for _ in 0..rotation_count {
+            let ratio = read_f32(reader)?;
+            let track = read_track(reader, anim.num_tracks)?;
+            let largest = read_u8(reader)?;
+            let value = [read_u16(reader)? as i16,read_u16(reader)? as i16,read_u16(reader)? as i16];
+            anim.rotations_.push(QuaternionKey { ratio, track, largest, value });
+        }
+        anim.scales_ = read_float3_keys(reader, anim.num_tracks)?;
+        let event_count = read_u32(reader)?;
+        for _ in 0..event_count {
+            anim.events.push(EventKey { ratio:read_f32(reader)?, name:read_str(reader)? });
+        }
+        check_track_keys(&anim.name, "translation", anim.num_tracks, anim.translations_.iter().map(|k| (k.track,k.ratio)))?;
+        check_track_keys(&anim.name, "rotation", anim.num_tracks, anim.rotations_.iter().map(|k| (k.track,k.ratio)))?;
+        check_track_keys(&anim.name, "scale", anim.num_tracks, anim.scales_.iter().map(|k| (k.track,k.ratio)))?;
+        anim_set.add(anim);
+    }
+    Ok(anim_set)
+}
+
+//采样时每个轨道至少需要两个关键帧,并且按顺序从ratio 0到1
+fn check_track_keys(name:&str,kind:&str,num_tracks:usize,keys:impl ExactSizeIterator<Item = (u16,f32)>) -> Result<()> {
+    //关键帧已经读出来了,轨道数量不超过关键帧数量的一半时才按轨道数量分配
+    if keys.len() < num_tracks * 2 {
+        bail!("animation {} {} keys too few:{}",name,kind,keys.len());
+    }
+    //每个轨道的(关键帧数量,上一个ratio)
+    let mut tracks = vec![(0usize,0f32);num_tracks];
+    for (track,ratio) in keys {
+        let (count,last) = &mut tracks[track as usize];
+        let is_sorted = if *count == 0 { ratio == 0f32 } else { ratio >= *last };
+        if !(0f32..=1f32).contains(&ratio) || !is_sorted {
+            bail!("animation {} {} track {} key ratio error:{}",name,kind,track,ratio);
+        }
+        *count += 1;
+        *last = ratio;
+    }
+    if let Some(track) = tracks.iter().position(|(count,last)| *count < 2 || *last != 1f32) {
+        bail!("animation {} {} track {} should have at least 2 keys and end at ratio 1",name,kind,track);
+    }
+    Ok(())
+}
+
+fn write_float3_keys<W:Write>(writer:&mut W,keys:&[Float3Key]) -> Result<()> {
+    writer.write_all(&(keys.len() as u32).to_le_bytes())?;
+    for key in keys.iter() {
+        writer.write_all(&key.ratio.to_le_bytes())?;
+        writer.write_all(&key.track.to_le_bytes())?;
+        for v in key.value.iter() {
+            writer.write_all(&v.to_le_bytes())?;
+        }
+    }
+    Ok(())
+}
+
+fn read_float3_keys<R:Read>(reader:&mut R,num_tracks:usize) -> Result<Vec<Float3Key>> {
+    let count = read_u32(reader)?;
+    let mut keys = vec![];
+    for _ in 0..count {
+        let ratio = read_f32(reader)?;
+        let track = read_track(reader, num_tracks)?;
+        let value = [read_u16(reader)?,read_u16(reader)?,read_u16(reader)?];
+        keys.push(Float3Key { ratio, track, value });
+    }
+    Ok(keys)
+}
+
+fn read_header<R:Read>(reader:&mut R,magic:&[u8;4]) -> Result<()> {
+    let mut buf = [0u8;4];
+    reader.read_exact(&mut buf)?;
+    if &buf != magic {
+        bail!("binary magic error:{:?}",buf);
+    }
+    let version = read_u32(reader)?;
+    if version != BINARY_VERSION {
+        bail!("binary version error:{}",version);
+    }
+    Ok(())
+}
+
+fn read_track<R:Read>(reader:&mut R,num_tracks:usize) -> Result<u16> {
+    let track = read_u16(reader)?;
+    if track as usize >= num_tracks {
+        bail!("animation track out of range:{}",track);
+    }
+    Ok(track)
+}
+
+fn write_str<W:Write>(writer:&mut W,value:&str) -> Result<()> {
+    if value.len() > u16::MAX as usize {
+        bail!("name too long:{} bytes",value.len());
+    }
+    writer.write_all(&(value.len() as u16).to_le_bytes())?;
+    writer.write_all(value.as_bytes())?;
+    Ok(())
+}
+
+fn read_str<R:Read>(reader:&mut R) -> Result<String> {
+    let len = read_u16(reader)?;
+    let mut bytes = vec![0u8;len as usize];
+    reader.read_exact(&mut bytes)?;
+    Ok(String::from_utf8(bytes)?)
+}
+
+fn read_u8<R:Read>(reader:&mut R) -> Result<u8> {
+    let mut buf = [0u8;1];
+    reader.read_exact(&mut buf)?;
+    Ok(buf[0])
+}
+
+fn read_u16<R:Read>(reader:&mut R) -> Result<u16> {
+    let mut buf = [0u8;2];
+    reader.read_exact(&mut buf)?;
+    Ok(u16::from_le_bytes(buf))
+}
+
+fn read_u32<R:Read>(reader:&mut R) -> Result<u32> {
+    let mut buf = [0u8;4];
+    reader.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_i32<R:Read>(reader:&mut R) -> Result<i32> {
+    Ok(read_u32(reader)? as i32)
+}
+
+fn read_f32<R:Read>(reader:&mut R) -> Result<f32> {
+    Ok(f32::from_bits(read_u32(reader)?))
+}
+
+#[derive(Default)]
+pub(crate) struct SkeletonLoader;
+
+#[async_trait]
+impl IAssetLoader for SkeletonLoader {
+    fn typ(&self) -> Uuid { Skeleton::TYPE_UUID }
+
+    fn add_to_asset(&self,world: &mut World,res:Box<dyn AssetDynamic>) -> anyhow::Result<HandleUntyped>  {
+        add_to_asset_type::<Skeleton>(world, res)
+    }
+
+    fn sync_load(&self,_: &mut World,path: &str,server: &AssetServer,_:Option<Box<dyn AssetLoaderParams>>) -> anyhow::Result<Box<dyn AssetDynamic>> {
+        let bytes = server.read_bytes(path)?;
+        Ok(Box::new(read_skeleton(&mut bytes.as_slice())?))
+    }
+
+    async fn async_load(&self,server:AssetServer,path:SmolStr,
+        _:Option<Box<dyn seija_asset::downcast_rs::DowncastSync>>,
+        _:Option<Box<dyn AssetLoaderParams>>) -> anyhow::Result<Box<dyn AssetDynamic>> {
+        let bytes = server.read_bytes_async(path.as_str()).await?;
+        Ok(Box::new(read_skeleton(&mut bytes.as_slice())?))
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct AnimationSetLoader;
+
+#[async_trait]
+impl IAssetLoader for AnimationSetLoader {
+    fn typ(&self) -> Uuid { AnimationSet::TYPE_UUID }
+
+    fn add_to_asset(&self,world: &mut World,res:Box<dyn AssetDynamic>) -> anyhow::Result<HandleUntyped>  {
+        add_to_asset_type::<AnimationSet>(world, res)
+    }
+
+    fn sync_load(&self,_: &mut World,path: &str,server: &AssetServer,_:Option<Box<dyn AssetLoaderParams>>) -> anyhow::Result<Box<dyn AssetDynamic>> {
+        let bytes = server.read_bytes(path)?;
+        Ok(Box::new(read_animation_set(&mut bytes.as_slice())?))
+    }
+
+    async fn async_load(&self,server:AssetServer,path:SmolStr,
+        _:Option<Box<dyn seija_asset::downcast_rs::DowncastSync>>,
+        _:Option<Box<dyn AssetLoaderParams>>) -> anyhow::Result<Box<dyn AssetDynamic>> {
+        let bytes = server.read_bytes_async(path.as_str()).await?;
+        Ok(Box::new(read_animation_set(&mut bytes.as_slice())?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{Vec3, Quat};
+    use seija_transform::TransformMatrix;
+    use crate::{Skeleton, AnimationSet, offine::{raw_animation::{RawAnimation, RawJointTrack, RawTranslationKey, RawRotationKey, RawEventKey},
+                animation_builder::AnimationBuilder}};
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let mut skeleton = Skeleton::new(2);
+        skeleton.joint_parents = vec![None,Some(0)];
+        skeleton.joint_names = vec![Some("root".into()),None];
+        skeleton.joint_rest_poses = vec![TransformMatrix::default(),TransformMatrix { position:Vec3::Y, ..Default::default() }];
+        let mut bytes = vec![];
+        write_skeleton(&skeleton, &mut bytes).unwrap();
+        let loaded = read_skeleton(&mut bytes.as_slice()).unwrap();
+        assert_eq!(loaded.joint_parents, skeleton.joint_parents);
+        assert_eq!(loaded.joint_names, skeleton.joint_names);
+        assert_eq!(loaded.joint_rest_poses[1].position, Vec3::Y);
+
+        let mut track = RawJointTrack::default();
+        track.translations.push(RawTranslationKey::new(0f32, Vec3::ZERO));
+        track.translations.push(RawTranslationKey::new(1f32, Vec3::new(1f32, 2f32, 3f32)));
+        track.rotations.push(RawRotationKey { time:0.5f32, value:Quat::from_rotation_z(1f32) });
+        let raw = RawAnimation { name:"walk".into(), duration:1f32, tracks:vec![track,RawJointTrack::default()], events:vec![RawEventKey::new(0.5f32, "step")] };
+        let mut anim_set = AnimationSet::default();
+        anim_set.add(AnimationBuilder::build(&raw));
+        let mut bytes = vec![];
+        write_animation_set(&anim_set, &mut bytes).unwrap();
+        let loaded = read_animation_set(&mut bytes.as_slice()).unwrap();
+        let (a,b) = (anim_set.get_index(0).unwrap(),loaded.get_index(loaded.index_of("walk").unwrap()).unwrap());
+        assert_eq!(a.num_tracks, b.num_tracks);
+        assert_eq!(a.translations_.len(), b.translations_.len());
+        assert_eq!(a.rotations_[2].value(), b.rotations_[2].value());
+        assert_eq!(b.translations_.iter().rev().find(|k| k.track == 0).unwrap().value(), Vec3::new(1f32, 2f32, 3f32));
+        assert_eq!(b.events[0].name, "step");
+
+        assert!(read_animation_set(&mut &bytes[..bytes.len() - 1]).is_err());
+        assert!(read_skeleton(&mut bytes.as_slice()).is_err());
+    }
+
+    #[test]
+    fn bad_input() {
+        //文件里的数量很大但数据不够时直接报错,不会按数量分配内存
+        let mut bytes = SKELETON_MAGIC.to_vec();
+        bytes.extend_from_slice(&BINARY_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&u32::MAX.to_le_bytes());
+        assert!(read_skeleton(&mut bytes.as_slice()).is_err());
+
+        let mut bytes = ANIMATION_MAGIC.to_vec();
+        bytes.extend_from_slice(&BINARY_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&1u32.to_le_bytes());
+        write_str(&mut bytes, "walk").unwrap();
+        bytes.extend_from_slice(&1f32.to_le_bytes());
+        bytes.extend_from_slice(&1u32.to_le_bytes());
+        bytes.extend_from_slice(&u32::MAX.to_le_bytes());
+        assert!(read_animation_set(&mut bytes.as_slice()).is_err());
+
+        let mut skeleton = Skeleton::new(1);
+        skeleton.joint_parents = vec![None];
+        skeleton.joint_names = vec![Some("a".repeat(u16::MAX as usize + 1))];
+        skeleton.joint_rest_poses = vec![TransformMatrix::default()];
+        assert!(write_skeleton(&skeleton, &mut vec![]).is_err());
+
+        //关键帧不满足采样的要求时拒绝加载,不会在采样时越界
+        let mut track = RawJointTrack::default();
+        track.translations.push(RawTranslationKey::new(0f32, Vec3::ZERO));
+        track.translations.push(RawTranslationKey::new(1f32, Vec3::ONE));
+        let raw = RawAnimation { name:"walk".into(), duration:1.3f32, tracks:vec![track,RawJointTrack::default()], events:vec![] };
+        let can_load = |f:fn(&mut Animation)| -> bool {
+            let mut anim = AnimationBuilder::build(&raw);
+            f(&mut anim);
+            let mut anim_set = AnimationSet::default();
+            anim_set.add(anim);
+            let mut bytes = vec![];
+            write_animation_set(&anim_set, &mut bytes).unwrap();
+            read_animation_set(&mut bytes.as_slice()).is_ok()
+        };
+        assert!(can_load(|_| {}));
+        assert!(!can_load(|a| { a.translations_.retain(|k| k.track != 1 || k.ratio == 0f32); }));
+        assert!(!can_load(|a| a.rotations_.swap(0, 2)));
+        assert!(!can_load(|a| a.scales_[0].ratio = -0.5f32));
+        assert!(!can_load(|a| a.scales_[2].ratio = 1.5f32));
+        assert!(!can_load(|a| a.translations_.last_mut().unwrap().ratio = 0.9f32));
+        assert!(!can_load(|a| a.num_tracks = 3));
+    }
+}
@@ -0,0 +1,120 @@
+use glam::{Vec3, Quat};
+
+//四元数去掉绝对值最大的分量后,剩下三个分量的绝对值不超过1/sqrt(2)
+const QUAT_SCALE:f32 = 32767f32 * std::f32::consts::SQRT_2;
+const QUAT_SIGN_BIT:u8 = 4;
+//半精度浮点数能表示的最大值
+pub const HALF_MAX:f32 = 65504f32;
+
+pub fn f32_to_half(value:f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = (bits >> 16) & 0x8000;
+    let exp = ((bits >> 23) & 0xff) as i32;
+    let mantissa = bits & 0x7f_ffff;
+    if exp == 0xff {
+        let nan = if mantissa != 0 { 0x200 } else { 0 };
+        return (sign | 0x7c00 | nan) as u16;
+    }
+    let half_exp = exp - 127 + 15;
+    if half_exp >= 0x1f {
+        return (sign | 0x7c00) as u16;
+    }
+    if half_exp <= 0 {
+        //非规格化数
+        if half_exp < -10 { return sign as u16; }
+        let mantissa = mantissa | 0x80_0000;
+        let shift = (14 - half_exp) as u32;
+        let round = (mantissa >> (shift - 1)) & 1;
+        return (sign | ((mantissa >> shift) + round)) as u16;
+    }
+    //舍入进位到指数上也是正确的结果
+    let round = (mantissa >> 12) & 1;
+    ((sign | ((half_exp as u32) << 10) | (mantissa >> 13)) + round) as u16
+}
+
+pub fn half_to_f32(half:u16) -> f32 {
+    let sign = ((half & 0x8000) as u32) << 16;
+    let exp = ((half >> 10) & 0x1f) as u32;
+    let mantissa = (half & 0x3ff) as u32;
+    match exp {
+        0 => {
+            let value = mantissa as f32 / 16777216f32;
+            if sign != 0 { -value } else { value }
+        },
+        0x1f => f32::from_bits(sign | 0x7f80_0000 | (mantissa << 13)),
+        _ => f32::from_bits(sign | ((exp + 112) << 23) | (mantissa << 13))
+    }
+}
+
+//超出半精度范围的分量限制到HALF_MAX,而不是变成无穷大
+pub fn compress_vec3(value:Vec3) -> [u16;3] {
+    if value.abs().max_element() > HALF_MAX {
+        log::warn!("vec3 out of half float range:{:?}",value);
+    }
+    let value = value.clamp(Vec3::splat(-HALF_MAX), Vec3::splat(HALF_MAX));
+    [f32_to_half(value.x),f32_to_half(value.y),f32_to_half(value.z)]
+}
+
+pub fn decompress_vec3(value:&[u16;3]) -> Vec3 {
+    Vec3::new(half_to_f32(value[0]), half_to_f32(value[1]), half_to_f32(value[2]))
+}
+
+//返回(最大分量的索引和符号,其他三个分量),保留符号让相邻关键帧的插值方向不变
+pub fn compress_quat(value:Quat) -> (u8,[i16;3]) {
+    let arr = value.normalize().to_array();
+    let mut largest = 0;
+    for index in 1..4 {
+        if arr[index].abs() > arr[largest].abs() {
+            largest = index;
+        }
+    }
+    let mut out = [0i16;3];
+    for (dst,src) in out.iter_mut().zip(arr.iter().enumerate().filter(|(i,_)| *i != largest)) {
+        *dst = (src.1 * QUAT_SCALE).round().clamp(-32767f32, 32767f32) as i16;
+    }
+    let sign = if arr[largest] < 0f32 { QUAT_SIGN_BIT } else { 0 };
+    (largest as u8 | sign,out)
+}
+
+pub fn decompress_quat(largest:u8,value:&[i16;3]) -> Quat {
+    let index = (largest & 3) as usize;
+    let mut arr = [0f32;4];
+    let mut sum = 0f32;
+    let mut values = value.iter();
+    for (i,dst) in arr.iter_mut().enumerate() {
+        if i == index { continue; }
+        let v = *values.next().unwrap_or(&0) as f32 / QUAT_SCALE;
+        *dst = v;
+        sum += v * v;
+    }
+    let w = (1f32 - sum).max(0f32).sqrt();
+    arr[index] = if largest & QUAT_SIGN_BIT != 0 { -w } else { w };
+    Quat::from_array(arr)
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{Vec3, Quat};
+    use super::*;
+
+    #[test]
+    fn quantize() {
+        for v in [0f32,1f32,-2.5f32,0.1f32,100.25f32,0.00002f32,65504f32] {
+            let half = half_to_f32(f32_to_half(v));
+            assert!((half - v).abs() <= v.abs() * 0.001f32 + 0.00001f32, "{} {}",v,half);
+        }
+        assert!(half_to_f32(f32_to_half(1e6f32)).is_infinite());
+
+        let v = Vec3::new(1.5f32, -0.25f32, 12f32);
+        assert!((decompress_vec3(&compress_vec3(v)) - v).length() < 0.01f32);
+        let v = decompress_vec3(&compress_vec3(Vec3::new(1e6f32, -7e4f32, 1f32)));
+        assert_eq!(v, Vec3::new(HALF_MAX, -HALF_MAX, 1f32));
+
+        let quats = [Quat::IDENTITY,-Quat::IDENTITY,Quat::from_rotation_y(2f32),Quat::from_euler(glam::EulerRot::XYZ, 0.3f32, -1.2f32, 2.5f32)];
+        for q in quats {
+            let (largest,value) = compress_quat(q);
+            let d = decompress_quat(largest, &value);
+            assert!(q.dot(d) > 0.9999f32, "{:?} {:?}",q,d);
+        }
+    }
+}
@@ -0,0 +1,215 @@
+use bevy_ecs::prelude::Entity;
+use glam::Vec3;
+use seija_app::{ecs::world::World, App};
+use seija_asset::{AssetServer, Assets, Handle, HandleId};
+use seija_core::TypeUuid;
+
+use crate::{Skeleton3dModule, Animator, AnimationControl, AnimStateMachine, IKControl};
+
+#[no_mangle]
+pub unsafe extern "C" fn skeleton3d_add_module(app: &mut App) {
+    app.add_module(Skeleton3dModule);
+}
+
+unsafe fn c_str<'a>(name:*const i8) -> &'a str {
+    std::ffi::CStr::from_ptr(name).to_str().unwrap_or_default()
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn animation_control_cross_fade(world: &mut World,entity_id:u64,index:i32,fade_time:f32) {
+    let entity = Entity::from_bits(entity_id);
+    if let Some(mut control) = world.entity_mut(entity).get_mut::<AnimationControl>() {
+        control.cross_fade(index as usize, fade_time);
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn animation_control_stop(world: &mut World,entity_id:u64) {
+    let entity = Entity::from_bits(entity_id);
+    if let Some(mut control) = world.entity_mut(entity).get_mut::<AnimationControl>() {
+        control.stop();
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn animation_control_set_root_motion(world: &mut World,entity_id:u64,root_motion:bool) {
+    let entity = Entity::from_bits(entity_id);
+    if let Some(mut control) = world.entity_mut(entity).get_mut::<AnimationControl>() {
+        control.set_root_motion(root_motion);
+    }
+}
+
+//name为空指针时用第0个关节
+#[no_mangle]
+pub unsafe extern "C" fn animation_control_set_root_joint(world: &mut World,entity_id:u64,name:*const i8) {
+    let entity = Entity::from_bits(entity_id);
+    if let Some(mut control) = world.entity_mut(entity).get_mut::<AnimationControl>() {
+        control.set_root_joint(if name.is_null() { None } else { Some(c_str(name)) });
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn animation_control_event_count(world: &mut World,entity_id:u64) -> i32 {
+    let entity = Entity::from_bits(entity_id);
+    world.entity(entity).get::<AnimationControl>().map(|c| c.events().len() as i32).unwrap_or(0)
+}
+
+//把这一帧第index个事件的名字写入out,返回字节长度,out_len不够时只返回长度,没有事件时返回-1
+#[no_mangle]
+pub unsafe extern "C" fn animation_control_get_event(world: &mut World,entity_id:u64,index:i32,out:*mut u8,out_len:usize) -> i32 {
+    let entity = Entity::from_bits(entity_id);
+    let control = match world.entity(entity).get::<AnimationControl>() { Some(v) => v, None => return -1 };
+    match control.events().get(index as usize) {
+        Some(event) => write_name(&event.name, out, out_len),
+        None => -1
+    }
+}
+
+unsafe fn write_name(name:&str,out:*mut u8,out_len:usize) -> i32 {
+    if !out.is_null() && name.len() <= out_len {
+        std::ptr::copy_nonoverlapping(name.as_ptr(), out, name.len());
+    }
+    name.len() as i32
+}
+
+//实体上需要已经有AnimationControl,machine_id必须是已经加载的状态机,否则返回false
+#[no_mangle]
+pub unsafe extern "C" fn animator_add(world: &mut World,entity_id:u64,machine_id:u64,layer:i32) -> bool {
+    let entity = Entity::from_bits(entity_id);
+    let hid = HandleId::new(AnimStateMachine::TYPE_UUID, machine_id);
+    if !world.get_resource::<Assets<AnimStateMachine>>().map(|m| m.contains(hid)).unwrap_or(false) {
+        log::error!("animator_add not found state machine {}",machine_id);
+        return false;
+    }
+    let ref_sender = world.get_resource::<AssetServer>().unwrap().get_ref_sender();
+    let h_machine:Handle<AnimStateMachine> = Handle::strong(hid, ref_sender);
+    insert_animator(world, entity, h_machine, layer);
+    true
+}
+
+//从xml或json文件同步加载状态机并添加Animator,加载失败返回false
+#[no_mangle]
+pub unsafe extern "C" fn animator_add_by_path(world: &mut World,entity_id:u64,path:*const i8,layer:i32) -> bool {
+    let entity = Entity::from_bits(entity_id);
+    let server = world.get_resource::<AssetServer>().unwrap().clone();
+    match server.load_sync::<AnimStateMachine>(world, c_str(path), None) {
+        Ok(h_machine) => {
+            insert_animator(world, entity, h_machine, layer);
+            true
+        },
+        Err(err) => {
+            log::error!("load state machine error:{:?}",err);
+            false
+        }
+    }
+}
+
+fn insert_animator(world: &mut World,entity:Entity,h_machine:Handle<AnimStateMachine>,layer:i32) {
+    let mut animator = Animator::new(h_machine);
+    animator.layer = layer.max(0) as usize;
+    world.entity_mut(entity).insert(animator);
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn animator_set_float(world: &mut World,entity_id:u64,name:*const i8,value:f32) {
+    let entity = Entity::from_bits(entity_id);
+    if let Some(mut animator) = world.entity_mut(entity).get_mut::<Animator>() {
+        animator.set_float(c_str(name), value);
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn animator_set_bool(world: &mut World,entity_id:u64,name:*const i8,value:bool) {
+    let entity = Entity::from_bits(entity_id);
+    if let Some(mut animator) = world.entity_mut(entity).get_mut::<Animator>() {
+        animator.set_bool(c_str(name), value);
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn animator_set_trigger(world: &mut World,entity_id:u64,name:*const i8) {
+    let entity = Entity::from_bits(entity_id);
+    if let Some(mut animator) = world.entity_mut(entity).get_mut::<Animator>() {
+        animator.set_trigger(c_str(name));
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn animator_play_state(world: &mut World,entity_id:u64,name:*const i8,fade_time:f32) {
+    let entity = Entity::from_bits(entity_id);
+    if let Some(mut animator) = world.entity_mut(entity).get_mut::<Animator>() {
+        animator.play_state(c_str(name), fade_time);
+    }
+}
+
+//把当前状态名写入out,返回字节长度,out_len不够时只返回长度,没有状态时返回-1
+#[no_mangle]
+pub unsafe extern "C" fn animator_get_state(world: &mut World,entity_id:u64,out:*mut u8,out_len:usize) -> i32 {
+    let entity = Entity::from_bits(entity_id);
+    let animator = match world.entity(entity).get::<Animator>() { Some(v) => v, None => return -1 };
+    let machines = world.get_resource::<Assets<AnimStateMachine>>().unwrap();
+    let state = machines.get(&animator.machine.id).and_then(|m| animator.current_state().and_then(|s| m.states.get(s)));
+    match state {
+        Some(state) => write_name(&state.name, out, out_len),
+        None => -1
+    }
+}
+
+unsafe fn c_str_list(names:*const *const i8,count:usize) -> Vec<String> {
+    std::slice::from_raw_parts(names, count).iter().map(|n| c_str(*n).to_string()).collect()
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ik_control_add(world: &mut World,entity_id:u64) {
+    let entity = Entity::from_bits(entity_id);
+    world.entity_mut(entity).insert(IKControl::default());
+}
+
+//返回IK链的索引,实体上没有IKControl时返回-1
+#[no_mangle]
+pub unsafe extern "C" fn ik_control_add_two_bone(world: &mut World,entity_id:u64,start:*const i8,mid:*const i8,end:*const i8,pole_vector:&Vec3) -> i32 {
+    let entity = Entity::from_bits(entity_id);
+    match world.entity_mut(entity).get_mut::<IKControl>() {
+        Some(mut ik) => ik.add_two_bone(c_str(start), c_str(mid), c_str(end), *pole_vector) as i32,
+        None => -1
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ik_control_add_aim(world: &mut World,entity_id:u64,names:*const *const i8,count:usize,
+                                            forward:&Vec3,up:&Vec3,pole_vector:&Vec3,chain_factor:f32) -> i32 {
+    let entity = Entity::from_bits(entity_id);
+    match world.entity_mut(entity).get_mut::<IKControl>() {
+        Some(mut ik) => ik.add_aim(c_str_list(names, count), *forward, *up, *pole_vector, chain_factor) as i32,
+        None => -1
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ik_control_add_fabrik(world: &mut World,entity_id:u64,names:*const *const i8,count:usize,tolerance:f32,max_iterations:i32) -> i32 {
+    let entity = Entity::from_bits(entity_id);
+    match world.entity_mut(entity).get_mut::<IKControl>() {
+        Some(mut ik) => ik.add_fabrik(c_str_list(names, count), tolerance, max_iterations.max(1) as usize) as i32,
+        None => -1
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ik_control_set_target(world: &mut World,entity_id:u64,index:i32,target:&Vec3) {
+    let entity = Entity::from_bits(entity_id);
+    if let Some(mut ik) = world.entity_mut(entity).get_mut::<IKControl>() {
+        if let Some(chain) = ik.chain_mut(index as usize) {
+            chain.target = *target;
+        }
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ik_control_set_weight(world: &mut World,entity_id:u64,index:i32,weight:f32) {
+    let entity = Entity::from_bits(entity_id);
+    if let Some(mut ik) = world.entity_mut(entity).get_mut::<IKControl>() {
+        if let Some(chain) = ik.chain_mut(index as usize) {
+            chain.weight = weight;
+        }
+    }
+}
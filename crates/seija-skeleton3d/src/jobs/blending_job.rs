@@ -0,0 +1,146 @@
+use glam::{Vec3, Quat};
+use seija_transform::TransformMatrix;
+
+pub struct BlendingLayer<'a> {
+    pub transform:&'a [TransformMatrix],
+    pub weight:f32,
+    //每个关节的权重,为空时所有关节都是1
+    pub joint_weights:Option<&'a [f32]>
+}
+
+impl<'a> BlendingLayer<'a> {
+    pub fn new(transform:&'a [TransformMatrix],weight:f32) -> Self {
+        BlendingLayer { transform, weight, joint_weights:None }
+    }
+
+    pub fn with_joint_weights(mut self,joint_weights:&'a [f32]) -> Self {
+        self.joint_weights = Some(joint_weights);
+        self
+    }
+
+    fn joint_weight(&self,index:usize) -> f32 {
+        let joint_weight = self.joint_weights.and_then(|w| w.get(index).copied()).unwrap_or(1f32);
+        (self.weight * joint_weight).max(0f32)
+    }
+}
+
+//按权重混合多个局部姿势,普通层按权重归一化,叠加层在结果上累加
+pub struct BlendingJob<'a> {
+    //关节的总权重小于这个值时用rest_pose补足
+    pub threshold:f32,
+    pub rest_pose:&'a [TransformMatrix],
+    pub layers:Vec<BlendingLayer<'a>>,
+    pub additive_layers:Vec<BlendingLayer<'a>>
+}
+
+impl<'a> BlendingJob<'a> {
+    pub fn new(rest_pose:&'a [TransformMatrix]) -> Self {
+        BlendingJob { threshold:0.1f32, rest_pose, layers:vec![], additive_layers:vec![] }
+    }
+
+    pub fn run(&self,output:&mut [TransformMatrix]) -> bool {
+        let num_joints = output.len();
+        if self.rest_pose.len() != num_joints || self.threshold <= 0f32 {
+            return false;
+        }
+        if self.layers.iter().chain(self.additive_layers.iter()).any(|l| l.transform.len() != num_joints) {
+            return false;
+        }
+        for (index,out) in output.iter_mut().enumerate() {
+            let mut position = Vec3::ZERO;
+            let mut scale = Vec3::ZERO;
+            let mut rotation = Quat::from_xyzw(0f32, 0f32, 0f32, 0f32);
+            let mut total = 0f32;
+            for layer in self.layers.iter() {
+                let weight = layer.joint_weight(index);
+                if weight <= 0f32 { continue; }
+                blend_into(&layer.transform[index], weight, &mut position, &mut rotation, &mut scale);
+                total += weight;
+            }
+            if total < self.threshold {
+                let weight = self.threshold - total;
+                blend_into(&self.rest_pose[index], weight, &mut position, &mut rotation, &mut scale);
+                total = self.threshold;
+            }
+            let inv_total = 1f32 / total;
+            out.position = position * inv_total;
+            out.scale = scale * inv_total;
+            out.rotation = rotation.normalize();
+
+            for layer in self.additive_layers.iter() {
+                let weight = layer.joint_weight(index).min(1f32);
+                if weight <= 0f32 { continue; }
+                let delta = &layer.transform[index];
+                out.position += delta.position * weight;
+                out.scale *= Vec3::ONE.lerp(delta.scale, weight);
+                out.rotation = (out.rotation * Quat::IDENTITY.slerp(delta.rotation, weight)).normalize();
+            }
+        }
+        true
+    }
+}
+
+fn blend_into(value:&TransformMatrix,weight:f32,position:&mut Vec3,rotation:&mut Quat,scale:&mut Vec3) {
+    *position += value.position * weight;
+    *scale += value.scale * weight;
+    //q和-q是同一个旋转,取和已累加结果同半球的那个
+    let sign = if rotation.dot(value.rotation) < 0f32 { -weight } else { weight };
+    *rotation = *rotation + value.rotation * sign;
+}
+
+//以root为根的子树权重为weight,其他关节为0,要求父关节的索引小于子关节
+pub fn joint_mask(joint_parents:&[Option<usize>],root:usize,weight:f32) -> Vec<f32> {
+    let mut mask = vec![0f32;joint_parents.len()];
+    for index in 0..joint_parents.len() {
+        let in_tree = index == root || joint_parents[index].map(|p| mask[p] > 0f32).unwrap_or(false);
+        if in_tree {
+            mask[index] = weight;
+        }
+    }
+    mask
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{Vec3, Quat};
+    use seija_transform::TransformMatrix;
+    use super::{BlendingJob, BlendingLayer, joint_mask};
+
+    fn pose(x:f32,angle:f32) -> Vec<TransformMatrix> {
+        let t = TransformMatrix { position:Vec3::new(x, 0f32, 0f32), rotation:Quat::from_rotation_y(angle), ..Default::default() };
+        vec![t;2]
+    }
+
+    #[test]
+    fn blend_layers() {
+        let rest = pose(0f32, 0f32);
+        let a = pose(2f32, 0f32);
+        let b = pose(4f32, std::f32::consts::FRAC_PI_2);
+        let mut out = pose(0f32, 0f32);
+        let mut job = BlendingJob::new(&rest);
+        job.layers.push(BlendingLayer::new(&a, 0.75f32));
+        job.layers.push(BlendingLayer::new(&b, 0.25f32));
+        assert!(job.run(&mut out));
+        assert!((out[0].position.x - 2.5f32).abs() < 0.0001f32);
+        let (_,angle) = out[0].rotation.to_axis_angle();
+        assert!(angle > 0f32 && angle < std::f32::consts::FRAC_PI_4);
+
+        //第二个关节只播放b
+        let mask = [0f32,1f32];
+        let mut job = BlendingJob::new(&rest);
+        job.layers.push(BlendingLayer::new(&b, 1f32).with_joint_weights(&mask));
+        assert!(job.run(&mut out));
+        assert!(out[0].position.x.abs() < 0.0001f32);
+        assert!((out[1].position.x - 4f32).abs() < 0.0001f32);
+
+        job.additive_layers.push(BlendingLayer::new(&a, 0.5f32));
+        assert!(job.run(&mut out));
+        assert!((out[1].position.x - 5f32).abs() < 0.0001f32);
+    }
+
+    #[test]
+    fn mask_subtree() {
+        let parents = [None,Some(0),Some(1),Some(0),Some(2)];
+        assert_eq!(joint_mask(&parents, 1, 1f32), vec![0f32,1f32,1f32,0f32,1f32]);
+    }
+}
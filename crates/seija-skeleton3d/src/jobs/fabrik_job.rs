@@ -0,0 +1,68 @@
+use glam::Vec3;
+
+//FABRIK求解任意长度的关节链,只改变位置,旋转由调用方根据新位置计算
+pub struct FabrikJob {
+    pub target:Vec3,
+    pub tolerance:f32,
+    pub max_iterations:usize
+}
+
+impl FabrikJob {
+    pub fn new(target:Vec3) -> Self {
+        FabrikJob { target, tolerance:0.001f32, max_iterations:10 }
+    }
+
+    //positions是从根到末端的关节位置,求解后写回,返回末端是否到达目标
+    pub fn run(&self,positions:&mut [Vec3]) -> bool {
+        let count = positions.len();
+        if count < 2 { return false; }
+        let lengths:Vec<f32> = positions.windows(2).map(|w| (w[1] - w[0]).length()).collect();
+        let root = positions[0];
+        //目标超出链的长度时朝目标伸直
+        if (self.target - root).length() >= lengths.iter().sum::<f32>() {
+            let dir = (self.target - root).normalize_or_zero();
+            for index in 1..count {
+                positions[index] = positions[index - 1] + dir * lengths[index - 1];
+            }
+            return false;
+        }
+        for _ in 0..self.max_iterations {
+            if (positions[count - 1] - self.target).length() <= self.tolerance {
+                return true;
+            }
+            positions[count - 1] = self.target;
+            for index in (0..count - 1).rev() {
+                let dir = (positions[index] - positions[index + 1]).normalize_or_zero();
+                positions[index] = positions[index + 1] + dir * lengths[index];
+            }
+            positions[0] = root;
+            for index in 1..count {
+                let dir = (positions[index] - positions[index - 1]).normalize_or_zero();
+                positions[index] = positions[index - 1] + dir * lengths[index - 1];
+            }
+        }
+        (positions[count - 1] - self.target).length() <= self.tolerance
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec3;
+    use super::FabrikJob;
+
+    #[test]
+    fn solve_chain() {
+        let mut positions:Vec<Vec3> = (0..4).map(|i| Vec3::new(0f32, i as f32, 0f32)).collect();
+        let job = FabrikJob::new(Vec3::new(1.5f32, 1.5f32, 0f32));
+        assert!(job.run(&mut positions));
+        assert_eq!(positions[0], Vec3::ZERO);
+        for w in positions.windows(2) {
+            assert!(((w[1] - w[0]).length() - 1f32).abs() < 0.001f32);
+        }
+
+        //够不到时伸直
+        let job = FabrikJob::new(Vec3::new(10f32, 0f32, 0f32));
+        assert!(!job.run(&mut positions));
+        assert!((positions[3] - Vec3::new(3f32, 0f32, 0f32)).length() < 0.001f32);
+    }
+}
@@ -0,0 +1,62 @@
+use glam::{Vec3, Quat, Mat4};
+
+use super::ik_two_bone_job::{twist_to_pole, to_local_correction};
+
+//让关节的forward轴朝向目标,up轴尽量朝向pole_vector,用于头部看向目标或炮塔瞄准
+pub struct IKAimJob<'a> {
+    pub target:Vec3,
+    //关节局部空间的朝向轴
+    pub forward:Vec3,
+    //关节局部空间的上方向轴
+    pub up:Vec3,
+    //模型空间
+    pub pole_vector:Vec3,
+    pub weight:f32,
+    pub joint:&'a Mat4
+}
+
+impl<'a> IKAimJob<'a> {
+    pub fn new(joint:&'a Mat4) -> Self {
+        IKAimJob { target:Vec3::ZERO, forward:Vec3::Z, up:Vec3::Y, pole_vector:Vec3::Y, weight:1f32, joint }
+    }
+
+    //返回乘到关节局部旋转右边的修正
+    pub fn run(&self) -> Quat {
+        let (_,rot,position) = self.joint.to_scale_rotation_translation();
+        let to_target = self.target - position;
+        let forward = rot * self.forward;
+        if self.weight <= 0f32 || to_target.length_squared() < 0.00001f32 || forward.length_squared() < 0.00001f32 {
+            return Quat::IDENTITY;
+        }
+        let aim_dir = to_target.normalize();
+        let aim = Quat::from_rotation_arc(forward.normalize(), aim_dir);
+        let twist = twist_to_pole(aim_dir, aim * rot * self.up, self.pole_vector);
+        to_local_correction(rot, Quat::IDENTITY.slerp(twist * aim, self.weight.min(1f32)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{Vec3, Quat, Mat4};
+    use super::IKAimJob;
+
+    #[test]
+    fn aim_target() {
+        //关节绕y轴转了90度,局部的z轴朝向模型空间的x
+        let rot = Quat::from_rotation_y(90f32.to_radians());
+        let joint = Mat4::from_rotation_translation(rot, Vec3::new(0f32, 1f32, 0f32));
+        let mut job = IKAimJob::new(&joint);
+        job.target = Vec3::new(0f32, 1f32, -3f32);
+        let new_rot = rot * job.run();
+        assert!((new_rot * Vec3::Z).abs_diff_eq(-Vec3::Z, 0.001f32));
+        assert!((new_rot * Vec3::Y).abs_diff_eq(Vec3::Y, 0.001f32));
+
+        //权重一半时转到一半
+        job.weight = 0.5f32;
+        let new_rot = rot * job.run();
+        assert!((new_rot * Vec3::Z).abs_diff_eq(Vec3::new(1f32, 0f32, -1f32).normalize(), 0.001f32));
+
+        job.weight = 0f32;
+        assert_eq!(job.run(), Quat::IDENTITY);
+    }
+}
@@ -0,0 +1,108 @@
+use glam::{Vec3, Quat, Mat4};
+
+const IK_EPSILON:f32 = 0.00001f32;
+
+//两段骨骼的IK,比如大腿小腿脚或者上臂前臂手,所有位置都在模型空间
+pub struct IKTwoBoneJob<'a> {
+    pub target:Vec3,
+    //弯曲的中间关节会朝向这个方向,比如膝盖朝前
+    pub pole_vector:Vec3,
+    pub weight:f32,
+    pub start_joint:&'a Mat4,
+    pub mid_joint:&'a Mat4,
+    pub end_joint:&'a Mat4
+}
+
+pub struct IKTwoBoneResult {
+    //乘到关节局部旋转的右边
+    pub start_correction:Quat,
+    pub mid_correction:Quat,
+    pub reached:bool
+}
+
+impl<'a> IKTwoBoneJob<'a> {
+    pub fn new(start_joint:&'a Mat4,mid_joint:&'a Mat4,end_joint:&'a Mat4) -> Self {
+        IKTwoBoneJob { target:Vec3::ZERO, pole_vector:Vec3::Y, weight:1f32, start_joint, mid_joint, end_joint }
+    }
+
+    pub fn run(&self) -> IKTwoBoneResult {
+        let mut result = IKTwoBoneResult { start_correction:Quat::IDENTITY, mid_correction:Quat::IDENTITY, reached:false };
+        let (_,start_rot,start) = self.start_joint.to_scale_rotation_translation();
+        let (_,mid_rot,mid) = self.mid_joint.to_scale_rotation_translation();
+        let end = self.end_joint.w_axis.truncate();
+        let weight = self.weight.clamp(0f32, 1f32);
+        let start_mid = mid - start;
+        let mid_end = end - mid;
+        let start_target = self.target - start;
+        let (len_a,len_b,target_len) = (start_mid.length(),mid_end.length(),start_target.length());
+        if weight <= 0f32 || len_a < IK_EPSILON || len_b < IK_EPSILON || target_len < IK_EPSILON {
+            return result;
+        }
+        result.reached = target_len <= len_a + len_b;
+        //够不到时伸直,太近时尽量折叠
+        let dist = target_len.clamp((len_a - len_b).abs(), len_a + len_b);
+
+        //用余弦定理算出中间关节需要的内角,绕弯曲平面的法线旋转
+        let cur_angle = (-start_mid).angle_between(mid_end);
+        let cos_angle = ((len_a * len_a + len_b * len_b - dist * dist) / (2f32 * len_a * len_b)).clamp(-1f32, 1f32);
+        let mut axis = mid_end.cross(-start_mid);
+        if axis.length_squared() < IK_EPSILON {
+            axis = mid_end.cross(self.pole_vector);
+        }
+        if axis.length_squared() < IK_EPSILON {
+            axis = mid_end.any_orthonormal_vector();
+        }
+        let mid_delta = Quat::from_axis_angle(axis.normalize(), cur_angle - cos_angle.acos());
+
+        //旋转起始关节让末端对准目标,再绕起始关节到目标的轴转向pole_vector
+        let target_dir = start_target / target_len;
+        let new_end = mid + mid_delta * mid_end;
+        let aim = Quat::from_rotation_arc((new_end - start).normalize(), target_dir);
+        let twist = twist_to_pole(target_dir, aim * start_mid, self.pole_vector);
+        let start_delta = twist * aim;
+
+        result.start_correction = to_local_correction(start_rot, Quat::IDENTITY.slerp(start_delta, weight));
+        result.mid_correction = to_local_correction(mid_rot, Quat::IDENTITY.slerp(mid_delta, weight));
+        result
+    }
+}
+
+//绕axis旋转,让dir在垂直axis的平面上的投影和pole的投影同向
+pub(crate) fn twist_to_pole(axis:Vec3,dir:Vec3,pole:Vec3) -> Quat {
+    let dir_plane = dir - axis * dir.dot(axis);
+    let pole_plane = pole - axis * pole.dot(axis);
+    if dir_plane.length_squared() < IK_EPSILON || pole_plane.length_squared() < IK_EPSILON {
+        return Quat::IDENTITY;
+    }
+    let angle = axis.dot(dir_plane.cross(pole_plane)).atan2(dir_plane.dot(pole_plane));
+    Quat::from_axis_angle(axis, angle)
+}
+
+//模型空间的旋转变化转换到关节的局部空间
+pub(crate) fn to_local_correction(joint_rot:Quat,delta:Quat) -> Quat {
+    (joint_rot.inverse() * delta * joint_rot).normalize()
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{Vec3, Mat4};
+    use super::IKTwoBoneJob;
+
+    #[test]
+    fn reach_target() {
+        //沿y轴向下的腿,膝盖朝z
+        let start = Mat4::from_translation(Vec3::new(0f32, 2f32, 0f32));
+        let mid = Mat4::from_translation(Vec3::new(0f32, 1f32, 0f32));
+        let end = Mat4::from_translation(Vec3::ZERO);
+        let mut job = IKTwoBoneJob::new(&start, &mid, &end);
+        job.target = Vec3::new(0.5f32, 0.8f32, 0f32);
+        job.pole_vector = Vec3::Z;
+        let result = job.run();
+        assert!(result.reached);
+
+        let new_mid = Vec3::new(0f32, 2f32, 0f32) + result.start_correction * Vec3::new(0f32, -1f32, 0f32);
+        let new_end = new_mid + result.start_correction * result.mid_correction * Vec3::new(0f32, -1f32, 0f32);
+        assert!((new_end - job.target).length() < 0.001f32);
+        assert!(new_mid.z > 0f32);
+    }
+}
@@ -14,10 +14,15 @@ impl<'a> LocalToModelJob<'a> {
     }
 
     pub fn run(&self,input:&Vec<TransformMatrix>,output:&mut Vec<Mat4>) {
+        self.run_from(input, output, 0);
+    }
+
+    //只重新计算from及之后的关节,关节按深度优先排列,from的子关节都在它后面
+    pub fn run_from(&self,input:&[TransformMatrix],output:&mut [Mat4],from:usize) {
         let parents = &self.skeleton.joint_parents;
         let ident = Mat4::IDENTITY;
         let root_mat4 = if let Some(root) = self.root { root } else { &ident };
-        for idx in 0..parents.len() {
+        for idx in from..parents.len() {
             let local = input[idx].matrix();
             if let Some(parent_index) =  parents[idx] {
                 output[idx] = output[parent_index] * local;
@@ -1,5 +1,13 @@
 mod sampling_job;
 mod local_to_model_job;
+mod blending_job;
+mod ik_two_bone_job;
+mod ik_aim_job;
+mod fabrik_job;
 
 pub use sampling_job::{SamplingJob};
-pub use local_to_model_job::{LocalToModelJob};
\ No newline at end of file
+pub use local_to_model_job::{LocalToModelJob};
+pub use blending_job::{BlendingJob,BlendingLayer,joint_mask};
+pub use ik_two_bone_job::{IKTwoBoneJob,IKTwoBoneResult};
+pub use ik_aim_job::{IKAimJob};
+pub use fabrik_job::{FabrikJob};
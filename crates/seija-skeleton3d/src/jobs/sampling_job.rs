@@ -34,19 +34,22 @@ impl SamplingJob {
     }
 
     pub fn init(&mut self, anim: &Animation) {
+        //切换动画时会重新init,先清掉上一个动画的缓存
+        self.last_ratio = -1f32;
+        self.cache_tracks.clear();
         for _ in 0..anim.num_tracks {
             let track_keys = TrackKeys::default();
             self.cache_tracks.push(track_keys);
         }
 
         for (idx, tk) in anim.translations_.iter().enumerate() {
-            self.cache_tracks[tk.track].translations.push(idx);
+            self.cache_tracks[tk.track as usize].translations.push(idx);
         }
         for (idx, tk) in anim.scales_.iter().enumerate() {
-            self.cache_tracks[tk.track].scales.push(idx);
+            self.cache_tracks[tk.track as usize].scales.push(idx);
         }
         for (idx, tk) in anim.rotations_.iter().enumerate() {
-            self.cache_tracks[tk.track].rotations.push(idx);
+            self.cache_tracks[tk.track as usize].rotations.push(idx);
         }
         self.track_cur_indexs = vec![TrackKeyIndex::default(); anim.num_tracks];
     }
@@ -99,8 +102,8 @@ impl SamplingJob {
                 let next_key = &animation.translations_[next_key_index];
                 let interp_t_ratio = (ratio - cur_key.ratio) * (1f32 / (next_key.ratio - cur_key.ratio));
               
-                let lerp_postion = cur_key.value.lerp(next_key.value, interp_t_ratio);
-                output[cur_key.track].position = lerp_postion;
+                let lerp_postion = cur_key.value().lerp(next_key.value(), interp_t_ratio);
+                output[cur_key.track as usize].position = lerp_postion;
             }
 
             let cur_s_index = key_index.scale_index;
@@ -108,8 +111,8 @@ impl SamplingJob {
                 let cur_key = &animation.scales_[cur_cache_track.scales[cur_s_index]];
                 let next_key = &animation.scales_[cur_cache_track.scales[cur_s_index + 1]];
                 let interp_s_ratio = (ratio - cur_key.ratio) * (1f32 / (next_key.ratio - cur_key.ratio));
-                let lerp_scale = cur_key.value.lerp(next_key.value, interp_s_ratio);
-                output[cur_key.track].scale = lerp_scale;
+                let lerp_scale = cur_key.value().lerp(next_key.value(), interp_s_ratio);
+                output[cur_key.track as usize].scale = lerp_scale;
             }
 
             let cur_r_index = key_index.rotation_index;
@@ -117,8 +120,8 @@ impl SamplingJob {
                 let cur_key = &animation.rotations_[cur_cache_track.rotations[cur_r_index]];
                 let next_key = &animation.rotations_[cur_cache_track.rotations[cur_r_index + 1]];
                 let interp_r_ratio = (ratio - cur_key.ratio) * (1f32 / (next_key.ratio - cur_key.ratio));
-                let lerp_rotation = cur_key.value.lerp(next_key.value, interp_r_ratio);
-                output[cur_key.track].rotation = lerp_rotation;
+                let lerp_rotation = cur_key.value().lerp(next_key.value(), interp_r_ratio);
+                output[cur_key.track as usize].rotation = lerp_rotation;
             }
         }       
         self.last_ratio = ratio;
@@ -146,7 +149,7 @@ impl SamplingJob {
                 last_index = index;
             }
             last_index = 0;
-            for (index,at_index) in track.rotations.iter().enumerate() {
+            for (index,at_index) in track.scales.iter().enumerate() {
                 let curkey = &anim.scales_[*at_index];
                 if curkey.ratio >= ratio {
                     self.track_cur_indexs[track_index].scale_index = last_index;
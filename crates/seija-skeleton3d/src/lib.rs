@@ -5,16 +5,25 @@ mod animation;
 mod render;
 mod animation_set;
 mod skin;
+pub mod state_machine;
+pub mod ffi;
+pub mod compression;
+pub mod binary;
 
-pub use animation::{Animation,QuaternionKey,Float3Key};
-use render::animation_system::update_skeleton_system;
+pub use animation::{Animation,QuaternionKey,Float3Key,EventKey};
+use render::{animation_system::update_skeleton_system, animator::update_animator_system, ik_control::update_ik_system};
 use seija_app::{IModule, App};
 use seija_asset::AddAsset;
-use seija_core::{CoreStage};
+use bevy_ecs::schedule::IntoSystemDescriptor;
+use seija_core::{CoreStage, AddCore};
+use seija_transform::update_transform_system;
 pub use skeleton::{Skeleton};
 pub use animation_set::{AnimationSet};
 pub use render::{render_plugin::{create_skeleton_plugin},
-                 runtime_skeleton::RuntimeSkeleton,animation_control::{AnimationControl}};
+                 runtime_skeleton::RuntimeSkeleton,animation_control::{AnimationControl,AnimationLayer,AnimationError,ClipEvent},
+                 animation_system::AnimationEvent,
+                 animator::Animator,ik_control::{IKControl,IKChain,IKSolver}};
+pub use state_machine::AnimStateMachine;
 pub use skin::{Skin};
 
 
@@ -27,7 +36,15 @@ impl IModule for Skeleton3dModule {
         app.add_asset::<AnimationSet>();
         app.add_asset::<RuntimeSkeleton>();
         app.add_asset::<Skin>();
+        app.add_asset::<AnimStateMachine>();
+        app.add_asset_loader::<AnimStateMachine,state_machine::AnimStateMachineLoader>();
+        app.add_asset_loader::<Skeleton,binary::SkeletonLoader>();
+        app.add_asset_loader::<AnimationSet,binary::AnimationSetLoader>();
+        app.add_event::<AnimationEvent>();
 
+        app.add_system(CoreStage::PreUpdate, update_animator_system.before(update_skeleton_system));
         app.add_system(CoreStage::PreUpdate, update_skeleton_system);
+        //IK的目标是世界空间,要用这一帧更新后的Transform
+        app.add_system(CoreStage::PostUpdate, update_ik_system.after(update_transform_system));
     }
 }
\ No newline at end of file
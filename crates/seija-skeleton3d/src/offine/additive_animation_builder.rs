@@ -0,0 +1,22 @@
+use glam::{Vec3, Quat};
+use super::raw_animation::{RawAnimation, RawJointTrack, RawTranslationKey, RawRotationKey, RawScaleKey};
+
+//把普通动画转成相对第一帧的差值动画,用于AnimationControl的叠加层
+pub struct AdditiveAnimationBuilder;
+
+impl AdditiveAnimationBuilder {
+    pub fn build(raw_animation:&RawAnimation) -> RawAnimation {
+        let mut additive = RawAnimation { name:raw_animation.name.clone(),duration:raw_animation.duration,tracks:vec![],events:raw_animation.events.clone() };
+        for track in raw_animation.tracks.iter() {
+            let ref_pos = track.translations.first().map(|k| k.value).unwrap_or(Vec3::ZERO);
+            let ref_rot_inv = track.rotations.first().map(|k| k.value.inverse()).unwrap_or(Quat::IDENTITY);
+            let ref_scale = track.scales.first().map(|k| k.value).unwrap_or(Vec3::ONE);
+            additive.tracks.push(RawJointTrack {
+                translations:track.translations.iter().map(|k| RawTranslationKey { time:k.time,value:k.value - ref_pos }).collect(),
+                rotations:track.rotations.iter().map(|k| RawRotationKey { time:k.time,value:(ref_rot_inv * k.value).normalize() }).collect(),
+                scales:track.scales.iter().map(|k| RawScaleKey { time:k.time,value:k.value / ref_scale }).collect()
+            });
+        }
+        additive
+    }
+}
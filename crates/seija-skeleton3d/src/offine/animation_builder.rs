@@ -2,7 +2,7 @@ use std::{cmp::Ordering};
 
 use glam::{Vec3, Quat, Vec4};
 
-use crate::animation::{Animation, Float3Key, QuaternionKey};
+use crate::animation::{Animation, Float3Key, QuaternionKey, EventKey};
 
 use super::raw_animation::{RawAnimation, RawTranslationKey, RawRotationKey, RawScaleKey};
 
@@ -34,9 +34,16 @@ impl AnimationBuilder {
         }
 
         let inv_duration:f32 = 1f32 / raw_animation.duration;
-        Self::copy_to_animation_v3(&mut sorting_translations,&mut animation.translations_,inv_duration);
-        Self::copy_to_animation_v3(&mut sorting_scales,&mut animation.scales_,inv_duration);
-        Self::copy_to_animation_quat(&mut sorting_rotations,&mut animation.rotations_,inv_duration);
+        //关键帧用除法计算ratio,保证最后一帧正好是1
+        Self::copy_to_animation_v3(&mut sorting_translations,&mut animation.translations_,raw_animation.duration);
+        Self::copy_to_animation_v3(&mut sorting_scales,&mut animation.scales_,raw_animation.duration);
+        Self::copy_to_animation_quat(&mut sorting_rotations,&mut animation.rotations_,raw_animation.duration);
+
+        for event in raw_animation.events.iter() {
+            let ratio = if raw_animation.duration > 0f32 { (event.time * inv_duration).clamp(0f32, 1f32) } else { 0f32 };
+            animation.events.push(EventKey { ratio, name:event.name.clone() });
+        }
+        animation.events.sort_by(|a,b| a.ratio.partial_cmp(&b.ratio).unwrap_or(Ordering::Equal));
 
         animation
     }
@@ -80,16 +87,11 @@ impl AnimationBuilder {
         }
     }
 
-    fn copy_to_animation_v3<ST>(src:&mut Vec<ST>,dst:&mut Vec<Float3Key>,inv_duration:f32) where ST:ISortKey {
+    fn copy_to_animation_v3<ST>(src:&mut Vec<ST>,dst:&mut Vec<Float3Key>,duration:f32) where ST:ISortKey {
         src.sort_by(Self::sort_fn);
         for item in src.iter() {
-            let mut key = Float3Key::default();
-            key.ratio = ST::key_time(item.key()) * inv_duration;
-            key.track = item.track() as usize;
-            if let Ok(v3) = item.value() {
-                key.value = v3.clone();
-            }
-            dst.push(key);
+            let value = item.value().copied().unwrap_or_default();
+            dst.push(Float3Key::new(ST::key_time(item.key()) / duration, item.track() as usize, value));
         }
     }
 
@@ -102,7 +104,7 @@ impl AnimationBuilder {
         }
     }
 
-    fn copy_to_animation_quat(src:&mut Vec<SortingRotationKey>,dst:&mut Vec<QuaternionKey>,inv_duration:f32) {
+    fn copy_to_animation_quat(src:&mut Vec<SortingRotationKey>,dst:&mut Vec<QuaternionKey>,duration:f32) {
         let ident:Quat = Quat::IDENTITY;
         let mut track:u16 = u16::MAX;
         for idx in 0..src.len() {
@@ -125,11 +127,7 @@ impl AnimationBuilder {
         }
         src.sort_by(Self::sort_fn);
         for item in src.iter() {
-            let mut dst_key = QuaternionKey::default();
-            dst_key.ratio = item.key.time * inv_duration;
-            dst_key.track = item.track as usize;
-            dst_key.value = item.key.value;
-            dst.push(dst_key);
+            dst.push(QuaternionKey::new(item.key.time / duration, item.track as usize, item.key.value));
         }
     }
 
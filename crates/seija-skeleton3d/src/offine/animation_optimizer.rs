@@ -0,0 +1,89 @@
+use super::raw_animation::{RawAnimation, RawJointTrack};
+
+//删除能由前后关键帧线性插值得到的关键帧,插值误差不超过容差
+pub struct AnimationOptimizer {
+    pub translation_tolerance:f32,
+    //弧度
+    pub rotation_tolerance:f32,
+    pub scale_tolerance:f32
+}
+
+impl Default for AnimationOptimizer {
+    fn default() -> Self {
+        AnimationOptimizer { translation_tolerance:0.001f32, rotation_tolerance:0.001f32, scale_tolerance:0.001f32 }
+    }
+}
+
+impl AnimationOptimizer {
+    pub fn optimize(&self,raw_animation:&RawAnimation) -> RawAnimation {
+        let tracks = raw_animation.tracks.iter().map(|track| RawJointTrack {
+            translations:decimate(&track.translations, self.translation_tolerance, |k| k.time,
+                                  |a,b,t| a.value.lerp(b.value, t), |k,v| (k.value - *v).length()),
+            rotations:decimate(&track.rotations, self.rotation_tolerance, |k| k.time, |a,b,t| {
+                //和采样时一样取最短路径插值
+                let end = if a.value.dot(b.value) < 0f32 { -b.value } else { b.value };
+                a.value.lerp(end, t).normalize()
+            }, |k,v| k.value.normalize().angle_between(*v)),
+            scales:decimate(&track.scales, self.scale_tolerance, |k| k.time,
+                            |a,b,t| a.value.lerp(b.value, t), |k,v| (k.value - *v).length())
+        }).collect();
+        RawAnimation {
+            name:raw_animation.name.clone(),
+            duration:raw_animation.duration,
+            tracks,
+            events:raw_animation.events.clone()
+        }
+    }
+}
+
+//每次保留区间内误差最大的关键帧,直到所有删掉的关键帧误差都在容差内
+fn decimate<K:Clone,V>(keys:&[K],tolerance:f32,time:impl Fn(&K) -> f32,lerp:impl Fn(&K,&K,f32) -> V,error:impl Fn(&K,&V) -> f32) -> Vec<K> {
+    if keys.len() <= 2 { return keys.to_vec(); }
+    let mut keep = vec![false;keys.len()];
+    keep[0] = true;
+    keep[keys.len() - 1] = true;
+    let mut ranges = vec![(0,keys.len() - 1)];
+    while let Some((first,last)) = ranges.pop() {
+        if last <= first + 1 { continue; }
+        let (first_time,last_time) = (time(&keys[first]),time(&keys[last]));
+        let mut max_error = (0f32,first);
+        for index in first + 1..last {
+            let t = if last_time > first_time { (time(&keys[index]) - first_time) / (last_time - first_time) } else { 0f32 };
+            let err = error(&keys[index], &lerp(&keys[first], &keys[last], t));
+            if err > max_error.0 {
+                max_error = (err,index);
+            }
+        }
+        if max_error.0 > tolerance {
+            keep[max_error.1] = true;
+            ranges.push((first,max_error.1));
+            ranges.push((max_error.1,last));
+        }
+    }
+    keys.iter().zip(keep).filter(|(_,k)| *k).map(|(key,_)| key.clone()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{Vec3, Quat};
+    use crate::offine::raw_animation::{RawAnimation, RawJointTrack, RawTranslationKey, RawRotationKey};
+    use super::AnimationOptimizer;
+
+    #[test]
+    fn reduce_keys() {
+        let mut track = RawJointTrack::default();
+        for i in 0..11 {
+            let time = i as f32 * 0.1f32;
+            //前半段直线,后半段停在原地
+            let x = if i <= 5 { time } else { 0.5f32 };
+            track.translations.push(RawTranslationKey::new(time, Vec3::new(x, 0f32, 0f32)));
+            track.rotations.push(RawRotationKey { time, value:Quat::from_rotation_y(time) });
+        }
+        let raw = RawAnimation { name:"test".into(), duration:1f32, tracks:vec![track], events:vec![] };
+        let optimized = AnimationOptimizer::default().optimize(&raw);
+        let times:Vec<f32> = optimized.tracks[0].translations.iter().map(|k| k.time).collect();
+        assert_eq!(times, vec![0f32,0.5f32,1f32]);
+        //匀速旋转的插值误差很小,但不是完全线性的
+        assert!(optimized.tracks[0].rotations.len() < 11);
+    }
+}
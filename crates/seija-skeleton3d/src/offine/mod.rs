@@ -1,4 +1,6 @@
 pub mod raw_animation;
 pub mod raw_skeleton;
 pub mod skeleton_builder;
-pub mod animation_builder;
\ No newline at end of file
+pub mod animation_builder;
+pub mod additive_animation_builder;
+pub mod animation_optimizer;
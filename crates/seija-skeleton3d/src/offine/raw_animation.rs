@@ -1,4 +1,6 @@
+use std::collections::HashMap;
 use glam::{Vec3, Quat};
+use serde::Deserialize;
 
 #[derive(Default,Clone,Debug)]
 #[repr(C)]
@@ -60,15 +62,45 @@ pub struct RawJointTrack {
 }
 
 
+//time秒时触发的命名事件,比如脚步声
+#[derive(Default,Clone,Debug,Deserialize)]
+pub struct RawEventKey {
+    pub time:f32,
+    pub name:String
+}
+
+impl RawEventKey {
+    pub fn new(time:f32,name:&str) -> RawEventKey {
+        RawEventKey { time, name:name.into() }
+    }
+}
+
 #[derive(Default)]
 pub struct RawAnimation {
     pub name:String,
     pub duration:f32,
     pub tracks:Vec<RawJointTrack>,
+    pub events:Vec<RawEventKey>
 }
 
 impl RawAnimation {
     pub fn num_tracks(&self) -> usize {
         self.tracks.len()
     }
-}
\ No newline at end of file
+}
+
+#[derive(Deserialize)]
+struct EventExtras {
+    #[serde(default)]
+    events:Vec<RawEventKey>
+}
+
+//glTF动画的extras: {"events":[{"time":0.3,"name":"footstep"}]}
+pub fn parse_event_extras(json:&str) -> Result<Vec<RawEventKey>,serde_json::Error> {
+    serde_json::from_str::<EventExtras>(json).map(|v| v.events)
+}
+
+//事件的外部配置文件,按动画名分组: {"Run":[{"time":0.3,"name":"footstep"}]}
+pub fn parse_event_file(json:&str) -> Result<HashMap<String,Vec<RawEventKey>>,serde_json::Error> {
+    serde_json::from_str(json)
+}
@@ -1,8 +1,9 @@
 use bevy_ecs::prelude::Component;
+use glam::{Vec3, Quat};
 use seija_asset::{Handle, Assets};
+use seija_transform::TransformMatrix;
 
-
-use crate::{Skeleton, AnimationSet, jobs::{SamplingJob, LocalToModelJob}};
+use crate::{Skeleton, AnimationSet, EventKey, jobs::{SamplingJob, LocalToModelJob, BlendingJob, BlendingLayer}};
 
 use super::runtime_skeleton::RuntimeSkeleton;
 
@@ -10,21 +11,223 @@ use super::runtime_skeleton::RuntimeSkeleton;
 pub enum AnimationError {
     NotFoundAnimSet,
     NotFoundAnim,
-    NotFoundSkeleton
+    NotFoundSkeleton,
+    NotFoundLayer,
+    JointCountMismatch
+}
+
+//这一帧播放经过的动画事件
+#[derive(Debug,Clone)]
+pub struct ClipEvent {
+    pub layer:usize,
+    pub anim_index:usize,
+    pub name:String,
+    //触发时动画的权重
+    pub weight:f32
+}
+
+//层中正在播放的一个动画,淡入淡出时同一层会同时存在多个
+pub(crate) struct PlayingClip {
+    //一起开始播放的动画属于同一组,同组的动画按相同的归一化时间同步播放
+    pub(crate) group:u32,
+    pub(crate) anim_index:usize,
+    pub(crate) ratio:f32,
+    pub(crate) is_loop:bool,
+    pub(crate) weight:f32,
+    //组内的混合权重,最终权重是weight * blend_weight
+    pub(crate) blend_weight:f32,
+    //每秒的权重变化,正数淡入,负数淡出
+    pub(crate) fade_speed:f32,
+    sample_job:Option<SamplingJob>,
+    pose:Vec<TransformMatrix>,
+    //上一次采样的根关节位移和旋转
+    last_root:Option<(Vec3,Quat)>,
+    //上一帧推进时是否循环回到了开头
+    wrapped:bool,
+    //不循环的动画这一帧已经采样到了最后一帧
+    ended:bool,
+    root_delta:(Vec3,Quat)
+}
+
+impl PlayingClip {
+    fn new(group:u32,anim_index:usize,blend_weight:f32,is_loop:bool,fade_time:f32) -> Self {
+        let (weight,fade_speed) = if fade_time > 0f32 { (0f32,1f32 / fade_time) } else { (1f32,0f32) };
+        PlayingClip { group, anim_index, ratio:0f32, is_loop, weight, blend_weight, fade_speed, sample_job:None, pose:vec![],
+                      last_root:None, wrapped:false, ended:false, root_delta:(Vec3::ZERO,Quat::IDENTITY) }
+    }
+
+    //根关节从上一次采样到这一次的变化,在上一次根关节的坐标系中计算,循环时分成到结尾和从开头两段
+    //最后按参考姿势的朝向转到实体的坐标系,这样参考姿势带旋转的骨骼也能直接累加到Transform上
+    fn update_root_delta(&mut self,anim:&crate::Animation,root:usize,rest_rotation:Quat) {
+        let cur = (self.pose[root].position,self.pose[root].rotation);
+        let delta = match self.last_root {
+            Some(last) if self.wrapped => {
+                let (start,end) = anim.track_range(root);
+                concat_delta(local_delta(last, end), local_delta(start, cur))
+            },
+            Some(last) => local_delta(last, cur),
+            None => (Vec3::ZERO,Quat::IDENTITY)
+        };
+        self.root_delta = (rest_rotation * delta.0,(rest_rotation * delta.1 * rest_rotation.inverse()).normalize());
+        self.last_root = Some(cur);
+    }
+
+    //播放完并且已经完全淡入,继续更新也不会再改变姿势
+    fn is_finished(&self) -> bool {
+        self.ended && self.fade_speed >= 0f32 && (self.weight >= 1f32 || self.fade_speed == 0f32)
+    }
+
+    fn fade_out(&mut self,fade_time:f32) {
+        self.fade_speed = if fade_time > 0f32 { -self.weight / fade_time } else { -1f32 };
+        if fade_time <= 0f32 {
+            self.weight = 0f32;
+        }
+    }
+}
+
+pub struct AnimationLayer {
+    pub weight:f32,
+    //叠加层的动画是相对参考姿势的差值,叠加到下面各层的结果上
+    pub additive:bool,
+    //每个关节的权重,为空时影响所有关节
+    pub mask:Option<Vec<f32>>,
+    pub(crate) clips:Vec<PlayingClip>,
+    pose:Vec<TransformMatrix>
+}
+
+impl AnimationLayer {
+    pub fn new(additive:bool,mask:Option<Vec<f32>>) -> Self {
+        AnimationLayer { weight:1f32, additive, mask, clips:vec![], pose:vec![] }
+    }
+
+    //最后开始播放且没有在淡出的动画
+    pub fn current_anim(&self) -> Option<usize> {
+        self.clips.iter().rev().find(|c| c.fade_speed >= 0f32).map(|c| c.anim_index)
+    }
+
+    //组的归一化播放时间,组已经淡出移除时返回None
+    pub fn group_ratio(&self,group:u32) -> Option<f32> {
+        self.clips.iter().find(|c| c.group == group).map(|c| c.ratio)
+    }
+
+    fn update(&mut self,anim_set:&AnimationSet,dt:f32,rest_pose:&[TransformMatrix],root:usize,index:usize,events:&mut Vec<ClipEvent>) -> Result<(),AnimationError> {
+        let num_joints = rest_pose.len();
+        //动画集里没有的动画直接移除,不影响层中其他动画和之后的播放
+        self.clips.retain(|c| {
+            let has_anim = anim_set.get_index(c.anim_index).is_some();
+            if !has_anim {
+                log::warn!("layer {} not found animation index:{}",index,c.anim_index);
+            }
+            has_anim
+        });
+        //同组动画的时长按混合权重平均
+        let mut group_durations:Vec<(u32,f32,f32)> = vec![];
+        for clip in self.clips.iter() {
+            let anim = anim_set.get_index(clip.anim_index).ok_or(AnimationError::NotFoundAnim)?;
+            match group_durations.iter_mut().find(|g| g.0 == clip.group) {
+                Some(g) => { g.1 += anim.duration * clip.blend_weight; g.2 += clip.blend_weight; },
+                None => group_durations.push((clip.group,anim.duration * clip.blend_weight,clip.blend_weight))
+            }
+        }
+        for clip in self.clips.iter_mut() {
+            let anim = anim_set.get_index(clip.anim_index).ok_or(AnimationError::NotFoundAnim)?;
+            let sample_job = clip.sample_job.get_or_insert_with(|| {
+                let mut job = SamplingJob::default();
+                job.init(anim);
+                job
+            });
+            if clip.pose.len() != num_joints {
+                clip.pose = vec![TransformMatrix::default();num_joints];
+            }
+            sample_job.run(anim, &mut clip.pose, clip.ratio);
+            clip.ended = !clip.is_loop && clip.ratio >= 1f32;
+            if root < num_joints {
+                clip.update_root_delta(anim, root, rest_pose[root].rotation);
+            }
+
+            clip.weight = (clip.weight + clip.fade_speed * dt).clamp(0f32, 1f32);
+            let duration = group_durations.iter().find(|g| g.0 == clip.group)
+                                          .filter(|g| g.2 > 0f32).map(|g| g.1 / g.2).unwrap_or(anim.duration);
+            let prev_ratio = clip.ratio;
+            clip.ratio += dt / duration;
+            //淡出中的动画不再触发事件
+            if clip.fade_speed >= 0f32 {
+                for event in crossed_events(&anim.events, prev_ratio, clip.ratio, clip.is_loop) {
+                    events.push(ClipEvent { layer:index, anim_index:clip.anim_index, name:event.name.clone(), weight:clip.weight * clip.blend_weight });
+                }
+            }
+            clip.wrapped = false;
+            if clip.ratio > 1f32 {
+                //不循环的动画停在最后一帧,下一帧采样到结尾后结束
+                clip.wrapped = clip.is_loop;
+                clip.ratio = if clip.is_loop { clip.ratio.fract() } else { 1f32 };
+            }
+        }
+        self.clips.retain(|c| c.fade_speed >= 0f32 || c.weight > 0f32);
+        Ok(())
+    }
+
+    fn blend_clips(&mut self,rest_pose:&[TransformMatrix],identity_pose:&[TransformMatrix]) -> bool {
+        if self.pose.len() != rest_pose.len() {
+            self.pose = rest_pose.to_vec();
+        }
+        //叠加层淡出时要回到不产生影响的单位姿势
+        let mut job = BlendingJob::new(if self.additive { identity_pose } else { rest_pose });
+        for clip in self.clips.iter() {
+            job.layers.push(BlendingLayer::new(&clip.pose, clip.weight * clip.blend_weight));
+        }
+        job.run(&mut self.pose)
+    }
+
+    //各动画根关节变化按权重的平均值
+    fn root_delta(&self) -> Option<(Vec3,Quat)> {
+        let mut position = Vec3::ZERO;
+        let mut rotation = Quat::from_xyzw(0f32, 0f32, 0f32, 0f32);
+        let mut total = 0f32;
+        for clip in self.clips.iter() {
+            let weight = clip.weight * clip.blend_weight;
+            if weight <= 0f32 { continue; }
+            position += clip.root_delta.0 * weight;
+            let sign = if rotation.dot(clip.root_delta.1) < 0f32 { -weight } else { weight };
+            rotation = rotation + clip.root_delta.1 * sign;
+            total += weight;
+        }
+        if total <= 0f32 { return None; }
+        Some((position / total,rotation.normalize()))
+    }
+
+    fn joint_weights(&self,num_joints:usize,out:&mut Vec<f32>) {
+        out.clear();
+        out.extend((0..num_joints).map(|i| self.weight * self.mask.as_ref().and_then(|m| m.get(i).copied()).unwrap_or(1f32)));
+    }
 }
+
 #[derive(Component)]
 pub struct AnimationControl {
     skeleton:Handle<Skeleton>,
     animation_set:Handle<AnimationSet>,
     runtime_skeleton:Handle<RuntimeSkeleton>,
 
-    pub(crate) anim_index:usize,
-    pub(crate) last_anim_index:i32,
-    pub(crate) ratio:f32,
     pub(crate) play:bool,
+    //新播放的动画是否循环
     pub(crate) is_loop:bool,
-
-    pub(crate) sample_job:SamplingJob
+    pub speed:f32,
+    //第0层是基础层,后面的层按顺序覆盖或叠加在前面的结果上
+    pub(crate) layers:Vec<AnimationLayer>,
+    next_group:u32,
+    blend_cache:Vec<TransformMatrix>,
+    identity_pose:Vec<TransformMatrix>,
+    //每层混合时的关节权重,复用避免每帧分配
+    joint_weights:Vec<f32>,
+    inv_weights:Vec<f32>,
+    //开启后根关节的位移和旋转从姿势中去掉,由系统应用到实体的Transform上
+    pub root_motion:bool,
+    //提取根运动的关节名,为空时用第0个关节
+    root_joint:Option<String>,
+    //root_joint在骨骼中的索引,第一次process时查找
+    root_index:Option<usize>,
+    root_delta:(Vec3,Quat),
+    events:Vec<ClipEvent>
 }
 
 impl AnimationControl {
@@ -34,12 +237,20 @@ impl AnimationControl {
             skeleton,
             animation_set,
             runtime_skeleton,
-            anim_index:0,
-            last_anim_index:-1 as i32,
-            ratio:0f32,
             play:false,
             is_loop:true,
-            sample_job:SamplingJob::default()
+            speed:1f32,
+            layers:vec![AnimationLayer::new(false, None)],
+            next_group:0,
+            blend_cache:vec![TransformMatrix::default();count],
+            identity_pose:vec![TransformMatrix::default();count],
+            joint_weights:vec![],
+            inv_weights:vec![],
+            root_motion:false,
+            root_joint:None,
+            root_index:None,
+            root_delta:(Vec3::ZERO,Quat::IDENTITY),
+            events:vec![]
         }
     }
 
@@ -47,43 +258,394 @@ impl AnimationControl {
         &self.runtime_skeleton
     }
 
+    pub fn skeleton(&self) -> &Handle<Skeleton> {
+        &self.skeleton
+    }
+
+    pub fn animation_set(&self) -> &Handle<AnimationSet> {
+        &self.animation_set
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.play
+    }
+
+    pub fn set_loop(&mut self,is_loop:bool) {
+        self.is_loop = is_loop;
+    }
+
+    pub fn set_root_motion(&mut self,root_motion:bool) {
+        self.root_motion = root_motion;
+    }
+
+    //设置提取根运动的关节,比如骨骼的第0个关节不是髋部时
+    pub fn set_root_joint(&mut self,name:Option<&str>) {
+        self.root_joint = name.map(|n| n.to_string());
+        self.root_index = None;
+        for clip in self.layers.iter_mut().flat_map(|l| l.clips.iter_mut()) {
+            clip.last_root = None;
+        }
+    }
+
+    //这一帧根关节在实体坐标系中的位移和旋转变化,只在root_motion开启时有值
+    pub fn root_delta(&self) -> (Vec3,Quat) {
+        self.root_delta
+    }
+
+    //这一帧经过的动画事件
+    pub fn events(&self) -> &[ClipEvent] {
+        &self.events
+    }
+
+    //在基础层上直接切换动画
     pub fn play_index(&mut self,idx:usize) {
+        self.cross_fade(idx, 0f32);
+    }
+
+    //在基础层上用fade_time秒从当前动画过渡到新动画
+    pub fn cross_fade(&mut self,idx:usize,fade_time:f32) {
+        let _ = self.play_layer(0, idx, fade_time);
+    }
+
+    pub fn play_layer(&mut self,layer:usize,idx:usize,fade_time:f32) -> Result<(),AnimationError> {
+        let is_loop = self.is_loop;
+        self.play_blend(layer, &[(idx,1f32)], fade_time, is_loop).map(|_| ())
+    }
+
+    //同时播放一组按权重混合的动画(混合树),当前层的其他动画淡出,返回组id
+    pub fn play_blend(&mut self,layer:usize,clips:&[(usize,f32)],fade_time:f32,is_loop:bool) -> Result<u32,AnimationError> {
+        let group = self.next_group;
+        let layer = self.layers.get_mut(layer).ok_or(AnimationError::NotFoundLayer)?;
+        for clip in layer.clips.iter_mut() {
+            clip.fade_out(fade_time);
+        }
+        layer.clips.retain(|c| c.weight > 0f32);
+        for (idx,blend_weight) in clips.iter() {
+            layer.clips.push(PlayingClip::new(group, *idx, *blend_weight, is_loop, fade_time));
+        }
+        self.next_group = self.next_group.wrapping_add(1);
         self.play = true;
-        self.ratio = 0f32;
-        self.anim_index = idx;
+        Ok(group)
+    }
+
+    //按play_blend时的顺序更新组内动画的混合权重
+    pub fn set_blend_weights(&mut self,layer:usize,group:u32,weights:&[f32]) -> Result<(),AnimationError> {
+        let layer = self.layers.get_mut(layer).ok_or(AnimationError::NotFoundLayer)?;
+        for (clip,weight) in layer.clips.iter_mut().filter(|c| c.group == group).zip(weights.iter()) {
+            clip.blend_weight = *weight;
+        }
+        Ok(())
+    }
+
+    pub fn stop_layer(&mut self,layer:usize,fade_time:f32) -> Result<(),AnimationError> {
+        let layer = self.layers.get_mut(layer).ok_or(AnimationError::NotFoundLayer)?;
+        for clip in layer.clips.iter_mut() {
+            clip.fade_out(fade_time);
+        }
+        layer.clips.retain(|c| c.weight > 0f32);
+        Ok(())
+    }
+
+    //返回新层的索引,mask可以用jobs::joint_mask生成
+    pub fn add_layer(&mut self,additive:bool,mask:Option<Vec<f32>>) -> usize {
+        self.layers.push(AnimationLayer::new(additive, mask));
+        self.layers.len() - 1
+    }
+
+    pub fn layer(&self,layer:usize) -> Option<&AnimationLayer> {
+        self.layers.get(layer)
+    }
 
+    pub fn layer_mut(&mut self,layer:usize) -> Option<&mut AnimationLayer> {
+        self.layers.get_mut(layer)
     }
 
     pub fn stop(&mut self) {
         self.play = false;
-        self.ratio = 0f32;
-        self.last_anim_index = -1;
+        for layer in self.layers.iter_mut() {
+            layer.clips.clear();
+        }
     }
 
     pub fn process(&mut self,anims:&Assets<AnimationSet>,skeletons:&Assets<Skeleton>,dt:f32,rt_skeleton:&mut RuntimeSkeleton) -> Result<(),AnimationError> {
-        if self.play == false { return Ok(()) }
+        self.events.clear();
+        self.root_delta = (Vec3::ZERO,Quat::IDENTITY);
+        if !self.play { return Ok(()) }
         let anim_set = anims.get(&self.animation_set.id).ok_or(AnimationError::NotFoundAnimSet)?;
-        let anim = anim_set.get_index(self.anim_index).ok_or(AnimationError::NotFoundAnim)?;
-        if self.last_anim_index != self.anim_index as i32 {
-            self.sample_job.init(anim);
-            self.last_anim_index = self.anim_index as i32;
-        }
         let skeleton = skeletons.get(&self.skeleton.id).ok_or(AnimationError::NotFoundSkeleton)?;
-        
-        self.sample_job.run(anim, &mut rt_skeleton.values, self.ratio);
-        let ltw = LocalToModelJob::new(skeleton);
-        ltw.run(&rt_skeleton.values, &mut rt_skeleton.mat4s);
+        let num_joints = rt_skeleton.values.len();
+        let rest_pose = &skeleton.joint_rest_poses;
+        if rest_pose.len() != num_joints { return Err(AnimationError::JointCountMismatch); }
+        let dt = dt * self.speed;
 
-        self.ratio += dt / anim.duration;
-        //println!("{:?}",rt_skeleton.mat4s);
-        if self.ratio > 1f32 {
-            if self.is_loop {
-                self.play_index(self.anim_index);
+        rt_skeleton.values.clone_from_slice(rest_pose);
+        self.blend_cache.resize(num_joints, TransformMatrix::default());
+        self.identity_pose.resize(num_joints, TransformMatrix::default());
+        let root = *self.root_index.get_or_insert_with(|| {
+            match self.root_joint.as_deref() {
+                Some(name) => skeleton.find_joint(name).unwrap_or_else(|| {
+                    log::warn!("root motion joint not found:{}",name);
+                    0
+                }),
+                None => 0
+            }
+        });
+        let mut root_delta = (Vec3::ZERO,Quat::IDENTITY);
+        for (index,layer) in self.layers.iter_mut().enumerate() {
+            layer.update(anim_set, dt, rest_pose, root, index, &mut self.events)?;
+            if layer.clips.is_empty() || layer.weight <= 0f32 || !layer.blend_clips(rest_pose, &self.identity_pose) {
+                continue;
+            }
+            layer.joint_weights(num_joints, &mut self.joint_weights);
+            let weights = &self.joint_weights;
+            if !layer.additive && root < num_joints {
+                if let Some((position,rotation)) = layer.root_delta() {
+                    let weight = weights[root].min(1f32);
+                    root_delta = (root_delta.0.lerp(position, weight),root_delta.1.slerp(rotation, weight));
+                }
+            }
+            let mut job = BlendingJob::new(rest_pose);
+            if layer.additive {
+                job.layers.push(BlendingLayer::new(&rt_skeleton.values, 1f32));
+                job.additive_layers.push(BlendingLayer::new(&layer.pose, 1f32).with_joint_weights(weights));
+                job.run(&mut self.blend_cache);
             } else {
-                self.stop();
+                //覆盖层按权重和遮罩与下面的结果插值
+                self.inv_weights.clear();
+                self.inv_weights.extend(weights.iter().map(|w| 1f32 - w.min(1f32)));
+                job.layers.push(BlendingLayer::new(&rt_skeleton.values, 1f32).with_joint_weights(&self.inv_weights));
+                job.layers.push(BlendingLayer::new(&layer.pose, 1f32).with_joint_weights(weights));
+                job.run(&mut self.blend_cache);
             }
-            
+            std::mem::swap(&mut rt_skeleton.values, &mut self.blend_cache);
+        }
+
+        if self.root_motion && root < num_joints {
+            rt_skeleton.values[root].position = rest_pose[root].position;
+            rt_skeleton.values[root].rotation = rest_pose[root].rotation;
+            self.root_delta = root_delta;
+        }
+
+        let ltw = LocalToModelJob::new(skeleton);
+        ltw.run(&rt_skeleton.values, &mut rt_skeleton.mat4s);
+        //所有层的动画都停在最后一帧时和以前一样停止播放,姿势保持不变,动画仍留在层中以便之后淡出
+        if self.layers.iter().all(|l| l.clips.iter().all(|c| c.is_finished())) {
+            self.play = false;
         }
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+//从from到to的变化,位移在from的坐标系中
+fn local_delta(from:(Vec3,Quat),to:(Vec3,Quat)) -> (Vec3,Quat) {
+    let inv_rotation = from.1.inverse();
+    (inv_rotation * (to.0 - from.0),inv_rotation * to.1)
+}
+
+//先做a的变化再做b的变化
+fn concat_delta(a:(Vec3,Quat),b:(Vec3,Quat)) -> (Vec3,Quat) {
+    (a.0 + a.1 * b.0,a.1 * b.1)
+}
+
+//ratio从prev走到cur时按播放顺序经过的事件,循环动画的cur大于1表示回到了开头
+fn crossed_events(events:&[EventKey],prev:f32,cur:f32,is_loop:bool) -> impl Iterator<Item = &EventKey> {
+    let first = events.iter().filter(move |e| {
+        if is_loop {
+            e.ratio >= prev && e.ratio < cur
+        } else {
+            //最后一帧上的事件在到达结尾时触发一次
+            e.ratio >= prev && (e.ratio < cur.min(1f32) || (e.ratio >= 1f32 && cur >= 1f32 && prev < 1f32))
+        }
+    });
+    let wrapped = events.iter().filter(move |e| is_loop && e.ratio + 1f32 >= prev && e.ratio + 1f32 < cur);
+    first.chain(wrapped)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use glam::{Vec3, Quat};
+    use seija_asset::{Assets, RefEvent};
+    use seija_core::smol::channel::{unbounded, Receiver};
+    use seija_transform::TransformMatrix;
+    use crate::{EventKey, Skeleton, AnimationSet, RuntimeSkeleton, offine::{animation_builder::AnimationBuilder,raw_animation::{RawAnimation,RawJointTrack,RawTranslationKey,RawRotationKey}}};
+    use super::{crossed_events, AnimationControl};
+
+    //根关节和一个子关节,每个动画两个关节的位移都是常量
+    pub(crate) fn anim(name:&str,root:Vec3,child:Vec3) -> crate::Animation {
+        let mut raw = RawAnimation { name:name.into(), duration:1f32, ..Default::default() };
+        for value in [root,child] {
+            let mut track = RawJointTrack::default();
+            track.translations.push(RawTranslationKey::new(0f32, value));
+            raw.tracks.push(track);
+        }
+        AnimationBuilder::build(&raw)
+    }
+
+    pub(crate) struct TestEnv {
+        pub(crate) anims:Assets<AnimationSet>,
+        skeletons:Assets<Skeleton>,
+        rt_skeleton:RuntimeSkeleton,
+        pub(crate) control:AnimationControl,
+        _receiver:Receiver<RefEvent>
+    }
+
+    impl TestEnv {
+        fn new() -> Self {
+            Self::with_anims(vec![anim("a", Vec3::ZERO, Vec3::new(1f32, 0f32, 0f32)),
+                                  anim("b", Vec3::ZERO, Vec3::new(3f32, 0f32, 0f32)),
+                                  anim("upper", Vec3::new(0f32, 4f32, 0f32), Vec3::new(0f32, 0f32, 4f32))])
+        }
+
+        pub(crate) fn with_anims(anims:Vec<crate::Animation>) -> Self {
+            let (sender,receiver) = unbounded();
+            let mut skeleton = Skeleton::new(2);
+            skeleton.joint_parents = vec![None,Some(0)];
+            skeleton.joint_rest_poses = vec![TransformMatrix::default();2];
+            skeleton.joint_names = vec![Some("root".into()),Some("child".into())];
+            let mut anim_set = AnimationSet::default();
+            for anim in anims {
+                anim_set.add(anim);
+            }
+            let mut anims = Assets::new(sender.clone());
+            let mut skeletons = Assets::new(sender.clone());
+            let mut rk_assets = Assets::new(sender);
+            let control = AnimationControl::new(2, skeletons.add(skeleton), anims.add(anim_set), &mut rk_assets);
+            TestEnv { anims, skeletons, rt_skeleton:RuntimeSkeleton::new(2), control, _receiver:receiver }
+        }
+
+        pub(crate) fn process(&mut self,dt:f32) -> Vec<Vec3> {
+            self.control.process(&self.anims, &self.skeletons, dt, &mut self.rt_skeleton).unwrap();
+            self.rt_skeleton.values.iter().map(|t| t.position).collect()
+        }
+    }
+
+    #[test]
+    fn root_motion() {
+        //子关节一边前进一边转90度,累加到实体上的变化应该和动画中关节的轨迹一致
+        let mut raw = RawAnimation { name:"turn".into(), duration:1f32, ..Default::default() };
+        raw.tracks.push(RawJointTrack::default());
+        let mut track = RawJointTrack::default();
+        track.translations.push(RawTranslationKey::new(0f32, Vec3::ZERO));
+        track.translations.push(RawTranslationKey::new(0.5f32, Vec3::new(0f32, 0f32, 2f32)));
+        track.translations.push(RawTranslationKey::new(1f32, Vec3::new(2f32, 0f32, 3f32)));
+        track.rotations.push(RawRotationKey::new(0f32, Quat::IDENTITY.to_array()));
+        track.rotations.push(RawRotationKey::new(1f32, Quat::from_rotation_y(90f32.to_radians()).to_array()));
+        raw.tracks.push(track);
+        let mut env = TestEnv::with_anims(vec![AnimationBuilder::build(&raw)]);
+        env.control.set_root_motion(true);
+        env.control.set_root_joint(Some("child"));
+        env.control.play_index(0);
+
+        let (mut position,mut rotation) = (Vec3::ZERO,Quat::IDENTITY);
+        let mut sampled = vec![];
+        for _ in 0..6 {
+            let pose = env.process(0.25f32);
+            //根关节的运动从姿势中去掉
+            assert!(pose[1].abs_diff_eq(Vec3::ZERO, 0.001f32));
+            let (delta_pos,delta_rot) = env.control.root_delta();
+            position += rotation * delta_pos;
+            rotation = (rotation * delta_rot).normalize();
+            sampled.push((position,rotation));
+        }
+        let sample = |ratio:f32| -> (Vec3,Quat) {
+            let anim = AnimationBuilder::build(&raw);
+            let mut job = crate::jobs::SamplingJob::default();
+            job.init(&anim);
+            let mut pose = vec![TransformMatrix::default();2];
+            job.run(&anim, &mut pose, ratio);
+            (pose[1].position,pose[1].rotation)
+        };
+        for (i,ratio) in [0f32,0.25f32,0.5f32,0.75f32,1f32].iter().enumerate() {
+            let (p,r) = sample(*ratio);
+            assert!(sampled[i].0.abs_diff_eq(p, 0.01f32), "{} {:?} {:?}", ratio, sampled[i].0, p);
+            assert!(sampled[i].1.abs_diff_eq(r, 0.001f32) || sampled[i].1.abs_diff_eq(-r, 0.001f32));
+        }
+        //循环回到开头后接着结尾继续累加
+        let (end_p,end_r) = sample(1f32);
+        let (p,r) = sample(0.25f32);
+        assert!(sampled[5].0.abs_diff_eq(end_p + end_r * p, 0.01f32));
+        assert!(sampled[5].1.abs_diff_eq(end_r * r, 0.001f32));
+    }
+
+    #[test]
+    fn cross_fade() {
+        let mut env = TestEnv::new();
+        env.control.play_index(0);
+        assert!(env.process(0f32)[1].abs_diff_eq(Vec3::new(1f32, 0f32, 0f32), 0.001f32));
+        env.control.cross_fade(1, 1f32);
+        assert!(env.process(0.5f32)[1].abs_diff_eq(Vec3::new(2f32, 0f32, 0f32), 0.001f32));
+        assert!(env.process(0.5f32)[1].abs_diff_eq(Vec3::new(3f32, 0f32, 0f32), 0.001f32));
+        //淡出完成的动画从层中移除
+        assert_eq!(env.control.layer(0).unwrap().clips.len(), 1);
+        assert_eq!(env.control.layer(0).unwrap().current_anim(), Some(1));
+    }
+
+    #[test]
+    fn layer_mask() {
+        let mut env = TestEnv::new();
+        env.control.play_index(0);
+        let layer = env.control.add_layer(false, Some(vec![0f32,1f32]));
+        env.control.play_layer(layer, 2, 0f32).unwrap();
+        //遮罩外的根关节保持基础层的结果,子关节被覆盖
+        let pose = env.process(0f32);
+        assert!(pose[0].abs_diff_eq(Vec3::ZERO, 0.001f32));
+        assert!(pose[1].abs_diff_eq(Vec3::new(0f32, 0f32, 4f32), 0.001f32));
+
+        env.control.layer_mut(layer).unwrap().weight = 0.5f32;
+        let pose = env.process(0f32);
+        assert!(pose[0].abs_diff_eq(Vec3::ZERO, 0.001f32));
+        assert!(pose[1].abs_diff_eq(Vec3::new(0.5f32, 0f32, 2f32), 0.001f32));
+
+        env.control.layer_mut(layer).unwrap().mask = None;
+        let pose = env.process(0f32);
+        assert!(pose[0].abs_diff_eq(Vec3::new(0f32, 2f32, 0f32), 0.001f32));
+    }
+
+    #[test]
+    fn stop_at_end() {
+        let mut env = TestEnv::new();
+        env.control.set_loop(false);
+        env.control.play_index(1);
+        env.process(0.6f32);
+        env.process(0.6f32);
+        assert!(env.control.is_playing());
+        //采样到最后一帧后停止,姿势保持在最后一帧
+        let pose = env.process(0.6f32);
+        assert!(!env.control.is_playing());
+        assert!(pose[1].abs_diff_eq(Vec3::new(3f32, 0f32, 0f32), 0.001f32));
+        assert_eq!(env.control.layer(0).unwrap().group_ratio(0), Some(1f32));
+        env.control.set_loop(true);
+        env.control.cross_fade(0, 1f32);
+        assert!(env.control.is_playing());
+        assert!(env.process(0.5f32)[1].abs_diff_eq(Vec3::new(2f32, 0f32, 0f32), 0.001f32));
+    }
+
+    #[test]
+    fn missing_anim() {
+        let mut env = TestEnv::new();
+        env.control.play_index(0);
+        env.process(0f32);
+        //不存在的动画被移除,原来的动画照常淡出
+        env.control.cross_fade(10, 1f32);
+        assert!(env.process(0.5f32)[1].abs_diff_eq(Vec3::new(1f32, 0f32, 0f32), 0.001f32));
+        assert_eq!(env.control.layer(0).unwrap().clips.len(), 1);
+        assert_eq!(env.control.layer(0).unwrap().current_anim(), None);
+        env.control.play_blend(0, &[(1,0.5f32),(11,0.5f32)], 0f32, true).unwrap();
+        assert!(env.process(0f32)[1].abs_diff_eq(Vec3::new(3f32, 0f32, 0f32), 0.001f32));
+        assert_eq!(env.control.layer(0).unwrap().clips.len(), 1);
+    }
+
+    #[test]
+    fn cross_events() {
+        let events = vec![EventKey { ratio:0f32, name:"start".into() },EventKey { ratio:0.5f32, name:"step".into() },EventKey { ratio:1f32, name:"end".into() }];
+        let names = |prev:f32,cur:f32,is_loop:bool| -> Vec<String> {
+            crossed_events(&events, prev, cur, is_loop).map(|e| e.name.clone()).collect()
+        };
+        assert_eq!(names(0f32, 0.2f32, true), vec!["start"]);
+        assert_eq!(names(0.2f32, 0.4f32, true), Vec::<String>::new());
+        assert_eq!(names(0.4f32, 1.1f32, true), vec!["step","end","start"]);
+        assert_eq!(names(0.1f32, 0.1f32, true), Vec::<String>::new());
+        assert_eq!(names(0.6f32, 1.2f32, false), vec!["end"]);
+        assert_eq!(names(1f32, 1.2f32, false), Vec::<String>::new());
+    }
+}
@@ -1,21 +1,41 @@
-use bevy_ecs::prelude::{Entity, Res,ResMut, Query};
+use bevy_ecs::prelude::{Entity, Res,ResMut, Query, EventWriter};
 use seija_asset::{Assets};
 use seija_core::{time::Time};
+use seija_transform::Transform;
 use crate::{RuntimeSkeleton, AnimationControl, AnimationSet, Skeleton};
 
+use super::animation_control::ClipEvent;
+
+//实体上的动画播放经过了事件关键帧
+#[derive(Debug,Clone)]
+pub struct AnimationEvent {
+    pub entity:Entity,
+    pub event:ClipEvent
+}
+
 pub fn update_skeleton_system(time:Res<Time>,
                               skeletons:Res<Assets<Skeleton>>,
                               mut rtskeletons:ResMut<Assets<RuntimeSkeleton>>,
                               anims:Res<Assets<AnimationSet>>,
-                              mut query:Query<(Entity,&mut AnimationControl)>,) {
-    for (_,mut animation_control) in query.iter_mut() {
+                              mut anim_events:EventWriter<AnimationEvent>,
+                              mut query:Query<(Entity,&mut AnimationControl,Option<&mut Transform>)>,) {
+    for (entity,mut animation_control,transform) in query.iter_mut() {
         let hid = animation_control.get_runtime_skeleton().id;
         if let Some(runtime_skeleton)  = rtskeletons.get_mut(&hid) {
             if let Err(err) = animation_control.process(&anims,&skeletons,time.delta_seconds(),runtime_skeleton) {
                 log::error!("{:?}",err);
             }
         }
-        
+        if animation_control.root_motion {
+            if let Some(mut transform) = transform {
+                let (position,rotation) = animation_control.root_delta();
+                let local = &mut transform.local;
+                local.position += local.rotation * (local.scale * position);
+                local.rotation = (local.rotation * rotation).normalize();
+            }
+        }
+        for event in animation_control.events() {
+            anim_events.send(AnimationEvent { entity, event:event.clone() });
+        }
     }
 }
-
@@ -0,0 +1,192 @@
+use bevy_ecs::prelude::{Component, Res, Query};
+use seija_asset::{Handle, Assets};
+
+use crate::{AnimationSet, AnimationControl, state_machine::{AnimStateMachine, AnimParams, StateMotion, ParamKind, BlendTree}};
+
+//用状态机驱动AnimationControl的一个层
+#[derive(Component)]
+pub struct Animator {
+    pub machine:Handle<AnimStateMachine>,
+    pub layer:usize,
+    params:AnimParams,
+    is_init:bool,
+    cur_state:Option<usize>,
+    cur_group:u32,
+    //play_state请求的状态和过渡时间,下一帧生效
+    next_state:Option<(String,f32)>
+}
+
+impl Animator {
+    pub fn new(machine:Handle<AnimStateMachine>) -> Self {
+        Animator {
+            machine,
+            layer:0,
+            params:AnimParams::default(),
+            is_init:false,
+            cur_state:None,
+            cur_group:0,
+            next_state:None
+        }
+    }
+
+    pub fn set_float(&mut self,name:&str,value:f32) {
+        self.params.insert(name.into(), value);
+    }
+
+    pub fn set_bool(&mut self,name:&str,value:bool) {
+        self.params.insert(name.into(), if value { 1f32 } else { 0f32 });
+    }
+
+    pub fn set_trigger(&mut self,name:&str) {
+        self.params.insert(name.into(), 1f32);
+    }
+
+    pub fn reset_trigger(&mut self,name:&str) {
+        self.params.insert(name.into(), 0f32);
+    }
+
+    pub fn get_float(&self,name:&str) -> f32 {
+        self.params.get(name).copied().unwrap_or(0f32)
+    }
+
+    pub fn current_state(&self) -> Option<usize> {
+        self.cur_state
+    }
+
+    //不经过转换条件直接切换状态
+    pub fn play_state(&mut self,name:&str,fade_time:f32) {
+        self.next_state = Some((name.into(),fade_time));
+    }
+
+    fn update(&mut self,machine:&AnimStateMachine,anim_set:&AnimationSet,control:&mut AnimationControl) {
+        if !self.is_init {
+            for param in machine.params.iter() {
+                self.params.entry(param.name.clone()).or_insert(param.default);
+            }
+            self.is_init = true;
+        }
+        if let Some((name,fade_time)) = self.next_state.take() {
+            match machine.state_index(&name) {
+                Some(index) => self.enter_state(machine, anim_set, control, index, fade_time),
+                None => log::warn!("animator not found state {}",name)
+            }
+        }
+        let cur_state = match self.cur_state {
+            Some(v) => v,
+            None => {
+                self.enter_state(machine, anim_set, control, machine.default_state, 0f32);
+                machine.default_state
+            }
+        };
+        let normalized_time = control.layer(self.layer).and_then(|l| l.group_ratio(self.cur_group)).unwrap_or(1f32);
+        if let Some(transition) = machine.find_transition(cur_state, &self.params, normalized_time) {
+            //触发转换的trigger被消耗掉
+            for cond in transition.conditions.iter() {
+                if machine.param_kind(&cond.param) == Some(ParamKind::Trigger) {
+                    self.params.insert(cond.param.clone(), 0f32);
+                }
+            }
+            let (to,duration) = (transition.to,transition.duration);
+            self.enter_state(machine, anim_set, control, to, duration);
+        }
+        if let Some(StateMotion::BlendTree(tree)) = self.cur_state.and_then(|s| machine.states.get(s)).map(|s| &s.motion) {
+            let weights:Vec<f32> = tree_clips(tree, anim_set, self.get_float(&tree.param)).iter().map(|c| c.1).collect();
+            let _ = control.set_blend_weights(self.layer, self.cur_group, &weights);
+        }
+    }
+
+    fn enter_state(&mut self,machine:&AnimStateMachine,anim_set:&AnimationSet,control:&mut AnimationControl,index:usize,fade_time:f32) {
+        let state = &machine.states[index];
+        let clips:Vec<(usize,f32)> = match &state.motion {
+            StateMotion::Clip(name) => anim_set.index_of(name).map(|i| vec![(i,1f32)]).unwrap_or_default(),
+            StateMotion::BlendTree(tree) => tree_clips(tree, anim_set, self.get_float(&tree.param))
+        };
+        if clips.is_empty() {
+            log::warn!("animator state {} has no animation",state.name);
+        }
+        match control.play_blend(self.layer, &clips, fade_time, state.is_loop) {
+            Ok(group) => self.cur_group = group,
+            Err(err) => log::error!("{:?}",err)
+        }
+        self.cur_state = Some(index);
+    }
+}
+
+//混合树中能在AnimationSet找到的动画和对应权重
+fn tree_clips(tree:&BlendTree,anim_set:&AnimationSet,value:f32) -> Vec<(usize,f32)> {
+    tree.clips.iter().zip(tree.weights(value)).filter_map(|(c,w)| anim_set.index_of(&c.name).map(|i| (i,w))).collect()
+}
+
+pub fn update_animator_system(machines:Res<Assets<AnimStateMachine>>,anims:Res<Assets<AnimationSet>>,
+                              mut query:Query<(&mut Animator,&mut AnimationControl)>) {
+    for (mut animator,mut control) in query.iter_mut() {
+        let machine = match machines.get(&animator.machine.id) { Some(v) => v, None => continue };
+        let anim_set = match anims.get(&control.animation_set().id) { Some(v) => v, None => continue };
+        animator.update(machine, anim_set, &mut control);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec3;
+    use crate::{AnimStateMachine, render::animation_control::tests::{TestEnv, anim}};
+    use super::Animator;
+
+    const XML:&str = r#"
+    <StateMachine default="idle">
+        <Param name="speed" type="float" default="0" />
+        <Param name="jump" type="trigger" />
+        <State name="idle" clip="Idle" />
+        <State name="move">
+            <BlendTree param="speed">
+                <Clip name="Walk" threshold="1" />
+                <Clip name="Run" threshold="4" />
+            </BlendTree>
+        </State>
+        <State name="jump" clip="Jump" loop="false" />
+        <Transition from="idle" to="move" duration="0.2"><Condition param="speed" op="greater" value="0.1" /></Transition>
+        <Transition from="*" to="jump" duration="0.1"><Condition param="jump" /></Transition>
+        <Transition from="jump" to="idle" exit_time="0.9" duration="0.2" />
+    </StateMachine>
+    "#;
+
+    fn update(animator:&mut Animator,machine:&AnimStateMachine,env:&mut TestEnv) {
+        let anim_set = env.anims.get(&env.control.animation_set().id).unwrap();
+        animator.update(machine, anim_set, &mut env.control);
+    }
+
+    #[test]
+    fn run_state_machine() {
+        let machine = AnimStateMachine::from_xml(XML).unwrap();
+        let mut env = TestEnv::with_anims(["Idle","Walk","Run","Jump"].iter().map(|n| anim(n, Vec3::ZERO, Vec3::ZERO)).collect());
+        let mut animator = Animator::new(seija_asset::Handle::weak(seija_asset::HandleId::random::<AnimStateMachine>()));
+        //第一次更新进入默认状态
+        update(&mut animator, &machine, &mut env);
+        assert_eq!(animator.current_state(), Some(0));
+        assert_eq!(env.control.layer(0).unwrap().current_anim(), Some(0));
+
+        //trigger触发转换后被消耗
+        animator.set_trigger("jump");
+        update(&mut animator, &machine, &mut env);
+        assert_eq!(animator.current_state(), Some(2));
+        assert_eq!(animator.get_float("jump"), 0f32);
+        update(&mut animator, &machine, &mut env);
+        assert_eq!(animator.current_state(), Some(2));
+
+        //没到exit_time前保持在jump,播放完后回到idle
+        env.process(0.5f32);
+        update(&mut animator, &machine, &mut env);
+        assert_eq!(animator.current_state(), Some(2));
+        env.process(0.5f32);
+        update(&mut animator, &machine, &mut env);
+        assert_eq!(animator.current_state(), Some(0));
+
+        //混合树按参数设置组内权重
+        animator.set_float("speed", 2.5f32);
+        update(&mut animator, &machine, &mut env);
+        assert_eq!(animator.current_state(), Some(1));
+        let layer = env.control.layer(0).unwrap();
+        let weights:Vec<(usize,f32)> = layer.clips.iter().filter(|c| c.group == animator.cur_group).map(|c| (c.anim_index,c.blend_weight)).collect();
+        assert_eq!(weights, vec![(1,0.5f32),(2,0.5f32)]);
+    }
+}
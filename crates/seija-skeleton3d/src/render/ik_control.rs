@@ -0,0 +1,227 @@
+use bevy_ecs::prelude::{Component, Res, ResMut, Query};
+use glam::{Vec3, Mat4, Quat};
+use seija_asset::Assets;
+use seija_transform::{Transform, TransformMatrix};
+
+use crate::{Skeleton, RuntimeSkeleton, AnimationControl, jobs::{LocalToModelJob, IKTwoBoneJob, IKAimJob, FabrikJob}};
+
+pub enum IKSolver {
+    //start,mid,end三个关节,比如大腿小腿脚
+    TwoBone { pole_vector:Vec3 },
+    //链上的关节依次朝向目标,最后一个关节用完整的权重,其他关节乘chain_factor
+    Aim { forward:Vec3, up:Vec3, pole_vector:Vec3, chain_factor:f32 },
+    //从根到末端的关节链
+    Fabrik { tolerance:f32, max_iterations:usize }
+}
+
+pub struct IKChain {
+    pub solver:IKSolver,
+    pub joint_names:Vec<String>,
+    //世界空间,实体没有Transform时是模型空间
+    pub target:Vec3,
+    pub weight:f32,
+    joints:Vec<usize>,
+    is_invalid:bool
+}
+
+impl IKChain {
+    pub fn new(solver:IKSolver,joint_names:Vec<String>) -> Self {
+        IKChain { solver, joint_names, target:Vec3::ZERO, weight:1f32, joints:vec![], is_invalid:false }
+    }
+
+    fn resolve(&mut self,skeleton:&Skeleton) -> bool {
+        if self.is_invalid { return false; }
+        if self.joints.is_empty() {
+            self.joints = self.joint_names.iter().filter_map(|name| skeleton.find_joint(name)).collect();
+            let need_count = if let IKSolver::TwoBone {..} = self.solver { 3 } else { self.joint_names.len() };
+            if self.joints.len() != self.joint_names.len() || self.joints.len() != need_count || self.joints.is_empty() {
+                log::warn!("ik chain not found joints {:?}",self.joint_names);
+                self.joints.clear();
+                self.is_invalid = true;
+                return false;
+            }
+            //FABRIK按相邻关节的连线旋转骨骼,链上每个关节必须是前一个的子关节
+            let is_fabrik = matches!(self.solver, IKSolver::Fabrik {..});
+            if is_fabrik && self.joints.windows(2).any(|w| skeleton.joint_parents[w[1]] != Some(w[0])) {
+                log::warn!("fabrik chain is not a parent to child sequence {:?}",self.joint_names);
+                self.joints.clear();
+                self.is_invalid = true;
+                return false;
+            }
+        }
+        true
+    }
+}
+
+//在动画采样和Transform更新之后按顺序执行IK链,只修改RuntimeSkeleton的模型矩阵,局部姿势保持动画的结果
+#[derive(Component,Default)]
+pub struct IKControl {
+    pub chains:Vec<IKChain>,
+    pose:Vec<TransformMatrix>
+}
+
+impl IKControl {
+    pub fn add_two_bone(&mut self,start:&str,mid:&str,end:&str,pole_vector:Vec3) -> usize {
+        self.add_chain(IKChain::new(IKSolver::TwoBone { pole_vector }, vec![start.into(),mid.into(),end.into()]))
+    }
+
+    pub fn add_aim(&mut self,joints:Vec<String>,forward:Vec3,up:Vec3,pole_vector:Vec3,chain_factor:f32) -> usize {
+        self.add_chain(IKChain::new(IKSolver::Aim { forward, up, pole_vector, chain_factor }, joints))
+    }
+
+    pub fn add_fabrik(&mut self,joints:Vec<String>,tolerance:f32,max_iterations:usize) -> usize {
+        self.add_chain(IKChain::new(IKSolver::Fabrik { tolerance, max_iterations }, joints))
+    }
+
+    pub fn add_chain(&mut self,chain:IKChain) -> usize {
+        self.chains.push(chain);
+        self.chains.len() - 1
+    }
+
+    pub fn chain_mut(&mut self,index:usize) -> Option<&mut IKChain> {
+        self.chains.get_mut(index)
+    }
+
+    fn apply(&mut self,skeleton:&Skeleton,rt_skeleton:&mut RuntimeSkeleton,to_model:&Mat4) {
+        if self.chains.iter().all(|c| c.weight <= 0f32) { return; }
+        if rt_skeleton.values.len() != skeleton.num_joints() || rt_skeleton.mat4s.len() != skeleton.num_joints() { return; }
+        self.pose.clone_from(&rt_skeleton.values);
+        let pose = &mut self.pose;
+        let mats = &mut rt_skeleton.mat4s;
+        let ltm = LocalToModelJob::new(skeleton);
+        //动画没有播放时模型矩阵里还是上一帧的IK结果,从局部姿势重新计算
+        ltm.run_from(pose, mats, 0);
+        for chain in self.chains.iter_mut() {
+            if chain.weight <= 0f32 || !chain.resolve(skeleton) { continue; }
+            let target = to_model.transform_point3(chain.target);
+            let joints = &chain.joints;
+            match &chain.solver {
+                IKSolver::TwoBone { pole_vector } => {
+                    let (start,mid,end) = (joints[0],joints[1],joints[2]);
+                    let mut job = IKTwoBoneJob::new(&mats[start], &mats[mid], &mats[end]);
+                    job.target = target;
+                    job.pole_vector = to_model.transform_vector3(*pole_vector);
+                    job.weight = chain.weight;
+                    let result = job.run();
+                    apply_correction(&mut pose[start], result.start_correction);
+                    apply_correction(&mut pose[mid], result.mid_correction);
+                    ltm.run_from(pose, mats, start);
+                },
+                IKSolver::Aim { forward, up, pole_vector, chain_factor } => {
+                    for (index,joint) in joints.iter().enumerate() {
+                        let weight = if index + 1 == joints.len() { chain.weight } else { chain.weight * chain_factor };
+                        let mut job = IKAimJob::new(&mats[*joint]);
+                        job.target = target;
+                        job.forward = *forward;
+                        job.up = *up;
+                        job.pole_vector = to_model.transform_vector3(*pole_vector);
+                        job.weight = weight;
+                        let correction = job.run();
+                        apply_correction(&mut pose[*joint], correction);
+                        ltm.run_from(pose, mats, *joint);
+                    }
+                },
+                IKSolver::Fabrik { tolerance, max_iterations } => {
+                    let mut positions:Vec<Vec3> = joints.iter().map(|j| mats[*j].w_axis.truncate()).collect();
+                    let job = FabrikJob { target, tolerance:*tolerance, max_iterations:*max_iterations };
+                    job.run(&mut positions);
+                    //按求解出的位置从根开始依次旋转每段骨骼
+                    for index in 0..joints.len() - 1 {
+                        let (joint,child) = (joints[index],joints[index + 1]);
+                        let (_,rot,position) = mats[joint].to_scale_rotation_translation();
+                        let cur_dir = mats[child].w_axis.truncate() - position;
+                        let new_dir = positions[index + 1] - position;
+                        if cur_dir.length_squared() < 0.00001f32 || new_dir.length_squared() < 0.00001f32 { continue; }
+                        let delta = Quat::IDENTITY.slerp(Quat::from_rotation_arc(cur_dir.normalize(), new_dir.normalize()), chain.weight.min(1f32));
+                        apply_correction(&mut pose[joint], (rot.inverse() * delta * rot).normalize());
+                        ltm.run_from(pose, mats, joint);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn apply_correction(value:&mut TransformMatrix,correction:Quat) {
+    value.rotation = (value.rotation * correction).normalize();
+}
+
+pub fn update_ik_system(skeletons:Res<Assets<Skeleton>>,
+                        mut rtskeletons:ResMut<Assets<RuntimeSkeleton>>,
+                        mut query:Query<(&mut IKControl,&AnimationControl,Option<&Transform>)>) {
+    for (mut ik_control,animation_control,transform) in query.iter_mut() {
+        let skeleton = match skeletons.get(&animation_control.skeleton().id) { Some(v) => v, None => continue };
+        let rt_skeleton = match rtskeletons.get_mut(&animation_control.get_runtime_skeleton().id) { Some(v) => v, None => continue };
+        //在PostUpdate的Transform更新之后执行,global已经包含这一帧的根运动
+        let to_model = transform.map(|t| t.global().matrix().inverse()).unwrap_or(Mat4::IDENTITY);
+        ik_control.apply(skeleton, rt_skeleton, &to_model);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{Vec3, Mat4};
+    use seija_transform::TransformMatrix;
+    use crate::{Skeleton, RuntimeSkeleton};
+    use super::IKControl;
+
+    //沿y轴的a-b-c-d关节链,每段长1
+    fn chain_skeleton() -> (Skeleton,RuntimeSkeleton) {
+        let mut skeleton = Skeleton::new(4);
+        skeleton.joint_parents = vec![None,Some(0),Some(1),Some(2)];
+        skeleton.joint_names = ["a","b","c","d"].iter().map(|n| Some(n.to_string())).collect();
+        skeleton.joint_rest_poses = (0..4).map(|i| {
+            let mut t = TransformMatrix::default();
+            if i > 0 { t.position = Vec3::Y; }
+            t
+        }).collect();
+        let mut rt_skeleton = RuntimeSkeleton::new(4);
+        rt_skeleton.values.clone_from(&skeleton.joint_rest_poses);
+        (skeleton,rt_skeleton)
+    }
+
+    fn joint_pos(rt_skeleton:&RuntimeSkeleton,index:usize) -> Vec3 {
+        rt_skeleton.mat4s[index].w_axis.truncate()
+    }
+
+    #[test]
+    fn apply_chains() {
+        let (skeleton,mut rt_skeleton) = chain_skeleton();
+        let mut ik = IKControl::default();
+        let fabrik = ik.add_fabrik(vec!["a".into(),"b".into(),"c".into(),"d".into()], 0.001f32, 20);
+        ik.chains[fabrik].target = Vec3::new(1.5f32, 1.5f32, 0f32);
+        ik.apply(&skeleton, &mut rt_skeleton, &Mat4::IDENTITY);
+        assert!(joint_pos(&rt_skeleton, 3).abs_diff_eq(Vec3::new(1.5f32, 1.5f32, 0f32), 0.01f32));
+        //局部姿势不变,只修改模型矩阵
+        assert_eq!(rt_skeleton.values[1].rotation, skeleton.joint_rest_poses[1].rotation);
+
+        //目标是世界空间,按实体的变换转到模型空间
+        let to_world = Mat4::from_translation(Vec3::new(10f32, 0f32, 0f32));
+        ik.chains[fabrik].target = Vec3::new(11.5f32, 1.5f32, 0f32);
+        ik.apply(&skeleton, &mut rt_skeleton, &to_world.inverse());
+        assert!(joint_pos(&rt_skeleton, 3).abs_diff_eq(Vec3::new(1.5f32, 1.5f32, 0f32), 0.01f32));
+
+        //最后一个关节的z轴朝向目标
+        let mut ik = IKControl::default();
+        let aim = ik.add_aim(vec!["d".into()], Vec3::Z, Vec3::Y, Vec3::Y, 1f32);
+        ik.chains[aim].target = Vec3::new(0f32, 3f32, -5f32);
+        ik.apply(&skeleton, &mut rt_skeleton, &Mat4::IDENTITY);
+        assert!(rt_skeleton.mat4s[3].transform_vector3(Vec3::Z).abs_diff_eq(-Vec3::Z, 0.001f32));
+    }
+
+    #[test]
+    fn invalid_fabrik_chain() {
+        let (skeleton,mut rt_skeleton) = chain_skeleton();
+        let mut ik = IKControl::default();
+        //不是父到子的顺序
+        let index = ik.add_fabrik(vec!["d".into(),"c".into(),"b".into()], 0.001f32, 20);
+        ik.chains[index].target = Vec3::new(1f32, 1f32, 0f32);
+        ik.apply(&skeleton, &mut rt_skeleton, &Mat4::IDENTITY);
+        assert!(ik.chains[index].is_invalid);
+        assert!(joint_pos(&rt_skeleton, 3).abs_diff_eq(Vec3::new(0f32, 3f32, 0f32), 0.001f32));
+        //跳过中间关节也不行
+        let index = ik.add_fabrik(vec!["a".into(),"c".into(),"d".into()], 0.001f32, 20);
+        ik.apply(&skeleton, &mut rt_skeleton, &Mat4::IDENTITY);
+        assert!(ik.chains[index].is_invalid);
+    }
+}
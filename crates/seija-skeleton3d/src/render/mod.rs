@@ -1,5 +1,7 @@
 pub mod animation_control;
 pub mod runtime_skeleton;
 pub mod animation_system;
+pub mod animator;
+pub mod ik_control;
 //pub mod skeleton_node;
 pub mod render_plugin;
@@ -20,4 +20,14 @@ impl Skeleton {
     }
 
     pub fn num_joints(&self) -> usize { self.joint_parents.len() }
+
+    pub fn find_joint(&self,name:&str) -> Option<usize> {
+        self.joint_names.iter().position(|n| n.as_deref() == Some(name))
+    }
+
+    //name关节及其所有子关节的权重为weight,用来让动画层只影响上半身之类的部分
+    pub fn joint_mask(&self,name:&str,weight:f32) -> Option<Vec<f32>> {
+        let root = self.find_joint(name)?;
+        Some(crate::jobs::joint_mask(&self.joint_parents, root, weight))
+    }
 }
\ No newline at end of file
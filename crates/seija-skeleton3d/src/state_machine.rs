@@ -0,0 +1,448 @@
+use std::collections::HashMap;
+use bevy_ecs::world::World;
+use quick_xml::events::{BytesStart, Event};
+use seija_asset::{IAssetLoader, AssetDynamic, HandleUntyped, add_to_asset_type, AssetServer, AssetLoaderParams};
+use seija_asset::async_trait::async_trait;
+use seija_core::{TypeUuid, uuid::Uuid, smol_str::SmolStr};
+use seija_core::anyhow::{self, Result, anyhow, bail};
+use serde::Deserialize;
+
+#[derive(Debug,Clone,Copy,PartialEq,Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ParamKind {
+    Float,
+    Bool,
+    //条件满足并触发转换后自动重置
+    Trigger
+}
+
+#[derive(Debug,Clone,Deserialize)]
+pub struct ParamDef {
+    pub name:String,
+    #[serde(rename = "type")]
+    pub kind:ParamKind,
+    #[serde(default)]
+    pub default:f32
+}
+
+#[derive(Debug,Clone,Deserialize)]
+pub struct BlendClip {
+    pub name:String,
+    pub threshold:f32
+}
+
+//按一个浮点参数在相邻两个动画间插值的一维混合树
+#[derive(Debug,Clone,Deserialize)]
+pub struct BlendTree {
+    pub param:String,
+    pub clips:Vec<BlendClip>
+}
+
+impl BlendTree {
+    //clips需要按threshold从小到大排列
+    pub fn weights(&self,value:f32) -> Vec<f32> {
+        let mut weights = vec![0f32;self.clips.len()];
+        if self.clips.is_empty() { return weights; }
+        let last = self.clips.len() - 1;
+        if value <= self.clips[0].threshold {
+            weights[0] = 1f32;
+        } else if value >= self.clips[last].threshold {
+            weights[last] = 1f32;
+        } else {
+            for index in 0..last {
+                let (a,b) = (self.clips[index].threshold,self.clips[index + 1].threshold);
+                if value >= a && value <= b {
+                    let t = if b > a { (value - a) / (b - a) } else { 0f32 };
+                    weights[index] = 1f32 - t;
+                    weights[index + 1] = t;
+                    break;
+                }
+            }
+        }
+        weights
+    }
+}
+
+#[derive(Debug,Clone)]
+pub enum StateMotion {
+    //AnimationSet中的动画名
+    Clip(String),
+    BlendTree(BlendTree)
+}
+
+#[derive(Debug,Clone)]
+pub struct AnimState {
+    pub name:String,
+    pub motion:StateMotion,
+    pub is_loop:bool
+}
+
+#[derive(Debug,Clone,Copy,PartialEq,Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConditionOp {
+    Greater,
+    Less,
+    //bool为true或trigger已触发
+    If,
+    IfNot
+}
+
+#[derive(Debug,Clone,Deserialize)]
+pub struct Condition {
+    pub param:String,
+    #[serde(default = "default_op")]
+    pub op:ConditionOp,
+    #[serde(default)]
+    pub value:f32
+}
+
+fn default_op() -> ConditionOp { ConditionOp::If }
+
+#[derive(Debug,Clone)]
+pub struct AnimTransition {
+    //None表示可以从任意状态转换
+    pub from:Option<usize>,
+    pub to:usize,
+    //过渡时间,秒
+    pub duration:f32,
+    //当前状态的归一化时间达到这个值后才能转换
+    pub exit_time:Option<f32>,
+    pub conditions:Vec<Condition>
+}
+
+//参数当前的值,bool和trigger用0和1表示
+pub type AnimParams = HashMap<String,f32>;
+
+impl Condition {
+    pub fn check(&self,params:&AnimParams) -> bool {
+        let value = params.get(&self.param).copied().unwrap_or(0f32);
+        match self.op {
+            ConditionOp::Greater => value > self.value,
+            ConditionOp::Less => value < self.value,
+            ConditionOp::If => value != 0f32,
+            ConditionOp::IfNot => value == 0f32
+        }
+    }
+}
+
+#[derive(Debug,Clone,TypeUuid)]
+#[uuid = "5a8a3f57-3e0a-4c55-a4d2-9f6f3b0f7c21"]
+pub struct AnimStateMachine {
+    pub params:Vec<ParamDef>,
+    pub states:Vec<AnimState>,
+    pub transitions:Vec<AnimTransition>,
+    pub default_state:usize
+}
+
+impl AnimStateMachine {
+    pub fn state_index(&self,name:&str) -> Option<usize> {
+        self.states.iter().position(|s| s.name == name)
+    }
+
+    pub fn param_kind(&self,name:&str) -> Option<ParamKind> {
+        self.params.iter().find(|p| p.name == name).map(|p| p.kind)
+    }
+
+    //按定义顺序找第一个满足条件的转换,不会转换到当前状态自身
+    pub fn find_transition(&self,cur_state:usize,params:&AnimParams,normalized_time:f32) -> Option<&AnimTransition> {
+        self.transitions.iter().find(|t| {
+            let from_ok = match t.from {
+                Some(from) => from == cur_state,
+                None => t.to != cur_state
+            };
+            from_ok && t.exit_time.map(|e| normalized_time >= e).unwrap_or(true)
+                    && t.conditions.iter().all(|c| c.check(params))
+        })
+    }
+
+    /*
+    <StateMachine default="idle">
+        <Param name="speed" type="float" default="0" />
+        <Param name="jump" type="trigger" />
+        <State name="idle" clip="Idle" />
+        <State name="move">
+            <BlendTree param="speed">
+                <Clip name="Walk" threshold="1" />
+                <Clip name="Run" threshold="4" />
+            </BlendTree>
+        </State>
+        <State name="jump" clip="Jump" loop="false" />
+        <Transition from="idle" to="move" duration="0.2"><Condition param="speed" op="greater" value="0.1" /></Transition>
+        <Transition from="*" to="jump" duration="0.1"><Condition param="jump" /></Transition>
+        <Transition from="jump" to="idle" exit_time="0.9" duration="0.2" />
+    </StateMachine>
+    */
+    pub fn from_xml(xml_string:&str) -> Result<AnimStateMachine> {
+        let mut xml_reader = quick_xml::Reader::from_str(xml_string);
+        xml_reader.trim_text(true);
+        let mut buf = Vec::new();
+        let mut state = XmlReadState::default();
+        loop {
+            match xml_reader.read_event(&mut buf) {
+                Ok(Event::Start(ref e)) => state.start(e)?,
+                Ok(Event::Empty(ref e)) => {
+                    state.start(e)?;
+                    state.end(e.name())?;
+                },
+                Ok(Event::End(ref e)) => state.end(e.name())?,
+                Ok(Event::Eof) => break,
+                Err(e) => bail!(e),
+                _ => {}
+            }
+            buf.clear();
+        }
+        state.data.build()
+    }
+
+    //和xml相同的结构,blend_tree和clip二选一,from为"*"或省略表示任意状态
+    pub fn from_json(bytes:&[u8]) -> Result<AnimStateMachine> {
+        let data:SerdeMachine = serde_json::from_slice(bytes)?;
+        data.build()
+    }
+}
+
+#[derive(Default,Deserialize)]
+struct SerdeState {
+    name:String,
+    clip:Option<String>,
+    blend_tree:Option<BlendTree>,
+    #[serde(rename = "loop")]
+    is_loop:Option<bool>
+}
+
+#[derive(Default,Deserialize)]
+struct SerdeTransition {
+    from:Option<String>,
+    to:String,
+    #[serde(default)]
+    duration:f32,
+    exit_time:Option<f32>,
+    #[serde(default)]
+    conditions:Vec<Condition>
+}
+
+#[derive(Default,Deserialize)]
+struct SerdeMachine {
+    default:Option<String>,
+    #[serde(default)]
+    params:Vec<ParamDef>,
+    states:Vec<SerdeState>,
+    #[serde(default)]
+    transitions:Vec<SerdeTransition>
+}
+
+impl SerdeMachine {
+    fn build(self) -> Result<AnimStateMachine> {
+        let mut states:Vec<AnimState> = vec![];
+        for state in self.states {
+            let motion = match (state.clip,state.blend_tree) {
+                (Some(clip),_) => StateMotion::Clip(clip),
+                (None,Some(mut tree)) => {
+                    tree.clips.sort_by(|a,b| a.threshold.total_cmp(&b.threshold));
+                    StateMotion::BlendTree(tree)
+                },
+                (None,None) => bail!("state {} miss clip or blend tree",state.name)
+            };
+            states.push(AnimState { name:state.name,motion,is_loop:state.is_loop.unwrap_or(true) });
+        }
+        let find_state = |name:&str| states.iter().position(|s| s.name == name).ok_or_else(|| anyhow!("not found state {}",name));
+        let default_state = match self.default.as_ref() {
+            Some(name) => find_state(name)?,
+            None if !states.is_empty() => 0,
+            None => bail!("state machine has no state")
+        };
+        let mut transitions:Vec<AnimTransition> = vec![];
+        for t in self.transitions {
+            let from = match t.from.as_deref() {
+                None | Some("*") => None,
+                Some(name) => Some(find_state(name)?)
+            };
+            transitions.push(AnimTransition { from,to:find_state(&t.to)?,duration:t.duration,exit_time:t.exit_time,conditions:t.conditions });
+        }
+        Ok(AnimStateMachine { params:self.params,states,transitions,default_state })
+    }
+}
+
+#[derive(Default)]
+struct XmlReadState {
+    data:SerdeMachine,
+    state:Option<SerdeState>,
+    transition:Option<SerdeTransition>
+}
+
+impl XmlReadState {
+    fn start(&mut self,e:&BytesStart) -> Result<()> {
+        let attrs = read_attrs(e)?;
+        let get = |name:&str| attrs.get(name).cloned();
+        let get_f32 = |name:&str| -> Result<Option<f32>> { Ok(match attrs.get(name) { Some(v) => Some(v.parse()?), None => None }) };
+        match e.name() {
+            b"StateMachine" => self.data.default = get("default"),
+            b"Param" => {
+                let name = get("name").ok_or_else(|| anyhow!("Param miss name"))?;
+                let kind = match get("type").as_deref() {
+                    Some("float") | None => ParamKind::Float,
+                    Some("bool") => ParamKind::Bool,
+                    Some("trigger") => ParamKind::Trigger,
+                    Some(typ) => bail!("unknown param type {}",typ)
+                };
+                let default = match (kind,get("default").as_deref()) {
+                    (ParamKind::Bool,Some("true")) => 1f32,
+                    (ParamKind::Float,Some(_)) => get_f32("default")?.unwrap_or(0f32),
+                    _ => 0f32
+                };
+                self.data.params.push(ParamDef { name,kind,default });
+            },
+            b"State" => {
+                let name = get("name").ok_or_else(|| anyhow!("State miss name"))?;
+                let is_loop = match get("loop") { Some(v) => Some(v.parse()?), None => None };
+                self.state = Some(SerdeState { name,clip:get("clip"),blend_tree:None,is_loop });
+            },
+            b"BlendTree" => {
+                let param = get("param").ok_or_else(|| anyhow!("BlendTree miss param"))?;
+                if let Some(state) = self.state.as_mut() {
+                    state.blend_tree = Some(BlendTree { param,clips:vec![] });
+                }
+            },
+            b"Clip" => {
+                let name = get("name").ok_or_else(|| anyhow!("Clip miss name"))?;
+                let threshold = get_f32("threshold")?.unwrap_or(0f32);
+                if let Some(tree) = self.state.as_mut().and_then(|s| s.blend_tree.as_mut()) {
+                    tree.clips.push(BlendClip { name,threshold });
+                }
+            },
+            b"Transition" => {
+                let to = get("to").ok_or_else(|| anyhow!("Transition miss to"))?;
+                self.transition = Some(SerdeTransition {
+                    from:get("from"),
+                    to,
+                    duration:get_f32("duration")?.unwrap_or(0f32),
+                    exit_time:get_f32("exit_time")?,
+                    conditions:vec![]
+                });
+            },
+            b"Condition" => {
+                let param = get("param").ok_or_else(|| anyhow!("Condition miss param"))?;
+                let op = match get("op").as_deref() {
+                    Some("greater") => ConditionOp::Greater,
+                    Some("less") => ConditionOp::Less,
+                    Some("if") | None => ConditionOp::If,
+                    Some("ifnot") => ConditionOp::IfNot,
+                    Some(op) => bail!("unknown condition op {}",op)
+                };
+                let value = get_f32("value")?.unwrap_or(0f32);
+                if let Some(t) = self.transition.as_mut() {
+                    t.conditions.push(Condition { param,op,value });
+                }
+            },
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn end(&mut self,name:&[u8]) -> Result<()> {
+        match name {
+            b"State" => {
+                if let Some(state) = self.state.take() {
+                    self.data.states.push(state);
+                }
+            },
+            b"Transition" => {
+                if let Some(t) = self.transition.take() {
+                    self.data.transitions.push(t);
+                }
+            },
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+fn read_attrs(e:&BytesStart) -> Result<HashMap<String,String>> {
+    let mut attrs = HashMap::default();
+    for attr in e.attributes() {
+        let attr = attr?;
+        attrs.insert(std::str::from_utf8(attr.key)?.to_string(), std::str::from_utf8(&attr.value)?.to_string());
+    }
+    Ok(attrs)
+}
+
+fn parse_machine(path:&str,bytes:&[u8]) -> Result<AnimStateMachine> {
+    if path.ends_with(".json") {
+        AnimStateMachine::from_json(bytes)
+    } else {
+        AnimStateMachine::from_xml(std::str::from_utf8(bytes)?)
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct AnimStateMachineLoader;
+
+#[async_trait]
+impl IAssetLoader for AnimStateMachineLoader {
+    fn typ(&self) -> Uuid { AnimStateMachine::TYPE_UUID }
+
+    fn add_to_asset(&self,world: &mut World,res:Box<dyn AssetDynamic>) -> anyhow::Result<HandleUntyped>  {
+        add_to_asset_type::<AnimStateMachine>(world, res)
+    }
+
+    fn sync_load(&self,_: &mut World,path: &str,server: &AssetServer,_:Option<Box<dyn AssetLoaderParams>>) -> anyhow::Result<Box<dyn AssetDynamic>> {
+        let bytes = server.read_bytes(path)?;
+        Ok(Box::new(parse_machine(path, &bytes)?))
+    }
+
+    async fn async_load(&self,server:AssetServer,path:SmolStr,
+        _:Option<Box<dyn seija_asset::downcast_rs::DowncastSync>>,
+        _:Option<Box<dyn AssetLoaderParams>>) -> anyhow::Result<Box<dyn AssetDynamic>> {
+        let bytes = server.read_bytes_async(path.as_str()).await?;
+        Ok(Box::new(parse_machine(path.as_str(), &bytes)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AnimStateMachine, AnimParams, StateMotion};
+
+    const XML:&str = r#"
+    <StateMachine default="idle">
+        <Param name="speed" type="float" default="0" />
+        <Param name="jump" type="trigger" />
+        <State name="idle" clip="Idle" />
+        <State name="move">
+            <BlendTree param="speed">
+                <Clip name="Run" threshold="4" />
+                <Clip name="Walk" threshold="1" />
+            </BlendTree>
+        </State>
+        <State name="jump" clip="Jump" loop="false" />
+        <Transition from="idle" to="move" duration="0.2"><Condition param="speed" op="greater" value="0.1" /></Transition>
+        <Transition from="*" to="jump" duration="0.1"><Condition param="jump" /></Transition>
+        <Transition from="jump" to="idle" exit_time="0.9" duration="0.2" />
+    </StateMachine>
+    "#;
+
+    #[test]
+    fn load_and_transition() {
+        let machine = AnimStateMachine::from_xml(XML).unwrap();
+        assert_eq!(machine.default_state, 0);
+        let tree = match &machine.states[1].motion { StateMotion::BlendTree(tree) => tree, _ => panic!() };
+        assert_eq!(tree.clips[0].name, "Walk");
+        assert_eq!(tree.weights(2.5f32), vec![0.5f32,0.5f32]);
+
+        let mut params = AnimParams::default();
+        assert!(machine.find_transition(0, &params, 0f32).is_none());
+        params.insert("speed".into(), 1f32);
+        assert_eq!(machine.find_transition(0, &params, 0f32).map(|t| t.to), Some(1));
+        params.insert("jump".into(), 1f32);
+        assert_eq!(machine.find_transition(1, &params, 0f32).map(|t| t.to), Some(2));
+        params.insert("jump".into(), 0f32);
+        assert!(machine.find_transition(2, &params, 0.5f32).is_none());
+        assert_eq!(machine.find_transition(2, &params, 0.95f32).map(|t| t.to), Some(0));
+
+        let json = r#"{"default":"idle","params":[{"name":"run","type":"bool"}],
+                       "states":[{"name":"idle","clip":"Idle"},{"name":"run","clip":"Run"}],
+                       "transitions":[{"from":"idle","to":"run","duration":0.3,"conditions":[{"param":"run"}]}]}"#;
+        let machine = AnimStateMachine::from_json(json.as_bytes()).unwrap();
+        params.insert("run".into(), 1f32);
+        assert_eq!(machine.find_transition(0, &params, 0f32).map(|t| t.to), Some(1));
+    }
+}
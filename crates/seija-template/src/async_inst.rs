@@ -0,0 +1,285 @@
+use std::collections::HashMap;
+use bevy_ecs::system::{Resource, CommandQueue};
+use seija_asset::{AssetServer, AssetRequest, Assets, Handle, HandleUntyped, LoadState};
+use seija_core::{bevy_ecs::{entity::Entity, world::World, event::Events}, anyhow::{Result, anyhow}, IDGenU64, info::EStateInfo};
+use seija_transform::events::WorldEntityEx;
+use smol_str::SmolStr;
+use crate::{Template, TOverride, TComponentManager, types::TTemplateEntity, inst::{InstPlan, plan_template}};
+
+//异步实例化完成时发送,失败时entity为None
+#[derive(Debug,Clone)]
+pub struct TemplateInstanceEvent {
+    pub id:u64,
+    pub entity:Option<Entity>
+}
+
+enum JobState {
+    Loading(AssetRequest),
+    Ready(Handle<Template>),
+    LoadAssets {
+        handle:Handle<Template>,
+        plan:InstPlan,
+        requests:Vec<AssetRequest>
+    },
+    Spawning {
+        _handle:Handle<Template>,
+        plan:InstPlan,
+        _holds:Vec<HandleUntyped>,
+        entities:Vec<Entity>
+    }
+}
+
+struct InstanceJob {
+    id:u64,
+    params:HashMap<SmolStr,SmolStr>,
+    overrides:Vec<TOverride>,
+    state:JobState
+}
+
+impl InstanceJob {
+    fn progress(&self) -> f32 {
+        match &self.state {
+            JobState::Spawning { plan, entities, .. } => entities.len() as f32 / plan.nodes.len() as f32,
+            _ => 0f32
+        }
+    }
+}
+
+//异步分帧实例化模板,先等待模板和用到的资源加载完成,再每帧创建budget个entity
+#[derive(Resource)]
+pub struct TemplateInstancer {
+    //所有任务每帧最多创建的entity数量
+    pub budget:usize,
+    ids:IDGenU64,
+    jobs:Vec<InstanceJob>
+}
+
+impl Default for TemplateInstancer {
+    fn default() -> Self {
+        TemplateInstancer { budget:64,ids:IDGenU64::new(),jobs:vec![] }
+    }
+}
+
+impl TemplateInstancer {
+    pub fn instance_path(&mut self,server:&AssetServer,path:&str,params:HashMap<SmolStr,SmolStr>,overrides:Vec<TOverride>) -> Result<u64> {
+        let request = server.load_async::<Template>(path, None)?;
+        Ok(self.add_job(JobState::Loading(request), params, overrides))
+    }
+
+    pub fn instance(&mut self,template:Handle<Template>,params:HashMap<SmolStr,SmolStr>,overrides:Vec<TOverride>) -> u64 {
+        self.add_job(JobState::Ready(template), params, overrides)
+    }
+
+    fn add_job(&mut self,state:JobState,params:HashMap<SmolStr,SmolStr>,overrides:Vec<TOverride>) -> u64 {
+        let id = self.ids.next();
+        self.jobs.push(InstanceJob { id,params,overrides,state });
+        id
+    }
+
+    //返回0到1的进度,等待资源时为0,任务不存在或者已经完成时返回None
+    pub fn progress(&self,id:u64) -> Option<f32> {
+        self.jobs.iter().find(|job| job.id == id).map(|job| job.progress())
+    }
+
+    pub fn is_running(&self,id:u64) -> bool {
+        self.jobs.iter().any(|job| job.id == id)
+    }
+
+    //取消任务,已经创建的entity会被删除
+    pub fn cancel(&mut self,world:&mut World,id:u64) {
+        if let Some(index) = self.jobs.iter().position(|job| job.id == id) {
+            let job = self.jobs.remove(index);
+            delete_spawned(world, &job);
+        }
+    }
+}
+
+//删除任务已经创建的entity,根节点已经被删除时跳过
+fn delete_spawned(world:&mut World,job:&InstanceJob) {
+    if let JobState::Spawning { entities, .. } = &job.state {
+        if let Some(root) = entities.first() {
+            let is_delete = match world.get_entity(*root) {
+                Some(entity) => entity.get::<EStateInfo>().map(|v| v.is_delete).unwrap_or(false),
+                None => true
+            };
+            if !is_delete {
+                world.delete(*root);
+            }
+        }
+    }
+}
+
+pub(crate) fn template_instance_system(world:&mut World) {
+    let mut instancer = match world.remove_resource::<TemplateInstancer>() {
+        Some(v) => v,
+        None => return
+    };
+    let mgr = world.get_resource::<TComponentManager>().unwrap().clone();
+    let server = world.get_resource::<AssetServer>().unwrap().clone();
+    let mut budget = instancer.budget.max(1);
+    let mut events:Vec<TemplateInstanceEvent> = vec![];
+    let mut index = 0;
+    while index < instancer.jobs.len() {
+        let job = &mut instancer.jobs[index];
+        match update_job(world, &mgr, &server, job, &mut budget) {
+            Ok(None) => { index += 1; },
+            Ok(Some(entity)) => {
+                events.push(TemplateInstanceEvent { id:job.id,entity:Some(entity) });
+                instancer.jobs.remove(index);
+            },
+            Err(err) => {
+                log::error!("template instance error:{:?}",err);
+                delete_spawned(world, job);
+                events.push(TemplateInstanceEvent { id:job.id,entity:None });
+                instancer.jobs.remove(index);
+            }
+        }
+    }
+    world.insert_resource(instancer);
+    if let Some(mut instance_events) = world.get_resource_mut::<Events<TemplateInstanceEvent>>() {
+        for event in events {
+            instance_events.send(event);
+        }
+    }
+}
+
+fn update_job(world:&mut World,mgr:&TComponentManager,server:&AssetServer,job:&mut InstanceJob,budget:&mut usize) -> Result<Option<Entity>> {
+    loop {
+        match &mut job.state {
+            JobState::Loading(request) => {
+                match request.load_state() {
+                    LoadState::Loaded => {
+                        let handle = request.make_handle().typed::<Template>();
+                        job.state = JobState::Ready(handle);
+                    },
+                    LoadState::Failed(err) => return Err(err.into()),
+                    _ => return Ok(None)
+                }
+            },
+            JobState::Ready(handle) => {
+                //资源加载完成后要等到下一次资源系统更新才会放入Assets
+                let template = match world.get_resource::<Assets<Template>>().and_then(|v| v.get(&handle.id)) {
+                    Some(v) => v.clone(),
+                    None => return Ok(None)
+                };
+                let mut plan = InstPlan::default();
+                let params = std::mem::take(&mut job.params);
+                let overrides = std::mem::take(&mut job.overrides);
+                let instance = TTemplateEntity { params:params.clone(),overrides:overrides.clone(),..Default::default() };
+                let root = plan_template(world, mgr, &mut plan, &template, params, overrides, None)?;
+                plan.mark_template(root, root, &template, instance, false);
+                let mut requests = vec![];
+                for (asset_typ,asset_path) in plan.assets.iter() {
+                    requests.push(server.load_async_untyped(asset_typ, asset_path.as_str(), None)?);
+                }
+                let handle = handle.clone();
+                job.state = JobState::LoadAssets { handle,plan,requests };
+            },
+            JobState::LoadAssets { handle, plan, requests } => {
+                for request in requests.iter() {
+                    match request.load_state() {
+                        LoadState::Loaded => {},
+                        LoadState::Failed(err) => return Err(err.into()),
+                        _ => return Ok(None)
+                    }
+                }
+                let holds = requests.iter().map(|r| r.make_handle()).collect();
+                let node_count = plan.nodes.len();
+                job.state = JobState::Spawning {
+                    _handle:handle.clone(),
+                    plan:std::mem::take(plan),
+                    _holds:holds,
+                    entities:Vec::with_capacity(node_count)
+                };
+            },
+            JobState::Spawning { plan, entities, .. } => {
+                let mut queue = CommandQueue::default();
+                let mut ret = Ok(());
+                while *budget > 0 && entities.len() < plan.nodes.len() {
+                    let index = entities.len();
+                    if !plan.parent_alive(world, index, entities) {
+                        ret = Err(anyhow!("template parent entity is deleted"));
+                        break;
+                    }
+                    ret = plan.spawn_node(world, mgr, server, &mut queue, index, entities);
+                    *budget -= 1;
+                    if ret.is_err() { break; }
+                }
+                queue.apply(world);
+                ret?;
+                if entities.len() < plan.nodes.len() {
+                    return Ok(None);
+                }
+                return entities.first().copied().map(Some).ok_or(anyhow!("template is empty"));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_ecs::event::ManualEventReader;
+    use seija_transform::hierarchy::Children;
+    use crate::tests::{new_world, add_template};
+    use super::*;
+
+    fn new_instancer_world(budget:usize) -> (World,Handle<Template>) {
+        let mut world = new_world(vec![]);
+        let handle = add_template(&mut world, r#"
+            <Entity name="root">
+                <Components><Transform /></Components>
+                <Children>
+                    <Entity name="a"><Components><Transform /></Components></Entity>
+                    <Entity name="b"><Components><Transform /></Components></Entity>
+                </Children>
+            </Entity>
+        "#);
+        world.insert_resource(TemplateInstancer { budget,..Default::default() });
+        world.insert_resource(Events::<TemplateInstanceEvent>::default());
+        (world,handle)
+    }
+
+    fn read_events(world:&World,reader:&mut ManualEventReader<TemplateInstanceEvent>) -> Vec<TemplateInstanceEvent> {
+        let events = world.get_resource::<Events<TemplateInstanceEvent>>().unwrap();
+        reader.iter(events).cloned().collect()
+    }
+
+    #[test]
+    fn budget_progress_event() {
+        let (mut world,handle) = new_instancer_world(2);
+        let mut reader = ManualEventReader::default();
+        let id = world.get_resource_mut::<TemplateInstancer>().unwrap().instance(handle, HashMap::default(), vec![]);
+        template_instance_system(&mut world);
+        let instancer = world.get_resource::<TemplateInstancer>().unwrap();
+        assert_eq!(instancer.progress(id), Some(2f32 / 3f32));
+        assert!(read_events(&world, &mut reader).is_empty());
+
+        template_instance_system(&mut world);
+        let instancer = world.get_resource::<TemplateInstancer>().unwrap();
+        assert!(!instancer.is_running(id));
+        assert!(instancer.progress(id).is_none());
+        let events = read_events(&world, &mut reader);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].id, id);
+        let root = events[0].entity.unwrap();
+        assert_eq!(world.get::<Children>(root).unwrap().children().len(), 2);
+    }
+
+    #[test]
+    fn cancel_dead_parent() {
+        let (mut world,handle) = new_instancer_world(1);
+        let mut reader = ManualEventReader::default();
+        let id = world.get_resource_mut::<TemplateInstancer>().unwrap().instance(handle, HashMap::default(), vec![]);
+        template_instance_system(&mut world);
+        assert_eq!(world.get_resource::<TemplateInstancer>().unwrap().progress(id), Some(1f32 / 3f32));
+        let root = world.query::<Entity>().iter(&world).next().unwrap();
+        world.delete(root);
+
+        template_instance_system(&mut world);
+        assert!(!world.get_resource::<TemplateInstancer>().unwrap().is_running(id));
+        let events = read_events(&world, &mut reader);
+        assert_eq!(events.len(), 1);
+        assert!(events[0].entity.is_none());
+        assert_eq!(world.query::<Entity>().iter(&world).count(), 1);
+    }
+}
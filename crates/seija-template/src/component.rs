@@ -2,17 +2,22 @@ use bevy_ecs::system::Resource;
 use relative_path::RelativePath;
 use seija_app::ecs::prelude::Entity;
 use seija_app::ecs::system::{CommandQueue, Insert};
+use seija_app::ecs::world::World;
 use seija_asset::AssetServer;
 use seija_core::anyhow::{Result};
+use seija_core::info::EInfo;
+use seija_core::reflect::{Reflect, ReflectStatic, not_found_field_error};
+use bevy_ecs::component::Component;
+use std::marker::PhantomData;
 use seija_core::math::{Vec3, Quat, EulerRot};
 use seija_core::uuid::Uuid;
-use seija_transform::Transform;
+use seija_transform::{Transform, hierarchy::Children};
 use smol_str::SmolStr;
 use std::{collections::HashMap, sync::Arc};
 
 use crate::errors::TemplateError;
-use crate::types::TEntityChildren;
-use crate::{TComponent, TEntity};
+use crate::types::{TEntityChildren, TTemplateEntity, TemplateInstance, has_params};
+use crate::{TComponent, TEntity, TOverride};
 
 #[derive(Clone,Resource)]
 pub struct TComponentManager {
@@ -46,6 +51,8 @@ impl TComponentManager {
 
     fn _search_assets(&self,entity: &mut TEntity,all_assets:&mut Vec<(Uuid,SmolStr)>,template_dir:&RelativePath)  -> Result<()> {
         for tcomponent in entity.components.iter_mut() {
+            //引用了模板参数的组件在实例化时才能确定资源
+            if tcomponent.has_params() { continue; }
             let opt = self.get_opt(tcomponent)?;
             let mut assets = opt.search_assets(tcomponent,template_dir)?;
             all_assets.extend(assets.drain(..));
@@ -61,10 +68,107 @@ impl TComponentManager {
         Ok(())
     }
 
+    //嵌套模板上附加的组件和属性覆盖引用的资源,覆盖值解析后的资源路径写入rt_value
+    pub fn search_template_assets(&self,template:&mut TTemplateEntity,template_dir:&RelativePath) -> Result<Vec<(Uuid,SmolStr)>> {
+        let mut all_assets = vec![];
+        for tcomponent in template.components.iter_mut() {
+            if tcomponent.has_params() { continue; }
+            let opt = self.get_opt(tcomponent)?;
+            all_assets.extend(opt.search_assets(tcomponent,template_dir)?);
+        }
+        for t_override in template.overrides.iter_mut() {
+            if has_params(&t_override.value) { continue; }
+            let mut tcomponent = TComponent::new(t_override.component.clone());
+            tcomponent.attrs.insert(t_override.attr.clone(), t_override.value.clone());
+            let opt = self.get_opt(&tcomponent)?;
+            all_assets.extend(opt.search_assets(&mut tcomponent,template_dir)?);
+            t_override.rt_value = tcomponent.rt_attrs.remove(t_override.attr.as_str());
+        }
+        Ok(all_assets)
+    }
+
+    //把World中的entity及其子节点转换回TEntity,只会写出注册了序列化的组件
+    //模板实例写成<Template>,作为根节点时是这个模板的变体
+    pub fn serialize_entity(&self,world:&World,server:&AssetServer,entity:Entity) -> Result<TEntity> {
+        if let Some(instance) = world.get::<TemplateInstance>(entity).filter(|v| !v.template.res.is_empty()) {
+            let base = self.serialize_template(world, server, entity, instance)?;
+            return Ok(TEntity { base:Some(base),..Default::default() });
+        }
+        let mut tentity = TEntity::default();
+        if let Some(info) = world.get::<EInfo>(entity) {
+            tentity.name = info.name.clone();
+            tentity.layer = info.layer;
+            tentity.tag = info.tag.clone();
+        }
+        tentity.components = self.serialize_components(world, server, entity)?;
+        if let Some(children) = world.get::<Children>(entity) {
+            for child in children.children().iter() {
+                let mut tchild = self.serialize_entity(world, server, *child)?;
+                match tchild.base.take() {
+                    Some(template) => tentity.children.push(TEntityChildren::Template(template)),
+                    None => tentity.children.push(TEntityChildren::TEntity(tchild))
+                }
+            }
+        }
+        Ok(tentity)
+    }
+
+    fn serialize_components(&self,world:&World,server:&AssetServer,entity:Entity) -> Result<Vec<TComponent>> {
+        let mut components = vec![];
+        let mut names:Vec<&SmolStr> = self.opts.keys().collect();
+        //Transform放在最前面,其余按名字排序保证输出稳定
+        names.sort_by_key(|name| (name.as_str() != "Transform",name.as_str()));
+        for name in names {
+            if let Some(mut tcomponent) = self.opts[name].serialize_component(world, server, entity)? {
+                tcomponent.typ = name.clone();
+                components.push(tcomponent);
+            }
+        }
+        Ok(components)
+    }
+
+    //模板根节点上模板自己定义的组件写成路径为空的属性覆盖,其他组件附加在<Template>上
+    //模板内部子节点的修改不会保存,实例化时传入的子节点覆盖原样保留
+    fn serialize_template(&self,world:&World,server:&AssetServer,entity:Entity,instance:&TemplateInstance) -> Result<TTemplateEntity> {
+        let mut template = instance.template.clone();
+        template.components.clear();
+        template.overrides.retain(|t_override| !t_override.path.is_empty());
+        let root = if instance.wrapper {
+            template.components = self.serialize_components(world, server, entity)?;
+            match world.get::<Children>(entity).and_then(|children| children.children().first().copied()) {
+                Some(root) => root,
+                None => return Ok(template)
+            }
+        } else {
+            if let Some(info) = world.get::<EInfo>(entity) {
+                template.name = info.name.clone();
+                template.layer = info.layer.max(0) as u32;
+                template.tag = info.tag.clone();
+            }
+            entity
+        };
+        for component in self.serialize_components(world, server, root)? {
+            if instance.root_types.contains(&component.typ) {
+                let mut attrs:Vec<_> = component.attrs.into_iter().collect();
+                attrs.sort_by(|a,b| a.0.cmp(&b.0));
+                for (attr,value) in attrs {
+                    template.overrides.push(TOverride { path:"".into(),component:component.typ.clone(),attr,value,rt_value:None });
+                }
+            } else if instance.wrapper {
+                log::warn!("skip serialize {} added on template root:{}",component.typ,template.res);
+            } else {
+                template.components.push(component);
+            }
+        }
+        Ok(template)
+    }
+
 }
 pub trait ITComponentOpt: Send + Sync + 'static {
     fn search_assets(&self, _component: &mut TComponent,_template_dir:&RelativePath) -> Result<Vec<(Uuid,SmolStr)>> { Ok(vec![]) }
     fn create_component(&self,server:&AssetServer, component: &TComponent,queue:&mut CommandQueue,entity:Entity)-> Result<()>;
+    //从entity上读取组件写回TComponent,entity没有这个组件时返回None
+    fn serialize_component(&self,_world:&World,_server:&AssetServer,_entity:Entity) -> Result<Option<TComponent>> { Ok(None) }
 }
 
 
@@ -85,4 +189,97 @@ impl ITComponentOpt for TransformTemplateOpt {
         queue.push(insert);
         Ok(())
     }
-}
\ No newline at end of file
+
+    fn serialize_component(&self,world:&World,_:&AssetServer,entity:Entity) -> Result<Option<TComponent>> {
+        let t = match world.get::<Transform>(entity) {
+            Some(t) => t,
+            None => return Ok(None)
+        };
+        let (y,x,z) = t.local.rotation.to_euler(EulerRot::YXZ);
+        let mut component = TComponent::new("Transform".into());
+        component.write_v3("position", t.local.position);
+        component.write_v3("rotation", Vec3::new(x.to_degrees(), y.to_degrees(), z.to_degrees()));
+        component.write_v3("scale", t.local.scale);
+        Ok(Some(component))
+    }
+}
+
+//使用#[derive(Reflect)]生成的字段信息读写组件,模板属性名就是反射的字段名
+pub(crate) struct ReflectTemplateOpt<T>(PhantomData<T>);
+
+impl<T> Default for ReflectTemplateOpt<T> {
+    fn default() -> Self { ReflectTemplateOpt(PhantomData) }
+}
+
+impl<T> ITComponentOpt for ReflectTemplateOpt<T> where T:Reflect + ReflectStatic + Component + Default {
+    fn create_component(&self,_:&AssetServer, component: &TComponent,queue:&mut CommandQueue,entity:Entity) -> Result<()> {
+        let mut value = T::default();
+        //按字段声明的顺序设置,切换类型的字段(如Camera.type)放在前面,不会覆盖后面设置的字段
+        for field in T::FIELDS.iter() {
+            if let Some(attr) = component.attrs.get(field.name) {
+                value.set_field_str(field.name, attr)?;
+            }
+        }
+        if let Some(name) = component.attrs.keys().find(|name| !T::FIELDS.iter().any(|f| f.name == name.as_str())) {
+            return Err(not_found_field_error(T::TYPE_NAME, name));
+        }
+        queue.push(Insert {entity,bundle:value });
+        Ok(())
+    }
+
+    fn serialize_component(&self,world:&World,_:&AssetServer,entity:Entity) -> Result<Option<TComponent>> {
+        let value = match world.get::<T>(entity) {
+            Some(v) => v,
+            None => return Ok(None)
+        };
+        let mut component = TComponent::new(T::TYPE_NAME.into());
+        for field in T::FIELDS.iter() {
+            if let Some(field_value) = value.get_field(field.name) {
+                component.attrs.insert(field.name.into(), field_value.to_string().into());
+            }
+        }
+        Ok(Some(component))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use seija_core::{reflect::Reflect, math::Vec3};
+    use crate::{read_tmpl_entity, serialize_entity_xml, tests::new_world};
+    use super::*;
+
+    #[derive(Reflect,Component,Default,Debug,PartialEq)]
+    struct TestMove {
+        speed:f32,
+        #[reflect(name = "dir")]
+        direction:Vec3,
+        running:bool
+    }
+
+    #[test]
+    fn reflect_round_trip() {
+        let mut world = new_world(vec![(TestMove::TYPE_NAME,Box::new(ReflectTemplateOpt::<TestMove>::default()))]);
+        let value = TestMove { speed:1.5,direction:Vec3::new(0.0, 1.0, -1.0),running:true };
+        let entity = world.spawn((Transform::default(),value)).id();
+        let xml = serialize_entity_xml(&world, entity).unwrap();
+        assert!(xml.contains("dir="));
+
+        let tentity = read_tmpl_entity(&xml).unwrap();
+        let mgr = world.get_resource::<TComponentManager>().unwrap().clone();
+        let server = world.get_resource::<AssetServer>().unwrap().clone();
+        let loaded = world.spawn_empty().id();
+        let mut queue = CommandQueue::default();
+        for tcomponent in tentity.components.iter() {
+            mgr.create(tcomponent, &server, &mut queue, loaded).unwrap();
+        }
+        queue.apply(&mut world);
+        assert_eq!(world.get::<TestMove>(loaded), world.get::<TestMove>(entity));
+
+        let mut bad = TComponent::new(TestMove::TYPE_NAME.into());
+        bad.attrs.insert("speed".into(), "fast".into());
+        assert!(mgr.create(&bad, &server, &mut queue, loaded).is_err());
+        let mut unknown = TComponent::new(TestMove::TYPE_NAME.into());
+        unknown.attrs.insert("jump".into(), "1".into());
+        assert!(mgr.create(&unknown, &server, &mut queue, loaded).is_err());
+    }
+}
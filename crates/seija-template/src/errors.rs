@@ -15,4 +15,10 @@ pub enum TemplateError {
     LoadChildenError(SmolStr),
     #[error("not found template opt {0}")]
     NotFoundChild(SmolStr),
+    #[error("not found template param {0}")]
+    NotFoundParam(SmolStr),
+    #[error("template param syntax error {0}")]
+    ParamSyntaxError(SmolStr),
+    #[error("template override path error {0}")]
+    OverridePathError(SmolStr),
 }
\ No newline at end of file
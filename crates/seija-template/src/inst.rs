@@ -1,60 +1,280 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, cell::Cell};
 
+use relative_path::RelativePath;
 use seija_app::ecs::{system::{CommandQueue}};
 use seija_asset::{AssetServer, HandleUntyped, Assets};
-use seija_core::{bevy_ecs::{entity::Entity,world::{World}}, info::EInfo};
+use seija_core::{bevy_ecs::{entity::Entity,world::{World}}, info::{EInfo, EStateInfo}};
 use seija_core::anyhow::{Result};
-use smallvec::SmallVec;
+use seija_core::uuid::Uuid;
 use smol_str::SmolStr;
 use seija_transform::events::{WorldEntityEx};
-use crate::{TEntity, TComponentManager, types::TEntityChildren, Template, errors::TemplateError};
-
+use crate::{TEntity, TComponent, TComponentManager, types::{TEntityChildren, TTemplateEntity, TOverride, TemplateInstance, has_params, replace_params}, Template, errors::TemplateError};
 
 pub fn instance_template_sync(world:&mut World,template:&Template) -> Result<Entity> {
+    instance_template_with(world, template, HashMap::default(), vec![])
+}
+
+pub fn instance_template_with(world:&mut World,template:&Template,params:HashMap<SmolStr,SmolStr>,overrides:Vec<TOverride>) -> Result<Entity> {
     let mgr = world.get_resource::<TComponentManager>().unwrap().clone();
-    let mut queue = CommandQueue::default();
     let server = world.get_resource::<AssetServer>().unwrap().clone();
-    let child_tmpl = &template.inner.childrens;
-    let ret = instance_entity_sync(world,&server,&template.inner.entity,&mgr,&mut queue,child_tmpl);
+    let mut plan = InstPlan::default();
+    let instance = TTemplateEntity { params:params.clone(),overrides:overrides.clone(),..Default::default() };
+    let root = plan_template(world, &mgr, &mut plan, template, params, overrides, None)?;
+    plan.mark_template(root, root, template, instance, false);
+    //实例化时加载的资源,保持引用直到组件创建完成
+    let mut holds:Vec<HandleUntyped> = vec![];
+    for (asset_typ,asset_path) in plan.assets.iter() {
+        holds.push(server.load_sync_untyped(world, asset_typ, asset_path.as_str(), None)?);
+    }
+    let mut queue = CommandQueue::default();
+    let mut entities:Vec<Entity> = Vec::with_capacity(plan.nodes.len());
+    let mut ret = Ok(());
+    for index in 0..plan.nodes.len() {
+        ret = plan.spawn_node(world, &mgr, &server, &mut queue, index, &mut entities);
+        if ret.is_err() { break; }
+    }
     queue.apply(world);
+    ret?;
+    Ok(entities[0])
+}
+
+//模板展开后的一个entity
+pub(crate) struct InstNode {
+    info:Option<EInfo>,
+    components:Vec<TComponent>,
+    parent:Option<usize>,
+    instance:Option<TemplateInstance>
+}
 
-    ret
+//模板按先序展开成的entity列表,父节点的索引总是小于子节点,可以分多帧创建
+#[derive(Default)]
+pub(crate) struct InstPlan {
+    pub nodes:Vec<InstNode>,
+    //参数替换后才能确定的资源,创建entity前需要加载
+    pub assets:Vec<(Uuid,SmolStr)>
 }
 
-fn instance_entity_sync(world:&mut World,server:&AssetServer,tentity:&TEntity,mgr:&TComponentManager,queue:&mut CommandQueue,child_tmpl:&HashMap<SmolStr,HandleUntyped>) -> Result<Entity> {
-    let mut childrens:SmallVec<[Entity;8]> = SmallVec::new();
+impl InstPlan {
+    pub fn spawn_node(&mut self,world:&mut World,mgr:&TComponentManager,server:&AssetServer,queue:&mut CommandQueue,
+                      index:usize,entities:&mut Vec<Entity>) -> Result<()> {
+        let node = &mut self.nodes[index];
+        let mut entity_mut = world.spawn_empty();
+        let eid = entity_mut.id();
+        if let Some(info) = node.info.take() {
+            entity_mut.insert(info);
+        }
+        if let Some(instance) = node.instance.take() {
+            entity_mut.insert(instance);
+        }
+        entities.push(eid);
+        if let Some(parent) = node.parent {
+            world.set_parent(eid, Some(entities[parent]));
+        }
+        for component in node.components.iter() {
+            mgr.create(component, server, queue, eid)?;
+        }
+        Ok(())
+    }
+
+    //分帧创建时已经创建的父节点可能被外部删除了,这时不能继续创建子节点
+    pub fn parent_alive(&self,world:&World,index:usize,entities:&[Entity]) -> bool {
+        match self.nodes[index].parent {
+            Some(parent) => match world.get_entity(entities[parent]) {
+                Some(entity) => !entity.get::<EStateInfo>().map(|v| v.is_delete).unwrap_or(false),
+                None => false
+            },
+            None => true
+        }
+    }
+}
+
+//实例化一个模板时的参数和属性覆盖
+struct InstContext<'a> {
+    params:HashMap<SmolStr,SmolStr>,
+    overrides:Vec<TOverride>,
+    //每个覆盖是否找到了对应的组件或转交给了嵌套模板
+    used:Vec<Cell<bool>>,
+    dir:&'a RelativePath,
+    child_tmpl:&'a HashMap<SmolStr,HandleUntyped>
+}
+
+pub(crate) fn plan_template(world:&World,mgr:&TComponentManager,plan:&mut InstPlan,template:&Template,
+                            params:HashMap<SmolStr,SmolStr>,overrides:Vec<TOverride>,parent:Option<usize>) -> Result<usize> {
+    let tentity = &template.inner.entity;
+    let mut all_params:HashMap<SmolStr,SmolStr> = HashMap::default();
+    for param in tentity.params.iter() {
+        if let Some(default) = param.default.as_ref() {
+            all_params.insert(param.name.clone(), default.clone());
+        }
+    }
+    all_params.extend(params);
+    for param in tentity.params.iter() {
+        if !all_params.contains_key(param.name.as_str()) {
+            return Err(TemplateError::NotFoundParam(param.name.clone()).into());
+        }
+    }
+    let ctx = InstContext {
+        params:all_params,
+        used:overrides.iter().map(|_| Cell::new(false)).collect(),
+        overrides,
+        dir:RelativePath::new(template.inner.dir.as_str()),
+        child_tmpl:&template.inner.childrens
+    };
+    let root = match tentity.base.as_ref() {
+        Some(base) => plan_variant(world, mgr, plan, &ctx, base, parent)?,
+        None => plan_entity(world, mgr, plan, tentity, &ctx, "", parent)?
+    };
+    if let Some(index) = ctx.used.iter().position(|used| !used.get()) {
+        return Err(TemplateError::OverridePathError(ctx.overrides[index].full_path().into()).into());
+    }
+    Ok(root)
+}
+
+//变体使用基础模板展开,再把自己的信息和组件应用到根节点上
+fn plan_variant(world:&World,mgr:&TComponentManager,plan:&mut InstPlan,ctx:&InstContext,base:&TTemplateEntity,parent:Option<usize>) -> Result<usize> {
+    let base_template = get_child_template(world, ctx, base)?;
+    let (base_params,mut overrides) = resolve_template_args(ctx, base)?;
+    //变体的参数会继续传给基础模板
+    let mut params = ctx.params.clone();
+    params.extend(base_params);
+    //根节点上变体自己有的组件在这里覆盖,其余的交给基础模板
+    for (t_override,used) in ctx.overrides.iter().zip(ctx.used.iter()) {
+        if t_override.path.is_empty() && base.components.iter().any(|c| c.typ == t_override.component) { continue; }
+        overrides.push(t_override.clone());
+        used.set(true);
+    }
+    let root = plan_template(world, mgr, plan, &base_template, params, overrides, parent)?;
+    if let Some(info) = plan.nodes[root].info.as_mut() {
+        if base.name.is_some() { info.name = base.name.clone(); }
+        if base.tag.is_some() { info.tag = base.tag.clone(); }
+        if base.layer > 0 { info.layer = base.layer as i32; }
+    }
+    for component in base.components.iter() {
+        let component = resolve_component(mgr, &mut plan.assets, ctx, component, "")?;
+        plan.nodes[root].components.push(component);
+    }
+    Ok(root)
+}
+
+fn plan_entity(world:&World,mgr:&TComponentManager,plan:&mut InstPlan,tentity:&TEntity,ctx:&InstContext,path:&str,parent:Option<usize>) -> Result<usize> {
+    let index = plan.nodes.len();
+    let mut components = Vec::with_capacity(tentity.components.len());
+    for component in tentity.components.iter() {
+        components.push(resolve_component(mgr, &mut plan.assets, ctx, component, path)?);
+    }
+    plan.nodes.push(InstNode { info:Some(create_einfo(tentity)),components,parent,instance:None });
     for child in tentity.children.iter() {
         match child {
             TEntityChildren::TEntity(childen) => {
-                childrens.push(instance_entity_sync(world,server,&childen,mgr,queue,child_tmpl)?);
+                let child_path = join_path(path, childen.name.as_ref());
+                plan_entity(world, mgr, plan, childen, ctx, &child_path, Some(index))?;
             },
             TEntityChildren::Template(xml_template) => {
-               let handle_id = child_tmpl.get(xml_template.res.as_str()).ok_or(TemplateError::NotFoundChild(xml_template.res.clone()))?.id;
-               let templates = world.get_resource::<Assets<Template>>().unwrap();
-               let template = templates.get(&handle_id).ok_or(TemplateError::NotFoundChild(xml_template.res.clone()))?.clone();
-               let entity = instance_template_sync(world,&template)?;
-
-               let template_entity = world.spawn_empty();
-               let template_entity_id = template_entity.id();
+               let child_path = join_path(path, xml_template.name.as_ref());
+               let template = get_child_template(world, ctx, xml_template)?;
+               let (params,mut overrides) = resolve_template_args(ctx, xml_template)?;
+               //外层的覆盖优先,路径转换成相对嵌套模板根节点的路径
+               //指向这个节点但<Template>上没有附加对应组件的覆盖交给嵌套模板的根节点
+               let prefix = format!("{}/",child_path);
+               for (t_override,used) in ctx.overrides.iter().zip(ctx.used.iter()) {
+                   let sub_path = if t_override.path == child_path {
+                       if xml_template.components.iter().any(|c| c.typ == t_override.component) { continue; }
+                       ""
+                   } else {
+                       match t_override.path.strip_prefix(prefix.as_str()) {
+                           Some(sub_path) => sub_path,
+                           None => continue
+                       }
+                   };
+                   let mut t_override = t_override.clone();
+                   t_override.path = sub_path.into();
+                   overrides.push(t_override);
+                   used.set(true);
+               }
+               let template_index = plan.nodes.len();
+               let mut components = Vec::with_capacity(xml_template.components.len());
                for component in xml_template.components.iter() {
-                    mgr.create(component, server,queue,template_entity_id)?
+                    components.push(resolve_component(mgr, &mut plan.assets, ctx, component, &child_path)?);
                }
-               childrens.push(template_entity_id);
-               world.set_parent(entity, Some(template_entity_id));
+               plan.nodes.push(InstNode { info:None,components,parent:Some(index),instance:None });
+               let root = plan_template(world, mgr, plan, &template, params, overrides, Some(template_index))?;
+               plan.mark_template(template_index, root, &template, xml_template.clone(), true);
             }
         }
     }
-    let mut entity_mut = world.spawn_empty();
-    let eid = entity_mut.id();
-    let info = create_einfo(tentity);
-    entity_mut.insert(info);
-    for component in tentity.components.iter() {
-        mgr.create(component, server,queue,eid)?
+    Ok(index)
+}
+
+impl InstPlan {
+    //记录index节点是由哪个模板实例化的,序列化时写回<Template>
+    //res使用模板的资源路径,从哪个文件加载保存的结果都能找到;代码里创建的模板没有路径时保持原来的res
+    pub(crate) fn mark_template(&mut self,index:usize,root:usize,template:&Template,mut tmpl:TTemplateEntity,wrapper:bool) {
+        if !template.inner.path.is_empty() {
+            tmpl.res = format!("/{}",template.inner.path).into();
+        }
+        let root_types = self.nodes[root].components.iter().map(|c| c.typ.clone()).collect();
+        self.nodes[index].instance = Some(TemplateInstance { template:tmpl,root_types,wrapper });
+    }
+}
+
+fn get_child_template(world:&World,ctx:&InstContext,xml_template:&TTemplateEntity) -> Result<Template> {
+    let handle_id = ctx.child_tmpl.get(xml_template.res.as_str()).ok_or(TemplateError::NotFoundChild(xml_template.res.clone()))?.id;
+    let templates = world.get_resource::<Assets<Template>>().unwrap();
+    let template = templates.get(&handle_id).ok_or(TemplateError::NotFoundChild(xml_template.res.clone()))?.clone();
+    Ok(template)
+}
+
+//嵌套模板的参数和覆盖值中可以引用外层模板的参数
+fn resolve_template_args(ctx:&InstContext,xml_template:&TTemplateEntity) -> Result<(HashMap<SmolStr,SmolStr>,Vec<TOverride>)> {
+    let mut params:HashMap<SmolStr,SmolStr> = HashMap::default();
+    for (name,value) in xml_template.params.iter() {
+        params.insert(name.clone(), replace_params(value, &ctx.params)?);
+    }
+    let mut overrides = xml_template.overrides.clone();
+    for t_override in overrides.iter_mut() {
+        if has_params(&t_override.value) {
+            t_override.value = replace_params(&t_override.value, &ctx.params)?;
+        }
+    }
+    Ok((params,overrides))
+}
+
+//应用参数和路径匹配的属性覆盖
+fn resolve_component(mgr:&TComponentManager,assets:&mut Vec<(Uuid,SmolStr)>,ctx:&InstContext,component:&TComponent,path:&str) -> Result<TComponent> {
+    let mut component = component.clone();
+    if component.has_params() {
+        for value in component.attrs.values_mut() {
+            if has_params(value) {
+                *value = replace_params(value, &ctx.params)?;
+            }
+        }
+        let opt = mgr.get_opt(&component)?;
+        assets.extend(opt.search_assets(&mut component, ctx.dir)?);
     }
-    for child in childrens {
-        world.set_parent(child, Some(eid));
-    }  
-    Ok(eid)
+    let overrides = ctx.overrides.iter().zip(ctx.used.iter()).filter(|(o,_)| o.path == path && o.component == component.typ);
+    for (t_override,used) in overrides {
+        used.set(true);
+        component.attrs.insert(t_override.attr.clone(), t_override.value.clone());
+        match t_override.rt_value.as_ref() {
+            Some(rt_value) => { component.rt_attrs.insert(t_override.attr.clone(), rt_value.clone()); },
+            None => {
+                let mut override_component = TComponent::new(component.typ.clone());
+                override_component.attrs.insert(t_override.attr.clone(), t_override.value.clone());
+                let opt = mgr.get_opt(&override_component)?;
+                assets.extend(opt.search_assets(&mut override_component, ctx.dir)?);
+                match override_component.rt_attrs.remove(t_override.attr.as_str()) {
+                    Some(rt_value) => { component.rt_attrs.insert(t_override.attr.clone(), rt_value); },
+                    None => { component.rt_attrs.remove(t_override.attr.as_str()); }
+                }
+            }
+        }
+    }
+    Ok(component)
+}
+
+fn join_path(path:&str,name:Option<&SmolStr>) -> String {
+    let name = name.map(|v| v.as_str()).unwrap_or_default();
+    if path.is_empty() { name.to_string() } else { format!("{}/{}",path,name) }
 }
 
 fn create_einfo(entity:&TEntity) -> EInfo {
@@ -67,4 +287,114 @@ fn create_einfo(entity:&TEntity) -> EInfo {
         info.tag = Some(tag.clone())
     }
     info
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use crate::{read_tmpl_entity, types::TemplateInner, tests::{new_world, new_template, add_template}};
+    use super::*;
+
+    const BASE_XML:&str = r#"
+        <Entity name="root">
+            <Params><Param name="speed" default="1" /></Params>
+            <Components><Transform position="${speed},0,0" /></Components>
+            <Children>
+                <Entity name="Arm.L"><Components><Transform scale="1,1,1" /></Components></Entity>
+            </Children>
+        </Entity>
+    "#;
+
+    fn new_base_world() -> (World,HashMap<SmolStr,HandleUntyped>) {
+        let mut world = new_world(vec![]);
+        let mut childrens = HashMap::default();
+        childrens.insert("base.xml".into(), add_template(&mut world, BASE_XML).untyped());
+        (world,childrens)
+    }
+
+    fn new_plan(world:&World,template:&Template,overrides:&[(&str,&str)]) -> Result<InstPlan> {
+        let mgr = world.get_resource::<TComponentManager>().unwrap().clone();
+        let overrides = overrides.iter().map(|(path,value)| TOverride::parse(path, value).unwrap()).collect();
+        let mut plan = InstPlan::default();
+        plan_template(world, &mgr, &mut plan, template, HashMap::default(), overrides, None)?;
+        Ok(plan)
+    }
+
+    fn attr<'a>(plan:&'a InstPlan,index:usize,component:usize,name:&str) -> &'a str {
+        plan.nodes[index].components[component].attrs.get(name).unwrap().as_str()
+    }
+
+    #[test]
+    fn variant_merge() {
+        let (world,childrens) = new_base_world();
+        let variant = new_template(r#"
+            <Template res="base.xml" name="variant" tag="enemy">
+                <Param name="speed" value="2" />
+                <Override path="Arm.L.Transform.scale" value="2,2,2" />
+                <Transform rotation="0,90,0" />
+            </Template>
+        "#, childrens);
+        let plan = new_plan(&world, &variant, &[(".Transform.rotation","0,45,0")]).unwrap();
+        assert_eq!(plan.nodes.len(), 2);
+        let info = plan.nodes[0].info.as_ref().unwrap();
+        assert_eq!(info.name.as_deref(), Some("variant"));
+        assert_eq!(info.tag.as_deref(), Some("enemy"));
+        assert_eq!(attr(&plan, 0, 0, "position"), "2,0,0");
+        assert_eq!(attr(&plan, 0, 1, "rotation"), "0,45,0");
+        assert_eq!(attr(&plan, 1, 0, "scale"), "2,2,2");
+        assert_eq!(plan.nodes[1].parent, Some(0));
+    }
+
+    #[test]
+    fn nested_overrides() {
+        let (world,childrens) = new_base_world();
+        let outer = new_template(r#"
+            <Entity name="outer">
+                <Children><Template res="base.xml" name="Body"><Param name="speed" value="3" /></Template></Children>
+            </Entity>
+        "#, childrens);
+        let plan = new_plan(&world, &outer, &[("Body.Transform.position","5,5,5"),("Body/Arm.L.Transform.scale","3,3,3")]).unwrap();
+        //outer,<Template>节点,嵌套模板根节点,Arm.L
+        assert_eq!(plan.nodes.len(), 4);
+        assert_eq!(attr(&plan, 2, 0, "position"), "5,5,5");
+        assert_eq!(attr(&plan, 3, 0, "scale"), "3,3,3");
+
+        let err = new_plan(&world, &outer, &[("Body/Leg.Transform.scale","1,1,1")]).err().unwrap();
+        assert!(matches!(err.downcast_ref::<TemplateError>(), Some(TemplateError::OverridePathError(_))));
+        assert!(new_plan(&world, &outer, &[("Body/Arm.L.Camera.fov","60")]).is_err());
+        assert!(new_plan(&world, &outer, &[("Arm.Transform.scale","1,1,1")]).is_err());
+    }
+
+    #[test]
+    fn serialize_instances() {
+        use seija_transform::{Transform, hierarchy::Children};
+        use crate::serialize_entity_xml;
+        let (mut world,childrens) = new_base_world();
+        let outer = new_template(r#"
+            <Entity name="outer">
+                <Children><Template res="base.xml" name="Body"><Param name="speed" value="3" /></Template></Children>
+            </Entity>
+        "#, childrens.clone());
+        let entity = instance_template_sync(&mut world, &outer).unwrap();
+        let wrapper = world.get::<Children>(entity).unwrap().children()[0];
+        let root = world.get::<Children>(wrapper).unwrap().children()[0];
+        world.get_mut::<Transform>(root).unwrap().local.position.y = 7f32;
+        //嵌套模板写回<Template>,根节点的修改变成路径为空的属性覆盖
+        let xml = serialize_entity_xml(&world, entity).unwrap();
+        assert!(!xml.contains("Arm.L"));
+        let plan = new_plan(&world, &new_template(&xml, childrens), &[]).unwrap();
+        assert_eq!(plan.nodes.len(), 4);
+        assert_eq!(attr(&plan, 2, 0, "position"), "3,7,0");
+
+        //有资源路径的模板实例化后写成这个模板的变体
+        let entity = read_tmpl_entity(BASE_XML).unwrap();
+        let base = Template { inner:Arc::new(TemplateInner { path:"ui/base.xml".into(),dir:"ui".into(),assets:vec![],childrens:HashMap::default(),entity:Arc::new(entity) }) };
+        let entity = instance_template_sync(&mut world, &base).unwrap();
+        world.get_mut::<Transform>(entity).unwrap().local.position.x = 5f32;
+        let tentity = read_tmpl_entity(&serialize_entity_xml(&world, entity).unwrap()).unwrap();
+        let variant = tentity.base.unwrap();
+        assert_eq!(variant.res.as_str(), "/ui/base.xml");
+        assert_eq!(variant.name.as_deref(), Some("root"));
+        assert!(variant.overrides.iter().any(|v| v.path.is_empty() && v.attr == "position" && v.value == "5,0,0"));
+    }
+}
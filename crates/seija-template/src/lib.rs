@@ -2,16 +2,22 @@ mod types;
 mod loader;
 mod inst;
 mod component;
+mod async_inst;
+pub use async_inst::{TemplateInstancer,TemplateInstanceEvent};
 use std::collections::HashMap;
 pub use component::{ITComponentOpt,TComponentManager};
-use component::TransformTemplateOpt;
+use component::{TransformTemplateOpt, ReflectTemplateOpt};
 use loader::TemplateLoader;
 use seija_app::{IModule, App, ecs::{world::World, system::Resource}};
 use seija_asset::AddAsset;
+use seija_core::{AddCore, CoreStage, bevy_ecs::component::Component, reflect::{Reflect, ReflectStatic}};
 use smol_str::SmolStr;
 pub mod errors;
 mod reader;
-pub use types::{TComponent,TEntity,Template};
+mod writer;
+pub use writer::{write_tmpl_entity,serialize_entity_xml};
+pub use reader::read_tmpl_entity;
+pub use types::{TComponent,TEntity,Template,TParam,TOverride,TemplateInstance};
 
 
 pub struct TemplateModule;
@@ -21,6 +27,9 @@ impl IModule for TemplateModule {
         app.add_asset::<Template>();
         app.add_asset_loader::<Template,TemplateLoader>();
         app.add_resource(CacheTComponentOpts(HashMap::default()));
+        app.add_resource(TemplateInstancer::default());
+        app.add_event::<TemplateInstanceEvent>();
+        app.add_system(CoreStage::PreUpdate, async_inst::template_instance_system);
     
         app.add_tcomponent_opt("Transform", TransformTemplateOpt)
     }
@@ -36,6 +45,8 @@ struct CacheTComponentOpts(HashMap<SmolStr,Box<dyn ITComponentOpt>>);
 
 pub trait AddTComponent {
     fn add_tcomponent_opt(&mut self,name:&str,func:impl ITComponentOpt);
+    //注册#[derive(Reflect)]的组件,模板中使用结构体名作为组件名
+    fn add_tcomponent_reflect<T:Reflect + ReflectStatic + Component + Default>(&mut self);
 }
 
 impl AddTComponent for App {
@@ -43,4 +54,40 @@ impl AddTComponent for App {
         let mut data = self.world.get_resource_mut::<CacheTComponentOpts>().unwrap();
         data.0.insert(SmolStr::new(name), Box::new(func));
     }
+
+    fn add_tcomponent_reflect<T:Reflect + ReflectStatic + Component + Default>(&mut self) {
+        self.register_reflect::<T>();
+        self.add_tcomponent_opt(T::TYPE_NAME, ReflectTemplateOpt::<T>::default());
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::{collections::HashMap, sync::Arc};
+    use seija_app::ecs::world::World;
+    use seija_asset::{AssetServer, Assets, Handle, HandleUntyped};
+    use smol_str::SmolStr;
+    use crate::{read_tmpl_entity, ITComponentOpt, TComponentManager, Template, component::TransformTemplateOpt, types::TemplateInner};
+
+    //测试用的World,默认带Transform的组件模板
+    pub(crate) fn new_world(opts:Vec<(&str,Box<dyn ITComponentOpt>)>) -> World {
+        let mut opts:HashMap<SmolStr,Box<dyn ITComponentOpt>> = opts.into_iter().map(|(name,opt)| (SmolStr::new(name),opt)).collect();
+        opts.insert("Transform".into(), Box::new(TransformTemplateOpt));
+        let server = AssetServer::new(std::env::temp_dir());
+        let mut world = World::default();
+        world.insert_resource(server.register_type::<Template>());
+        world.insert_resource(server);
+        world.insert_resource(TComponentManager::new(opts));
+        world
+    }
+
+    pub(crate) fn new_template(xml:&str,childrens:HashMap<SmolStr,HandleUntyped>) -> Template {
+        let entity = read_tmpl_entity(xml).unwrap();
+        Template { inner:Arc::new(TemplateInner { path:"".into(),dir:"".into(),assets:vec![],childrens,entity:Arc::new(entity) }) }
+    }
+
+    pub(crate) fn add_template(world:&mut World,xml:&str) -> Handle<Template> {
+        let template = new_template(xml, HashMap::default());
+        world.get_resource_mut::<Assets<Template>>().unwrap().add(template)
+    }
 }
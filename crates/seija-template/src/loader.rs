@@ -1,7 +1,7 @@
 use crate::component::TComponentManager;
 use crate::errors::TemplateError;
 use crate::reader::read_tmpl_entity;
-use crate::types::{TEntityChildren, TemplateInner};
+use crate::types::{TEntityChildren, TemplateInner, TTemplateEntity};
 use crate::{TEntity, Template};
 use relative_path::RelativePath;
 use seija_app::ecs::world::World;
@@ -12,7 +12,7 @@ use seija_asset::{
     AssetDynamic, AssetLoaderParams, AssetRequest, AssetServer, AsyncLoadMode, HandleUntyped
 };
 use seija_core::anyhow::{anyhow, Result};
-use seija_core::{smol, OptionExt};
+use seija_core::OptionExt;
 use seija_core::TypeUuid;
 use smol_str::SmolStr;
 use std::collections::HashMap;
@@ -46,8 +46,7 @@ impl IAssetLoader for TemplateLoader {
         let template_dir = file_path.parent().get()?;
     
         let mgr = world.get_resource::<TComponentManager>().unwrap().clone();
-        let full_path = server.full_path(path)?;
-        let xml_string = std::fs::read_to_string(full_path)?;
+        let xml_string = server.read_string(path)?;
         let mut entity = read_tmpl_entity(&xml_string)?;
         let mut assets: Vec<HandleUntyped> = vec![];
       
@@ -57,7 +56,12 @@ impl IAssetLoader for TemplateLoader {
             let handle = server.load_sync_untyped(world,&asset_typ, asset_path.as_str(), None)?;
             assets.push(handle);
         }
+        for handle in assets.iter().chain(childrens.values()) {
+            server.add_dependency(path, handle.id);
+        }
         let inner = TemplateInner {
+            path: path.into(),
+            dir: template_dir.as_str().into(),
             assets,
             childrens,
             entity: Arc::new(entity),
@@ -91,11 +95,10 @@ impl IAssetLoader for TemplateLoader {
                 .downcast::<TComponentManager>()
                 .map_err(|_| TemplateError::TypeCastError)?;
 
-            let full_path = server.full_path(path.as_str())?;
             let path_buf = RelativePath::new(path.as_str());
             let template_dir = path_buf.parent().get()?;
 
-            let xml_string = smol::fs::read_to_string(full_path).await?;
+            let xml_string = server.read_string_async(path.as_str()).await?;
             let mut entity = read_tmpl_entity(&xml_string)?;
 
             let mut assets = vec![];
@@ -108,8 +111,13 @@ impl IAssetLoader for TemplateLoader {
                     .ok_or(TemplateError::LoadAssetError)?;
                 assets.push(handle);
             }
+            for handle in assets.iter().chain(childrens.values()) {
+                server.add_dependency(path.as_str(), handle.id);
+            }
 
             let inner = TemplateInner {
+                path: path.clone(),
+                dir: template_dir.as_str().into(),
                 assets,
                 childrens,
                 entity: Arc::new(entity),
@@ -132,21 +140,16 @@ async fn load_dep_template(
     template_dir:&RelativePath
 ) -> Result<HashMap<SmolStr, HandleUntyped>> {
     let mut req_list: Vec<(AssetRequest, SmolStr)> = vec![];
-    for children in tentiy.children.iter_mut() {
-        if let TEntityChildren::Template(template) = children {
-            let req = server.load_async::<Template>(template.res.as_str(), None)?;
-            req_list.push((req, template.res.clone()));
-            for comp in template.components.iter_mut() {
-                let opt = mgr.get_opt(comp)?;
-                for (asset_typ, asset_path) in opt.search_assets(comp,template_dir)? {
-                    let req = server.load_async_untyped(&asset_typ, asset_path.as_str(), None)?;
-                    let handle = req
-                        .wait_handle()
-                        .await
-                        .ok_or(TemplateError::LoadAssetError)?;
-                    all_assets.push(handle);
-                }
-            }
+    for template in dep_templates(tentiy) {
+        let req = server.load_async::<Template>(template.res.as_str(), None)?;
+        req_list.push((req, template.res.clone()));
+        for (asset_typ, asset_path) in mgr.search_template_assets(template,template_dir)? {
+            let req = server.load_async_untyped(&asset_typ, asset_path.as_str(), None)?;
+            let handle = req
+                .wait_handle()
+                .await
+                .ok_or(TemplateError::LoadAssetError)?;
+            all_assets.push(handle);
         }
     }
 
@@ -170,18 +173,32 @@ fn load_dep_template_sync(
     template_dir:&RelativePath
 ) -> Result<HashMap<SmolStr, HandleUntyped>> {
     let mut ret_map:HashMap<SmolStr,HandleUntyped> = HashMap::default();
-    for children in tentiy.children.iter_mut() {
-        if let TEntityChildren::Template(template) = children {
-            let res = server.load_sync::<Template>(world,template.res.as_str(), None)?;
-            ret_map.insert(template.res.clone(), res.untyped());
-            for comp in template.components.iter_mut() {
-                let opt = mgr.get_opt(comp)?;
-                for (asset_typ, asset_path) in opt.search_assets(comp,template_dir)? {
-                    let cres = server.load_sync_untyped(world,&asset_typ, asset_path.as_str(), None)?;
-                    all_assets.push(cres);
-                }
-            }
+    for template in dep_templates(tentiy) {
+        let res = server.load_sync::<Template>(world,template.res.as_str(), None)?;
+        ret_map.insert(template.res.clone(), res.untyped());
+        for (asset_typ, asset_path) in mgr.search_template_assets(template,template_dir)? {
+            let cres = server.load_sync_untyped(world,&asset_typ, asset_path.as_str(), None)?;
+            all_assets.push(cres);
         }
     }
     Ok(ret_map)
 }
+
+//模板中所有嵌套的<Template>,包括变体的基础模板
+fn dep_templates(tentity: &mut TEntity) -> Vec<&mut TTemplateEntity> {
+    let mut list = vec![];
+    collect_dep_templates(tentity, &mut list);
+    list
+}
+
+fn collect_dep_templates<'a>(tentity: &'a mut TEntity,list:&mut Vec<&'a mut TTemplateEntity>) {
+    if let Some(base) = tentity.base.as_mut() {
+        list.push(base);
+    }
+    for children in tentity.children.iter_mut() {
+        match children {
+            TEntityChildren::TEntity(entity) => collect_dep_templates(entity, list),
+            TEntityChildren::Template(template) => list.push(template)
+        }
+    }
+}
@@ -1,13 +1,14 @@
-use seija_core::anyhow::{Result,bail};
+use seija_core::anyhow::{Result,bail,anyhow};
 use quick_xml::events::{BytesStart, Event};
 use smol_str::SmolStr;
-use crate::{TEntity, types::{TEntityChildren, TTemplateEntity}, errors::TemplateError, TComponent};
+use crate::{TEntity, types::{TEntityChildren, TTemplateEntity, TParam, TOverride}, errors::TemplateError, TComponent};
 
 pub fn read_tmpl_entity(xml_string: &str) -> Result<TEntity> {
     let mut xml_reader = quick_xml::Reader::from_str(xml_string);
     xml_reader.trim_text(true);
     let mut entity_stack: Vec<TEntityChildren> = vec![];
     let mut in_components = false;
+    let mut in_params = false;
     let mut cur_component: Option<Vec<u8>> = None;
     let mut buf = Vec::new();
     loop {
@@ -40,6 +41,9 @@ pub fn read_tmpl_entity(xml_string: &str) -> Result<TEntity> {
                 b"Components" => {
                     in_components = true;
                 }
+                b"Params" => {
+                    in_params = true;
+                }
                 b"Children" => {}
                 _ if in_components => {
                     cur_component = Some(e.name().to_vec());
@@ -53,8 +57,26 @@ pub fn read_tmpl_entity(xml_string: &str) -> Result<TEntity> {
             Ok(Event::Empty(e))  => {
                 if e.name() == b"Template" {
                     let template = read_template(&e)?;
-                    if let Some(TEntityChildren::TEntity(e)) = entity_stack.last_mut() {
-                        e.children.push(TEntityChildren::Template(template));
+                    match entity_stack.last_mut() {
+                        Some(TEntityChildren::TEntity(e)) => e.children.push(TEntityChildren::Template(template)),
+                        None => entity_stack.push(TEntityChildren::Template(template)),
+                        _ => {}
+                    }
+                } else if e.name() == b"Param" {
+                    let (name,value) = read_param(&e)?;
+                    match entity_stack.last_mut() {
+                        Some(TEntityChildren::TEntity(entity)) if in_params => {
+                            entity.params.push(TParam { name,default:value });
+                        },
+                        Some(TEntityChildren::Template(template)) => {
+                            template.params.insert(name, value.unwrap_or_default());
+                        },
+                        _ => {}
+                    }
+                } else if e.name() == b"Override" {
+                    let t_override = read_override(&e)?;
+                    if let Some(TEntityChildren::Template(template)) = entity_stack.last_mut() {
+                        template.overrides.push(t_override);
                     }
                 } else {
                     if in_components {
@@ -82,6 +104,7 @@ pub fn read_tmpl_entity(xml_string: &str) -> Result<TEntity> {
             }
             Ok(Event::End(ref e)) => match e.name() {
                 b"Components" => in_components = false,
+                b"Params" => in_params = false,
                 b"Entity" | b"Template" => {
                     if entity_stack.len() > 1 {
                         let pop = entity_stack.pop().unwrap();
@@ -101,10 +124,11 @@ pub fn read_tmpl_entity(xml_string: &str) -> Result<TEntity> {
             _ => (),
         }
     }
-    if let Some(TEntityChildren::TEntity(e)) = entity_stack.pop() {
-        return Ok(e);
-    } else {
-        bail!("top entity error")
+    match entity_stack.pop() {
+        Some(TEntityChildren::TEntity(e)) => Ok(e),
+        //根节点是<Template>时作为res模板的变体
+        Some(TEntityChildren::Template(t)) => Ok(TEntity { base:Some(t),..Default::default() }),
+        None => bail!("top entity error")
     }
 }
 
@@ -130,6 +154,37 @@ fn read_template<'a>(e: &BytesStart<'a>) -> Result<TTemplateEntity> {
     Ok(template)
 }
 
+fn read_param<'a>(e: &BytesStart<'a>) -> Result<(SmolStr,Option<SmolStr>)> {
+    let mut name:Option<SmolStr> = None;
+    let mut value:Option<SmolStr> = None;
+    for attr in e.attributes() {
+        if let Ok(item) = attr {
+            match item.key {
+                b"name" => { name = Some(std::str::from_utf8(&item.value)?.into()) },
+                b"default" | b"value" => { value = Some(std::str::from_utf8(&item.value)?.into()) },
+                _ => {}
+            }
+        }
+    }
+    let name = name.ok_or(anyhow!("template param miss name"))?;
+    Ok((name,value))
+}
+
+fn read_override<'a>(e: &BytesStart<'a>) -> Result<TOverride> {
+    let mut path:SmolStr = SmolStr::default();
+    let mut value:SmolStr = SmolStr::default();
+    for attr in e.attributes() {
+        if let Ok(item) = attr {
+            match item.key {
+                b"path" => { path = std::str::from_utf8(&item.value)?.into() },
+                b"value" => { value = std::str::from_utf8(&item.value)?.into() },
+                _ => {}
+            }
+        }
+    }
+    Ok(TOverride::parse(&path, &value).ok_or(TemplateError::OverridePathError(path))?)
+}
+
 fn read_tmpl_component<'a>(e: &BytesStart<'a>) -> Result<TComponent> {
     let name: SmolStr = std::str::from_utf8(e.name())?.into();
     let mut component = TComponent::new(name);
@@ -144,3 +199,39 @@ fn read_tmpl_component<'a>(e: &BytesStart<'a>) -> Result<TComponent> {
     Ok(component)
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_params_overrides() {
+        let entity = read_tmpl_entity(r#"
+            <Entity name="root">
+                <Params>
+                    <Param name="speed" default="1" />
+                    <Param name="color" />
+                </Params>
+                <Children>
+                    <Template res="arm.xml" name="Arm">
+                        <Param name="speed" value="${speed}" />
+                        <Override path="Hand.L.Transform.scale" value="2,2,2" />
+                    </Template>
+                </Children>
+            </Entity>
+        "#).unwrap();
+        assert_eq!(entity.params.len(), 2);
+        assert_eq!(entity.params[0].default.as_deref(), Some("1"));
+        assert!(entity.params[1].default.is_none());
+        let template = match &entity.children[0] {
+            TEntityChildren::Template(t) => t,
+            _ => panic!("need template")
+        };
+        assert_eq!(template.params.get("speed").map(|v| v.as_str()), Some("${speed}"));
+        assert_eq!(template.overrides[0].path, "Hand.L");
+        assert_eq!(template.overrides[0].value, "2,2,2");
+
+        assert!(read_tmpl_entity(r#"<Template res="a.xml"><Override path="position" value="1" /></Template>"#).is_err());
+        assert!(read_tmpl_entity(r#"<Entity><Params><Param default="1" /></Params></Entity>"#).is_err());
+    }
+}
@@ -1,10 +1,10 @@
 use std::{collections::HashMap, sync::Arc};
-use seija_app::ecs::{world::World, prelude::Entity};
+use seija_app::ecs::{world::World, prelude::{Entity, Component}};
 use seija_asset::HandleUntyped;
 use smol_str::SmolStr;
-use seija_core::{anyhow::{Result}, info::EInfo, math::Vec3};
+use seija_core::{anyhow::{Result, anyhow}, info::EInfo, math::Vec3, reflect::{IReflectValue, ReflectValue}};
 
-use crate::{inst::instance_template_sync};
+use crate::{inst::{instance_template_sync, instance_template_with}, errors::TemplateError};
 use seija_core::{TypeUuid,uuid::Uuid};
 
 #[derive(Default,Debug,TypeUuid,Clone)]
@@ -15,6 +15,9 @@ pub struct Template {
 
 #[derive(Default,Debug)]
 pub(crate) struct TemplateInner {
+    //模板的资源路径,保存实例时写回<Template res>
+    pub(crate) path:SmolStr,
+    pub(crate) dir:SmolStr,
     pub(crate) assets:Vec<HandleUntyped>,
     pub(crate) childrens:HashMap<SmolStr,HandleUntyped>,
     pub entity:Arc<TEntity>
@@ -26,6 +29,15 @@ impl Template {
         instance_template_sync(world, &self)
     }
 
+    //使用参数和属性覆盖创建实例,覆盖路径相对模板的根节点
+    pub fn instance_with(self,world:&mut World,params:HashMap<SmolStr,SmolStr>,overrides:Vec<TOverride>) -> Result<Entity> {
+        instance_template_with(world, &self, params, overrides)
+    }
+
+    pub fn params(&self) -> &Vec<TParam> {
+        &self.inner.entity.params
+    }
+
     pub fn assets(&self) -> &Vec<HandleUntyped> {
         &self.inner.assets
     }
@@ -41,7 +53,11 @@ pub struct TEntity {
     pub layer:i32,
     pub tag:Option<SmolStr>,
     pub components:Vec<TComponent>,
-    pub children:Vec<TEntityChildren>
+    pub children:Vec<TEntityChildren>,
+    //模板参数,只在根节点上有效,组件属性中使用${name}引用
+    pub params:Vec<TParam>,
+    //根节点是<Template>时这个模板是另一个模板的变体
+    pub base:Option<TTemplateEntity>
 }
 
 #[derive(Debug)]
@@ -52,17 +68,93 @@ pub enum TEntityChildren {
 
 impl Default for TEntity {
     fn default() -> Self {
-        Self { layer:1,children:vec![],components:vec![],name:None,tag:None }
+        Self { layer:1,children:vec![],components:vec![],name:None,tag:None,params:vec![],base:None }
     }
 }
 
-#[derive(Default,Debug)]
+#[derive(Default,Debug,Clone)]
 pub struct TTemplateEntity {
     pub res:SmolStr,
     pub name:Option<SmolStr>,
     pub layer:u32,
     pub tag:Option<SmolStr>,
     pub components:Vec<TComponent>,
+    pub params:HashMap<SmolStr,SmolStr>,
+    pub overrides:Vec<TOverride>
+}
+
+//模板实例化时挂在<Template>对应的entity上,保存时写回<Template>而不是展开的子节点
+//嵌套模板多一个挂<Template>上组件的父节点(wrapper),模板的根节点是它唯一的子节点
+#[derive(Component,Debug,Clone)]
+pub struct TemplateInstance {
+    pub(crate) template:TTemplateEntity,
+    //模板根节点上模板自己定义的组件,保存时写成根节点的属性覆盖
+    pub(crate) root_types:Vec<SmolStr>,
+    pub(crate) wrapper:bool
+}
+
+impl TemplateInstance {
+    pub fn res(&self) -> &SmolStr {
+        &self.template.res
+    }
+}
+
+#[derive(Default,Debug,Clone)]
+pub struct TParam {
+    pub name:SmolStr,
+    pub default:Option<SmolStr>
+}
+
+//按子节点路径覆盖嵌套模板中组件的属性,例如Body/Arm.Transform.position
+//组件名和属性名取最后两段,节点名中可以带'.',路径为空表示根节点,Body/表示嵌套模板Body的根节点
+#[derive(Default,Debug,Clone)]
+pub struct TOverride {
+    pub path:SmolStr,
+    pub component:SmolStr,
+    pub attr:SmolStr,
+    pub value:SmolStr,
+    //value中的资源路径解析后的结果,在加载外层模板时设置
+    pub rt_value:Option<SmolStr>
+}
+
+impl TOverride {
+    pub fn parse(full_path:&str,value:&str) -> Option<TOverride> {
+        let mut iter = full_path.rsplitn(3, '.');
+        let attr = iter.next().filter(|v| !v.is_empty())?;
+        let component = iter.next().filter(|v| !v.is_empty() && !v.contains('/'))?;
+        let path = iter.next().unwrap_or_default();
+        Some(TOverride {
+            path:path.into(),
+            component:component.into(),
+            attr:attr.into(),
+            value:value.into(),
+            rt_value:None
+        })
+    }
+
+    pub fn full_path(&self) -> String {
+        format!("{}.{}.{}",self.path,self.component,self.attr)
+    }
+}
+
+pub fn has_params(value:&str) -> bool {
+    value.contains("${")
+}
+
+//把value中的${name}替换成参数值
+pub fn replace_params(value:&str,params:&HashMap<SmolStr,SmolStr>) -> Result<SmolStr> {
+    let mut ret = String::new();
+    let mut rest = value;
+    while let Some(start) = rest.find("${") {
+        ret.push_str(&rest[..start]);
+        let len = rest[start..].find('}').ok_or(TemplateError::ParamSyntaxError(value.into()))?;
+        let name = &rest[start + 2..start + len];
+        let param = params.get(name).ok_or(TemplateError::NotFoundParam(name.into()))?;
+        ret.push_str(param.as_str());
+        rest = &rest[start + len + 1..];
+    }
+    ret.push_str(rest);
+    Ok(ret.into())
 }
 
 
@@ -80,7 +172,7 @@ impl TEntity {
     }
 }
 
-#[derive(Default,Debug)]
+#[derive(Default,Debug,Clone)]
 pub struct TComponent {
     pub typ:SmolStr,
     pub attrs:HashMap<SmolStr,SmolStr>,
@@ -92,6 +184,10 @@ impl TComponent {
         TComponent { typ, attrs:HashMap::default(),rt_attrs:HashMap::default() }
     }
     
+    pub fn has_params(&self) -> bool {
+        self.attrs.values().any(|v| has_params(v))
+    }
+
     pub fn read_float(&self,name:&str,default:f32) -> f32 {
         self.attrs.get(name).and_then(|v| v.parse().ok()).unwrap_or(default)
     }
@@ -106,8 +202,63 @@ impl TComponent {
         }
         None
     }
+
+    pub fn write_float(&mut self,name:&str,value:f32) {
+        self.attrs.insert(name.into(), value.to_string().into());
+    }
+
+    pub fn write_v3(&mut self,name:&str,value:Vec3) {
+        self.attrs.insert(name.into(), format!("{},{},{}",value.x,value.y,value.z).into());
+    }
+
+    //按反射字段的格式读写属性,枚举使用变体名,没有这个属性时返回None
+    pub fn read_value<T:IReflectValue>(&self,name:&str) -> Result<Option<T>> {
+        match self.attrs.get(name) {
+            Some(attr) => {
+                let value = ReflectValue::parse(T::TYPE, attr)?;
+                T::from_value(value).map(Some).ok_or_else(|| anyhow!("{}.{} error value:{}",self.typ,name,attr))
+            },
+            None => Ok(None)
+        }
+    }
+
+    pub fn write_value<T:IReflectValue>(&mut self,name:&str,value:&T) {
+        self.attrs.insert(name.into(), value.to_value().to_string().into());
+    }
 }
 
 pub trait FormTComponent<T> {
     fn from(&self,attrs:&HashMap<SmolStr,SmolStr>) -> Result<T>;
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn override_parse() {
+        let t = TOverride::parse("Body/Arm.L.Transform.position", "1,2,3").unwrap();
+        assert_eq!((t.path.as_str(),t.component.as_str(),t.attr.as_str()), ("Body/Arm.L","Transform","position"));
+        assert_eq!(t.full_path(), "Body/Arm.L.Transform.position");
+        let root = TOverride::parse("Transform.position", "").unwrap();
+        assert_eq!(root.path, "");
+        let nested_root = TOverride::parse("Body/.Transform.position", "").unwrap();
+        assert_eq!(nested_root.path, "Body/");
+        let full = TOverride::parse(&root.full_path(), "").unwrap();
+        assert_eq!((full.path.as_str(),full.component.as_str()), ("","Transform"));
+        assert!(TOverride::parse("position", "").is_none());
+        assert!(TOverride::parse("Body/Transform.", "").is_none());
+    }
+
+    #[test]
+    fn params_replace() {
+        let mut params = HashMap::default();
+        params.insert(SmolStr::new("a"), SmolStr::new("1"));
+        params.insert(SmolStr::new("name"), SmolStr::new("box"));
+        assert_eq!(replace_params("${a},${a},0", &params).unwrap(), "1,1,0");
+        assert_eq!(replace_params("res/${name}.mesh", &params).unwrap(), "res/box.mesh");
+        assert_eq!(replace_params("none", &params).unwrap(), "none");
+        assert!(replace_params("${b}", &params).is_err());
+        assert!(replace_params("${a", &params).is_err());
+        assert!(has_params("x${a}") && !has_params("$a"));
+    }
+}
@@ -0,0 +1,122 @@
+use std::io::Cursor;
+use quick_xml::{Writer, events::{BytesStart, BytesEnd, BytesText, Event}};
+use seija_app::ecs::{world::World, prelude::Entity};
+use seija_asset::AssetServer;
+use seija_core::anyhow::Result;
+use crate::{TEntity, TComponent, TComponentManager, types::{TEntityChildren, TTemplateEntity}};
+
+//把World中的entity子树保存成模板xml
+pub fn serialize_entity_xml(world:&World,entity:Entity) -> Result<String> {
+    let mgr = world.get_resource::<TComponentManager>().unwrap();
+    let server = world.get_resource::<AssetServer>().unwrap();
+    let tentity = mgr.serialize_entity(world, server, entity)?;
+    write_tmpl_entity(&tentity)
+}
+
+pub fn write_tmpl_entity(entity:&TEntity) -> Result<String> {
+    let mut writer = Writer::new_with_indent(Cursor::new(Vec::new()), b' ', 4);
+    write_entity(&mut writer, entity)?;
+    Ok(String::from_utf8(writer.into_inner().into_inner())?)
+}
+
+fn write_entity(writer:&mut Writer<Cursor<Vec<u8>>>,entity:&TEntity) -> Result<()> {
+    if let Some(base) = entity.base.as_ref() {
+        return write_template(writer, base);
+    }
+    let mut start = BytesStart::owned_name(b"Entity".to_vec());
+    if let Some(name) = entity.name.as_ref() {
+        start.push_attribute(("name",name.as_str()));
+    }
+    start.push_attribute(("layer",entity.layer.to_string().as_str()));
+    if let Some(tag) = entity.tag.as_ref() {
+        start.push_attribute(("tag",tag.as_str()));
+    }
+    //reader只识别<Entity>开始标签,空节点也不能写成<Entity/>
+    writer.write_event(Event::Start(start))?;
+    if !entity.params.is_empty() {
+        writer.write_event(Event::Start(BytesStart::borrowed_name(b"Params")))?;
+        for param in entity.params.iter() {
+            let mut param_start = BytesStart::borrowed_name(b"Param");
+            param_start.push_attribute(("name",param.name.as_str()));
+            if let Some(default) = param.default.as_ref() {
+                param_start.push_attribute(("default",default.as_str()));
+            }
+            writer.write_event(Event::Empty(param_start))?;
+        }
+        writer.write_event(Event::End(BytesEnd::borrowed(b"Params")))?;
+    }
+    if !entity.components.is_empty() {
+        writer.write_event(Event::Start(BytesStart::borrowed_name(b"Components")))?;
+        for component in entity.components.iter() {
+            write_component(writer, component)?;
+        }
+        writer.write_event(Event::End(BytesEnd::borrowed(b"Components")))?;
+    }
+    if !entity.children.is_empty() {
+        writer.write_event(Event::Start(BytesStart::borrowed_name(b"Children")))?;
+        for child in entity.children.iter() {
+            match child {
+                TEntityChildren::TEntity(e) => write_entity(writer, e)?,
+                TEntityChildren::Template(t) => write_template(writer, t)?
+            }
+        }
+        writer.write_event(Event::End(BytesEnd::borrowed(b"Children")))?;
+    }
+    writer.write_event(Event::End(BytesEnd::borrowed(b"Entity")))?;
+    Ok(())
+}
+
+fn write_template(writer:&mut Writer<Cursor<Vec<u8>>>,template:&TTemplateEntity) -> Result<()> {
+    let mut start = BytesStart::owned_name(b"Template".to_vec());
+    start.push_attribute(("res",template.res.as_str()));
+    if let Some(name) = template.name.as_ref() {
+        start.push_attribute(("name",name.as_str()));
+    }
+    start.push_attribute(("layer",template.layer.to_string().as_str()));
+    if let Some(tag) = template.tag.as_ref() {
+        start.push_attribute(("tag",tag.as_str()));
+    }
+    if template.components.is_empty() && template.params.is_empty() && template.overrides.is_empty() {
+        writer.write_event(Event::Empty(start))?;
+        return Ok(());
+    }
+    writer.write_event(Event::Start(start))?;
+    let mut params:Vec<_> = template.params.iter().collect();
+    params.sort_by_key(|(k,_)| k.as_str());
+    for (name,value) in params {
+        let mut param_start = BytesStart::borrowed_name(b"Param");
+        param_start.push_attribute(("name",name.as_str()));
+        param_start.push_attribute(("value",value.as_str()));
+        writer.write_event(Event::Empty(param_start))?;
+    }
+    for t_override in template.overrides.iter() {
+        let mut override_start = BytesStart::borrowed_name(b"Override");
+        override_start.push_attribute(("path",t_override.full_path().as_str()));
+        override_start.push_attribute(("value",t_override.value.as_str()));
+        writer.write_event(Event::Empty(override_start))?;
+    }
+    for component in template.components.iter() {
+        write_component(writer, component)?;
+    }
+    writer.write_event(Event::End(BytesEnd::borrowed(b"Template")))?;
+    Ok(())
+}
+
+fn write_component(writer:&mut Writer<Cursor<Vec<u8>>>,component:&TComponent) -> Result<()> {
+    let mut start = BytesStart::owned_name(component.typ.as_bytes().to_vec());
+    //属性按名字排序,保证同一个场景每次保存的结果一致
+    let mut attrs:Vec<_> = component.attrs.iter().filter(|(k,_)| k.as_str() != "innerText").collect();
+    attrs.sort_by_key(|(k,_)| k.as_str());
+    for (k,v) in attrs {
+        start.push_attribute((k.as_str(),v.as_str()));
+    }
+    match component.attrs.get("innerText") {
+        Some(text) => {
+            writer.write_event(Event::Start(start))?;
+            writer.write_event(Event::Text(BytesText::from_plain_str(text.as_str())))?;
+            writer.write_event(Event::End(BytesEnd::owned(component.typ.as_bytes().to_vec())))?;
+        }
+        None => { writer.write_event(Event::Empty(start))?; }
+    }
+    Ok(())
+}
@@ -0,0 +1,194 @@
+use std::collections::HashMap;
+use bevy_ecs::system::Resource;
+use seija_core::Rect;
+use crate::{FontSystem, swash::{CacheKey, SwashCache, SwashContent}};
+
+//字形在图集中的位置,left和top是相对字形原点的偏移(top向上为正)
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct AtlasGlyph {
+    pub x:u32,
+    pub y:u32,
+    pub width:u32,
+    pub height:u32,
+    pub left:i32,
+    pub top:i32,
+    //彩色字形(emoji)直接使用图集颜色,否则只使用alpha
+    pub is_color:bool
+}
+
+struct Shelf {
+    y:u32,
+    height:u32,
+    x:u32
+}
+
+//RGBA8的字形图集,按行(shelf)装箱,满了之后清空重建
+//重建后generation加1,之前取到的AtlasGlyph都失效,使用者需要重新取字形生成网格
+#[derive(Resource)]
+pub struct GlyphAtlas {
+    pub width:u32,
+    pub height:u32,
+    pub data:Vec<u8>,
+    shelves:Vec<Shelf>,
+    glyphs:HashMap<CacheKey,Option<AtlasGlyph>>,
+    dirty:Option<Rect<u32>>,
+    generation:u32
+}
+
+const PADDING:u32 = 1;
+
+impl GlyphAtlas {
+    pub fn new(width:u32,height:u32) -> Self {
+        GlyphAtlas {
+            width,
+            height,
+            data:vec![0u8;(width * height * 4) as usize],
+            shelves:vec![],
+            glyphs:HashMap::default(),
+            dirty:None,
+            generation:0
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.data.fill(0);
+        self.shelves.clear();
+        self.glyphs.clear();
+        self.dirty = Some(Rect { x:0,y:0,width:self.width,height:self.height });
+        self.generation += 1;
+    }
+
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+
+    //取出上次之后写入过的区域,用来更新贴图
+    pub fn take_dirty(&mut self) -> Option<Rect<u32>> {
+        self.dirty.take()
+    }
+
+    pub fn uv(&self,glyph:&AtlasGlyph) -> Rect<f32> {
+        Rect {
+            x:glyph.x as f32 / self.width as f32,
+            y:glyph.y as f32 / self.height as f32,
+            width:glyph.width as f32 / self.width as f32,
+            height:glyph.height as f32 / self.height as f32
+        }
+    }
+
+    //没有图像的字形返回None,图集满了时清空后再放,单个字形比图集还大时返回None
+    pub fn get_glyph(&mut self,font_system:&mut FontSystem,swash_cache:&mut SwashCache,key:CacheKey) -> Option<AtlasGlyph> {
+        if let Some(glyph) = self.glyphs.get(&key) {
+            return *glyph;
+        }
+        let glyph = swash_cache.get_image(font_system, key).and_then(|image| {
+            let width = image.placement.width;
+            let height = image.placement.height;
+            if width == 0 || height == 0 {
+                return None;
+            }
+            let (x,y) = match self.allocate_or_evict(width, height) {
+                Some(v) => v,
+                None => { log::warn!("glyph is larger than atlas {}x{}",width,height); return None; }
+            };
+            let is_color = matches!(image.content,SwashContent::Color);
+            for row in 0..height {
+                for col in 0..width {
+                    let src = (row * width + col) as usize;
+                    let dst = (((y + row) * self.width + x + col) * 4) as usize;
+                    let pixel = match image.content {
+                        SwashContent::Mask => [255,255,255,image.data[src]],
+                        SwashContent::Color => [image.data[src * 4],image.data[src * 4 + 1],image.data[src * 4 + 2],image.data[src * 4 + 3]],
+                        //Format::Alpha时不会出现,按三个通道平均处理
+                        SwashContent::SubpixelMask => {
+                            let a = (image.data[src * 4] as u32 + image.data[src * 4 + 1] as u32 + image.data[src * 4 + 2] as u32) / 3;
+                            [255,255,255,a as u8]
+                        }
+                    };
+                    self.data[dst..dst + 4].copy_from_slice(&pixel);
+                }
+            }
+            self.mark_dirty(x, y, width, height);
+            Some(AtlasGlyph { x,y,width,height,left:image.placement.left,top:image.placement.top,is_color })
+        });
+        self.glyphs.insert(key, glyph);
+        glyph
+    }
+
+    fn allocate_or_evict(&mut self,width:u32,height:u32) -> Option<(u32,u32)> {
+        if let Some(pos) = self.allocate(width, height) {
+            return Some(pos);
+        }
+        if self.shelves.is_empty() {
+            return None;
+        }
+        log::info!("glyph atlas is full, rebuild");
+        self.clear();
+        self.allocate(width, height)
+    }
+
+    fn allocate(&mut self,width:u32,height:u32) -> Option<(u32,u32)> {
+        let w = width + PADDING;
+        let h = height + PADDING;
+        if w > self.width || h > self.height {
+            return None;
+        }
+        //放到高度够用且最矮的行里
+        let best = self.shelves.iter_mut().filter(|s| s.height >= h && s.x + w <= self.width).min_by_key(|s| s.height);
+        if let Some(shelf) = best {
+            let x = shelf.x;
+            shelf.x += w;
+            return Some((x,shelf.y));
+        }
+        let y = self.shelves.last().map(|s| s.y + s.height).unwrap_or(0);
+        if y + h > self.height {
+            return None;
+        }
+        self.shelves.push(Shelf { y,height:h,x:w });
+        Some((0,y))
+    }
+
+    fn mark_dirty(&mut self,x:u32,y:u32,width:u32,height:u32) {
+        self.dirty = Some(match self.dirty.take() {
+            Some(r) => {
+                let min_x = r.x.min(x);
+                let min_y = r.y.min(y);
+                let max_x = (r.x + r.width).max(x + width);
+                let max_y = (r.y + r.height).max(y + height);
+                Rect { x:min_x,y:min_y,width:max_x - min_x,height:max_y - min_y }
+            },
+            None => Rect { x,y,width,height }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GlyphAtlas;
+
+    #[test]
+    fn shelf_allocate() {
+        let mut atlas = GlyphAtlas::new(32, 32);
+        assert_eq!(atlas.allocate(10, 10), Some((0,0)));
+        assert_eq!(atlas.allocate(10, 8), Some((11,0)));
+        assert_eq!(atlas.allocate(12, 12), Some((0,11)));
+        assert_eq!(atlas.allocate(9, 5), Some((22,0)));
+        //第一行剩下的宽度不够,放到第二行
+        assert_eq!(atlas.allocate(9, 5), Some((13,11)));
+        assert_eq!(atlas.allocate(20, 20), None);
+    }
+
+    #[test]
+    fn evict_when_full() {
+        let mut atlas = GlyphAtlas::new(16, 16);
+        assert_eq!(atlas.allocate_or_evict(10, 10), Some((0,0)));
+        atlas.take_dirty();
+        assert_eq!(atlas.generation(), 0);
+        assert_eq!(atlas.allocate_or_evict(10, 10), Some((0,0)));
+        assert_eq!(atlas.generation(), 1);
+        assert_eq!(atlas.take_dirty().map(|r| (r.width,r.height)), Some((16,16)));
+        //比图集还大的字形不会触发重建
+        assert_eq!(atlas.allocate_or_evict(20, 20), None);
+        assert_eq!(atlas.generation(), 1);
+    }
+}
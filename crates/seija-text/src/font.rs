@@ -3,6 +3,7 @@ use std::sync::Arc;
 pub struct Font {
     swash: (u32, swash::CacheKey),
     data:Arc<dyn AsRef<[u8]> + Send + Sync>,
+    index:u32,
     id: fontdb::ID,
 }
 
@@ -18,10 +19,15 @@ impl Font {
         Some(Font {
             swash:swash_key,
             data,
+            index:info.index,
             id:info.id
         })
     }
 
+    pub fn id(&self) -> fontdb::ID {
+        self.id
+    }
+
     pub fn as_swash_font(&self) -> swash::FontRef<'_> {
         let swash = &self.swash;
         let bytes:&[u8] = self.data.as_ref().as_ref();
@@ -31,4 +37,13 @@ impl Font {
             key: swash.1,
         }
     }
-}
\ No newline at end of file
+
+    //rustybuzz的Face只是对数据的解析,需要时再创建
+    pub fn as_rustybuzz(&self) -> Option<rustybuzz::Face<'_>> {
+        rustybuzz::Face::from_slice(self.data.as_ref().as_ref(), self.index)
+    }
+
+    pub fn has_char(&self,chr:char) -> bool {
+        self.as_swash_font().charmap().map(chr) != 0
+    }
+}
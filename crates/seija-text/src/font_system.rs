@@ -2,27 +2,53 @@ use std::{collections::HashMap, sync::Arc};
 use bevy_ecs::system::Resource;
 use crate::font::Font;
 
+//主字体缺字时依次尝试的字体,覆盖中日韩和emoji,可以用TextConfig.fallbacks替换
+pub const DEFAULT_FALLBACKS:&[&str] = &[
+    "Microsoft YaHei",
+    "PingFang SC",
+    "Noto Sans CJK SC",
+    "Source Han Sans SC",
+    "Noto Sans CJK JP",
+    "Noto Sans CJK KR",
+    "Malgun Gothic",
+    "Noto Sans Arabic",
+    "Noto Sans Hebrew",
+    "Segoe UI",
+    "Segoe UI Emoji",
+    "Apple Color Emoji",
+    "Noto Color Emoji",
+    "DejaVu Sans",
+];
+
 #[derive(Resource)]
 pub struct FontSystem {
    pub db:fontdb::Database,
+   //按顺序查找的后备字体族
+   pub fallbacks:Vec<String>,
    font_cache: HashMap<fontdb::ID, Option<Arc<Font>>>,
+   fallback_cache: HashMap<fontdb::ID, Vec<fontdb::ID>>,
 }
 
 impl Default for FontSystem {
     fn default() -> Self {
-        FontSystem { 
-            db:fontdb::Database::default(),
-            font_cache:HashMap::default()
-        }
+        FontSystem::new(DEFAULT_FALLBACKS.iter().map(|v| v.to_string()).collect())
     }
 }
 
 impl FontSystem {
+    pub fn new(fallbacks:Vec<String>) -> Self {
+        FontSystem {
+            db:fontdb::Database::default(),
+            fallbacks,
+            font_cache:HashMap::default(),
+            fallback_cache:HashMap::default()
+        }
+    }
 
     pub fn query_family(&self,family_name:&str) -> Option<fontdb::ID> {
-        self.db.query(&fontdb::Query { 
+        self.db.query(&fontdb::Query {
             families: &[fontdb::Family::Name(family_name)],
-            ..Default::default() 
+            ..Default::default()
          })
     }
 
@@ -36,4 +62,46 @@ impl FontSystem {
             }
         }).clone()
     }
-}
\ No newline at end of file
+
+    //加载字体后后备链可能变化,直接操作db加载时需要调用clear_fallback_cache
+    pub fn load_system_fonts(&mut self) {
+        self.db.load_system_fonts();
+        self.fallback_cache.clear();
+    }
+
+    pub fn clear_fallback_cache(&mut self) {
+        self.fallback_cache.clear();
+    }
+
+    pub fn set_fallbacks(&mut self,fallbacks:Vec<String>) {
+        self.fallbacks = fallbacks;
+        self.fallback_cache.clear();
+    }
+
+    //主字体在前,后面是能找到的后备字体
+    pub fn fallback_chain(&mut self,primary:fontdb::ID) -> Vec<fontdb::ID> {
+        if let Some(chain) = self.fallback_cache.get(&primary) {
+            return chain.clone();
+        }
+        let mut chain = vec![primary];
+        for family in self.fallbacks.iter() {
+            if let Some(id) = self.query_family(family) {
+                if !chain.contains(&id) {
+                    chain.push(id);
+                }
+            }
+        }
+        self.fallback_cache.insert(primary, chain.clone());
+        chain
+    }
+
+    //从后备链中找第一个包含这个字符的字体,都没有时返回主字体
+    pub fn font_for_char(&mut self,primary:fontdb::ID,chr:char) -> fontdb::ID {
+        for id in self.fallback_chain(primary) {
+            if self.get_font(id).map(|f| f.has_char(chr)).unwrap_or(false) {
+                return id;
+            }
+        }
+        primary
+    }
+}
@@ -0,0 +1,231 @@
+use unicode_bidi::{BidiInfo, Level};
+use crate::{FontSystem, shape::{shape_paragraph, ShapeWord}, swash::CacheKey};
+
+#[derive(Debug,Clone,Copy,PartialEq,Eq,Default)]
+#[repr(u8)]
+pub enum TextAlign {
+    //从左到右的段落左对齐,从右到左的段落右对齐
+    #[default]
+    Auto,
+    Left,
+    Center,
+    Right
+}
+
+#[derive(Debug,Clone)]
+pub struct TextStyle {
+    pub font_id:fontdb::ID,
+    pub font_size:f32,
+    //行高相对字体默认行高的倍数
+    pub line_height:f32,
+    pub max_width:Option<f32>,
+    pub align:TextAlign
+}
+
+impl TextStyle {
+    pub fn new(font_id:fontdb::ID,font_size:f32) -> Self {
+        TextStyle { font_id,font_size,line_height:1f32,max_width:None,align:TextAlign::Auto }
+    }
+}
+
+//排版后的字形,x是相对行首的位置,y是相对基线向下的偏移,start和end是在整段文字中的字节范围
+#[derive(Debug,Clone)]
+pub struct LayoutGlyph {
+    pub x:f32,
+    pub y:f32,
+    pub advance:f32,
+    pub font_id:fontdb::ID,
+    pub glyph_id:u16,
+    pub font_size:f32,
+    pub start:usize,
+    pub end:usize,
+    pub rtl:bool
+}
+
+impl LayoutGlyph {
+    pub fn cache_key(&self) -> CacheKey {
+        CacheKey { font_id:self.font_id,glyph_id:self.glyph_id,font_size_bits:self.font_size.to_bits() }
+    }
+}
+
+#[derive(Debug,Clone)]
+pub struct LayoutLine {
+    pub glyphs:Vec<LayoutGlyph>,
+    //不包含行尾空白的宽度
+    pub width:f32,
+    //基线到整个排版顶部的距离
+    pub baseline:f32,
+    pub ascent:f32,
+    pub descent:f32,
+    pub rtl:bool
+}
+
+#[derive(Debug,Clone,Default)]
+pub struct TextLayout {
+    pub lines:Vec<LayoutLine>,
+    pub width:f32,
+    pub height:f32
+}
+
+impl TextLayout {
+    pub fn glyphs(&self) -> impl Iterator<Item = (&LayoutLine,&LayoutGlyph)> {
+        self.lines.iter().flat_map(|line| line.glyphs.iter().map(move |g| (line,g)))
+    }
+}
+
+//按'\n'分段,每段整形后按宽度自动换行,再把每行按bidi级别重排成显示顺序
+pub fn layout_text(font_system:&mut FontSystem,text:&str,style:&TextStyle) -> TextLayout {
+    let mut lines:Vec<LayoutLine> = vec![];
+    let mut offset = 0;
+    let mut y = 0f32;
+    for para in text.split('\n') {
+        let shape = shape_paragraph(font_system, para, style.font_id, style.font_size);
+        for word_line in wrap_words(&shape.words, style.max_width) {
+            let mut line = build_line(font_system, &word_line, offset, shape.rtl, style);
+            line.baseline = y + line.ascent * style.line_height;
+            y += (line.ascent + line.descent) * style.line_height;
+            lines.push(line);
+        }
+        offset += para.len() + 1;
+    }
+
+    let width = style.max_width.unwrap_or_else(|| lines.iter().map(|l| l.width).fold(0f32, f32::max));
+    for line in lines.iter_mut() {
+        let align_x = match style.align {
+            TextAlign::Auto if line.rtl => width - line.width,
+            TextAlign::Auto | TextAlign::Left => 0f32,
+            TextAlign::Center => (width - line.width) * 0.5f32,
+            TextAlign::Right => width - line.width
+        };
+        for glyph in line.glyphs.iter_mut() {
+            glyph.x += align_x;
+        }
+    }
+    TextLayout { lines,width,height:y }
+}
+
+fn wrap_words(words:&[ShapeWord],max_width:Option<f32>) -> Vec<Vec<&ShapeWord>> {
+    let mut lines:Vec<Vec<&ShapeWord>> = vec![vec![]];
+    let mut width = 0f32;
+    for word in words.iter() {
+        let cur_line = lines.last_mut().unwrap();
+        if let Some(max_width) = max_width {
+            if !word.blank && word.can_break && !cur_line.is_empty() && width + word.width > max_width {
+                lines.push(vec![]);
+                width = 0f32;
+            }
+        }
+        let cur_line = lines.last_mut().unwrap();
+        //自动换行后行首的空白不显示
+        if word.blank && cur_line.is_empty() && width == 0f32 && lines.len() > 1 {
+            continue;
+        }
+        width += word.width;
+        cur_line.push(word);
+    }
+    lines
+}
+
+fn build_line(font_system:&mut FontSystem,words:&[&ShapeWord],offset:usize,rtl:bool,style:&TextStyle) -> LayoutLine {
+    let (mut ascent,mut descent) = font_metrics(font_system, style.font_id, style.font_size);
+    let content_len = words.iter().rposition(|w| !w.blank).map(|i| i + 1).unwrap_or(0);
+    let width = words[..content_len].iter().map(|w| w.width).sum();
+
+    let levels:Vec<Level> = words.iter().map(|w| w.level).collect();
+    let mut glyphs:Vec<LayoutGlyph> = vec![];
+    let mut x = 0f32;
+    let visual = if levels.is_empty() { vec![] } else { BidiInfo::reorder_visual(&levels) };
+    for index in visual {
+        let word = words[index];
+        for glyph in word.glyphs.iter() {
+            if glyph.font_id != style.font_id {
+                let (a,d) = font_metrics(font_system, glyph.font_id, style.font_size);
+                ascent = ascent.max(a);
+                descent = descent.max(d);
+            }
+            glyphs.push(LayoutGlyph {
+                x:x + glyph.x_offset,
+                y:-glyph.y_offset,
+                advance:glyph.x_advance,
+                font_id:glyph.font_id,
+                glyph_id:glyph.glyph_id,
+                font_size:style.font_size,
+                start:offset + glyph.start,
+                end:offset + glyph.end,
+                rtl:word.level.is_rtl()
+            });
+            x += glyph.x_advance;
+        }
+    }
+    //从右到左的行尾空白在左边,去掉它占的宽度
+    if rtl {
+        let blank_width:f32 = words[content_len..].iter().map(|w| w.width).sum();
+        for glyph in glyphs.iter_mut() {
+            glyph.x -= blank_width;
+        }
+    }
+    LayoutLine { glyphs,width,baseline:0f32,ascent,descent,rtl }
+}
+
+fn font_metrics(font_system:&mut FontSystem,font_id:fontdb::ID,font_size:f32) -> (f32,f32) {
+    match font_system.get_font(font_id) {
+        Some(font) => {
+            let metrics = font.as_swash_font().metrics(&[]).scale(font_size);
+            (metrics.ascent,metrics.descent + metrics.leading)
+        },
+        None => (font_size,0f32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::FontSystem;
+    use super::{layout_text, TextStyle};
+
+    //Fira Mono是等宽字体,每个字符的宽度是0.6倍字号
+    fn font_system() -> (FontSystem,fontdb::ID) {
+        let mut font_system = FontSystem::new(vec![]);
+        font_system.db.load_font_data(include_bytes!("../../../seija-examples/res/ui/FiraMono-Medium.ttf").to_vec());
+        let font_id = font_system.query_family("Fira Mono").unwrap();
+        (font_system,font_id)
+    }
+
+    #[test]
+    fn wrap_max_width() {
+        let (mut font_system,font_id) = font_system();
+        let mut style = TextStyle::new(font_id, 10f32);
+        style.max_width = Some(60f32);
+        let layout = layout_text(&mut font_system, "aaa bbb ccc", &style);
+        assert_eq!(layout.lines.len(), 2);
+        assert_eq!(layout.width, 60f32);
+        //行尾空白留在第一行但不计入宽度
+        assert_eq!(layout.lines[0].glyphs.len(), 8);
+        assert!((layout.lines[0].width - 42f32).abs() < 0.001f32);
+        let second = &layout.lines[1];
+        assert_eq!(second.glyphs.iter().map(|g| g.start).collect::<Vec<_>>(), vec![8,9,10]);
+        assert_eq!(second.glyphs[0].x, 0f32);
+        assert!(second.baseline > layout.lines[0].baseline);
+
+        style.max_width = None;
+        assert_eq!(layout_text(&mut font_system, "aaa bbb ccc", &style).lines.len(), 1);
+    }
+
+    #[test]
+    fn bidi_reorder() {
+        let (mut font_system,font_id) = font_system();
+        let layout = layout_text(&mut font_system, "ab \u{5d0}\u{5d1} cd", &TextStyle::new(font_id, 10f32));
+        assert_eq!(layout.lines.len(), 1);
+        let line = &layout.lines[0];
+        assert!(!line.rtl);
+        //从左到右的段落中希伯来文单词内部反过来显示
+        assert_eq!(line.glyphs.iter().map(|g| g.start).collect::<Vec<_>>(), vec![0,1,2,5,3,7,8,9]);
+        assert_eq!(line.glyphs.iter().map(|g| g.rtl).collect::<Vec<_>>(), vec![false,false,false,true,true,false,false,false]);
+        assert!(line.glyphs.windows(2).all(|g| g[0].x < g[1].x));
+
+        let layout = layout_text(&mut font_system, "\u{5d0}\u{5d1} cd", &TextStyle::new(font_id, 10f32));
+        let line = &layout.lines[0];
+        assert!(line.rtl);
+        //从右到左的段落中拉丁文单词在左边
+        assert_eq!(line.glyphs.iter().map(|g| g.start).collect::<Vec<_>>(), vec![5,6,4,2,0]);
+    }
+}
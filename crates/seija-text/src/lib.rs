@@ -1,18 +1,41 @@
-pub use font_system::FontSystem;
+pub use font_system::{FontSystem, DEFAULT_FALLBACKS};
+pub use swash::{SwashCache, CacheKey};
+pub use atlas::{GlyphAtlas, AtlasGlyph};
 use seija_app::{IModule, App};
 use seija_core::CoreStage;
 use system::update_text_size_system;
 mod font_system;
 mod font;
 pub mod text;
+pub mod shape;
+pub mod layout;
 mod swash;
+mod atlas;
 mod system;
 
-pub struct TextModule;
+#[derive(Debug,Clone)]
+pub struct TextConfig {
+    //主字体缺字时按顺序查找的字体族
+    pub fallbacks:Vec<String>,
+    //字形图集的宽高,放不下时清空重建
+    pub atlas_size:u32
+}
+
+impl Default for TextConfig {
+    fn default() -> Self {
+        TextConfig { fallbacks:DEFAULT_FALLBACKS.iter().map(|v| v.to_string()).collect(),atlas_size:1024 }
+    }
+}
+
+//只负责整形排版和字形图集,seija-ui的Text还是通过glyph_brush渲染
+#[derive(Default)]
+pub struct TextModule(pub TextConfig);
 
 impl IModule for TextModule {
     fn init(&mut self,app:&mut App) {
-        app.add_resource(FontSystem::default());
+        app.add_resource(FontSystem::new(self.0.fallbacks.clone()));
+        app.add_resource(SwashCache::new());
+        app.add_resource(GlyphAtlas::new(self.0.atlas_size, self.0.atlas_size));
         app.add_system(CoreStage::LateUpdate, update_text_size_system);
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,173 @@
+use std::ops::Range;
+use rustybuzz::{UnicodeBuffer, Direction};
+use unicode_bidi::{BidiInfo, Level};
+use unicode_linebreak::linebreaks;
+use crate::FontSystem;
+
+//整形后的一个字形,start和end是对应字符在段落中的字节范围,长度单位都是像素
+#[derive(Debug,Clone)]
+pub struct ShapeGlyph {
+    pub start:usize,
+    pub end:usize,
+    pub x_advance:f32,
+    pub y_advance:f32,
+    pub x_offset:f32,
+    pub y_offset:f32,
+    pub font_id:fontdb::ID,
+    pub glyph_id:u16
+}
+
+//换行的最小单位,glyphs已经是从左到右的显示顺序
+#[derive(Debug,Clone)]
+pub struct ShapeWord {
+    pub range:Range<usize>,
+    pub level:Level,
+    //只包含空白
+    pub blank:bool,
+    //是否允许在这个词前面换行,bidi方向切换处不一定是换行点
+    pub can_break:bool,
+    pub width:f32,
+    pub glyphs:Vec<ShapeGlyph>
+}
+
+//不包含换行符的一段文字
+#[derive(Debug,Clone)]
+pub struct ShapeParagraph {
+    pub rtl:bool,
+    pub words:Vec<ShapeWord>
+}
+
+pub fn shape_paragraph(font_system:&mut FontSystem,text:&str,font_id:fontdb::ID,font_size:f32) -> ShapeParagraph {
+    let bidi = BidiInfo::new(text, None);
+    let rtl = bidi.paragraphs.first().map(|p| p.level.is_rtl()).unwrap_or(false);
+    let break_points:Vec<usize> = linebreaks(text).map(|(index,_)| index).collect();
+    //换行点和bidi级别变化的位置都要切分
+    let mut cuts:Vec<usize> = break_points.clone();
+    for index in 1..text.len() {
+        if text.is_char_boundary(index) && bidi.levels[index] != bidi.levels[index - 1] {
+            cuts.push(index);
+        }
+    }
+    cuts.sort_unstable();
+    cuts.dedup();
+
+    let mut words:Vec<ShapeWord> = vec![];
+    let mut start = 0;
+    for end in cuts {
+        if end <= start { continue; }
+        let level = bidi.levels[start];
+        let can_break = start == 0 || break_points.binary_search(&start).is_ok();
+        let segment = &text[start..end];
+        //词后面的空白单独作为一个词,行尾的空白不计入宽度
+        let trimmed = start + segment.trim_end().len();
+        if trimmed > start {
+            words.push(shape_word(font_system, text, start..trimmed, level, can_break, false, font_id, font_size));
+        }
+        if trimmed < end {
+            words.push(shape_word(font_system, text, trimmed..end, level, trimmed == start && can_break, true, font_id, font_size));
+        }
+        start = end;
+    }
+    ShapeParagraph { rtl,words }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn shape_word(font_system:&mut FontSystem,text:&str,range:Range<usize>,level:Level,can_break:bool,blank:bool,font_id:fontdb::ID,font_size:f32) -> ShapeWord {
+    //按字体切分,当前字体能显示的字符继续使用当前字体,这样组合符号和emoji序列不会被拆开
+    let mut font_runs:Vec<(fontdb::ID,Range<usize>)> = vec![];
+    for (index,chr) in text[range.clone()].char_indices() {
+        let index = range.start + index;
+        let end = index + chr.len_utf8();
+        if let Some((run_font,run)) = font_runs.last_mut() {
+            let keep = chr.is_whitespace() || chr.is_control() || font_system.get_font(*run_font).map(|f| f.has_char(chr)).unwrap_or(false);
+            if keep {
+                run.end = end;
+                continue;
+            }
+        }
+        let char_font = font_system.font_for_char(font_id, chr);
+        match font_runs.last_mut() {
+            Some((run_font,run)) if *run_font == char_font => run.end = end,
+            _ => font_runs.push((char_font,index..end))
+        }
+    }
+    //从右到左时字体段也要反过来排
+    if level.is_rtl() {
+        font_runs.reverse();
+    }
+    let mut glyphs:Vec<ShapeGlyph> = vec![];
+    for (run_font,run) in font_runs {
+        shape_run(font_system, text, run, level, run_font, font_size, &mut glyphs);
+    }
+    let width = glyphs.iter().map(|g| g.x_advance).sum();
+    ShapeWord { range,level,blank,can_break,width,glyphs }
+}
+
+fn shape_run(font_system:&mut FontSystem,text:&str,range:Range<usize>,level:Level,font_id:fontdb::ID,font_size:f32,glyphs:&mut Vec<ShapeGlyph>) {
+    let font = match font_system.get_font(font_id) {
+        Some(v) => v,
+        None => { log::warn!("not found font {:?}", font_id); return; }
+    };
+    let face = match font.as_rustybuzz() {
+        Some(v) => v,
+        None => { log::warn!("rustybuzz parse font error {:?}", font_id); return; }
+    };
+    let mut buffer = UnicodeBuffer::new();
+    buffer.push_str(&text[range.clone()]);
+    buffer.set_direction(if level.is_rtl() { Direction::RightToLeft } else { Direction::LeftToRight });
+    buffer.guess_segment_properties();
+    //默认开启连字和字距调整
+    let glyph_buffer = rustybuzz::shape(&face, &[], buffer);
+    let scale = font_size / face.units_per_em() as f32;
+
+    let infos = glyph_buffer.glyph_infos();
+    let mut clusters:Vec<usize> = infos.iter().map(|info| info.cluster as usize).collect();
+    clusters.sort_unstable();
+    clusters.dedup();
+    for (info,pos) in infos.iter().zip(glyph_buffer.glyph_positions().iter()) {
+        let cluster = info.cluster as usize;
+        //一个cluster对应的字符到下一个cluster为止
+        let next = clusters.iter().find(|c| **c > cluster).copied().unwrap_or(range.len());
+        glyphs.push(ShapeGlyph {
+            start:range.start + cluster,
+            end:range.start + next,
+            x_advance:pos.x_advance as f32 * scale,
+            y_advance:pos.y_advance as f32 * scale,
+            x_offset:pos.x_offset as f32 * scale,
+            y_offset:pos.y_offset as f32 * scale,
+            font_id,
+            glyph_id:info.glyph_id as u16
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::FontSystem;
+    use super::shape_paragraph;
+
+    #[test]
+    fn fallback_font_runs() {
+        let mut font_system = FontSystem::new(vec!["VonwaonBitmap 16px".into()]);
+        font_system.db.load_font_data(include_bytes!("../../../seija-examples/res/ui/FiraMono-Medium.ttf").to_vec());
+        font_system.db.load_font_data(include_bytes!("../../../seija-examples/res/ui/VonwaonBitmap-16px.ttf").to_vec());
+        let fira = font_system.query_family("Fira Mono").unwrap();
+        let vonwaon = font_system.query_family("VonwaonBitmap 16px").unwrap();
+        assert_eq!(font_system.fallback_chain(fira), vec![fira,vonwaon]);
+
+        //Fira Mono没有汉字,汉字使用后备字体
+        let shape = shape_paragraph(&mut font_system, "ab 中文", fira, 16f32);
+        assert!(!shape.rtl);
+        let fonts:Vec<_> = shape.words.iter().flat_map(|w| w.glyphs.iter().map(|g| (g.start,g.font_id))).collect();
+        assert_eq!(fonts, vec![(0,fira),(1,fira),(2,fira),(3,vonwaon),(6,vonwaon)]);
+        //汉字之间可以换行
+        assert_eq!(shape.words.iter().map(|w| (w.range.clone(),w.blank,w.can_break)).collect::<Vec<_>>(),
+                   vec![(0..2,false,true),(2..3,true,false),(3..6,false,true),(6..9,false,true)]);
+        assert!((shape.words[0].width - 16f32 * 0.6f32 * 2f32).abs() < 0.001f32);
+
+        font_system.set_fallbacks(vec![]);
+        let shape = shape_paragraph(&mut font_system, "中", fira, 16f32);
+        assert_eq!(shape.words[0].glyphs[0].font_id, fira);
+        assert_eq!(shape.words[0].glyphs[0].glyph_id, 0);
+    }
+}
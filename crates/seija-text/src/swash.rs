@@ -21,6 +21,12 @@ pub struct SwashCache {
     pub image_cache: HashMap<CacheKey, Option<SwashImage>>,
 }
 
+impl Default for SwashCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl SwashCache {
     pub fn new() -> Self {
         Self {
@@ -29,8 +35,10 @@ impl SwashCache {
         }
     }
 
-    pub fn get_image(&self,font_system:&mut FontSystem,cache_key:CacheKey) {
-
+    //光栅化的结果会缓存,空格这类没有图像的字形返回None
+    pub fn get_image(&mut self,font_system:&mut FontSystem,cache_key:CacheKey) -> Option<&SwashImage> {
+        let context = &mut self.context;
+        self.image_cache.entry(cache_key).or_insert_with(|| swash_image(font_system, context, cache_key)).as_ref()
     }
 }
 
@@ -43,11 +51,10 @@ fn swash_image(font_system: &mut FontSystem,context: &mut ScaleContext,cache_key
     let mut scaler = context.builder(font.as_swash_font())
                             .size(f32::from_bits(cache_key.font_size_bits))
                             .hint(true).build();
-    
+
     Render::new(&[
         Source::ColorOutline(0),
         Source::ColorBitmap(StrikeWith::BestFit),
         Source::Outline,
-    ]).format(Format::Alpha).render(&mut scaler, cache_key.glyph_id);
-    None
-}
\ No newline at end of file
+    ]).format(Format::Alpha).render(&mut scaler, cache_key.glyph_id)
+}
@@ -1,14 +1,27 @@
 use bevy_ecs::prelude::Component;
 use seija_core::math::Vec4;
+use crate::layout::{TextAlign, TextLayout};
 
+//update_text_size_system排版后记录大小和TextLayout,绘制时用layout中的字形从GlyphAtlas取图像
 #[derive(Component)]
 pub struct Text {
     pub color:Vec4,
     pub font_size:u32,
     pub need_calc_size:bool,
     pub x_size:f32,
+    pub y_size:f32,
+    //设置后超过宽度自动换行
+    pub max_width:Option<f32>,
+    pub align:TextAlign,
     pub font_id:Option<fontdb::ID>,
     pub text:String,
+    pub(crate) layout:Option<TextLayout>
+}
+
+impl Text {
+    pub fn layout(&self) -> Option<&TextLayout> {
+        self.layout.as_ref()
+    }
 }
 
 impl Default for Text {
@@ -19,7 +32,11 @@ impl Default for Text {
             font_id: None,
             need_calc_size:false,
             x_size:0f32,
-            text: String::default() 
+            y_size:0f32,
+            max_width:None,
+            align:TextAlign::Auto,
+            text: String::default(),
+            layout:None
         }
     }
-}
\ No newline at end of file
+}
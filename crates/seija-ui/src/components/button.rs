@@ -0,0 +1,115 @@
+use bevy_ecs::event::{EventReader, EventWriter};
+use bevy_ecs::prelude::{Component, Entity};
+use bevy_ecs::query::{Added, Changed, Or};
+use bevy_ecs::system::{Query, Commands, Res, ParamSet};
+use seija_core::reflect::Reflect;
+use seija_input::Input as SysInput;
+use crate::event::{UIEvent, UIEventType};
+use super::sprite::Sprite;
+use super::widget::{WidgetEvent, add_event_type};
+
+#[derive(Debug,Clone,Copy,PartialEq,Eq,Default)]
+#[repr(u8)]
+pub enum ButtonState {
+    #[default]
+    Normal,
+    Hover,
+    Pressed,
+    Disabled
+}
+
+//按钮,不同状态下切换同一个图集中的Sprite
+#[derive(Component,Reflect,Debug,Clone)]
+#[repr(C)]
+pub struct Button {
+    pub normal:usize,
+    pub hover:usize,
+    pub pressed:usize,
+    pub disabled:usize,
+    pub interactable:bool,
+    #[reflect(skip)]
+    pub(crate) is_hover:bool,
+    #[reflect(skip)]
+    pub(crate) is_pressed:bool
+}
+
+impl Default for Button {
+    fn default() -> Self { Button::new(0, 0, 0, 0) }
+}
+
+impl Button {
+    pub fn new(normal:usize,hover:usize,pressed:usize,disabled:usize) -> Button {
+        Button { normal,hover,pressed,disabled,interactable:true,is_hover:false,is_pressed:false }
+    }
+
+    pub fn state(&self) -> ButtonState {
+        if !self.interactable {
+            ButtonState::Disabled
+        } else if self.is_pressed {
+            ButtonState::Pressed
+        } else if self.is_hover {
+            ButtonState::Hover
+        } else {
+            ButtonState::Normal
+        }
+    }
+
+    pub fn sprite_index(&self) -> usize {
+        match self.state() {
+            ButtonState::Normal => self.normal,
+            ButtonState::Hover => self.hover,
+            ButtonState::Pressed => self.pressed,
+            ButtonState::Disabled => self.disabled
+        }
+    }
+}
+
+pub fn button_system(mut commands:Commands,
+                     mut sets:ParamSet<(Query<&mut Button>,Query<Entity,Or<(Added<Button>,Changed<Button>)>>,Query<Entity,Added<Button>>)>,
+                     mut sprites:Query<&mut Sprite>,
+                     mut ui_events:EventReader<UIEvent>,
+                     mut widget_events:EventWriter<WidgetEvent>,
+                     sys_input:Res<SysInput>) {
+    let event_type = UIEventType::TOUCH_START | UIEventType::TOUCH_END | UIEventType::MOUSE_ENTER | UIEventType::MOUSE_LEAVE | UIEventType::CLICK | UIEventType::SUBMIT;
+    for entity in sets.p2().iter() {
+        add_event_type(entity, event_type, UIEventType::CLICK | UIEventType::SUBMIT, &mut commands);
+    }
+
+    //在按钮外松开时不会收到TOUCH_END
+    if sys_input.has_mouse_up() {
+        for mut button in sets.p0().iter_mut() {
+            if button.is_pressed {
+                button.is_pressed = false;
+            }
+        }
+    }
+
+    for ev in ui_events.iter() {
+        if let Ok(mut button) = sets.p0().get_mut(ev.entity) {
+            if !button.interactable { continue; }
+            if ev.event_type.contains(UIEventType::MOUSE_ENTER) {
+                button.is_hover = true;
+            } else if ev.event_type.contains(UIEventType::MOUSE_LEAVE) {
+                button.is_hover = false;
+            } else if ev.event_type.contains(UIEventType::TOUCH_START) {
+                button.is_pressed = true;
+            } else if ev.event_type.contains(UIEventType::TOUCH_END) {
+                button.is_pressed = false;
+            } else if ev.event_type.intersects(UIEventType::CLICK | UIEventType::SUBMIT) && !ev.handled {
+                widget_events.send(WidgetEvent::Click(ev.entity));
+            }
+        }
+    }
+
+    let changed = sets.p1().iter().collect::<Vec<_>>();
+    for entity in changed {
+        if let Ok(button) = sets.p0().get(entity) {
+            if let Ok(mut sprite) = sprites.get_mut(entity) {
+                let index = button.sprite_index();
+                if sprite.sprite_index != index {
+                    sprite.sprite_index = index;
+                }
+            }
+        }
+    }
+}
@@ -2,16 +2,18 @@ use std::sync::Arc;
 
 use bevy_ecs::{prelude::{Component, Entity}, system::{Query, Commands}};
 use seija_asset::{HandleId, Assets, AssetServer, Handle};
-use seija_core::{math::{Mat4, Vec4, Vec4Swizzles}, info::EStateInfo};
+use seija_core::{math::{Mat4, Vec4, Vec4Swizzles}, info::EStateInfo, reflect::Reflect};
 use seija_render::{resource::{Mesh, MeshAttributeType, Indices}, material::{Material, MaterialDef}};
 use seija_transform::{hierarchy::{Children, Parent}, Transform};
-use crate::{render::UIRender2D, system::UIRenderRoot};
+use crate::{render::UIRender2D, system::UIRenderRoot, text::SdfMaterialParams};
 use  wgpu::PrimitiveTopology;
 pub const Z_SCALE: f32 = 0.00001;
 
-#[derive(Component,Default)]
+#[derive(Component,Reflect,Default)]
 pub struct Canvas {
+    #[reflect(skip)]
     pub(crate) draw_calls:Vec<UIDrawCall>,
+    #[reflect(name = "isClip")]
     pub is_clip:bool,
 }
 
@@ -125,24 +127,26 @@ impl UIDrawCall {
         let mut texture = None;
         let mut material_def:Option<Arc<MaterialDef>> = None;
         let mut custom_mat:Option<Handle<Material>> = None;
+        let mut sdf_params:Option<SdfMaterialParams> = None;
         for (index,entity) in entitys.iter().enumerate() {
             if let Ok(render2d) = render2ds.get(*entity) {
                 let mat4 = calc_trans(trans, parents, *entity,Some(canvas_entity));
                 let z_value:f32 = index as f32 * Z_SCALE;
                 texture = render2d.texture.clone();
                 material_def = Some(render2d.mat_def.clone());
-                for vert in render2d.mesh2d.points.iter() {
+                for (vert_index,vert) in render2d.mesh2d.points.iter().enumerate() {
                     let mut pos4 = Vec4::new(vert.pos.x, vert.pos.y, z_value, 1f32);
                     pos4 = mat4 * pos4;
                     positons.push(pos4.xyz().into());
                     uvs.push(vert.uv.into());
-                    colors.push(render2d.mesh2d.color.into());
+                    colors.push(render2d.mesh2d.vertex_color(vert_index).into());
                 }
                 indexs.extend(render2d.mesh2d.indexs.iter().map(|v| v + index_offset));
                 index_offset += render2d.mesh2d.points.len() as u32;
                 if let Some(mat) = render2d.custom_mat.as_ref() {
                     custom_mat = Some(mat.clone_weak());
                 }
+                sdf_params = render2d.sdf_params;
             }
         }
 
@@ -162,6 +166,9 @@ impl UIDrawCall {
             if let Some(texture) = texture {
                 new_material.texture_props.set("mainTexture", texture.clone());
             }
+            if let Some(params) = sdf_params {
+                set_sdf_props(&mut new_material, &params);
+            }
             materials.add(new_material)
         };
 
@@ -215,8 +222,23 @@ impl ZOrder {
 }
 
 
+fn set_sdf_props(mat:&mut Material,params:&SdfMaterialParams) {
+    mat.props.set_float4("outlineColor", params.outline_color, 0);
+    mat.props.set_float4("glowColor", params.glow_color, 0);
+    mat.props.set_float4("shadowColor", params.shadow_color, 0);
+    mat.props.set_float4("effectParams", params.effect_params, 0);
+    mat.props.set_float4("shadowOffset", params.shadow_offset, 0);
+}
+
+//材质定义,贴图,自定义材质和SDF参数都相同的才能合并
+type BatchKey = (*const MaterialDef,Option<HandleId>,Option<HandleId>,Option<SdfMaterialParams>);
+
+fn batch_key(render2d:&UIRender2D) -> BatchKey {
+    (Arc::as_ptr(&render2d.mat_def),render2d.texture.as_ref().map(|v| v.id),render2d.custom_mat.as_ref().map(|v| v.id),render2d.sdf_params)
+}
+
 struct ScanDrawCall {
-    cur_texture:Option<Option<HandleId>>,
+    cur_key:Option<BatchKey>,
     //cur_canvas:Option<Entity>,
     entity_group:Vec<Vec<Entity>>,
     cache:Vec<Entity>,
@@ -225,9 +247,9 @@ struct ScanDrawCall {
 impl ScanDrawCall {
     pub fn scan_entity_group(entity:Entity,infos:&Query<&EStateInfo>,children:&Query<&Children>,
                              uirenders:&Query<&UIRender2D>,canvases:&Query<&mut Canvas>) -> Vec<Vec<Entity>> {
-        let mut scan_drawcall = ScanDrawCall { entity_group:vec![],cache:vec![], cur_texture:None  };
+        let mut scan_drawcall = ScanDrawCall { entity_group:vec![],cache:vec![], cur_key:None  };
         if let Ok(render2d) = uirenders.get(entity) {
-            scan_drawcall.cur_texture = Some(render2d.texture.as_ref().map(|v| v.id));
+            scan_drawcall.cur_key = Some(batch_key(render2d));
             scan_drawcall.cache.push(entity);
         }
         if let Ok(child_comp) = children.get(entity) {
@@ -264,12 +286,13 @@ impl ScanDrawCall {
         if let Ok(render2d) = uirenders.get(entity) {
             let is_active = infos.get(entity).map(|v| v.is_active_global()).unwrap_or(true);
             if is_active {
-                match self.cur_texture {
-                    None => self.cur_texture = Some(render2d.texture.as_ref().map(|v| v.id)),
-                    Some(id) => {
-                        if id != render2d.texture.as_ref().map(|v| v.id) {
+                let key = batch_key(render2d);
+                match self.cur_key {
+                    None => self.cur_key = Some(key),
+                    Some(cur_key) => {
+                        if cur_key != key {
                             self.emit();
-                            self.cur_texture = Some(render2d.texture.as_ref().map(|v| v.id));
+                            self.cur_key = Some(key);
                         }
                     }
                 }
@@ -0,0 +1,374 @@
+use bevy_ecs::event::{EventReader, Events, ManualEventReader};
+use bevy_ecs::prelude::{Component, Entity};
+use bevy_ecs::query::{Added, Without};
+use bevy_ecs::system::{Query, Commands, Res, ResMut, Resource, SystemParam, Local};
+use seija_core::info::EStateInfo;
+use seija_core::math::{Vec2, Vec3, Vec4};
+use seija_core::reflect::Reflect;
+use seija_input::Input as SysInput;
+use seija_input::event::{MouseButton, GamepadButton};
+use seija_input::keycode::KeyCode;
+use seija_transform::Transform;
+use seija_transform::events::{EntityCommandsEx, HierarchyEvent};
+use seija_transform::hierarchy::Parent;
+use seija_2d::common::Rect2D;
+use crate::event::{UIEvent, UIEventType, EventNode, EventListener, EventNodeSource, dispatch_event};
+use super::input::InputTextSystemData;
+use super::widget::add_event_type;
+
+//可以获得键盘/手柄焦点的节点,Tab顺序按tab_index从小到大,相同时按从上到下从左到右
+#[derive(Component,Reflect,Debug,Clone)]
+#[repr(C)]
+pub struct Focusable {
+    #[reflect(name = "tabIndex")]
+    pub tab_index:i32,
+    pub interactable:bool
+}
+
+impl Default for Focusable {
+    fn default() -> Self {
+        Focusable { tab_index:0,interactable:true }
+    }
+}
+
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum FocusDirection {
+    Up,
+    Down,
+    Left,
+    Right
+}
+
+impl FocusDirection {
+    fn to_vec(self) -> Vec2 {
+        match self {
+            FocusDirection::Up => Vec2::Y,
+            FocusDirection::Down => -Vec2::Y,
+            FocusDirection::Left => -Vec2::X,
+            FocusDirection::Right => Vec2::X
+        }
+    }
+}
+
+//焦点操作,键盘由focus_system自己转换,手柄等其他输入发送这个事件
+#[derive(Debug,Clone)]
+pub enum FocusCommand {
+    Next,
+    Prev,
+    Move(FocusDirection),
+    Submit,
+    Set(Entity),
+    Clear
+}
+
+#[derive(Resource)]
+pub struct FocusSystemData {
+    focused:Option<Entity>,
+    //焦点框,会被移动并拉伸到当前焦点节点上,没有焦点时隐藏
+    pub ring_entity:Option<Entity>,
+    pub ring_padding:f32,
+    //是否处理键盘的Tab/方向键/回车/空格
+    pub use_keyboard:bool,
+    //是否处理手柄的十字键和South键
+    pub use_gamepad:bool
+}
+
+impl Default for FocusSystemData {
+    fn default() -> Self {
+        FocusSystemData { focused:None,ring_entity:None,ring_padding:4f32,use_keyboard:true,use_gamepad:true }
+    }
+}
+
+impl FocusSystemData {
+    pub fn focused(&self) -> Option<Entity> {
+        self.focused
+    }
+}
+
+#[derive(SystemParam)]
+pub struct FocusParams<'w,'s> {
+    pub(crate) focusables:Query<'w,'s,(Entity,&'static Focusable,&'static Rect2D,&'static Transform,Option<&'static EStateInfo>)>,
+    pub(crate) added:Query<'w,'s,Entity,Added<Focusable>>,
+    pub(crate) nodes:Query<'w,'s,&'static EventNode>,
+    pub(crate) listeners:Query<'w,'s,&'static EventListener>,
+    pub(crate) parents:Query<'w,'s,&'static Parent>,
+    pub(crate) trans:Query<'w,'s,&'static mut Transform,Without<Focusable>>,
+    pub(crate) rects:Query<'w,'s,&'static mut Rect2D,Without<Focusable>>,
+    pub(crate) commands:Commands<'w,'s>,
+}
+
+impl<'w,'s> EventNodeSource for FocusParams<'w,'s> {
+    fn node(&self,entity:Entity) -> Option<&EventNode> { self.nodes.get(entity).ok() }
+    fn listener(&self,entity:Entity) -> Option<&EventListener> { self.listeners.get(entity).ok() }
+}
+
+pub fn focus_system(mut params:FocusParams,
+                    mut sys_data:ResMut<FocusSystemData>,
+                    mut input_data:ResMut<InputTextSystemData>,
+                    sys_input:Res<SysInput>,
+                    mut focus_commands:EventReader<FocusCommand>,
+                    mut tree_events:EventReader<HierarchyEvent>,
+                    mut ui_events:ResMut<Events<UIEvent>>,
+                    mut ui_reader:Local<ManualEventReader<UIEvent>>) {
+    for entity in params.added.iter().collect::<Vec<_>>() {
+        let event_type = UIEventType::TOUCH_START | UIEventType::FOCUS_IN | UIEventType::FOCUS_OUT | UIEventType::SUBMIT;
+        add_event_type(entity, event_type, UIEventType::NONE, &mut params.commands);
+    }
+
+    let mut commands:Vec<FocusCommand> = vec![];
+    for ev in tree_events.iter() {
+        if let HierarchyEvent::Delete(_,all_entitys,_) = ev {
+            if sys_data.focused.map(|e| all_entitys.contains(&e)).unwrap_or(false) {
+                commands.push(FocusCommand::Clear);
+            }
+        }
+    }
+    //点击也会移动焦点
+    for ev in ui_reader.iter(&ui_events) {
+        if ev.event_type.contains(UIEventType::TOUCH_START) && params.focusables.contains(ev.entity) {
+            commands.push(FocusCommand::Set(ev.entity));
+        }
+    }
+    if sys_data.use_keyboard {
+        //输入框激活时方向键和空格留给输入框
+        let is_editing = input_data.active_input.is_some();
+        let is_shift = sys_input.get_key(KeyCode::LShift) || sys_input.get_key(KeyCode::RShift);
+        if sys_input.get_key_down(KeyCode::Tab) {
+            commands.push(if is_shift { FocusCommand::Prev } else { FocusCommand::Next });
+        }
+        if !is_editing {
+            let keys = [(KeyCode::Up,FocusDirection::Up),(KeyCode::Down,FocusDirection::Down),
+                        (KeyCode::Left,FocusDirection::Left),(KeyCode::Right,FocusDirection::Right)];
+            for (key,dir) in keys {
+                if sys_input.get_key_down(key) {
+                    commands.push(FocusCommand::Move(dir));
+                }
+            }
+            if sys_input.get_key_down(KeyCode::Return) || sys_input.get_key_down(KeyCode::NumpadEnter) || sys_input.get_key_down(KeyCode::Space) {
+                commands.push(FocusCommand::Submit);
+            }
+        }
+    }
+    if sys_data.use_gamepad {
+        let buttons = [(GamepadButton::DPadUp,FocusDirection::Up),(GamepadButton::DPadDown,FocusDirection::Down),
+                       (GamepadButton::DPadLeft,FocusDirection::Left),(GamepadButton::DPadRight,FocusDirection::Right)];
+        for (button,dir) in buttons {
+            if sys_input.any_gamepad_button_down(button) {
+                commands.push(FocusCommand::Move(dir));
+            }
+        }
+        if sys_input.any_gamepad_button_down(GamepadButton::South) {
+            commands.push(FocusCommand::Submit);
+        }
+    }
+    commands.extend(focus_commands.iter().cloned());
+
+    let old_focused = sys_data.focused;
+    //焦点节点被隐藏或者禁用时失去焦点
+    if let Some(focused) = sys_data.focused {
+        if !is_focusable(focused, &params) {
+            sys_data.focused = None;
+        }
+    }
+    for command in commands {
+        match command {
+            //Tab会离开正在编辑的输入框
+            FocusCommand::Next => {
+                input_data.set_active_input(None);
+                sys_data.focused = tab_focus(sys_data.focused, &params, 1);
+            },
+            FocusCommand::Prev => {
+                input_data.set_active_input(None);
+                sys_data.focused = tab_focus(sys_data.focused, &params, -1);
+            },
+            FocusCommand::Move(dir) => {
+                sys_data.focused = match sys_data.focused {
+                    Some(cur) => move_focus(cur, dir, &params).or(Some(cur)),
+                    None => tab_focus(None, &params, 1)
+                };
+            },
+            FocusCommand::Set(entity) => {
+                if is_focusable(entity, &params) {
+                    sys_data.focused = Some(entity);
+                }
+            },
+            FocusCommand::Clear => sys_data.focused = None,
+            FocusCommand::Submit => {
+                if let Some(focused) = sys_data.focused {
+                    send_focus_event(focused, UIEventType::SUBMIT, &params, &mut ui_events);
+                }
+            }
+        }
+    }
+
+    if old_focused != sys_data.focused {
+        //焦点离开激活的输入框时取消激活,焦点移到输入框上时激活
+        if old_focused.is_some() && old_focused == input_data.active_input() {
+            input_data.set_active_input(None);
+        }
+        if let Some(focused) = sys_data.focused.filter(|e| input_data.cache_dict.contains_key(e)) {
+            input_data.set_active_input(Some(focused));
+        }
+        if let Some(old) = old_focused {
+            if params.focusables.contains(old) {
+                send_focus_event(old, UIEventType::FOCUS_OUT, &params, &mut ui_events);
+            }
+        }
+        if let Some(new) = sys_data.focused {
+            send_focus_event(new, UIEventType::FOCUS_IN, &params, &mut ui_events);
+        }
+        if let Some(ring) = sys_data.ring_entity {
+            params.commands.entity(ring).set_active(sys_data.focused.is_some());
+        }
+    }
+    if let (Some(ring),Some(focused)) = (sys_data.ring_entity,sys_data.focused) {
+        update_focus_ring(ring, focused, sys_data.ring_padding, &mut params);
+    }
+}
+
+//和点击一样沿父节点路径捕获和冒泡
+fn send_focus_event(entity:Entity,typ:UIEventType,params:&FocusParams,sender:&mut Events<UIEvent>) {
+    if !params.nodes.contains(entity) { return; }
+    let mut path = vec![];
+    let mut cur_entity = Some(entity);
+    while let Some(cur) = cur_entity {
+        if params.nodes.contains(cur) {
+            path.push(cur);
+        }
+        cur_entity = params.parents.get(cur).ok().map(|v| v.0);
+    }
+    path.reverse();
+    dispatch_event(&path, typ, MouseButton::Left, Vec2::ZERO, params, &mut |ev| sender.send(ev));
+}
+
+fn is_focusable(entity:Entity,params:&FocusParams) -> bool {
+    match params.focusables.get(entity) {
+        Ok((_,focusable,_,_,state)) => focusable.interactable && state.map(|v| v.is_active_global()).unwrap_or(true),
+        Err(_) => false
+    }
+}
+
+fn world_center(rect:&Rect2D,t:&Transform) -> Vec2 {
+    let center = Vec4::new((rect.left() + rect.right()) * 0.5f32, (rect.top() + rect.bottom()) * 0.5f32, 0f32, 1f32);
+    let pos = t.global().matrix() * center;
+    Vec2::new(pos.x, pos.y)
+}
+
+fn tab_focus(cur:Option<Entity>,params:&FocusParams,step:i32) -> Option<Entity> {
+    let mut lst:Vec<(i32,Vec2,Entity)> = params.focusables.iter()
+        .filter(|(e,..)| Some(*e) == cur || is_focusable(*e, params))
+        .map(|(e,focusable,rect,t,_)| (focusable.tab_index,world_center(rect, t),e)).collect();
+    if lst.is_empty() { return None; }
+    //y轴向上,从上到下是y从大到小
+    lst.sort_by(|a,b| a.0.cmp(&b.0).then(b.1.y.total_cmp(&a.1.y)).then(a.1.x.total_cmp(&b.1.x)));
+    let len = lst.len() as i32;
+    let index = match cur.and_then(|c| lst.iter().position(|v| v.2 == c)) {
+        Some(index) => (index as i32 + step).rem_euclid(len),
+        None => if step > 0 { 0 } else { len - 1 }
+    };
+    Some(lst[index as usize].2)
+}
+
+//在方向上的距离加上偏离方向的距离,偏离的权重更大,取最小的
+fn move_focus(cur:Entity,dir:FocusDirection,params:&FocusParams) -> Option<Entity> {
+    let (_,_,cur_rect,cur_t,_) = params.focusables.get(cur).ok()?;
+    let cur_pos = world_center(cur_rect, cur_t);
+    let dir_vec = dir.to_vec();
+    let mut best:Option<(f32,Entity)> = None;
+    for (entity,_,rect,t,_) in params.focusables.iter() {
+        if entity == cur || !is_focusable(entity, params) { continue; }
+        let offset = world_center(rect, t) - cur_pos;
+        let along = offset.dot(dir_vec);
+        if along <= 0f32 { continue; }
+        let side = (offset - dir_vec * along).length();
+        let score = along + side * 2f32;
+        if best.map(|(s,_)| score < s).unwrap_or(true) {
+            best = Some((score,entity));
+        }
+    }
+    best.map(|v| v.1)
+}
+
+fn update_focus_ring(ring:Entity,focused:Entity,padding:f32,params:&mut FocusParams) {
+    let (center,size) = match params.focusables.get(focused) {
+        Ok((_,_,rect,t,_)) => {
+            let scale = t.global().scale;
+            (world_center(rect, t),Vec2::new(rect.width * scale.x, rect.height * scale.y))
+        },
+        Err(_) => return
+    };
+    //焦点框不改变父节点,把焦点节点的位置换算到焦点框父节点的空间
+    let parent_mat = params.parents.get(ring).ok().and_then(|p| {
+        params.focusables.get(p.0).map(|v| v.3.global().clone()).ok()
+              .or_else(|| params.trans.get(p.0).map(|v| v.global().clone()).ok())
+    });
+    let (local_pos,local_scale) = match parent_mat {
+        Some(mat) => {
+            let pos = mat.matrix().inverse() * Vec4::new(center.x, center.y, 0f32, 1f32);
+            (Vec2::new(pos.x, pos.y),Vec2::new(mat.scale.x, mat.scale.y))
+        },
+        None => (center,Vec2::ONE)
+    };
+    if let Ok(mut rect) = params.rects.get_mut(ring) {
+        let width = size.x / local_scale.x + padding * 2f32;
+        let height = size.y / local_scale.y + padding * 2f32;
+        if rect.width != width || rect.height != height {
+            rect.width = width;
+            rect.height = height;
+        }
+    }
+    if let Ok(mut t) = params.trans.get_mut(ring) {
+        let pos = Vec3::new(local_pos.x, local_pos.y, t.local.position.z);
+        if t.local.position != pos {
+            t.local.position = pos;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_ecs::prelude::World;
+    use bevy_ecs::system::SystemState;
+    use seija_core::math::Quat;
+    use super::*;
+
+    fn spawn_focusable(world:&mut World,x:f32,y:f32,tab_index:i32) -> Entity {
+        let mut t = Transform::new(Vec3::new(x, y, 0f32), Quat::IDENTITY, Vec3::ONE);
+        t.set_global(t.local.clone());
+        world.spawn((t,Rect2D::new(50f32, 50f32),Focusable { tab_index,interactable:true })).id()
+    }
+
+    #[test]
+    fn tab_and_move() {
+        let mut world = World::new();
+        let a = spawn_focusable(&mut world, 0f32, 100f32, 0);
+        let b = spawn_focusable(&mut world, 100f32, 100f32, 0);
+        let c = spawn_focusable(&mut world, 0f32, 0f32, 0);
+        let d = spawn_focusable(&mut world, 200f32, 0f32, 1);
+        let disabled = spawn_focusable(&mut world, 300f32, 300f32, 0);
+        world.get_mut::<Focusable>(disabled).unwrap().interactable = false;
+        let hidden = spawn_focusable(&mut world, -100f32, 0f32, 0);
+        let state_info = EStateInfo::default();
+        state_info.set_active(false);
+        world.entity_mut(hidden).insert(state_info);
+
+        let mut state:SystemState<FocusParams> = SystemState::new(&mut world);
+        let params = state.get_mut(&mut world);
+        //tab_index相同时从上到下从左到右,到结尾后回到开头
+        assert_eq!(tab_focus(None, &params, 1), Some(a));
+        assert_eq!(tab_focus(Some(a), &params, 1), Some(b));
+        assert_eq!(tab_focus(Some(b), &params, 1), Some(c));
+        assert_eq!(tab_focus(Some(c), &params, 1), Some(d));
+        assert_eq!(tab_focus(Some(d), &params, 1), Some(a));
+        assert_eq!(tab_focus(None, &params, -1), Some(d));
+        assert_eq!(tab_focus(Some(a), &params, -1), Some(d));
+        assert_eq!(tab_focus(Some(disabled), &params, 1), Some(a));
+
+        assert_eq!(move_focus(a, FocusDirection::Right, &params), Some(b));
+        assert_eq!(move_focus(a, FocusDirection::Down, &params), Some(c));
+        assert_eq!(move_focus(c, FocusDirection::Right, &params), Some(d));
+        assert_eq!(move_focus(b, FocusDirection::Left, &params), Some(a));
+        assert_eq!(move_focus(a, FocusDirection::Up, &params), None);
+        assert_eq!(move_focus(c, FocusDirection::Left, &params), None);
+    }
+}
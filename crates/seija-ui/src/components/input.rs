@@ -8,6 +8,7 @@ use bevy_ecs::{system::{SystemParam, Query}, prelude::Entity, query::{Or, Change
 use glyph_brush::ab_glyph::{FontArc, Font, ScaleFont};
 use seija_asset::{AssetServer, Assets, Handle};
 use seija_core::math::{Vec3, Vec2, Vec4};
+use seija_core::reflect::Reflect;
 use seija_core::time::Time;
 use seija_core::window::AppWindow;
 use seija_input::event::{ImeEvent, KeyboardInput, InputState, MouseButton};
@@ -24,11 +25,14 @@ use crate::system::UIRenderRoot;
 use crate::text::{Text, Font as TextFont};
 use seija_2d::common::{Rect2D,Mesh2D,Vertex2D};
 use super::ui_canvas::UICanvas;
-#[derive(Component,Debug,Clone,Default)]
+#[derive(Component,Reflect,Debug,Clone,Default)]
 #[repr(C)] 
 pub struct Input {
+    #[reflect(name = "fontSize")]
     pub font_size:u32,
+    #[reflect(name = "caretColor")]
     pub caret_color:Vec3,
+    #[reflect(skip)]
     pub text_entity:Option<Entity>,
     pub text:String,
 }
@@ -37,7 +41,31 @@ pub struct Input {
 #[derive(Resource,Default)]
 pub struct InputTextSystemData {
     pub(crate) cache_dict:HashMap<Entity,InputTextCache>,
-    active_input:Option<Entity>
+    pub(crate) active_input:Option<Entity>
+}
+
+impl InputTextSystemData {
+    pub fn active_input(&self) -> Option<Entity> {
+        self.active_input
+    }
+
+    //切换激活的输入框,光标和输入法在input_system中更新
+    pub(crate) fn set_active_input(&mut self,entity:Option<Entity>) {
+        let entity = entity.filter(|e| self.cache_dict.contains_key(e));
+        if self.active_input == entity { return; }
+        if let Some(old_cache) = self.active_input.and_then(|e| self.cache_dict.get_mut(&e)) {
+            old_cache.is_active = false;
+            old_cache.is_show = false;
+            old_cache.is_caret_dirty = true;
+        }
+        if let Some(cache) = entity.and_then(|e| self.cache_dict.get_mut(&e)) {
+            cache.is_active = true;
+            cache.is_show = true;
+            cache.time = 0f32;
+            cache.is_caret_dirty = true;
+        }
+        self.active_input = entity;
+    }
 }
 
 #[derive(Debug)]
@@ -59,7 +87,9 @@ pub struct InputTextCache {
     pub cache_char_infos:Vec<CharInfo>,
     pub caret_color:Vec3,
     pub is_active:bool,
-    pub is_string_dirty:bool
+    pub is_string_dirty:bool,
+    //激活状态在别处改变后需要重新设置光标颜色
+    pub is_caret_dirty:bool
 }
 
 impl InputTextCache {
@@ -75,6 +105,7 @@ impl InputTextCache {
             is_show:false,
             is_active:false,
             is_string_dirty:false,
+            is_caret_dirty:false,
             cache_rect:Rect2D::default(),
             cache_char_infos:vec![],
             caret_color:Vec3::new(1f32, 1f32, 1f32)
@@ -142,15 +173,11 @@ pub fn input_system(mut params:InputParams,
                 t.local.position.y = 0f32
             }
 
-            if let Some(old_input) = params.sys_data.active_input {
-               if let Some(mut old_cache) = params.sys_data.cache_dict.get_mut(&old_input) {
-                 old_cache.is_active = false;
-               }
-            }
-            params.sys_data.active_input = Some(ev.entity);  
+            params.sys_data.set_active_input(Some(ev.entity));
          }
      }
    }
+   update_caret_state(&mut params.sys_data, &mut mat_assets, &window);
    flash_input(&mut params.sys_data, &mut mat_assets, &time);
    
    //接收到输入事件
@@ -319,7 +346,8 @@ fn init_input(entity:Entity,input:&Input,rect:&Rect2D,sys_data:&mut InputTextSys
         mesh2d:caret_mesh,
         texture:None,
         mat_def:root.caret_mat_def.clone(),
-        custom_mat:Some(h_mat.clone())
+        custom_mat:Some(h_mat.clone()),
+        sdf_params:None
     };
     let caret_entity = commands.spawn((Transform::default(),rect.clone(),r2d)).set_parent(Some(entity)).id();
     let mut text_cache = InputTextCache::new(entity,caret_entity,h_mat);
@@ -420,6 +448,21 @@ fn world_to_window(mut mouse_pos:Vec2,window:&AppWindow) -> Vec2 {
     mouse_pos
 }
 
+fn update_caret_state(sys_data:&mut InputTextSystemData,mats:&mut Assets<Material>,window:&AppWindow) {
+    for cache in sys_data.cache_dict.values_mut() {
+        if !cache.is_caret_dirty { continue; }
+        cache.is_caret_dirty = false;
+        if cache.is_active {
+            window.set_ime_allowed(true);
+        }
+        if let Some(caret_material) = mats.get_mut(&cache.caret_mat.id) {
+            let color = cache.caret_color;
+            let alpha = if cache.is_show { 1f32 } else { 0f32 };
+            caret_material.props.set_float4("color", Vec4::new(color.x, color.y, color.z, alpha), 0);
+        }
+    }
+}
+
 fn flash_input(sys_data:&mut InputTextSystemData,mats:&mut Assets<Material>,time:&Time) {
     if let Some(active_entity) = sys_data.active_input {
         if let Some(cache) = sys_data.cache_dict.get_mut(&active_entity) {
@@ -466,6 +509,7 @@ fn build_input_caret_mesh(rect2d:&Rect2D,z_order:f32) -> Mesh2D {
 
     Mesh2D { 
         color:Vec4::ONE, 
+        colors:vec![],
         points, 
         indexs 
     }
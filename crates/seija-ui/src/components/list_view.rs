@@ -0,0 +1,265 @@
+use std::collections::HashMap;
+use bevy_ecs::event::{EventReader, EventWriter};
+use bevy_ecs::prelude::{Component, Entity};
+use bevy_ecs::query::Without;
+use bevy_ecs::system::{Query, Commands, Resource, ResMut};
+use seija_core::reflect::Reflect;
+use seija_transform::Transform;
+use seija_transform::events::{EntityCommandsEx, HierarchyEvent};
+use seija_2d::common::Rect2D;
+use crate::layout::comps::Orientation;
+use super::scroll_view::ScrollView;
+use super::widget::WidgetEvent;
+
+//虚拟列表,需要和ScrollView放在同一个entity上
+//只为可见范围内的index创建item,滚出范围的item回收后绑定到新的index上
+#[derive(Component,Reflect,Debug,Clone)]
+#[repr(C)]
+pub struct ListView {
+    pub count:usize,
+    #[reflect(name = "itemSize")]
+    pub item_size:f32,
+    pub spacing:f32,
+    pub orientation:Orientation,
+    #[reflect(skip)]
+    pub(crate) is_dirty:bool
+}
+
+impl Default for ListView {
+    fn default() -> Self { ListView::new(0, 0f32, 0f32, Orientation::Vertical) }
+}
+
+impl ListView {
+    pub fn new(count:usize,item_size:f32,spacing:f32,orientation:Orientation) -> ListView {
+        ListView { count,item_size,spacing,orientation,is_dirty:true }
+    }
+
+    pub fn set_count(&mut self,count:usize) {
+        self.count = count;
+        self.is_dirty = true;
+    }
+
+    //数据变化后重新绑定所有可见的item
+    pub fn refresh(&mut self) {
+        self.is_dirty = true;
+    }
+
+    pub fn content_length(&self) -> f32 {
+        if self.count == 0 { return 0f32; }
+        self.count as f32 * self.item_size + (self.count - 1) as f32 * self.spacing
+    }
+
+    //可见的index范围[start,end)
+    fn visible_range(&self,offset:f32,view_length:f32) -> (usize,usize) {
+        let step = self.item_size + self.spacing;
+        if step <= 0f32 || self.count == 0 { return (0,0); }
+        let start = ((offset / step).floor().max(0f32) as usize).min(self.count);
+        let end = (((offset + view_length) / step).ceil().max(0f32) as usize).min(self.count);
+        (start,end)
+    }
+}
+
+#[derive(Default)]
+pub struct ListViewCache {
+    pub actives:HashMap<usize,Entity>,
+    pub pool:Vec<Entity>
+}
+
+#[derive(Resource,Default)]
+pub struct ListViewSystemData {
+    pub(crate) cache_dict:HashMap<Entity,ListViewCache>
+}
+
+impl ListViewSystemData {
+    pub fn get_item(&self,list:Entity,index:usize) -> Option<Entity> {
+        self.cache_dict.get(&list)?.actives.get(&index).copied()
+    }
+}
+
+pub fn list_view_system(mut commands:Commands,
+                        mut lists:Query<(Entity,&mut ListView,&ScrollView,&Rect2D)>,
+                        mut rects:Query<&mut Rect2D,Without<ListView>>,
+                        mut trans:Query<&mut Transform>,
+                        mut sys_data:ResMut<ListViewSystemData>,
+                        mut widget_events:EventWriter<WidgetEvent>,
+                        mut tree_events:EventReader<HierarchyEvent>) {
+    for ev in tree_events.iter() {
+        if let HierarchyEvent::Delete(_,all_entitys,_) = ev {
+            for e in all_entitys.iter() {
+                sys_data.cache_dict.remove(e);
+            }
+            for cache in sys_data.cache_dict.values_mut() {
+                cache.actives.retain(|_,item| !all_entitys.contains(item));
+                cache.pool.retain(|item| !all_entitys.contains(item));
+            }
+        }
+    }
+
+    for (entity,mut list,scroll,rect) in lists.iter_mut() {
+        let content_entity = match scroll.content_entity {
+            Some(v) => v,
+            None => continue
+        };
+        let (view_length,cross_length,offset) = match list.orientation {
+            Orientation::Horizontal => (rect.width,rect.height,scroll.offset.x),
+            Orientation::Vertical => (rect.height,rect.width,scroll.offset.y)
+        };
+        //content的大小由item数量决定,ScrollView根据它计算滚动范围
+        let (content_left,content_top) = match rects.get_mut(content_entity) {
+            Ok(mut content_rect) => {
+                let (width,height) = match list.orientation {
+                    Orientation::Horizontal => (list.content_length(),cross_length),
+                    Orientation::Vertical => (cross_length,list.content_length())
+                };
+                if content_rect.width != width || content_rect.height != height {
+                    content_rect.width = width;
+                    content_rect.height = height;
+                }
+                (content_rect.left(),content_rect.top())
+            },
+            Err(_) => continue
+        };
+
+        let cache = sys_data.cache_dict.entry(entity).or_default();
+        let (start,end) = list.visible_range(offset, view_length);
+        let is_dirty = list.is_dirty;
+        let mut recycles = vec![];
+        cache.actives.retain(|index,item| {
+            let is_keep = !is_dirty && *index >= start && *index < end;
+            if !is_keep { recycles.push(*item); }
+            is_keep
+        });
+        //新回收的item放在pool末尾,优先复用,没有用到的再隐藏
+        let old_pool_len = cache.pool.len();
+        cache.pool.extend(recycles);
+
+        for index in start..end {
+            if cache.actives.contains_key(&index) { continue; }
+            let step = index as f32 * (list.item_size + list.spacing) + list.item_size * 0.5f32;
+            let mut t = Transform::default();
+            match list.orientation {
+                Orientation::Horizontal => {
+                    t.local.position.x = content_left + step;
+                    t.local.position.y = content_top - cross_length * 0.5f32;
+                },
+                Orientation::Vertical => {
+                    t.local.position.x = content_left + cross_length * 0.5f32;
+                    t.local.position.y = content_top - step;
+                }
+            }
+            let (item,is_new) = match cache.pool.pop() {
+                Some(item) => {
+                    if let Ok(mut item_t) = trans.get_mut(item) {
+                        item_t.local.position = t.local.position;
+                    }
+                    commands.entity(item).set_active(true);
+                    (item,false)
+                },
+                None => {
+                    let item_rect = match list.orientation {
+                        Orientation::Horizontal => Rect2D::new(list.item_size, cross_length),
+                        Orientation::Vertical => Rect2D::new(cross_length, list.item_size)
+                    };
+                    (commands.spawn((t,item_rect)).set_parent(Some(content_entity)).id(),true)
+                }
+            };
+            cache.actives.insert(index, item);
+            widget_events.send(WidgetEvent::ListItemBind { list:entity,item,index,is_new });
+        }
+        for item in cache.pool.iter().skip(old_pool_len) {
+            commands.entity(*item).set_active(false);
+        }
+        if is_dirty {
+            list.is_dirty = false;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_ecs::event::{Events, ManualEventReader};
+    use bevy_ecs::prelude::World;
+    use bevy_ecs::system::{IntoSystem, System};
+    use seija_core::info::EStateInfo;
+    use super::*;
+
+    #[test]
+    fn visible_range() {
+        let list = ListView::new(10, 10f32, 2f32, Orientation::Vertical);
+        assert_eq!(list.content_length(), 118f32);
+        assert_eq!(list.visible_range(0f32, 30f32), (0,3));
+        assert_eq!(list.visible_range(25f32, 30f32), (2,5));
+        assert_eq!(list.visible_range(200f32, 30f32), (10,10));
+        let empty = ListView::new(0, 10f32, 2f32, Orientation::Vertical);
+        assert_eq!(empty.content_length(), 0f32);
+        assert_eq!(empty.visible_range(0f32, 30f32), (0,0));
+    }
+
+    fn run_system(world:&mut World,system:&mut impl System<In = (),Out = ()>) {
+        system.run((), world);
+        system.apply_buffers(world);
+    }
+
+    fn read_binds(world:&World,reader:&mut ManualEventReader<WidgetEvent>) -> Vec<(usize,Entity,bool)> {
+        let events = world.get_resource::<Events<WidgetEvent>>().unwrap();
+        reader.iter(events).filter_map(|ev| match ev {
+            WidgetEvent::ListItemBind { index, item, is_new, .. } => Some((*index,*item,*is_new)),
+            _ => None
+        }).collect()
+    }
+
+    #[test]
+    fn recycle_items() {
+        let mut world = World::new();
+        world.insert_resource(ListViewSystemData::default());
+        world.insert_resource(Events::<WidgetEvent>::default());
+        world.insert_resource(Events::<HierarchyEvent>::default());
+        let content = world.spawn((Transform::default(),Rect2D::new(100f32, 0f32))).id();
+        let list_view = ListView::new(10, 10f32, 2f32, Orientation::Vertical);
+        let list = world.spawn((list_view,ScrollView::new(content, false, true),Rect2D::new(100f32, 30f32))).id();
+        let mut system = IntoSystem::into_system(list_view_system);
+        system.initialize(&mut world);
+        let mut reader = ManualEventReader::default();
+
+        run_system(&mut world, &mut system);
+        let binds = read_binds(&world, &mut reader);
+        assert_eq!(binds.iter().map(|v| (v.0,v.2)).collect::<Vec<_>>(), vec![(0,true),(1,true),(2,true)]);
+        assert_eq!(world.get::<Rect2D>(content).unwrap().height, 118f32);
+        let items:Vec<Entity> = binds.iter().map(|v| v.1).collect();
+
+        //滚动到[2,5),0和1回收后绑定到3和4
+        world.get_mut::<ScrollView>(list).unwrap().offset.y = 25f32;
+        run_system(&mut world, &mut system);
+        let binds = read_binds(&world, &mut reader);
+        assert_eq!(binds.iter().map(|v| (v.0,v.2)).collect::<Vec<_>>(), vec![(3,false),(4,false)]);
+        let mut reused:Vec<Entity> = binds.iter().map(|v| v.1).collect();
+        reused.sort();
+        assert_eq!(reused, vec![items[0],items[1]]);
+        let sys_data = world.get_resource::<ListViewSystemData>().unwrap();
+        assert_eq!(sys_data.get_item(list, 2), Some(items[2]));
+        assert_eq!(sys_data.get_item(list, 3), Some(binds[0].1));
+        assert!(sys_data.get_item(list, 0).is_none());
+        let pos = world.get::<Transform>(binds[0].1).unwrap().local.position;
+        assert_eq!((pos.x,pos.y), (0f32,59f32 - 41f32));
+
+        //全部滚出范围,item隐藏后留在pool中
+        world.get_mut::<ScrollView>(list).unwrap().offset.y = 200f32;
+        run_system(&mut world, &mut system);
+        assert!(read_binds(&world, &mut reader).is_empty());
+        let sys_data = world.get_resource::<ListViewSystemData>().unwrap();
+        assert_eq!(sys_data.cache_dict[&list].pool.len(), 3);
+        for item in items.iter() {
+            assert!(!world.get::<EStateInfo>(*item).unwrap().is_active());
+        }
+
+        //数据变化后重新绑定,回收的item重新显示
+        let mut list_view = world.get_mut::<ListView>(list).unwrap();
+        list_view.set_count(3);
+        world.get_mut::<ScrollView>(list).unwrap().offset.y = 0f32;
+        run_system(&mut world, &mut system);
+        let binds = read_binds(&world, &mut reader);
+        assert_eq!(binds.len(), 3);
+        assert!(binds.iter().all(|v| !v.2 && items.contains(&v.1)));
+        assert!(world.get::<EStateInfo>(items[0]).unwrap().is_active());
+    }
+}
@@ -2,5 +2,10 @@ pub mod sprite;
 pub mod canvas;
 pub mod input;
 pub mod ui_canvas;
-
-
+pub mod widget;
+pub mod button;
+pub mod toggle;
+pub mod slider;
+pub mod scroll_view;
+pub mod list_view;
+pub mod focus;
@@ -0,0 +1,187 @@
+use bevy_ecs::event::{EventReader, EventWriter};
+use bevy_ecs::prelude::{Component, Entity};
+use bevy_ecs::query::{Added, Without};
+use bevy_ecs::system::{Query, Commands, Res, ParamSet};
+use seija_core::math::Vec2;
+use seija_core::reflect::Reflect;
+use seija_core::time::Time;
+use seija_input::Input as SysInput;
+use seija_transform::Transform;
+use seija_2d::common::Rect2D;
+use crate::event::{UIEvent, UIEventType};
+use super::canvas::Canvas;
+use super::widget::{WidgetEvent, add_event_type};
+
+const DRAG_EVENTS:UIEventType = UIEventType::from_bits_truncate(UIEventType::BEGIN_DRAG.bits() | UIEventType::DRAG.bits() | UIEventType::END_DRAG.bits());
+
+//滚动区域,content_entity在自身的裁剪Canvas内按offset移动
+#[derive(Component,Reflect,Debug,Clone)]
+#[repr(C)]
+pub struct ScrollView {
+    #[reflect(skip)]
+    pub content_entity:Option<Entity>,
+    pub horizontal:bool,
+    pub vertical:bool,
+    pub inertia:bool,
+    //松开后每秒保留的速度比例
+    #[reflect(name = "decelerationRate")]
+    pub deceleration_rate:f32,
+    //滚轮滚动一格移动的距离
+    #[reflect(name = "wheelSpeed")]
+    pub wheel_speed:f32,
+    #[reflect(skip)]
+    pub(crate) offset:Vec2,
+    #[reflect(skip)]
+    pub(crate) velocity:Vec2,
+    #[reflect(skip)]
+    pub(crate) is_dragging:bool,
+    #[reflect(skip)]
+    pub(crate) is_hover:bool,
+    #[reflect(skip)]
+    pub(crate) is_dirty:bool
+}
+
+impl Default for ScrollView {
+    fn default() -> Self {
+        ScrollView {
+            content_entity:None,
+            horizontal:false,
+            vertical:true,
+            inertia:true,
+            deceleration_rate:0.135f32,
+            wheel_speed:30f32,
+            offset:Vec2::ZERO,
+            velocity:Vec2::ZERO,
+            is_dragging:false,
+            is_hover:false,
+            is_dirty:true
+        }
+    }
+}
+
+impl ScrollView {
+    pub fn new(content_entity:Entity,horizontal:bool,vertical:bool) -> ScrollView {
+        ScrollView { content_entity:Some(content_entity),horizontal,vertical,..Default::default() }
+    }
+
+    //content左上角相对可见区域左上角的滚动距离,向下和向右为正
+    pub fn offset(&self) -> Vec2 {
+        self.offset
+    }
+
+    pub fn set_offset(&mut self,offset:Vec2) {
+        self.offset = offset;
+        self.velocity = Vec2::ZERO;
+        self.is_dirty = true;
+    }
+
+    pub fn max_offset(view:&Rect2D,content:&Rect2D) -> Vec2 {
+        Vec2::new((content.width - view.width).max(0f32), (content.height - view.height).max(0f32))
+    }
+
+    fn scroll(&mut self,delta:Vec2) {
+        if self.horizontal { self.offset.x += delta.x; }
+        if self.vertical { self.offset.y += delta.y; }
+        self.is_dirty = true;
+    }
+}
+
+pub fn scroll_view_system(mut commands:Commands,
+                          mut sets:ParamSet<(Query<(Entity,&mut ScrollView,&Rect2D)>,Query<(Entity,Option<&Canvas>),Added<ScrollView>>)>,
+                          content_rects:Query<&Rect2D,Without<ScrollView>>,
+                          mut trans:Query<&mut Transform,Without<ScrollView>>,
+                          mut ui_events:EventReader<UIEvent>,
+                          mut widget_events:EventWriter<WidgetEvent>,
+                          sys_input:Res<SysInput>,
+                          time:Res<Time>) {
+    let event_type = DRAG_EVENTS | UIEventType::MOUSE_ENTER | UIEventType::MOUSE_LEAVE;
+    for (entity,canvas) in sets.p1().iter() {
+        add_event_type(entity, event_type, DRAG_EVENTS, &mut commands);
+        if canvas.is_none() {
+            commands.entity(entity).insert(Canvas::new(true));
+        }
+    }
+
+    let dt = time.delta_seconds();
+    let mut views = sets.p0();
+    for ev in ui_events.iter() {
+        //子节点已经处理过的拖动(比如Slider或者内层的ScrollView)不再滚动
+        if ev.handled && ev.event_type.intersects(UIEventType::BEGIN_DRAG | UIEventType::DRAG) { continue; }
+        if let Ok((_,mut view,_)) = views.get_mut(ev.entity) {
+            if ev.event_type.contains(UIEventType::BEGIN_DRAG) {
+                view.is_dragging = true;
+                view.velocity = Vec2::ZERO;
+            } else if ev.event_type.contains(UIEventType::DRAG) {
+                //屏幕坐标y轴向上,向上拖动时content向上移动,offset增加
+                let delta = Vec2::new(-ev.pos.x, ev.pos.y);
+                view.scroll(delta);
+                if dt > 0f32 {
+                    let velocity = delta / dt;
+                    view.velocity = view.velocity.lerp(velocity, 0.5f32);
+                }
+            } else if ev.event_type.contains(UIEventType::END_DRAG) {
+                view.is_dragging = false;
+            } else if ev.event_type.contains(UIEventType::MOUSE_ENTER) {
+                view.is_hover = true;
+            } else if ev.event_type.contains(UIEventType::MOUSE_LEAVE) {
+                view.is_hover = false;
+            }
+        }
+    }
+
+    let wheel = sys_input.get_mouse_wheel().copied();
+    for (entity,mut view,rect) in views.iter_mut() {
+        if let Some(wheel) = wheel {
+            if view.is_hover && !view.is_dragging {
+                //只能横向滚动时滚轮也作用在横向上
+                let delta = if view.vertical { Vec2::new(wheel.x, -wheel.y) } else { Vec2::new(-wheel.y, 0f32) };
+                let wheel_speed = view.wheel_speed;
+                view.velocity = Vec2::ZERO;
+                view.scroll(delta * wheel_speed);
+            }
+        }
+        if !view.is_dragging && view.velocity != Vec2::ZERO {
+            if view.inertia {
+                let velocity = view.velocity;
+                view.scroll(velocity * dt);
+                let rate = view.deceleration_rate.powf(dt);
+                view.velocity *= rate;
+                if view.velocity.length() < 1f32 {
+                    view.velocity = Vec2::ZERO;
+                }
+            } else {
+                view.velocity = Vec2::ZERO;
+            }
+        }
+
+        let content_entity = match view.content_entity {
+            Some(v) => v,
+            None => continue
+        };
+        let content_rect = match content_rects.get(content_entity) {
+            Ok(v) => v,
+            Err(_) => continue
+        };
+        let max_offset = ScrollView::max_offset(rect, content_rect);
+        let clamped = view.offset.clamp(Vec2::ZERO, max_offset);
+        if clamped.x != view.offset.x { view.velocity.x = 0f32; }
+        if clamped.y != view.offset.y { view.velocity.y = 0f32; }
+        if clamped != view.offset {
+            view.offset = clamped;
+        }
+
+        if let Ok(mut t) = trans.get_mut(content_entity) {
+            //content的左上角对齐可见区域的左上角再按offset偏移
+            let x = rect.left() - content_rect.left() - view.offset.x;
+            let y = rect.top() - content_rect.top() + view.offset.y;
+            if t.local.position.x != x || t.local.position.y != y {
+                t.local.position.x = x;
+                t.local.position.y = y;
+            }
+        }
+        if view.is_dirty {
+            view.is_dirty = false;
+            widget_events.send(WidgetEvent::ScrollChanged(entity, view.offset));
+        }
+    }
+}
@@ -0,0 +1,205 @@
+use bevy_ecs::event::{EventReader, EventWriter};
+use bevy_ecs::prelude::{Component, Entity};
+use bevy_ecs::query::{Added, Changed, Or, With, Without};
+use bevy_ecs::system::{Query, Commands, ParamSet};
+use seija_core::math::{Vec2, Vec4};
+use seija_core::reflect::Reflect;
+use seija_transform::Transform;
+use seija_2d::common::Rect2D;
+use crate::event::{UIEvent, UIEventType};
+use crate::layout::comps::Orientation;
+use super::widget::{WidgetEvent, add_event_type};
+
+//滑动条,handle_entity跟随当前值移动,fill_entity的大小按当前值拉伸
+#[derive(Component,Reflect,Debug,Clone)]
+#[repr(C)]
+pub struct Slider {
+    pub value:f32,
+    pub min:f32,
+    pub max:f32,
+    pub orientation:Orientation,
+    pub interactable:bool,
+    #[reflect(skip)]
+    pub handle_entity:Option<Entity>,
+    #[reflect(skip)]
+    pub fill_entity:Option<Entity>,
+    //拖动时没有限制范围的值,拖出范围再拖回来时handle不会提前移动
+    #[reflect(skip)]
+    pub(crate) drag_value:f32
+}
+
+impl Default for Slider {
+    fn default() -> Self { Slider::new(0f32, 0f32, 1f32, Orientation::Horizontal) }
+}
+
+impl Slider {
+    pub fn new(value:f32,min:f32,max:f32,orientation:Orientation) -> Slider {
+        Slider { value,min,max,orientation,interactable:true,handle_entity:None,fill_entity:None,drag_value:value }
+    }
+
+    pub fn normalized_value(&self) -> f32 {
+        if self.max <= self.min { return 0f32; }
+        ((self.value - self.min) / (self.max - self.min)).clamp(0f32, 1f32)
+    }
+
+    //滑动方向上的起点和长度
+    fn axis_range(&self,rect:&Rect2D) -> (f32,f32) {
+        match self.orientation {
+            Orientation::Horizontal => (rect.left(),rect.width),
+            Orientation::Vertical => (rect.bottom(),rect.height)
+        }
+    }
+
+    //slider局部坐标转换成值,不限制在[min,max]内
+    fn pos_to_value(&self,rect:&Rect2D,local:Vec2) -> f32 {
+        let (start,length) = self.axis_range(rect);
+        let pos = match self.orientation {
+            Orientation::Horizontal => local.x,
+            Orientation::Vertical => local.y
+        };
+        self.min + (pos - start) / length * (self.max - self.min)
+    }
+
+    //当前值在slider局部坐标中的位置
+    fn value_to_pos(&self,rect:&Rect2D) -> f32 {
+        let (start,length) = self.axis_range(rect);
+        start + length * self.normalized_value()
+    }
+
+    fn set_drag_value(&mut self,value:f32) -> bool {
+        self.drag_value = value;
+        let new_value = value.clamp(self.min, self.max.max(self.min));
+        if new_value != self.value {
+            self.value = new_value;
+            return true;
+        }
+        false
+    }
+}
+
+pub fn slider_system(mut commands:Commands,
+                     mut sets:ParamSet<(Query<(&mut Slider,&Rect2D,&Transform)>,
+                                        Query<Entity,(With<Slider>,Or<(Added<Slider>,Changed<Slider>,Changed<Rect2D>)>)>,
+                                        Query<Entity,Added<Slider>>)>,
+                     mut trans:Query<&mut Transform,Without<Slider>>,
+                     mut rects:Query<&mut Rect2D,Without<Slider>>,
+                     mut ui_events:EventReader<UIEvent>,
+                     mut widget_events:EventWriter<WidgetEvent>) {
+    for entity in sets.p2().iter() {
+        let event_type = UIEventType::TOUCH_START | UIEventType::BEGIN_DRAG | UIEventType::DRAG;
+        add_event_type(entity, event_type, event_type, &mut commands);
+    }
+
+    for ev in ui_events.iter() {
+        let mut sliders = sets.p0();
+        if let Ok((mut slider,rect,t)) = sliders.get_mut(ev.entity) {
+            if !slider.interactable { continue; }
+            let (_,length) = slider.axis_range(rect);
+            if length <= 0f32 { continue; }
+            let range = slider.max - slider.min;
+            let is_changed = if ev.event_type.contains(UIEventType::TOUCH_START) {
+                let local = t.global().matrix().inverse() * Vec4::new(ev.pos.x, ev.pos.y, 0f32, 1f32);
+                let drag_value = slider.pos_to_value(rect, local.truncate().truncate());
+                slider.set_drag_value(drag_value)
+            } else if ev.event_type.contains(UIEventType::DRAG) {
+                //DRAG事件的pos是屏幕上的偏移量,需要换算到slider的缩放下
+                let (delta,scale) = match slider.orientation {
+                    Orientation::Horizontal => (ev.pos.x,t.global().scale.x),
+                    Orientation::Vertical => (ev.pos.y,t.global().scale.y)
+                };
+                let drag_value = slider.drag_value + delta / (length * scale) * range;
+                slider.set_drag_value(drag_value)
+            } else {
+                false
+            };
+            if is_changed {
+                widget_events.send(WidgetEvent::SliderChanged(ev.entity, slider.value));
+            }
+        }
+    }
+
+    let changed = sets.p1().iter().collect::<Vec<_>>();
+    let sliders = sets.p0();
+    for entity in changed {
+        if let Ok((slider,rect,_)) = sliders.get(entity) {
+            update_slider_view(slider, rect, &mut trans, &mut rects);
+        }
+    }
+}
+
+fn update_slider_view(slider:&Slider,rect:&Rect2D,trans:&mut Query<&mut Transform,Without<Slider>>,rects:&mut Query<&mut Rect2D,Without<Slider>>) {
+    let rate = slider.normalized_value();
+    let (start,length) = slider.axis_range(rect);
+    let axis_pos = |v:&mut Vec2,pos:f32| {
+        match slider.orientation {
+            Orientation::Horizontal => v.x = pos,
+            Orientation::Vertical => v.y = pos
+        }
+    };
+    if let Some(Ok(mut t)) = slider.handle_entity.map(|e| trans.get_mut(e)) {
+        let mut pos = t.local.position.truncate();
+        axis_pos(&mut pos, slider.value_to_pos(rect));
+        if pos != t.local.position.truncate() {
+            t.local.position.x = pos.x;
+            t.local.position.y = pos.y;
+        }
+    }
+    if let Some(fill_entity) = slider.fill_entity {
+        if let Ok(mut fill_rect) = rects.get_mut(fill_entity) {
+            let fill_length = length * rate;
+            match slider.orientation {
+                Orientation::Horizontal => if fill_rect.width != fill_length { fill_rect.width = fill_length; },
+                Orientation::Vertical => if fill_rect.height != fill_length { fill_rect.height = fill_length; }
+            }
+        }
+        if let Ok(mut t) = trans.get_mut(fill_entity) {
+            let mut pos = t.local.position.truncate();
+            axis_pos(&mut pos, start + length * rate * 0.5f32);
+            if pos != t.local.position.truncate() {
+                t.local.position.x = pos.x;
+                t.local.position.y = pos.y;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn value_pos_mapping() {
+        let rect = Rect2D::new(200f32, 20f32);
+        let mut slider = Slider::new(5f32, 0f32, 10f32, Orientation::Horizontal);
+        assert_eq!(slider.value_to_pos(&rect), 0f32);
+        assert_eq!(slider.pos_to_value(&rect, Vec2::new(-100f32, 0f32)), 0f32);
+        assert_eq!(slider.pos_to_value(&rect, Vec2::new(50f32, 0f32)), 7.5f32);
+        slider.value = 7.5f32;
+        assert_eq!(slider.value_to_pos(&rect), 50f32);
+
+        let mut vertical = Slider::new(0f32, -1f32, 1f32, Orientation::Vertical);
+        let rect = Rect2D::new(20f32, 100f32);
+        assert_eq!(vertical.value_to_pos(&rect), 0f32);
+        assert_eq!(vertical.pos_to_value(&rect, Vec2::new(0f32, -25f32)), -0.5f32);
+        vertical.value = 1f32;
+        assert_eq!(vertical.value_to_pos(&rect), 50f32);
+    }
+
+    #[test]
+    fn drag_value_clamp() {
+        let mut slider = Slider::new(5f32, 0f32, 10f32, Orientation::Horizontal);
+        assert!(slider.set_drag_value(12f32));
+        assert_eq!(slider.value, 10f32);
+        assert_eq!(slider.normalized_value(), 1f32);
+        //拖出范围后往回拖,回到范围内之前值不变
+        assert!(!slider.set_drag_value(11f32));
+        assert_eq!(slider.value, 10f32);
+        assert!(slider.set_drag_value(9f32));
+        assert_eq!(slider.value, 9f32);
+        assert!(slider.set_drag_value(-3f32));
+        assert_eq!(slider.value, 0f32);
+
+        let empty = Slider::new(3f32, 2f32, 2f32, Orientation::Horizontal);
+        assert_eq!(empty.normalized_value(), 0f32);
+    }
+}
@@ -50,7 +50,8 @@ impl Sprite {
             mat_def,
             mesh2d,
             texture:Some(atlas.texture.clone()),
-            custom_mat:None
+            custom_mat:None,
+            sdf_params:None
         })  
     }
 }
\ No newline at end of file
@@ -0,0 +1,57 @@
+use bevy_ecs::event::{EventReader, EventWriter};
+use bevy_ecs::prelude::{Component, Entity};
+use bevy_ecs::query::{Added, Changed, Or};
+use bevy_ecs::system::{Query, Commands, ParamSet};
+use seija_core::reflect::Reflect;
+use seija_transform::events::EntityCommandsEx;
+use crate::event::{UIEvent, UIEventType};
+use super::widget::{WidgetEvent, add_event_type};
+
+//开关/复选框,打开时显示check_entity
+#[derive(Component,Reflect,Debug,Clone)]
+#[repr(C)]
+pub struct Toggle {
+    #[reflect(name = "isOn")]
+    pub is_on:bool,
+    pub interactable:bool,
+    #[reflect(skip)]
+    pub check_entity:Option<Entity>
+}
+
+impl Default for Toggle {
+    fn default() -> Self { Toggle::new(false, None) }
+}
+
+impl Toggle {
+    pub fn new(is_on:bool,check_entity:Option<Entity>) -> Toggle {
+        Toggle { is_on,interactable:true,check_entity }
+    }
+}
+
+pub fn toggle_system(mut commands:Commands,
+                     mut sets:ParamSet<(Query<&mut Toggle>,Query<Entity,Or<(Added<Toggle>,Changed<Toggle>)>>,Query<Entity,Added<Toggle>>)>,
+                     mut ui_events:EventReader<UIEvent>,
+                     mut widget_events:EventWriter<WidgetEvent>) {
+    for entity in sets.p2().iter() {
+        let event_type = UIEventType::CLICK | UIEventType::SUBMIT;
+        add_event_type(entity, event_type, event_type, &mut commands);
+    }
+
+    for ev in ui_events.iter() {
+        if !ev.event_type.intersects(UIEventType::CLICK | UIEventType::SUBMIT) || ev.handled { continue; }
+        if let Ok(mut toggle) = sets.p0().get_mut(ev.entity) {
+            if !toggle.interactable { continue; }
+            toggle.is_on = !toggle.is_on;
+            widget_events.send(WidgetEvent::ToggleChanged(ev.entity, toggle.is_on));
+        }
+    }
+
+    let changed = sets.p1().iter().collect::<Vec<_>>();
+    for entity in changed {
+        if let Ok(toggle) = sets.p0().get(entity) {
+            if let Some(check_entity) = toggle.check_entity {
+                commands.entity(check_entity).set_active(toggle.is_on);
+            }
+        }
+    }
+}
@@ -1,5 +1,5 @@
 use bevy_ecs::{prelude::{Component, Entity, EventReader,Res}, query::{Changed}, system::Query};
-use seija_core::{window::AppWindow, math::Vec3};
+use seija_core::{window::AppWindow, math::Vec3, reflect::Reflect, impl_reflect_enum};
 use seija_render::camera::camera::{Camera, Projection};
 use seija_transform::Transform;
 use seija_winit::event::WindowResized;
@@ -19,6 +19,8 @@ impl Into<UIScaleMode> for u8 {
     }
 }
 
+impl_reflect_enum!(UIScaleMode,WithScreenSize,ConstantPhysicalSize);
+
 impl Default for UIScaleMode {
     fn default() -> Self {
         UIScaleMode::ConstantPhysicalSize
@@ -26,8 +28,9 @@ impl Default for UIScaleMode {
 }
 
 #[repr(C)]
-#[derive(Component,Default)]
+#[derive(Component,Reflect,Default)]
 pub struct UICanvas {
+   #[reflect(name = "scaleMode")]
    pub(crate) scale_mode:UIScaleMode
 }
 
@@ -0,0 +1,59 @@
+use bevy_ecs::prelude::{Entity, World};
+use bevy_ecs::system::{Command, Commands};
+use seija_core::math::Vec2;
+use crate::event::{EventNode, UIEventType};
+
+//控件状态变化时发送的事件
+#[derive(Debug,Clone)]
+pub enum WidgetEvent {
+    Click(Entity),
+    ToggleChanged(Entity,bool),
+    SliderChanged(Entity,f32),
+    ScrollChanged(Entity,Vec2),
+    //ListView的item需要显示index位置的数据,is_new表示item是新创建的entity
+    ListItemBind { list:Entity,item:Entity,index:usize,is_new:bool },
+    //点击了富文本中的链接,index是RichTextInfo.links中的位置
+    LinkClick { text:Entity,index:usize,link:String }
+}
+
+impl WidgetEvent {
+    pub fn entity(&self) -> Entity {
+        match self {
+            WidgetEvent::Click(e) => *e,
+            WidgetEvent::ToggleChanged(e, _) => *e,
+            WidgetEvent::SliderChanged(e, _) => *e,
+            WidgetEvent::ScrollChanged(e, _) => *e,
+            WidgetEvent::ListItemBind { list, .. } => *list,
+            WidgetEvent::LinkClick { text, .. } => *text
+        }
+    }
+}
+
+//控件需要的UIEvent类型合并到EventNode上,handle是控件会处理掉的类型,没有EventNode时添加一个冒泡模式的
+//在命令里合并,同一帧多个控件系统给同一个entity添加时不会互相覆盖
+pub(crate) fn add_event_type(entity:Entity,typ:UIEventType,handle:UIEventType,commands:&mut Commands) {
+    commands.add(AddEventType { entity,typ,handle });
+}
+
+struct AddEventType {
+    entity:Entity,
+    typ:UIEventType,
+    handle:UIEventType
+}
+
+impl Command for AddEventType {
+    fn write(self, world: &mut World) {
+        if let Some(mut node) = world.get_mut::<EventNode>(self.entity) {
+            if !node.event_type.contains(self.typ) || !node.handle_event.contains(self.handle) {
+                node.event_type |= self.typ;
+                node.handle_event |= self.handle;
+            }
+        } else if let Some(mut entity_mut) = world.get_entity_mut(self.entity) {
+            let mut node = EventNode::default();
+            node.event_type = self.typ;
+            node.handle_event = self.handle;
+            node.use_capture = false;
+            entity_mut.insert(node);
+        }
+    }
+}
@@ -2,3 +2,4 @@ mod system;
 mod types;
 pub use types::*;
 pub use system::ui_event_system;
+pub(crate) use system::{dispatch_event, EventNodeSource};
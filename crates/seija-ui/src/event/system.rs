@@ -1,61 +1,128 @@
 use bevy_ecs::{prelude::*, system::SystemParam};
 use seija_core::{math::{Vec4, Vec2}, window::AppWindow, info::EStateInfo};
-use seija_input::{Input, event::MouseButton};
+use seija_input::{Input, event::MouseButton, gesture::GestureEvent};
 use seija_transform::{Transform, hierarchy::{Children, Parent}};
 use seija_2d::common::Rect2D;
-use super::{UIEventSystem, EventNode, UIEventType, EventNodeState, UIEvent};
+use super::{UIEventSystem, EventNode, UIEventType, EventNodeState, UIEvent, UIEventPhase, EventListener, UIEventContext};
 
 #[derive(SystemParam)]
 pub struct EventParams<'w,'s> {
     pub(crate) input:Res<'w,Input>,
     pub(crate) infos:Query<'w,'s,(Entity,Option<&'static Rect2D>,&'static Transform,Option<&'static EStateInfo>)>,
-    pub(crate) ui_systems:Query<'w,'s,(Entity,&'static UIEventSystem)>,
     pub(crate) childs:Query<'w,'s,&'static Children>,
     pub(crate) window:Res<'w,AppWindow>,
     pub(crate) parent:Query<'w,'s,&'static Parent>,
+    pub(crate) listeners:Query<'w,'s,&'static EventListener>,
 }
 
-pub fn ui_event_system(params:EventParams,mut event_nodes:Query<(Entity,&mut EventNode)>,mut sender:EventWriter<UIEvent>) {
-    for (entity,ui_system) in params.ui_systems.iter() {
-      ui_system_handle(entity, ui_system, &params,&mut event_nodes,&mut sender);
+pub fn ui_event_system(params:EventParams,mut ui_systems:Query<(Entity,&mut UIEventSystem)>,mut event_nodes:Query<(Entity,&mut EventNode)>,
+                       mut sender:EventWriter<UIEvent>,mut gesture_reader:EventReader<GestureEvent>) {
+    let gestures:Vec<GestureEvent> = gesture_reader.iter().cloned().collect();
+    for (entity,mut ui_system) in ui_systems.iter_mut() {
+      ui_system_handle(entity, &mut ui_system, &params,&mut event_nodes,&mut sender);
+      dispatch_gestures(entity, &gestures, &params, &mut event_nodes, &mut sender);
     }
 }
 
+//手势按开始位置命中的节点分发,和点击一样走捕获和冒泡
+fn dispatch_gestures(system_entity:Entity,gestures:&[GestureEvent],params:&EventParams,
+                     event_nodes:&mut Query<(Entity,&mut EventNode)>,sender:&mut EventWriter<UIEvent>) {
+    for gesture in gestures.iter() {
+        let (typ,pos) = match gesture {
+            GestureEvent::Tap(pos) => (UIEventType::TAP,mouse_pos_to_ui(*pos, params)),
+            GestureEvent::DoubleTap(pos) => (UIEventType::DOUBLE_TAP,mouse_pos_to_ui(*pos, params)),
+            GestureEvent::LongPress(pos) => (UIEventType::LONG_PRESS,mouse_pos_to_ui(*pos, params)),
+            //窗口坐标y轴向下,UI里y轴向上
+            GestureEvent::Swipe { delta, .. } => (UIEventType::SWIPE,Vec2::new(delta.x, -delta.y)),
+            GestureEvent::Pinch { scale, .. } => (UIEventType::PINCH,Vec2::new(*scale, 0f32)),
+            GestureEvent::Rotate { angle, .. } => (UIEventType::ROTATE,Vec2::new(-*angle, 0f32))
+        };
+        let hit_pos = mouse_pos_to_world(gesture.position(), system_entity, params);
+        let path = hit_path(system_entity, hit_pos, params, event_nodes);
+        dispatch_path(&path, typ, MouseButton::Left, pos, params, event_nodes, sender);
+    }
+}
 
-pub fn ui_system_handle(system_entity:Entity,_:&UIEventSystem,params:&EventParams,event_nodes:&mut Query<(Entity,&mut EventNode)>,sender:&mut EventWriter<UIEvent>) {
+const DRAG_EVENTS:UIEventType = UIEventType::from_bits_truncate(UIEventType::BEGIN_DRAG.bits() | UIEventType::DRAG.bits() | UIEventType::END_DRAG.bits());
+
+pub fn ui_system_handle(system_entity:Entity,ui_system:&mut UIEventSystem,params:&EventParams,event_nodes:&mut Query<(Entity,&mut EventNode)>,sender:&mut EventWriter<UIEvent>) {
+    let ui_pos = mouse_pos_to_ui(params.input.mouse_position,params);
     if params.input.has_mouse_down() || params.input.has_mouse_up() {
         let mouse_pos = mouse_pos_to_world(params.input.mouse_position, system_entity, params);
-        let ui_pos = mouse_pos_to_ui(params.input.mouse_position,params);
         let (fire_type,mouse_btn) = if params.input.has_mouse_down() {
             (UIEventType::TOUCH_START,params.input.mouse_down_iter().next().cloned() )
         } else {
             (UIEventType::TOUCH_END,params.input.mouse_up_iter().next().cloned())
         };
         let mouse_btn = mouse_btn.unwrap_or(seija_input::event::MouseButton::Left);
-        
-        capture_ui_system(system_entity,fire_type,mouse_pos,params,event_nodes,sender,mouse_btn);
-        
+
+        let path = hit_path(system_entity, mouse_pos, params, event_nodes);
+        if fire_type == UIEventType::TOUCH_START {
+            for entity in path.iter() {
+                if let Ok((_,mut event_node)) = event_nodes.get_mut(*entity) {
+                    event_node.state |= EventNodeState::TOUCH_IN.bits();
+                }
+            }
+            ui_system.press_path = path.clone();
+        }
+        dispatch_path(&path, fire_type, mouse_btn, mouse_pos, params, event_nodes, sender);
+
         if params.input.has_mouse_up() {
-            for (entity,mut event_node) in event_nodes.iter_mut() {
-                event_node.state &= !EventNodeState::TOUCH_IN.bits();
-                if   event_node.event_type.bits() & UIEventType::END_DRAG.bits() > 0
-                  && event_node.state & EventNodeState::DRAG_IN.bits() > 0 {
-                    sender.send(UIEvent {
-                        entity,
-                        btn:MouseButton::Left,
-                        event_type:UIEventType::END_DRAG,
-                        user_key:event_node.user_key.clone(),
-                        pos:ui_pos
-                    });
+            if ui_system.drag_path.is_empty() {
+                //按下和松开都在节点内才算点击
+                let click_path:Vec<Entity> = path.iter().copied().filter(|e| {
+                    event_nodes.get(*e).map(|(_,n)| n.is_touch_in()).unwrap_or(false)
+                }).collect();
+                dispatch_path(&click_path, UIEventType::CLICK, mouse_btn, mouse_pos, params, event_nodes, sender);
+            } else {
+                //拖动过就不再触发点击
+                let drag_path = std::mem::take(&mut ui_system.drag_path);
+                dispatch_path(&drag_path, UIEventType::END_DRAG, MouseButton::Left, ui_pos, params, event_nodes, sender);
+            }
+            for (_,mut event_node) in event_nodes.iter_mut() {
+                if event_node.state & (EventNodeState::TOUCH_IN.bits() | EventNodeState::DRAG_IN.bits()) != 0 {
+                    event_node.state &= !(EventNodeState::TOUCH_IN.bits() | EventNodeState::DRAG_IN.bits());
                 }
-                event_node.state &= !EventNodeState::DRAG_IN.bits();
             }
+            ui_system.press_path.clear();
         }
     }
 
     if params.input.is_mouse_move {
+        if !ui_system.press_path.is_empty() {
+            if ui_system.drag_path.is_empty() {
+                //拖动的目标是按下路径上最深的可拖动节点
+                let drag_index = ui_system.press_path.iter().rposition(|e| {
+                    event_nodes.get(*e).map(|(_,n)| n.event_type.intersects(DRAG_EVENTS)).unwrap_or(false)
+                });
+                if let Some(drag_index) = drag_index {
+                    let drag_path = ui_system.press_path[..=drag_index].to_vec();
+                    for entity in drag_path.iter() {
+                        if let Ok((_,mut event_node)) = event_nodes.get_mut(*entity) {
+                            if event_node.event_type.intersects(DRAG_EVENTS) {
+                                event_node.state |= EventNodeState::DRAG_IN.bits();
+                                event_node.drag_pos = ui_pos;
+                            }
+                        }
+                    }
+                    dispatch_path(&drag_path, UIEventType::BEGIN_DRAG, MouseButton::Left, ui_pos, params, event_nodes, sender);
+                    ui_system.drag_path = drag_path;
+                    ui_system.drag_pos = ui_pos;
+                }
+            } else {
+                let delta = ui_pos - ui_system.drag_pos;
+                ui_system.drag_pos = ui_pos;
+                for entity in ui_system.drag_path.iter() {
+                    if let Ok((_,mut event_node)) = event_nodes.get_mut(*entity) {
+                        event_node.drag_pos = ui_pos;
+                    }
+                }
+                dispatch_path(&ui_system.drag_path, UIEventType::DRAG, MouseButton::Left, delta, params, event_nodes, sender);
+            }
+        }
+
+        //进入和离开不冒泡,每个节点单独判断
         let mouse_pos = mouse_pos_to_world(params.input.mouse_position, system_entity, params);
-        let ui_pos = mouse_pos_to_ui(params.input.mouse_position,params);
         for (entity,mut event_node) in event_nodes.iter_mut() {
             let mut is_in_rect = false;
             if let Ok((_,Some(rect),t,state)) = params.infos.get(entity) {
@@ -65,47 +132,11 @@ pub fn ui_system_handle(system_entity:Entity,_:&UIEventSystem,params:&EventParam
                 }
             }
 
-            if event_node.event_type.bits() & UIEventType::BEGIN_DRAG.bits() != 0 {
-                if event_node.state & EventNodeState::TOUCH_IN.bits() > 0 
-                   && is_in_rect
-                   && event_node.state & EventNodeState::DRAG_IN.bits() == 0 {
-                    event_node.state |= EventNodeState::DRAG_IN.bits();
-                    event_node.drag_pos = ui_pos;
-                    sender.send(UIEvent {
-                        btn:MouseButton::Left,
-                        entity:entity,
-                        event_type:UIEventType::BEGIN_DRAG,
-                        user_key:event_node.user_key.clone(),
-                        pos:ui_pos
-                    });
-                }
-            }
-
-            if event_node.event_type.bits() & UIEventType::DRAG.bits() != 0 {
-                if event_node.state & EventNodeState::DRAG_IN.bits() > 0 {
-                    let delta = ui_pos - event_node.drag_pos;
-                    event_node.drag_pos = ui_pos;
-                    sender.send(UIEvent {
-                        btn:MouseButton::Left,
-                        entity:entity,
-                        event_type:UIEventType::DRAG,
-                        user_key:event_node.user_key.clone(),
-                        pos:delta
-                    });
-                }
-            }
-
             if event_node.event_type.bits() & UIEventType::MOUSE_ENTER.bits() != 0 {
                 if event_node.state & EventNodeState::MOVE_IN.bits() == 0 {
                     if is_in_rect {
                         event_node.state |= EventNodeState::MOVE_IN.bits();
-                        sender.send(UIEvent {
-                            btn:MouseButton::Left,
-                            entity:entity,
-                            event_type:UIEventType::MOUSE_ENTER,
-                            user_key:event_node.user_key.clone(),
-                            pos:mouse_pos
-                        });  
+                        sender.send(UIEvent::new(entity, UIEventType::MOUSE_ENTER, MouseButton::Left, event_node.user_key.clone(), mouse_pos));
                     }
                 }
             }
@@ -113,17 +144,11 @@ pub fn ui_system_handle(system_entity:Entity,_:&UIEventSystem,params:&EventParam
                 if event_node.state & EventNodeState::MOVE_IN.bits() != 0 {
                     if !is_in_rect {
                         event_node.state &= !EventNodeState::MOVE_IN.bits();
-                        sender.send(UIEvent {
-                            btn:MouseButton::Left,
-                            entity,
-                            event_type:UIEventType::MOUSE_LEAVE,
-                            user_key:event_node.user_key.clone(),
-                            pos:mouse_pos
-                        });  
+                        sender.send(UIEvent::new(entity, UIEventType::MOUSE_LEAVE, MouseButton::Left, event_node.user_key.clone(), mouse_pos));
                     }
                 }
             }
-            
+
         }
     }
 }
@@ -144,132 +169,201 @@ fn mouse_pos_to_ui(mut mouse_pos:Vec2,params:&EventParams) -> Vec2 {
     mouse_pos
 }
 
-fn capture_ui_system(system_entity:Entity,fire_type:UIEventType,
-                     mouse_pos:Vec2,params:&EventParams,
-                     event_nodes:&mut Query<(Entity,&mut EventNode)>,
-                     sender:&mut EventWriter<UIEvent>,btn:MouseButton) {
+//命中的最深的EventNode,以及它到根节点路径上所有的EventNode,从根到目标排列
+fn hit_path(system_entity:Entity,mouse_pos:Vec2,params:&EventParams,event_nodes:&Query<(Entity,&mut EventNode)>) -> Vec<Entity> {
+    let mut target = None;
     if let Ok(system_child) = params.childs.get(system_entity) {
         for child_entity in system_child.iter() {
-           let target_entity = capture_event_node(*child_entity,fire_type,mouse_pos,false,params, event_nodes,sender,btn);
-           if let Some(target_entity) = target_entity {
-               //println!("hit target:{:?}",target_entity);
-               bubble_event_node(target_entity, fire_type, false, mouse_pos, params, event_nodes, sender,btn);
-           }
+            if let Some(hit_entity) = hit_test(*child_entity, mouse_pos, params, event_nodes) {
+                target = Some(hit_entity);
+            }
+        }
+    }
+    let mut path = vec![];
+    let mut cur_entity = target;
+    while let Some(entity) = cur_entity {
+        if entity == system_entity { break; }
+        if event_nodes.contains(entity) {
+            path.push(entity);
         }
+        cur_entity = params.parent.get(entity).ok().map(|v| v.0);
     }
+    path.reverse();
+    path
 }
 
-fn capture_event_node(event_entity:Entity,
-                      fire_type:UIEventType,
-                      mouse_pos:Vec2,
-                      mut stop_capture:bool,
-                      params:&EventParams,
-                      event_nodes:&mut Query<(Entity,&mut EventNode)>,
-                      sender:&mut EventWriter<UIEvent>,btn:MouseButton) -> Option<Entity> {
+fn hit_test(entity:Entity,mouse_pos:Vec2,params:&EventParams,event_nodes:&Query<(Entity,&mut EventNode)>) -> Option<Entity> {
     let mut last_hit_entity = None;
-    if let Err(err) =  params.infos.get(event_entity) {
-        log::error!("capture_event_node error:{:?}",err);
+    if let Err(err) = params.infos.get(entity) {
+        log::error!("hit_test error:{:?}",err);
     }
-    if let Ok((_,Some(rect2d),t,state)) = params.infos.get(event_entity) {
+    if let Ok((_,Some(rect2d),t,state)) = params.infos.get(entity) {
         let is_active = state.map(|v| v.is_active_global()).unwrap_or(true);
         if !is_active || !rect2d.test(t.global(), mouse_pos) {
             return None;
         }
-        
-        if let Ok((_,mut event_node)) = event_nodes.get_mut(event_entity) {
-
-            last_hit_entity = Some(event_entity);
-            if !stop_capture {
-                if event_node.stop_capture {
-                    stop_capture = true;
-                }
-                let is_click = (event_node.event_type.bits() & UIEventType::CLICK.bits() != 0) && 
-                               fire_type == UIEventType::TOUCH_END 
-                               && (event_node.state & EventNodeState::TOUCH_IN.bits() != 0);
-                let is_start_or_end = event_node.event_type.bits() & fire_type.bits() != 0;
-                if event_node.use_capture {
-                    if is_start_or_end {
-                        sender.send(UIEvent {
-                            entity:event_entity,
-                            event_type:fire_type,
-                            btn,
-                            user_key:event_node.user_key.clone(),
-                            pos:mouse_pos
-                        });
-                    }
-                    if is_click {
-                        sender.send(UIEvent {
-                            entity:event_entity,
-                            btn,
-                            event_type:UIEventType::CLICK,
-                            user_key:event_node.user_key.clone(),
-                            pos:mouse_pos
-                        });
-                    }
-                }
-            }
-
-            if fire_type == UIEventType::TOUCH_START {
-                event_node.state |= EventNodeState::TOUCH_IN.bits();
-            }
+        if event_nodes.contains(entity) {
+            last_hit_entity = Some(entity);
         }
-        
-        if let Ok(child) = params.childs.get(event_entity) {
+        if let Ok(child) = params.childs.get(entity) {
             for child_entity in child.iter() {
-                let capture_entity = capture_event_node(*child_entity,fire_type,mouse_pos,stop_capture, params, event_nodes,sender,btn);
-                if capture_entity.is_some() {
-                    last_hit_entity = capture_entity;
+                let hit_entity = hit_test(*child_entity, mouse_pos, params, event_nodes);
+                if hit_entity.is_some() {
+                    last_hit_entity = hit_entity;
                 }
             }
         }
-    } else {
-        
     }
     last_hit_entity
 }
 
+//分发时需要读取路径上节点的EventNode和EventListener,不同系统中的Query类型不同
+pub(crate) trait EventNodeSource {
+    fn node(&self,entity:Entity) -> Option<&EventNode>;
+    fn listener(&self,entity:Entity) -> Option<&EventListener>;
+}
 
-fn bubble_event_node(event_entity:Entity,
-                     fire_type:UIEventType,
-                     mut stop_bubble:bool,
-                     mouse_pos:Vec2,
-                     params:&EventParams,
-                     event_nodes:&mut Query<(Entity,&mut EventNode)>,
-                     sender:&mut EventWriter<UIEvent>,btn:MouseButton) {
-    //println!("bubble:{:?}",event_entity);
-    if let Ok((_,event_node)) = event_nodes.get_mut(event_entity) {
-        if !stop_bubble {
-            let is_click =  (event_node.event_type.bits() & UIEventType::CLICK.bits() != 0) && 
-                            fire_type == UIEventType::TOUCH_END && 
-                            (event_node.state & EventNodeState::TOUCH_IN.bits() != 0);
-            let is_start_or_end = event_node.event_type.bits() & fire_type.bits() != 0;
-            if event_node.use_capture == false {
-                if is_start_or_end {
-                    sender.send(UIEvent {
-                        btn,
-                        entity:event_entity,
-                        event_type:fire_type,
-                        user_key:event_node.user_key.clone(),
-                        pos:mouse_pos
-                    });
-                }
-                if is_click {
-                    sender.send(UIEvent {
-                        btn,
-                        entity:event_entity,
-                        event_type:UIEventType::CLICK,
-                        user_key:event_node.user_key.clone(),
-                        pos:mouse_pos
-                    });
-                }
-            }
-            if event_node.stop_bubble {
-                stop_bubble = true;
-            }  
+impl EventNodeSource for World {
+    fn node(&self,entity:Entity) -> Option<&EventNode> { self.get::<EventNode>(entity) }
+    fn listener(&self,entity:Entity) -> Option<&EventListener> { self.get::<EventListener>(entity) }
+}
+
+struct SystemNodes<'a,'w,'s,'q> {
+    params:&'a EventParams<'w,'s>,
+    nodes:&'a Query<'w,'s,(Entity,&'q mut EventNode)>
+}
+
+impl<'a,'w,'s,'q> EventNodeSource for SystemNodes<'a,'w,'s,'q> {
+    fn node(&self,entity:Entity) -> Option<&EventNode> { self.nodes.get(entity).ok().map(|(_,node)| node) }
+    fn listener(&self,entity:Entity) -> Option<&EventListener> { self.params.listeners.get(entity).ok() }
+}
+
+fn dispatch_path(path:&[Entity],typ:UIEventType,btn:MouseButton,pos:Vec2,params:&EventParams,
+                 event_nodes:&Query<(Entity,&mut EventNode)>,sender:&mut EventWriter<UIEvent>) {
+    let source = SystemNodes { params,nodes:event_nodes };
+    dispatch_event(path, typ, btn, pos, &source, &mut |ev| sender.send(ev));
+}
+
+//沿路径分发事件:use_capture的节点在捕获阶段从根向下收到,目标节点总是收到,其他节点在冒泡阶段从下向上收到
+//stop_capture在捕获阶段,stop_bubble在目标和冒泡阶段,或者回调里调用stop_propagation会停止后续的分发
+pub(crate) fn dispatch_event(path:&[Entity],typ:UIEventType,btn:MouseButton,pos:Vec2,source:&impl EventNodeSource,send:&mut impl FnMut(UIEvent)) {
+    let (target,parents) = match path.split_last() {
+        Some(v) => v,
+        None => return
+    };
+    let mut handled = false;
+    for entity in parents.iter() {
+        let (is_receive,is_stop) = match source.node(*entity) {
+            Some(node) => (node.use_capture && node.event_type.intersects(typ),node.stop_capture),
+            None => continue
+        };
+        if is_receive && deliver_event(*entity, *target, typ, UIEventPhase::Capture, btn, pos, &mut handled, source, send) {
+            return;
         }
+        if is_stop { return; }
     }
 
-    if let Ok(parent) = params.parent.get(event_entity) {
-        bubble_event_node(parent.0,fire_type,stop_bubble,mouse_pos,params,event_nodes,sender,btn);
+    if let Some(node) = source.node(*target) {
+        let (is_receive,is_stop) = (node.event_type.intersects(typ),node.stop_bubble);
+        if is_receive && deliver_event(*target, *target, typ, UIEventPhase::Target, btn, pos, &mut handled, source, send) {
+            return;
+        }
+        if is_stop { return; }
     }
-}
\ No newline at end of file
+
+    for entity in parents.iter().rev() {
+        let (is_receive,is_stop) = match source.node(*entity) {
+            Some(node) => (!node.use_capture && node.event_type.intersects(typ),node.stop_bubble),
+            None => continue
+        };
+        if is_receive && deliver_event(*entity, *target, typ, UIEventPhase::Bubble, btn, pos, &mut handled, source, send) {
+            return;
+        }
+        if is_stop { return; }
+    }
+}
+
+//返回是否停止传播
+fn deliver_event(entity:Entity,target:Entity,typ:UIEventType,phase:UIEventPhase,btn:MouseButton,pos:Vec2,handled:&mut bool,
+                 source:&impl EventNodeSource,send:&mut impl FnMut(UIEvent)) -> bool {
+    let (user_key,handle_event) = match source.node(entity) {
+        Some(node) => (node.user_key.clone(),node.handle_event),
+        None => return false
+    };
+    let event = UIEvent { entity,event_type:typ,btn,user_key,pos,target,phase,handled:*handled };
+    let mut ctx = UIEventContext { event:&event,is_stop:false,is_handled:*handled };
+    if let Some(listener) = source.listener(entity) {
+        listener.call(&mut ctx);
+    }
+    let is_stop = ctx.is_stop;
+    *handled = ctx.is_handled || handle_event.intersects(typ);
+    send(event);
+    is_stop
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spawn_node(world:&mut World,use_capture:bool) -> Entity {
+        world.spawn(EventNode { event_type:UIEventType::CLICK,use_capture,..Default::default() }).id()
+    }
+
+    fn dispatch(world:&World,path:&[Entity]) -> Vec<(Entity,UIEventPhase,bool)> {
+        let mut events = vec![];
+        dispatch_event(path, UIEventType::CLICK, MouseButton::Left, Vec2::ZERO, world, &mut |ev| events.push(ev));
+        assert!(events.iter().all(|ev| ev.target == *path.last().unwrap()));
+        events.into_iter().map(|ev| (ev.entity,ev.phase,ev.handled)).collect()
+    }
+
+    #[test]
+    fn capture_target_bubble() {
+        let mut world = World::new();
+        let capture = spawn_node(&mut world, true);
+        let bubble0 = spawn_node(&mut world, false);
+        let bubble1 = spawn_node(&mut world, false);
+        let target = spawn_node(&mut world, true);
+        let skip = spawn_node(&mut world, false);
+        world.get_mut::<EventNode>(skip).unwrap().event_type = UIEventType::DRAG;
+        let path = [capture,bubble0,skip,bubble1,target];
+        assert_eq!(dispatch(&world, &path), vec![(capture,UIEventPhase::Capture,false),(target,UIEventPhase::Target,false),
+                                                 (bubble1,UIEventPhase::Bubble,false),(bubble0,UIEventPhase::Bubble,false)]);
+        assert!(dispatch(&world, &[]).is_empty());
+
+        //stop_bubble的节点自己会收到,后面的节点收不到
+        world.get_mut::<EventNode>(bubble1).unwrap().stop_bubble = true;
+        assert_eq!(dispatch(&world, &path).len(), 3);
+        world.get_mut::<EventNode>(capture).unwrap().stop_capture = true;
+        assert_eq!(dispatch(&world, &path), vec![(capture,UIEventPhase::Capture,false)]);
+    }
+
+    #[test]
+    fn stop_propagation_and_handled() {
+        let mut world = World::new();
+        let capture = spawn_node(&mut world, true);
+        let bubble = spawn_node(&mut world, false);
+        let target = spawn_node(&mut world, false);
+        let path = [capture,bubble,target];
+
+        world.get_mut::<EventNode>(target).unwrap().handle_event = UIEventType::CLICK;
+        assert_eq!(dispatch(&world, &path), vec![(capture,UIEventPhase::Capture,false),(target,UIEventPhase::Target,false),
+                                                 (bubble,UIEventPhase::Bubble,true)]);
+        world.get_mut::<EventNode>(target).unwrap().handle_event = UIEventType::NONE;
+
+        let mut listener = EventListener::default();
+        listener.add(UIEventType::CLICK, |ctx| ctx.set_handled());
+        world.entity_mut(capture).insert(listener);
+        assert_eq!(dispatch(&world, &path), vec![(capture,UIEventPhase::Capture,false),(target,UIEventPhase::Target,true),
+                                                 (bubble,UIEventPhase::Bubble,true)]);
+
+        let mut listener = EventListener::default();
+        listener.add(UIEventType::CLICK, |ctx| ctx.stop_propagation());
+        world.entity_mut(target).insert(listener);
+        assert_eq!(dispatch(&world, &path).len(), 2);
+        //回调只处理注册的类型
+        let mut listener = EventListener::default();
+        listener.add(UIEventType::DRAG, |ctx| ctx.stop_propagation());
+        world.entity_mut(target).insert(listener);
+        assert_eq!(dispatch(&world, &path).len(), 3);
+    }
+}
@@ -5,11 +5,32 @@ use seija_input::event::MouseButton;
 
 #[derive(Clone,Debug)]
 pub struct UIEvent {
+    ///当前收到事件的节点
     pub entity:Entity,
     pub event_type:UIEventType,
     pub btn:MouseButton,
     pub user_key:Option<SmolStr>,
-    pub pos:Vec2
+    pub pos:Vec2,
+    ///最初命中的节点
+    pub target:Entity,
+    pub phase:UIEventPhase,
+    ///路径上之前的节点是否已经处理过这个事件
+    pub handled:bool
+}
+
+impl UIEvent {
+    pub fn new(entity:Entity,event_type:UIEventType,btn:MouseButton,user_key:Option<SmolStr>,pos:Vec2) -> UIEvent {
+        UIEvent { entity,event_type,btn,user_key,pos,target:entity,phase:UIEventPhase::Target,handled:false }
+    }
+}
+
+///事件从根节点向下捕获,到达目标,再向上冒泡
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+#[repr(u8)]
+pub enum UIEventPhase {
+    Capture,
+    Target,
+    Bubble
 }
 
 bitflags! {
@@ -23,6 +44,16 @@ bitflags! {
         const BEGIN_DRAG  = 0b00100000;
         const DRAG        = 0b01000000;
         const END_DRAG    = 0b10000000;
+        const FOCUS_IN    = 0b100000000;
+        const FOCUS_OUT   = 0b1000000000;
+        const SUBMIT      = 0b10000000000;
+        //手势事件,pos是UI坐标的手势位置,SWIPE的pos是滑动距离,PINCH的pos.x是缩放比例,ROTATE的pos.x是逆时针旋转的弧度
+        const TAP         = 0b100000000000;
+        const DOUBLE_TAP  = 0b1000000000000;
+        const LONG_PRESS  = 0b10000000000000;
+        const SWIPE       = 0b100000000000000;
+        const PINCH       = 0b1000000000000000;
+        const ROTATE      = 0b10000000000000000;
     }
 }
 
@@ -48,7 +79,9 @@ pub struct EventNode {
     ///是否使用捕获模式
     pub use_capture:bool,
     pub user_key:Option<SmolStr>,
-    pub drag_pos:Vec2
+    pub drag_pos:Vec2,
+    ///收到这些类型的事件后标记为已处理,后面的节点收到的事件handled为true
+    pub handle_event:UIEventType
 }
 
 impl Default for EventNode {
@@ -60,7 +93,8 @@ impl Default for EventNode {
             use_capture:true,
             user_key:None,
             event_type:UIEventType::NONE,
-            drag_pos:Vec2::ZERO
+            drag_pos:Vec2::ZERO,
+            handle_event:UIEventType::NONE
         }
     }
 }
@@ -75,13 +109,54 @@ impl EventNode {
     }
 }
 
-#[derive(Component)]
+#[derive(Component,Default)]
 pub struct UIEventSystem {
+    ///按下时命中的节点路径,从根到目标
+    pub(crate) press_path:Vec<Entity>,
+    ///正在拖动的节点路径
+    pub(crate) drag_path:Vec<Entity>,
+    pub(crate) drag_pos:Vec2
 }
 
-impl Default for UIEventSystem {
-    fn default() -> Self {
-        Self {
+///事件分发时的上下文,回调里可以停止传播或者标记为已处理
+pub struct UIEventContext<'a> {
+    pub event:&'a UIEvent,
+    pub(crate) is_stop:bool,
+    pub(crate) is_handled:bool
+}
+
+impl<'a> UIEventContext<'a> {
+    pub fn stop_propagation(&mut self) {
+        self.is_stop = true;
+    }
+
+    pub fn set_handled(&mut self) {
+        self.is_handled = true;
+    }
+
+    pub fn is_handled(&self) -> bool {
+        self.is_handled
+    }
+}
+
+pub type UIEventCallback = Box<dyn Fn(&mut UIEventContext) + Send + Sync>;
+
+///分发过程中同步调用的事件回调
+#[derive(Component,Default)]
+pub struct EventListener {
+    callbacks:Vec<(UIEventType,UIEventCallback)>
+}
+
+impl EventListener {
+    pub fn add(&mut self,typ:UIEventType,f:impl Fn(&mut UIEventContext) + Send + Sync + 'static) {
+        self.callbacks.push((typ,Box::new(f)));
+    }
+
+    pub(crate) fn call(&self,ctx:&mut UIEventContext) {
+        for (typ,f) in self.callbacks.iter() {
+            if typ.intersects(ctx.event.event_type) {
+                f(ctx);
+            }
         }
     }
 }
\ No newline at end of file
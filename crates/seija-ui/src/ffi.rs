@@ -7,9 +7,10 @@ use seija_render::RenderConfig;
 use spritesheet::SpriteSheet;
 use seija_2d::common::{Rect2D,types::Thickness};
 use crate::{
-    components::{canvas::Canvas, sprite::Sprite, ui_canvas::UICanvas, input::{Input, InputTextSystemData}},
+    components::{canvas::Canvas, sprite::Sprite, ui_canvas::UICanvas, input::{Input, InputTextSystemData},
+                 widget::WidgetEvent, focus::{Focusable, FocusSystemData, FocusCommand, FocusDirection}, button::Button, toggle::Toggle, slider::Slider, scroll_view::ScrollView, list_view::ListView},
     event::{UIEventSystem, EventNode, UIEvent},
-    update_ui_render, UIModule, layout::{comps::{Orientation, StackLayout, FlexLayout, FlexItem}, types::{LayoutElement, CommonView, UISize, SizeValue, TypeElement, FreeLayoutItem}}, text::{Text, Font},
+    update_ui_render, UIModule, layout::{comps::{Orientation, StackLayout, FlexLayout, FlexItem}, types::{LayoutElement, CommonView, UISize, SizeValue, TypeElement, FreeLayoutItem}}, text::{Text, Font, RichTextInfo, TextEffect},
 };
 
 #[no_mangle]
@@ -155,6 +156,7 @@ pub unsafe extern "C" fn entity_add_event_node(world: &mut World,entity_id:u64,n
     event_node.stop_bubble = node.stop_bubble;
     event_node.stop_capture = node.stop_capture;
     event_node.use_capture = node.use_capture;
+    event_node.handle_event = node.handle_event;
     world.entity_mut(entity).insert(event_node);
 }
 
@@ -347,11 +349,65 @@ pub unsafe extern "C" fn entity_add_text(world: &mut World,entity_id: u64,text:&
         text:text_string.into(),
         line_mode: text.line_mode,
         font:Some(Handle::strong(HandleId::new(Font::TYPE_UUID, font_id), ref_sender)),
-        is_auto_size: text.is_auto_size
+        is_auto_size: text.is_auto_size,
+        rich:false,
+        bold_font:None,
+        sprite_sheet:None,
+        sdf:false,
+        effect:TextEffect::default(),
+        world_space:false
     };
     world.entity_mut(entity).insert(new_text);
 }
 
+//bold_font_id和sprite_sheet_id为0时不使用
+#[no_mangle]
+pub unsafe extern "C" fn entity_text_set_rich(world: &mut World,entity_id: u64,rich:bool,bold_font_id:u64,sprite_sheet_id:u64) {
+    let entity = Entity::from_bits(entity_id);
+    let ref_sender = world.get_resource::<AssetServer>().clone().unwrap().get_ref_sender();
+    if let Some(mut text) = world.entity_mut(entity).get_mut::<Text>() {
+        text.rich = rich;
+        text.bold_font = if bold_font_id > 0 { Some(Handle::strong(HandleId::new(Font::TYPE_UUID, bold_font_id), ref_sender.clone())) } else { None };
+        text.sprite_sheet = if sprite_sheet_id > 0 { Some(Handle::strong(HandleId::new(SpriteSheet::TYPE_UUID, sprite_sheet_id), ref_sender)) } else { None };
+    }
+}
+
+//effect为空时保持原来的效果参数
+#[no_mangle]
+pub unsafe extern "C" fn entity_text_set_sdf(world: &mut World,entity_id: u64,sdf:bool,effect:*const TextEffect) {
+    let entity = Entity::from_bits(entity_id);
+    if let Some(mut text) = world.entity_mut(entity).get_mut::<Text>() {
+        text.sdf = sdf;
+        if let Some(effect) = effect.as_ref() {
+            text.effect = *effect;
+        }
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn entity_text_set_world_space(world: &mut World,entity_id: u64,world_space:bool) {
+    let entity = Entity::from_bits(entity_id);
+    if let Some(mut text) = world.entity_mut(entity).get_mut::<Text>() {
+        text.world_space = world_space;
+    }
+}
+
+//把链接的字符串写入out,返回字节长度,out_len不够时只返回长度,找不到返回-1
+#[no_mangle]
+pub unsafe extern "C" fn entity_text_get_link(world: &mut World,entity_id: u64,index:i32,out:*mut u8,out_len:usize) -> i32 {
+    let entity = Entity::from_bits(entity_id);
+    let link = world.entity(entity).get::<RichTextInfo>().and_then(|info| info.links.get(index as usize));
+    match link {
+        Some(link) => {
+            if !out.is_null() && link.link.len() <= out_len {
+                std::ptr::copy_nonoverlapping(link.link.as_ptr(), out, link.link.len());
+            }
+            link.link.len() as i32
+        },
+        None => -1
+    }
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn entity_get_text(world: &mut World,entity_id: u64) -> *mut Text {
     let entity = Entity::from_bits(entity_id);
@@ -400,6 +456,163 @@ pub unsafe extern "C" fn input_set_string(input_mut:&mut Input,cstr:*mut i8) {
 }
 
 
+////////widget/////////
+#[no_mangle]
+pub unsafe extern "C" fn entity_add_button(world: &mut World,entity_id:u64,normal:i32,hover:i32,pressed:i32,disabled:i32) {
+    let entity = Entity::from_bits(entity_id);
+    let button = Button::new(normal as usize, hover as usize, pressed as usize, disabled as usize);
+    world.entity_mut(entity).insert(button);
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn entity_get_button(world: &mut World,entity_id:u64) -> *mut Button {
+    let entity = Entity::from_bits(entity_id);
+    match world.entity_mut(entity).get_mut::<Button>() {
+        Some(mut ptr) => ptr.as_mut() as *mut Button,
+        None => std::ptr::null_mut()
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn entity_add_toggle(world: &mut World,entity_id:u64,is_on:bool,check_entity_id:u64) {
+    let entity = Entity::from_bits(entity_id);
+    let check_entity = if check_entity_id == 0 { None } else { Some(Entity::from_bits(check_entity_id)) };
+    world.entity_mut(entity).insert(Toggle::new(is_on, check_entity));
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn entity_get_toggle(world: &mut World,entity_id:u64) -> *mut Toggle {
+    let entity = Entity::from_bits(entity_id);
+    match world.entity_mut(entity).get_mut::<Toggle>() {
+        Some(mut ptr) => ptr.as_mut() as *mut Toggle,
+        None => std::ptr::null_mut()
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn entity_add_slider(world: &mut World,entity_id:u64,value:f32,min:f32,max:f32,ori:u8,handle_id:u64,fill_id:u64) {
+    let entity = Entity::from_bits(entity_id);
+    let mut slider = Slider::new(value, min, max, Orientation::from_primitive(ori));
+    if handle_id != 0 { slider.handle_entity = Some(Entity::from_bits(handle_id)); }
+    if fill_id != 0 { slider.fill_entity = Some(Entity::from_bits(fill_id)); }
+    world.entity_mut(entity).insert(slider);
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn entity_get_slider(world: &mut World,entity_id:u64) -> *mut Slider {
+    let entity = Entity::from_bits(entity_id);
+    match world.entity_mut(entity).get_mut::<Slider>() {
+        Some(mut ptr) => ptr.as_mut() as *mut Slider,
+        None => std::ptr::null_mut()
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn entity_add_scroll_view(world: &mut World,entity_id:u64,content_id:u64,horizontal:bool,vertical:bool) {
+    let entity = Entity::from_bits(entity_id);
+    let content_entity = Entity::from_bits(content_id);
+    world.entity_mut(entity).insert(ScrollView::new(content_entity, horizontal, vertical));
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn scroll_view_set_offset(world: &mut World,entity_id:u64,offset:&Vec2) {
+    let entity = Entity::from_bits(entity_id);
+    if let Some(mut view) = world.entity_mut(entity).get_mut::<ScrollView>() {
+        view.set_offset(*offset);
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn entity_add_list_view(world: &mut World,entity_id:u64,count:i32,item_size:f32,spacing:f32,ori:u8) {
+    let entity = Entity::from_bits(entity_id);
+    let list = ListView::new(count.max(0) as usize, item_size, spacing, Orientation::from_primitive(ori));
+    world.entity_mut(entity).insert(list);
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn list_view_set_count(world: &mut World,entity_id:u64,count:i32) {
+    let entity = Entity::from_bits(entity_id);
+    if let Some(mut list) = world.entity_mut(entity).get_mut::<ListView>() {
+        list.set_count(count.max(0) as usize);
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn list_view_refresh(world: &mut World,entity_id:u64) {
+    let entity = Entity::from_bits(entity_id);
+    if let Some(mut list) = world.entity_mut(entity).get_mut::<ListView>() {
+        list.refresh();
+    }
+}
+
+//typ: 0 Click,1 ToggleChanged(x为0/1),2 SliderChanged(x为值),3 ScrollChanged(x,y为offset),4 ListItemBind(y为1时是新item),5 LinkClick(index为链接序号)
+#[no_mangle]
+pub unsafe extern "C" fn read_widget_events(world: &mut World,f:extern fn(entity:u64,typ:u32,item:u64,index:i32,x:f32,y:f32)) {
+    let events = world.get_resource_mut::<Events<WidgetEvent>>().unwrap();
+    let mut reader:ManualEventReader<WidgetEvent> = events.get_reader();
+    for event in reader.iter(&events) {
+        let entity = event.entity().to_bits();
+        match event {
+            WidgetEvent::Click(_) => f(entity,0,0,0,0f32,0f32),
+            WidgetEvent::ToggleChanged(_, is_on) => f(entity,1,0,0,if *is_on { 1f32 } else { 0f32 },0f32),
+            WidgetEvent::SliderChanged(_, value) => f(entity,2,0,0,*value,0f32),
+            WidgetEvent::ScrollChanged(_, offset) => f(entity,3,0,0,offset.x,offset.y),
+            WidgetEvent::ListItemBind { item, index, is_new, .. } => {
+                f(entity,4,item.to_bits(),*index as i32,0f32,if *is_new { 1f32 } else { 0f32 })
+            },
+            WidgetEvent::LinkClick { index, .. } => f(entity,5,0,*index as i32,0f32,0f32)
+        }
+    }
+}
+
+////////focus/////////
+#[no_mangle]
+pub unsafe extern "C" fn entity_add_focusable(world: &mut World,entity_id:u64,tab_index:i32) {
+    let entity = Entity::from_bits(entity_id);
+    world.entity_mut(entity).insert(Focusable { tab_index,interactable:true });
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn entity_get_focusable(world: &mut World,entity_id:u64) -> *mut Focusable {
+    let entity = Entity::from_bits(entity_id);
+    match world.entity_mut(entity).get_mut::<Focusable>() {
+        Some(mut ptr) => ptr.as_mut() as *mut Focusable,
+        None => std::ptr::null_mut()
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ui_set_focus_ring(world: &mut World,entity_id:u64,padding:f32) {
+    if let Some(mut data) = world.get_resource_mut::<FocusSystemData>() {
+        data.ring_entity = if entity_id == 0 { None } else { Some(Entity::from_bits(entity_id)) };
+        data.ring_padding = padding;
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ui_get_focused(world: &World) -> u64 {
+    world.get_resource::<FocusSystemData>().and_then(|v| v.focused()).map(|e| e.to_bits()).unwrap_or(0)
+}
+
+//typ: 0 Next,1 Prev,2 Up,3 Down,4 Left,5 Right,6 Submit,7 Set(entity),其他 Clear
+#[no_mangle]
+pub unsafe extern "C" fn ui_send_focus_command(world: &mut World,typ:u8,entity_id:u64) {
+    let command = match typ {
+        0 => FocusCommand::Next,
+        1 => FocusCommand::Prev,
+        2 => FocusCommand::Move(FocusDirection::Up),
+        3 => FocusCommand::Move(FocusDirection::Down),
+        4 => FocusCommand::Move(FocusDirection::Left),
+        5 => FocusCommand::Move(FocusDirection::Right),
+        6 => FocusCommand::Submit,
+        7 => FocusCommand::Set(Entity::from_bits(entity_id)),
+        _ => FocusCommand::Clear
+    };
+    if let Some(mut events) = world.get_resource_mut::<Events<FocusCommand>>() {
+        events.send(command);
+    }
+}
+
 
 type PostLayoutProcessF = extern fn(step:i32,vec_ptr:*mut Vec<u64>);
 #[no_mangle]
@@ -1,5 +1,7 @@
 use bevy_ecs::prelude::Component;
 use seija_core::math::Vec2;
+use seija_core::impl_reflect_enum;
+use seija_core::reflect::{Reflect, IReflectValue, ReflectType, ReflectValue};
 use num_enum::FromPrimitive;
 
 use super::types::{UISize, SizeValue};
@@ -11,6 +13,8 @@ pub enum Orientation {
     Horizontal,
     Vertical,
 }
+
+impl_reflect_enum!(Orientation,Horizontal,Vertical);
 #[repr(C)]
 pub struct StackLayout {
     pub spacing:f32,
@@ -59,13 +63,14 @@ impl Default for FlexLayout {
     }
 }
 
-#[derive(Component,Clone)]
+#[derive(Component,Reflect,Clone)]
 #[repr(C)]
 pub struct FlexItem {
     pub order:i32,
     pub grow:f32,
     pub shrink:f32,
     pub basis:FlexBasis,
+    #[reflect(name = "alignSelf")]
     pub align_self:FlexAlignSelf
 }
 
@@ -94,6 +99,8 @@ pub enum FlexDirection {
     
 }
 
+impl_reflect_enum!(FlexDirection,Row,RowReverse,Column,ColumnReverse);
+
 
 #[derive(Clone,Copy,Hash,PartialEq,Eq,Debug)]
 #[repr(u8)]
@@ -102,6 +109,8 @@ pub enum FlexWrap {
     Wrap
 }
 
+impl_reflect_enum!(FlexWrap,NoWrap,Wrap);
+
 #[derive(Clone, Copy,Hash,PartialEq,Eq,Debug)]
 #[repr(u8)]
 pub enum FlexJustify {
@@ -112,6 +121,8 @@ pub enum FlexJustify {
     SpaceAround
 }
 
+impl_reflect_enum!(FlexJustify,Start,Center,End,SpaceBetween,SpaceAround);
+
 #[derive(Clone, Copy,Hash,PartialEq,Eq,Debug)]
 #[repr(u8)]
 pub enum FlexAlignItems {
@@ -121,6 +132,8 @@ pub enum FlexAlignItems {
     End
 }
 
+impl_reflect_enum!(FlexAlignItems,Stretch,Center,Start,End);
+
 #[derive(Clone,Copy,Hash,PartialEq,Eq,Debug)]
 #[repr(u8)]
 pub enum  FlexAlignContent {
@@ -132,6 +145,8 @@ pub enum  FlexAlignContent {
     SpaceAround
 }
 
+impl_reflect_enum!(FlexAlignContent,Stretch,Center,Start,End,SpaceBetween,SpaceAround);
+
 #[derive(Clone, Copy,Hash,PartialEq, Eq)]
 #[repr(C)]
 pub enum FlexAlignSelf {
@@ -142,6 +157,8 @@ pub enum FlexAlignSelf {
     End
 }
 
+impl_reflect_enum!(FlexAlignSelf,Auto,Stretch,Center,Start,End);
+
 #[repr(C)]
 #[derive(Clone)]
 pub struct FlexBasis {
@@ -149,6 +166,26 @@ pub struct FlexBasis {
     pub is_relative:bool
 }
 
+//作为反射字段时写成数字,is_relative为true时加上%
+impl IReflectValue for FlexBasis {
+    const TYPE:ReflectType = ReflectType::String;
+    fn to_value(&self) -> ReflectValue {
+        let suffix = if self.is_relative { "%" } else { "" };
+        ReflectValue::String(format!("{}{}",self.length,suffix).into())
+    }
+    fn from_value(value:ReflectValue) -> Option<Self> {
+        let value = match value {
+            ReflectValue::String(v) => v,
+            _ => return None
+        };
+        let value = value.trim();
+        match value.strip_suffix('%') {
+            Some(v) => Some(FlexBasis { length:v.trim().parse().ok()?,is_relative:true }),
+            None => Some(FlexBasis { length:value.parse().ok()?,is_relative:false })
+        }
+    }
+}
+
 
 pub struct TiledLayout {
     pub item:TiledItem
@@ -1,5 +1,6 @@
 use bevy_ecs::prelude::Component;
-use seija_core::math::Vec2;
+use seija_core::{math::Vec2, impl_reflect_enum, anyhow::{Result, bail}};
+use seija_core::reflect::{Reflect, ReflectStatic, ReflectValue, ReflectType, IReflectValue, FieldInfo, field_type_error, not_found_field_error};
 use num_enum::FromPrimitive;
 use super::comps::{FlexLayout, Orientation, StackLayout, TiledItem, TiledLayout};
 use seija_2d::common::{Rect2D,types::Thickness};
@@ -13,6 +14,9 @@ pub enum LayoutAlignment {
     End = 2,
     Stretch = 3,
 }
+
+impl_reflect_enum!(LayoutAlignment,Start,Center,End,Stretch);
+
 #[derive(Default,Clone, Copy,Debug)]
 pub struct UISize {
    pub width:SizeValue,
@@ -73,6 +77,28 @@ impl Default for SizeValue {
     fn default() -> Self { Self::Auto }
 }
 
+//作为反射字段时写成数字,Auto或者Rect(使用Rect2D的大小)
+impl IReflectValue for SizeValue {
+    const TYPE:ReflectType = ReflectType::String;
+    fn to_value(&self) -> ReflectValue {
+        match self {
+            SizeValue::Auto => ReflectValue::String("Auto".into()),
+            SizeValue::PixelFromRect => ReflectValue::String("Rect".into()),
+            SizeValue::Pixel(v) => ReflectValue::String(v.to_string().into())
+        }
+    }
+    fn from_value(value:ReflectValue) -> Option<Self> {
+        match value {
+            ReflectValue::String(v) => match v.trim() {
+                "Auto" => Some(SizeValue::Auto),
+                "Rect" => Some(SizeValue::PixelFromRect),
+                v => Some(SizeValue::Pixel(v.parse().ok()?))
+            },
+            _ => None
+        }
+    }
+}
+
 #[derive(Component,Debug)]
 #[repr(C)]
 pub struct CommonView {
@@ -120,7 +146,7 @@ pub enum TypeElement {
 pub struct FreeLayout {}
 
 
-#[derive(Component,Default)]
+#[derive(Component,Reflect,Default)]
 #[repr(C)]
 pub struct FreeLayoutItem {
    pub pos:Vec2
@@ -189,4 +215,120 @@ impl LayoutElement {
             _ => true
         }
     }
-}
\ No newline at end of file
+}
+
+impl Default for LayoutElement {
+    fn default() -> Self { LayoutElement::create_view() }
+}
+
+//布局类型的参数展开成LayoutElement的字段,type切换布局类型,模板按字段顺序设置,type需要放在第一个
+//平铺布局是树形结构,不能通过反射设置
+impl ReflectStatic for LayoutElement {
+    const TYPE_NAME:&'static str = "LayoutElement";
+    const FIELDS:&'static [FieldInfo] = &[
+        FieldInfo { name:"type",typ:ReflectType::String },
+        FieldInfo { name:"margin",typ:ReflectType::Vec4 },
+        FieldInfo { name:"padding",typ:ReflectType::Vec4 },
+        FieldInfo { name:"hor",typ:ReflectType::String },
+        FieldInfo { name:"ver",typ:ReflectType::String },
+        FieldInfo { name:"useRectSize",typ:ReflectType::Bool },
+        FieldInfo { name:"pixelPerfect",typ:ReflectType::Bool },
+        FieldInfo { name:"width",typ:ReflectType::String },
+        FieldInfo { name:"height",typ:ReflectType::String },
+        FieldInfo { name:"spacing",typ:ReflectType::F32 },
+        FieldInfo { name:"orientation",typ:ReflectType::String },
+        FieldInfo { name:"direction",typ:ReflectType::String },
+        FieldInfo { name:"wrap",typ:ReflectType::String },
+        FieldInfo { name:"justify",typ:ReflectType::String },
+        FieldInfo { name:"alignItems",typ:ReflectType::String },
+        FieldInfo { name:"alignContent",typ:ReflectType::String },
+    ];
+}
+
+fn set_value<T:IReflectValue>(field:&mut T,name:&str,value:ReflectValue) -> Result<()> {
+    let typ = value.typ();
+    *field = T::from_value(value).ok_or_else(|| field_type_error(LayoutElement::TYPE_NAME, name, typ))?;
+    Ok(())
+}
+
+impl Reflect for LayoutElement {
+    fn type_name(&self) -> &'static str { Self::TYPE_NAME }
+
+    fn fields(&self) -> &'static [FieldInfo] { Self::FIELDS }
+
+    //不属于当前布局类型的字段返回None
+    fn get_field(&self,name:&str) -> Option<ReflectValue> {
+        let common = &self.common;
+        let value = match (name,&self.typ_elem) {
+            ("type",typ_elem) => {
+                let typ = match typ_elem {
+                    TypeElement::View => "View",
+                    TypeElement::Free(_) => "Free",
+                    TypeElement::Stack(_) => "Stack",
+                    TypeElement::Flex(_) => "Flex",
+                    TypeElement::Tiled(_) => "Tiled"
+                };
+                ReflectValue::String(typ.into())
+            },
+            ("margin",_) => common.margin.to_value(),
+            ("padding",_) => common.padding.to_value(),
+            ("hor",_) => common.hor.to_value(),
+            ("ver",_) => common.ver.to_value(),
+            ("useRectSize",_) => common.use_rect_size.to_value(),
+            ("pixelPerfect",_) => common.pixel_ferfact.to_value(),
+            ("width",_) => common.ui_size.width.to_value(),
+            ("height",_) => common.ui_size.height.to_value(),
+            ("spacing",TypeElement::Stack(stack)) => stack.spacing.to_value(),
+            ("orientation",TypeElement::Stack(stack)) => stack.orientation.to_value(),
+            ("direction",TypeElement::Flex(flex)) => flex.direction.to_value(),
+            ("wrap",TypeElement::Flex(flex)) => flex.warp.to_value(),
+            ("justify",TypeElement::Flex(flex)) => flex.justify.to_value(),
+            ("alignItems",TypeElement::Flex(flex)) => flex.align_items.to_value(),
+            ("alignContent",TypeElement::Flex(flex)) => flex.align_content.to_value(),
+            _ => return None
+        };
+        Some(value)
+    }
+
+    fn set_field(&mut self,name:&str,value:ReflectValue) -> Result<()> {
+        let common = &mut self.common;
+        match (name,&mut self.typ_elem) {
+            ("type",typ_elem) => {
+                let mut typ = String::default();
+                set_value(&mut typ, name, value)?;
+                //类型相同时保留原有参数
+                let same = matches!((typ.as_str(),&*typ_elem),("View",TypeElement::View) | ("Free",TypeElement::Free(_)) |
+                                                               ("Stack",TypeElement::Stack(_)) | ("Flex",TypeElement::Flex(_)));
+                if !same {
+                    *typ_elem = match typ.as_str() {
+                        "View" => TypeElement::View,
+                        "Free" => TypeElement::Free(FreeLayout {}),
+                        "Stack" => TypeElement::Stack(StackLayout { spacing:0f32,orientation:Orientation::Horizontal }),
+                        "Flex" => TypeElement::Flex(FlexLayout::default()),
+                        _ => bail!("error layout type:{}",typ)
+                    };
+                }
+                Ok(())
+            },
+            ("margin",_) => set_value(&mut common.margin, name, value),
+            ("padding",_) => set_value(&mut common.padding, name, value),
+            ("hor",_) => set_value(&mut common.hor, name, value),
+            ("ver",_) => set_value(&mut common.ver, name, value),
+            ("useRectSize",_) => set_value(&mut common.use_rect_size, name, value),
+            ("pixelPerfect",_) => set_value(&mut common.pixel_ferfact, name, value),
+            ("width",_) => set_value(&mut common.ui_size.width, name, value),
+            ("height",_) => set_value(&mut common.ui_size.height, name, value),
+            ("spacing",TypeElement::Stack(stack)) => set_value(&mut stack.spacing, name, value),
+            ("orientation",TypeElement::Stack(stack)) => set_value(&mut stack.orientation, name, value),
+            ("direction",TypeElement::Flex(flex)) => set_value(&mut flex.direction, name, value),
+            ("wrap",TypeElement::Flex(flex)) => set_value(&mut flex.warp, name, value),
+            ("justify",TypeElement::Flex(flex)) => set_value(&mut flex.justify, name, value),
+            ("alignItems",TypeElement::Flex(flex)) => set_value(&mut flex.align_items, name, value),
+            ("alignContent",TypeElement::Flex(flex)) => set_value(&mut flex.align_content, name, value),
+            ("spacing" | "orientation" | "direction" | "wrap" | "justify" | "alignItems" | "alignContent",_) => {
+                bail!("LayoutElement.{} not used by current layout type",name)
+            },
+            _ => Err(not_found_field_error(Self::TYPE_NAME, name))
+        }
+    }
+}
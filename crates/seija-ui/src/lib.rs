@@ -17,10 +17,11 @@ pub mod ffi;
 pub use seija_2d::common::Rect2D;
 pub use seija_2d::common::types::Thickness;
 
-use components::{ui_canvas::update_ui_canvas, input::input_system};
+use components::{ui_canvas::update_ui_canvas, input::input_system, widget::WidgetEvent, button::button_system,
+                 focus::{focus_system, FocusCommand, FocusSystemData}, toggle::toggle_system, slider::slider_system, scroll_view::scroll_view_system, list_view::{list_view_system, ListViewSystemData}};
 pub use render::update_ui_render;
-use system::{on_ui_start, update_render_mesh_system, update_canvas_render, update_canvas_trans, update_ui_clips};
-use text::{FontLoader, Font};
+use system::{on_ui_start, update_render_mesh_system, sdf_atlas_rebuild_system, update_canvas_render, update_canvas_trans, update_ui_clips};
+use text::{FontLoader, Font, rich_text_link_system};
 #[derive(Clone, Copy,Hash,Debug,PartialEq, Eq,StageLabel)]
 pub enum UIStage {
     PreUI,
@@ -35,6 +36,10 @@ impl IModule for UIModule {
         //app.world.insert_resource(SpriteAllocator::new());
         app.add_event::<UIEvent>();
         app.add_event::<WriteFontAtlas>();
+        app.add_event::<WidgetEvent>();
+        app.init_resource::<ListViewSystemData>();
+        app.add_event::<FocusCommand>();
+        app.init_resource::<FocusSystemData>();
         app.add_asset::<Font>();
         app.add_asset_loader::<Font,FontLoader>();
         app.add_system2(CoreStage::Startup,StartupStage::PostStartup, on_ui_start);
@@ -64,7 +69,15 @@ impl IModule for UIModule {
         
         app.add_system(CoreStage::LateUpdate,ui_layout_system.at_end());
         app.add_system(UIStage::PreUI, input_system.at_start());
-        app.add_system(UIStage::PreUI, update_render_mesh_system);
+        app.add_system(UIStage::PreUI, focus_system);
+        app.add_system(UIStage::PreUI, button_system.after(focus_system));
+        app.add_system(UIStage::PreUI, toggle_system.after(button_system));
+        app.add_system(UIStage::PreUI, slider_system.after(toggle_system));
+        app.add_system(UIStage::PreUI, scroll_view_system.after(slider_system));
+        app.add_system(UIStage::PreUI, list_view_system.after(scroll_view_system));
+        app.add_system(UIStage::PreUI, rich_text_link_system.after(list_view_system));
+        app.add_system(UIStage::PreUI, update_render_mesh_system.after(rich_text_link_system));
+        app.add_system(UIStage::PreUI, sdf_atlas_rebuild_system.after(update_render_mesh_system));
         app.add_system(UIStage::UI, update_canvas_render);
         app.add_system(UIStage::UI, update_canvas_trans.after(update_canvas_render));
         app.add_system(UIStage::PostUI, update_ui_clips);
@@ -3,7 +3,7 @@ use glyph_brush::Rectangle;
 use bevy_ecs::{world::World, prelude::{Component, Events}};
 use seija_asset::{Handle, Assets};
 use seija_render::{RenderContext, resource::{Texture, BufferId, RenderResources}, material::{MaterialDef, Material}};
-use crate::system::UIRenderRoot;
+use crate::{system::UIRenderRoot, text::SdfMaterialParams};
 use seija_2d::common::Mesh2D;
 #[derive(Component,Debug)]
 pub struct UIRender2D {
@@ -11,6 +11,8 @@ pub struct UIRender2D {
    pub custom_mat:Option<Handle<Material>>,
    pub texture:Option<Handle<Texture>>,
    pub mesh2d:Mesh2D,
+   //SDF文字的材质参数,创建drawcall材质时设置
+   pub sdf_params:Option<SdfMaterialParams>
 }
 
 #[derive(Clone, Copy)]
@@ -20,45 +22,57 @@ pub struct WriteFontAtlas {
 
 pub fn update_ui_render(world:&mut World,ctx:&mut RenderContext) {
     //TODO 尝试优化为根据Rect写内存
-    let font_buffer_id = check_init_font_buffer(world, ctx);
+    let font_buffer_id = check_init_buffer(world, ctx, false);
+    let sdf_buffer_id = check_init_buffer(world, ctx, true);
     let mut write_atlas = world.get_resource_mut::<Events<WriteFontAtlas>>().unwrap();
     let write_events = write_atlas.drain().collect::<Vec<_>>();
     let textures = world.get_resource::<Assets<Texture>>().unwrap();
     let render_root = world.get_resource::<UIRenderRoot>().unwrap();
-    let font_texture = textures.get(&render_root.font_texture.id).unwrap();
-    let font_texture_size = font_texture.desc().desc.size;
-    let cache_bytes = font_texture.cast_image_data().unwrap();
-    let texture_id = ctx.resources.get_render_resource(&render_root.font_texture.id, 0).and_then(|v| v.into_texture_id()).unwrap();
-    
     if write_events.len() > 0 {
-        ctx.resources.map_buffer(&font_buffer_id, wgpu::MapMode::Write);
-        ctx.resources.write_mapped_buffer(&font_buffer_id, 0..cache_bytes.len() as u64, &mut |bytes,_| {
-            //for rect in write_events.iter() {
-            //    log::error!("write rect:{:?}",rect.rect);
-            //}
-            bytes[0..cache_bytes.len()].copy_from_slice(cache_bytes);
-        });
-        ctx.resources.unmap_buffer(&font_buffer_id);
-    
-        let command = ctx.command_encoder.as_mut().unwrap();
-        let aligned_width = RenderResources::get_aligned_texture_size(1024);
-        ctx.resources.copy_buffer_to_texture(command,font_buffer_id,0,
-                                             NonZeroU32::new((1 * aligned_width) as u32).unwrap(), 
-                                             &texture_id,wgpu::Origin3d::default(),0,font_texture_size,None)
+        upload_atlas(ctx, textures, &render_root.font_texture, &font_buffer_id);
     }
+    //显存贴图还没创建时保留dirty,下一帧再上传
+    let sdf_uploaded = render_root.sdf_atlas.is_dirty && upload_atlas(ctx, textures, &render_root.sdf_texture, &sdf_buffer_id);
+    if sdf_uploaded {
+        world.get_resource_mut::<UIRenderRoot>().unwrap().sdf_atlas.is_dirty = false;
+    }
+}
+
+//把整张1024x1024的R8图集通过buffer拷贝到显存,显存贴图还不存在时返回false
+fn upload_atlas(ctx:&mut RenderContext,textures:&Assets<Texture>,h_texture:&Handle<Texture>,buffer_id:&BufferId) -> bool {
+    let texture = textures.get(&h_texture.id).unwrap();
+    let texture_size = texture.desc().desc.size;
+    let cache_bytes = texture.cast_image_data().unwrap();
+    let texture_id = match ctx.resources.get_render_resource(&h_texture.id, 0).and_then(|v| v.into_texture_id()) {
+        Some(v) => v,
+        None => return false
+    };
+    ctx.resources.map_buffer(buffer_id, wgpu::MapMode::Write);
+    ctx.resources.write_mapped_buffer(buffer_id, 0..cache_bytes.len() as u64, &mut |bytes,_| {
+        bytes[0..cache_bytes.len()].copy_from_slice(cache_bytes);
+    });
+    ctx.resources.unmap_buffer(buffer_id);
+
+    let command = ctx.command_encoder.as_mut().unwrap();
+    let aligned_width = RenderResources::get_aligned_texture_size(1024);
+    ctx.resources.copy_buffer_to_texture(command,buffer_id.clone(),0,
+                                         NonZeroU32::new((1 * aligned_width) as u32).unwrap(), 
+                                         &texture_id,wgpu::Origin3d::default(),0,texture_size,None);
+    true
 }
 
-fn check_init_font_buffer(world:&mut World,ctx:&mut RenderContext) -> BufferId {
+fn check_init_buffer(world:&mut World,ctx:&mut RenderContext,is_sdf:bool) -> BufferId {
     let mut render_root = world.get_resource_mut::<UIRenderRoot>().unwrap();
-    if let Some(buffer_id) = render_root.font_buffer.as_ref() {
+    let slot = if is_sdf { &mut render_root.sdf_buffer } else { &mut render_root.font_buffer };
+    if let Some(buffer_id) = slot.as_ref() {
         return buffer_id.clone();
     }
     let buffer_id = ctx.resources.create_buffer(&wgpu::BufferDescriptor {
-        label: Some("UI Font Buffer"),
+        label: Some(if is_sdf { "UI SDF Font Buffer" } else { "UI Font Buffer" }),
         size: 1024 * 1024,
         usage: wgpu::BufferUsages::COPY_SRC | wgpu::BufferUsages::MAP_WRITE,
         mapped_at_creation: false,
     });
-    render_root.font_buffer = Some(buffer_id.clone());
+    *slot = Some(buffer_id.clone());
     buffer_id
-}
\ No newline at end of file
+}
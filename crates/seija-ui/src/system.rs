@@ -1,27 +1,35 @@
 use std::{sync::Arc, collections::{HashSet, HashMap}};
 use bevy_ecs::{world::World, system::{Resource, 
-    SystemParam, Query, Commands, Res, ResMut}, prelude::{Entity, EventWriter, EventReader}, query::{Or, Changed, ChangeTrackers}};
+    SystemParam, Query, Commands, Res, ResMut}, prelude::{Entity, EventWriter, EventReader, DetectChanges}, query::{Or, Changed, ChangeTrackers}};
 use seija_asset::{AssetServer, Assets, Handle};
 use seija_core::{math::{Vec3, Vec4}, info::EStateInfo};
 use seija_render::{material::{MaterialDefineAsset, MaterialDef, Material},
-                   resource::{ Mesh, Texture, ImageInfo, TextureDescInfo, BufferId}};
+                   resource::{ Mesh, Texture, TextureType, ImageInfo, TextureDescInfo, BufferId}};
 use seija_transform::{hierarchy::{Parent, Children}, Transform, events::HierarchyEvent};
 use spritesheet::SpriteSheet;
-use glyph_brush::{GlyphBrush, GlyphBrushBuilder,FontId,BrushAction};
+use glyph_brush::{GlyphBrush, GlyphBrushBuilder,FontId,BrushAction,GlyphCruncher,SectionGlyph,ab_glyph::{Font as ABFont,ScaleFont}};
 use crate::{components::{sprite::Sprite, canvas::{Canvas, ZOrder, Z_SCALE}, input::InputTextSystemData}, 
             render::{UIRender2D, WriteFontAtlas}, 
-           text::{Text, Font, glyph_to_mesh, write_font_texture}, types::Box2D};
-use seija_2d::common::{Rect2D,Vertex2D};
+           text::{Text, Font, GlyphQuad, RichFonts, RichTextInfo, TextLink, SpriteSlot, glyph_to_mesh, write_font_texture, parse_rich_text, layout_rich_text, SdfAtlas, SdfMaterialParams, build_sdf_mesh}, types::Box2D};
+use seija_2d::common::{Rect2D, Mesh2D};
+use seija_transform::events::EntityCommandsEx;
 use wgpu::TextureFormat;
 #[derive(Resource)]
 pub struct UIRenderRoot {
     pub(crate) baseui:Arc<MaterialDef>,
     pub(crate) basetext:Arc<MaterialDef>,
     pub(crate) caret_mat_def:Arc<MaterialDef>,
-    pub(crate) text_brush:GlyphBrush<Vec<Vertex2D>>,
+    pub(crate) text_brush:GlyphBrush<GlyphQuad>,
     pub(crate) font_texture:Handle<Texture>,
     pub(crate) font_caches:HashMap<Handle<Font>,FontId>,
     pub(crate) font_buffer:Option<BufferId>,
+    pub(crate) sdftext:Arc<MaterialDef>,
+    pub(crate) sdftext3d:Arc<MaterialDef>,
+    pub(crate) sdf_texture:Handle<Texture>,
+    pub(crate) sdf_atlas:SdfAtlas,
+    pub(crate) sdf_buffer:Option<BufferId>,
+    //已经处理过的SDF图集重建次数
+    pub(crate) sdf_generation:u32,
 
     pub(crate) entity2canvas:HashMap<Entity,Entity>,
     pub(crate) despawn_next_frame:Vec<Entity>,
@@ -32,17 +40,24 @@ pub(crate) fn on_ui_start(world:&mut World) {
     let mut h_baseui = server.load_sync::<MaterialDefineAsset>(world, "materials/ui.mat.clj", None).unwrap();
     let mut h_basetext = server.load_sync::<MaterialDefineAsset>(world, "materials/text.mat.clj", None).unwrap();
     let mut h_caret = server.load_sync::<MaterialDefineAsset>(world, "materials/inputCaret.mat.clj", None).unwrap();
+    let mut h_sdftext = server.load_sync::<MaterialDefineAsset>(world, "materials/sdfText.mat.clj", None).unwrap();
+    let mut h_sdftext3d = server.load_sync::<MaterialDefineAsset>(world, "materials/sdfText3D.mat.clj", None).unwrap();
 
     let mats = world.get_resource::<Assets<MaterialDefineAsset>>().unwrap();
     let arc_mat_define = mats.get(&h_baseui.id).unwrap().define.clone();
     let arc_text_mat_define = mats.get(&h_basetext.id).unwrap().define.clone();
     let arc_caret_mat_define = mats.get(&h_caret.id).unwrap().define.clone();
+    let arc_sdftext_mat_define = mats.get(&h_sdftext.id).unwrap().define.clone();
+    let arc_sdftext3d_mat_define = mats.get(&h_sdftext3d.id).unwrap().define.clone();
     //常驻
     h_baseui.forget();
     h_basetext.forget();
     h_caret.forget();
+    h_sdftext.forget();
+    h_sdftext3d.forget();
 
-    let font_texture = create_font_texture(world);
+    let font_texture = create_font_texture(world,"font_texture");
+    let sdf_texture = create_font_texture(world,"sdf_font_texture");
     world.insert_resource(UIRenderRoot {
         baseui:arc_mat_define,
         basetext:arc_text_mat_define,
@@ -52,6 +67,12 @@ pub(crate) fn on_ui_start(world:&mut World) {
         font_caches:HashMap::default(),
         font_texture,
         font_buffer:None,
+        sdftext:arc_sdftext_mat_define,
+        sdftext3d:arc_sdftext3d_mat_define,
+        sdf_texture,
+        sdf_atlas:SdfAtlas::default(),
+        sdf_buffer:None,
+        sdf_generation:0,
         despawn_next_frame:vec![],
         entity2canvas:HashMap::default(),
         caret_mat_def:arc_caret_mat_define
@@ -59,10 +80,10 @@ pub(crate) fn on_ui_start(world:&mut World) {
     world.insert_resource(InputTextSystemData::default());
 }
 
-fn create_font_texture(world:&mut World) -> Handle<Texture> {
-    let image_info = ImageInfo {width:1024,height:1024,format:TextureFormat::R8Unorm,data:vec![0u8;1024 * 1024] };
+fn create_font_texture(world:&mut World,label:&'static str) -> Handle<Texture> {
+    let image_info = ImageInfo {width:1024,height:1024,format:TextureFormat::R8Unorm,data:vec![0u8;1024 * 1024],mips:vec![] };
     let mut texture_desc = TextureDescInfo::default();
-    texture_desc.desc.label = "font_texture".into();
+    texture_desc.desc.label = label.into();
     let font_texture = Texture::create_image(image_info, texture_desc);
     let mut textures = world.get_resource_mut::<Assets<Texture>>().unwrap();
     let h_texture = textures.add(font_texture);
@@ -77,6 +98,7 @@ pub struct RenderMeshParams<'w,'s> {
     pub(crate) font_assets:Res<'w,Assets<Font>>,
     pub(crate) sprites:Query<'w,'s,(&'static Sprite,&'static Rect2D)>,
     pub(crate) texts:Query<'w,'s,(&'static Text,&'static Rect2D)>,
+    pub(crate) rich_infos:Query<'w,'s,&'static mut RichTextInfo>,
     pub(crate) spritesheets:Res<'w,Assets<SpriteSheet>>,
     pub(crate) textures:ResMut<'w,Assets<Texture>>,
     pub(crate) ui_roots:ResMut<'w,UIRenderRoot>,
@@ -114,17 +136,47 @@ pub fn update_render_mesh_system(mut params:RenderMeshParams,server:Res<AssetSer
     //更新Text的Mesh
     for entity in params.update_texts.iter() {
         if let Ok((text,rect)) = params.texts.get(entity) {
+            let mut rich_layout:Option<(Vec<TextLink>,Vec<SpriteSlot>)> = None;
+            let mut sdf_mesh:Option<Mesh2D> = None;
             if let Some(h_font) = text.font.as_ref() {
                 //更新字体缓存
-                if !params.ui_roots.font_caches.contains_key(h_font) {
-                   params.font_assets.get(&h_font.id).map(|font| {
-                      let font_id = params.ui_roots.text_brush.add_font(font.asset.clone());
-                      params.ui_roots.font_caches.insert(h_font.clone(),font_id);
-                   });
+                let font_id = cache_font(h_font, &params.font_assets, &mut params.ui_roots).unwrap_or_default();
+                let spans = if text.rich { parse_rich_text(&text.text) } else { vec![] };
+                let section = if text.rich {
+                    let bold_id = text.bold_font.as_ref().and_then(|h| cache_font(h, &params.font_assets, &mut params.ui_roots));
+                    let space_advance = params.font_assets.get(&h_font.id).map(|font| {
+                        let scaled = font.asset.as_scaled(1f32);
+                        scaled.h_advance(scaled.glyph_id(' '))
+                    }).unwrap_or(0.25f32);
+                    text.build_rich_section(rect, &spans, &RichFonts { font_id,bold_id,space_advance })
+                } else {
+                    text.build_section(rect)
+                };
+                let ui_roots = &mut *params.ui_roots;
+                let glyphs:Vec<SectionGlyph> = if text.rich || text.sdf { ui_roots.text_brush.glyphs(&section).cloned().collect() } else { vec![] };
+                if text.rich {
+                    rich_layout = Some(layout_rich_text(&spans, glyphs.iter(), ui_roots.text_brush.fonts()));
+                }
+                if text.sdf {
+                    //SDF字形不经过glyph_brush的位图缓存,只使用它的排版结果
+                    let sdf_texture = params.textures.get_mut(&ui_roots.sdf_texture.id).unwrap();
+                    let rich_spans = if text.rich { Some(&spans[..]) } else { None };
+                    if let TextureType::Image(image) = &mut sdf_texture.texture {
+                        sdf_mesh = Some(build_sdf_mesh(text.color, rich_spans, &glyphs, ui_roots.text_brush.fonts(), &mut ui_roots.sdf_atlas, &mut image.data));
+                    }
+                } else {
+                    ui_roots.text_brush.queue(section);
                 }
-               
-                let section = text.build_section(rect);
-                params.ui_roots.text_brush.queue(section);
+            }
+            update_rich_info(entity, text, rich_layout, &mut params.rich_infos, &params.spritesheets, &mut params.commands);
+            if let Some(mesh2d) = sdf_mesh {
+                if let Some(render2d) = update_sdf_render(entity, text, mesh2d, &mut params.render2d, &params.ui_roots) {
+                    params.commands.entity(entity).insert(render2d);
+                }
+                if let Some(top_canvas_entity) = find_top_canvas(entity, &params.parents, &params.canvases) {
+                    top_changed_canvas.insert(top_canvas_entity);
+                }
+                continue;
             }
             let font_texture = params.textures.get_mut(&params.ui_roots.font_texture.id).unwrap();
             
@@ -134,16 +186,20 @@ pub fn update_render_mesh_system(mut params:RenderMeshParams,server:Res<AssetSer
             },glyph_to_mesh);
             match action {
                 Ok(BrushAction::Draw(verts)) => {
-                   let mesh2d = Text::build_mesh(verts,text.color);
+                   let mesh2d = Text::build_mesh(verts);
                    if let Ok(mut render) = params.render2d.get_mut(entity) {
                       render.texture = Some(params.ui_roots.font_texture.clone());
+                      render.mat_def = params.ui_roots.basetext.clone();
+                      render.custom_mat = None;
+                      render.sdf_params = None;
                       render.mesh2d = mesh2d;
                     } else {
                         let render2d = UIRender2D {
                             mat_def:params.ui_roots.basetext.clone(),
                             texture:Some(params.ui_roots.font_texture.clone()),
                             mesh2d,
-                            custom_mat:None
+                            custom_mat:None,
+                            sdf_params:None
                         };
                         params.commands.entity(entity).insert(render2d);
                     }
@@ -169,6 +225,102 @@ pub fn update_render_mesh_system(mut params:RenderMeshParams,server:Res<AssetSer
     }
 }
 
+//SDF图集满了重建后,已有SDF文字的uv都失效了,标记为改变下一帧重新生成网格
+pub(crate) fn sdf_atlas_rebuild_system(mut ui_roots:ResMut<UIRenderRoot>,mut texts:Query<&mut Text>) {
+    let generation = ui_roots.sdf_atlas.generation();
+    if ui_roots.sdf_generation == generation {
+        return;
+    }
+    ui_roots.sdf_generation = generation;
+    for mut text in texts.iter_mut() {
+        if text.sdf {
+            text.set_changed();
+        }
+    }
+}
+
+//SDF文字的效果参数放在UIRender2D上,由drawcall创建材质时设置,参数相同的文字可以合并,还没有UIRender2D时返回新建的
+fn update_sdf_render(entity:Entity,text:&Text,mesh2d:Mesh2D,render2ds:&mut Query<&mut UIRender2D>,ui_roots:&UIRenderRoot) -> Option<UIRender2D> {
+    let render2d = UIRender2D {
+        mat_def:if text.world_space { ui_roots.sdftext3d.clone() } else { ui_roots.sdftext.clone() },
+        texture:Some(ui_roots.sdf_texture.clone()),
+        mesh2d,
+        custom_mat:None,
+        sdf_params:Some(SdfMaterialParams::new(&text.effect, text.font_size as f32))
+    };
+    match render2ds.get_mut(entity) {
+        Ok(mut render) => { *render = render2d; None },
+        Err(_) => Some(render2d)
+    }
+}
+
+fn cache_font(h_font:&Handle<Font>,font_assets:&Assets<Font>,ui_roots:&mut UIRenderRoot) -> Option<FontId> {
+    if let Some(font_id) = ui_roots.font_caches.get(h_font) {
+        return Some(*font_id);
+    }
+    let font = font_assets.get(&h_font.id)?;
+    let font_id = ui_roots.text_brush.add_font(font.asset.clone());
+    ui_roots.font_caches.insert(h_font.clone(),font_id);
+    Some(font_id)
+}
+
+//重建富文本的链接区域,内嵌图片子节点按顺序复用,多出来的删除,不再是富文本时移除
+fn update_rich_info(entity:Entity,text:&Text,layout:Option<(Vec<TextLink>,Vec<SpriteSlot>)>,rich_infos:&mut Query<&mut RichTextInfo>,
+                    spritesheets:&Assets<SpriteSheet>,commands:&mut Commands) {
+    let (has_info,old_sprites) = match rich_infos.get_mut(entity) {
+        Ok(mut info) => (true,std::mem::take(&mut info.sprites)),
+        Err(_) => (false,vec![])
+    };
+    let (links,slots) = match layout {
+        Some(v) => v,
+        None => {
+            for sprite_entity in old_sprites {
+                commands.entity(sprite_entity).delete();
+            }
+            if has_info {
+                commands.entity(entity).remove::<RichTextInfo>();
+            }
+            return;
+        }
+    };
+    let mut old_sprites = old_sprites.into_iter();
+    let mut sprites:Vec<Entity> = vec![];
+    if let Some(h_sheet) = text.sprite_sheet.as_ref() {
+        let sheet = spritesheets.get(&h_sheet.id);
+        for slot in slots.iter() {
+            let index = match sheet.and_then(|s| s.get_index(&slot.name)) {
+                Some(v) => v,
+                None => {
+                    log::warn!("rich text sprite not found:{}",slot.name);
+                    continue;
+                }
+            };
+            let mut t = Transform::default();
+            t.local.position = Vec3::new(slot.rect.x + slot.rect.width * 0.5f32, slot.rect.y + slot.rect.height * 0.5f32, 0f32);
+            let sprite = Sprite::simple(index, Some(h_sheet.clone()), slot.color.unwrap_or(Vec4::ONE));
+            let sprite_entity = match old_sprites.next() {
+                Some(sprite_entity) => {
+                    commands.entity(sprite_entity).insert((t,Rect2D::new(slot.rect.width, slot.rect.height),sprite));
+                    sprite_entity
+                },
+                None => commands.spawn((t,Rect2D::new(slot.rect.width, slot.rect.height),sprite)).set_parent(Some(entity)).id()
+            };
+            sprites.push(sprite_entity);
+        }
+    }
+    for sprite_entity in old_sprites {
+        commands.entity(sprite_entity).delete();
+    }
+    if has_info {
+        if let Ok(mut info) = rich_infos.get_mut(entity) {
+            info.links = links;
+            info.sprites = sprites;
+        }
+    } else {
+        commands.entity(entity).insert(RichTextInfo { links,sprites });
+    }
+}
+
 #[derive(SystemParam)]
 pub struct CanvasRenderParams<'w,'s> {
     pub(crate) update_render2ds:Query<'w,'s,Entity,Changed<UIRender2D>>,
@@ -26,8 +26,7 @@ impl IAssetLoader for FontLoader {
     }
 
     fn sync_load(&self,_: &mut World,path: &str,server: &AssetServer,_:Option<Box<dyn AssetLoaderParams>>) -> anyhow::Result<Box<dyn AssetDynamic>> {
-        let full_path = server.full_path(path)?;
-        let bytes = std::fs::read(&full_path)?;
+        let bytes = server.read_bytes(path)?;
         let font = FontArc::try_from_vec(bytes)?;
         Ok(Box::new(Font { asset: font }))
     }
@@ -35,8 +34,7 @@ impl IAssetLoader for FontLoader {
     async fn async_load(&self,server:AssetServer,path:SmolStr,
         _:Option<Box<dyn seija_asset::downcast_rs::DowncastSync>>,
         _:Option<Box<dyn AssetLoaderParams>>) -> anyhow::Result<Box<dyn AssetDynamic>> {
-        let full_path = server.full_path(path.as_str())?;
-        let bytes = smol::fs::read(&full_path).await?;
+        let bytes = server.read_bytes_async(path.as_str()).await?;
         let font = FontArc::try_from_vec(bytes)?;
         Ok(Box::new(Font { asset: font }))
     }
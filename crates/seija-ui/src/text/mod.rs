@@ -1,4 +1,8 @@
 mod font;
 mod text;
+mod rich;
+mod sdf;
 pub use text::*;
-pub use font::*;
\ No newline at end of file
+pub use font::*;
+pub use rich::*;
+pub use sdf::*;
\ No newline at end of file
@@ -0,0 +1,275 @@
+use bevy_ecs::prelude::{Component, Entity, EventReader, EventWriter};
+use bevy_ecs::query::Added;
+use bevy_ecs::system::{Commands, Query};
+use glyph_brush::{SectionGlyph, ab_glyph::{FontArc, Font as ABFont, ScaleFont}};
+use seija_core::{math::{Vec2, Vec4}, Rect};
+use seija_transform::Transform;
+use crate::components::widget::{WidgetEvent, add_event_type};
+use crate::event::{UIEvent, UIEventType};
+
+//富文本的样式,没有设置的使用Text上的值
+#[derive(Debug,Clone,PartialEq,Default)]
+pub struct RichStyle {
+    pub color:Option<Vec4>,
+    pub size:Option<f32>,
+    pub bold:bool,
+    pub link:Option<String>
+}
+
+//样式相同的一段文字或者一个内嵌图片
+#[derive(Debug,Clone,PartialEq)]
+pub enum RichSpan {
+    Text { text:String,style:RichStyle },
+    Sprite { name:String,style:RichStyle }
+}
+
+enum RichTag {
+    Color(Vec4),
+    Size(f32),
+    Bold,
+    Link(String),
+    Sprite(String),
+    End(&'static str)
+}
+
+//支持<color=#f00>,<size=20>,<b>,<link=id>和<sprite name=icon>,无法识别的标签按原文输出
+pub fn parse_rich_text(text:&str) -> Vec<RichSpan> {
+    let mut spans:Vec<RichSpan> = vec![];
+    let mut colors:Vec<Vec4> = vec![];
+    let mut sizes:Vec<f32> = vec![];
+    let mut bold_count = 0;
+    let mut links:Vec<String> = vec![];
+    let mut cur_text = String::default();
+    let mut rest = text;
+    while let Some(start) = rest.find('<') {
+        let tag = rest[start..].find('>').and_then(|end| parse_tag(&rest[start + 1..start + end]).map(|t| (t,start + end + 1)));
+        let (tag,next) = match tag {
+            Some(v) => v,
+            None => {
+                cur_text.push_str(&rest[..=start]);
+                rest = &rest[start + 1..];
+                continue;
+            }
+        };
+        cur_text.push_str(&rest[..start]);
+        rest = &rest[next..];
+        let style = RichStyle { color:colors.last().copied(),size:sizes.last().copied(),bold:bold_count > 0,link:links.last().cloned() };
+        if !cur_text.is_empty() {
+            spans.push(RichSpan::Text { text:std::mem::take(&mut cur_text),style:style.clone() });
+        }
+        match tag {
+            RichTag::Color(color) => colors.push(color),
+            RichTag::Size(size) => sizes.push(size),
+            RichTag::Bold => bold_count += 1,
+            RichTag::Link(link) => links.push(link),
+            RichTag::Sprite(name) => spans.push(RichSpan::Sprite { name,style }),
+            RichTag::End("color") => { colors.pop(); },
+            RichTag::End("size") => { sizes.pop(); },
+            RichTag::End("b") => bold_count = (bold_count - 1).max(0),
+            RichTag::End(_) => { links.pop(); }
+        }
+    }
+    cur_text.push_str(rest);
+    if !cur_text.is_empty() {
+        let style = RichStyle { color:colors.last().copied(),size:sizes.last().copied(),bold:bold_count > 0,link:links.last().cloned() };
+        spans.push(RichSpan::Text { text:cur_text,style });
+    }
+    spans
+}
+
+fn parse_tag(tag:&str) -> Option<RichTag> {
+    let tag = tag.trim();
+    match tag {
+        "b" => return Some(RichTag::Bold),
+        "/b" => return Some(RichTag::End("b")),
+        "/color" => return Some(RichTag::End("color")),
+        "/size" => return Some(RichTag::End("size")),
+        "/link" => return Some(RichTag::End("link")),
+        _ => {}
+    }
+    let (name,value) = tag.split_once('=')?;
+    let value = value.trim().trim_matches(|c| c == '"' || c == '\'');
+    match name.trim() {
+        "color" => parse_color(value).map(RichTag::Color),
+        "size" => value.parse::<f32>().ok().filter(|v| *v > 0f32).map(RichTag::Size),
+        "link" => Some(RichTag::Link(value.to_string())),
+        //<sprite=icon>和<sprite name=icon>两种写法
+        "sprite" | "sprite name" => Some(RichTag::Sprite(value.to_string())),
+        _ => None
+    }
+}
+
+//#rgb,#rgba,#rrggbb,#rrggbbaa或者常用颜色名
+pub fn parse_color(value:&str) -> Option<Vec4> {
+    match value {
+        "white" => return Some(Vec4::new(1f32, 1f32, 1f32, 1f32)),
+        "black" => return Some(Vec4::new(0f32, 0f32, 0f32, 1f32)),
+        "red" => return Some(Vec4::new(1f32, 0f32, 0f32, 1f32)),
+        "green" => return Some(Vec4::new(0f32, 1f32, 0f32, 1f32)),
+        "blue" => return Some(Vec4::new(0f32, 0f32, 1f32, 1f32)),
+        "yellow" => return Some(Vec4::new(1f32, 1f32, 0f32, 1f32)),
+        _ => {}
+    }
+    let hex = value.strip_prefix('#')?;
+    let digits = hex.chars().map(|c| c.to_digit(16).map(|v| v as f32)).collect::<Option<Vec<f32>>>()?;
+    let channels:Vec<f32> = match digits.len() {
+        3 | 4 => digits.iter().map(|v| v * 17f32 / 255f32).collect(),
+        6 | 8 => digits.chunks(2).map(|v| (v[0] * 16f32 + v[1]) / 255f32).collect(),
+        _ => return None
+    };
+    Some(Vec4::new(channels[0], channels[1], channels[2], channels.get(3).copied().unwrap_or(1f32)))
+}
+
+#[derive(Debug,Clone)]
+pub struct TextLink {
+    pub link:String,
+    //Text本地坐标下的区域,换行时有多个
+    pub rects:Vec<Rect<f32>>
+}
+
+//富文本排版后的结果,sprites是内嵌图片创建的子节点
+#[derive(Component,Debug,Default)]
+pub struct RichTextInfo {
+    pub links:Vec<TextLink>,
+    pub(crate) sprites:Vec<Entity>
+}
+
+impl RichTextInfo {
+    pub fn hit_link(&self,pos:Vec2) -> Option<usize> {
+        self.links.iter().position(|link| link.rects.iter().any(|r| {
+            pos.x >= r.x && pos.x <= r.x + r.width && pos.y >= r.y && pos.y <= r.y + r.height
+        }))
+    }
+}
+
+pub(crate) struct SpriteSlot {
+    pub name:String,
+    pub color:Option<Vec4>,
+    pub rect:Rect<f32>
+}
+
+//根据排版出的字形位置计算链接区域和图片位置,section里每个span对应一个glyph_brush::Text
+pub(crate) fn layout_rich_text<'a>(spans:&[RichSpan],glyphs:impl Iterator<Item = &'a SectionGlyph>,fonts:&[FontArc]) -> (Vec<TextLink>,Vec<SpriteSlot>) {
+    let mut links:Vec<TextLink> = vec![];
+    let mut link_span:Option<usize> = None;
+    let mut sprites:Vec<SpriteSlot> = vec![];
+    for glyph in glyphs {
+        let font = match fonts.get(glyph.font_id.0) {
+            Some(v) => v.as_scaled(glyph.glyph.scale),
+            None => continue
+        };
+        let left = glyph.glyph.position.x;
+        let baseline = -glyph.glyph.position.y;
+        let width = font.h_advance(glyph.glyph.id);
+        match spans.get(glyph.section_index) {
+            Some(RichSpan::Sprite { name, style }) => {
+                let height = glyph.glyph.scale.y;
+                let rect = Rect { x:left,y:baseline + font.descent(),width,height };
+                sprites.push(SpriteSlot { name:name.clone(),color:style.color,rect });
+            },
+            Some(RichSpan::Text { style:RichStyle { link:Some(link), .. }, .. }) => {
+                let rect = Rect { x:left,y:baseline + font.descent(),width,height:font.ascent() - font.descent() };
+                //同一个链接在同一行的字形合并成一个区域
+                if link_span != Some(glyph.section_index) || links.last().map(|l| l.link != *link).unwrap_or(true) {
+                    links.push(TextLink { link:link.clone(),rects:vec![] });
+                }
+                link_span = Some(glyph.section_index);
+                let rects = &mut links.last_mut().unwrap().rects;
+                match rects.last_mut() {
+                    Some(last) if (last.y - rect.y).abs() < 0.01f32 && rect.x >= last.x => {
+                        last.width = rect.x + rect.width - last.x;
+                        last.height = last.height.max(rect.height);
+                    },
+                    _ => rects.push(rect)
+                }
+            },
+            _ => link_span = None
+        }
+    }
+    (links,sprites)
+}
+
+//点击富文本时按点击位置找到链接,发送WidgetEvent::LinkClick
+pub(crate) fn rich_text_link_system(mut commands:Commands,
+                                    added:Query<Entity,Added<RichTextInfo>>,
+                                    infos:Query<(&RichTextInfo,&Transform)>,
+                                    mut ui_events:EventReader<UIEvent>,
+                                    mut widget_events:EventWriter<WidgetEvent>) {
+    for entity in added.iter() {
+        add_event_type(entity, UIEventType::CLICK, UIEventType::NONE, &mut commands);
+    }
+    for ev in ui_events.iter() {
+        if !ev.event_type.contains(UIEventType::CLICK) { continue; }
+        if let Ok((info,t)) = infos.get(ev.entity) {
+            let local = t.global().matrix().inverse() * Vec4::new(ev.pos.x, ev.pos.y, 0f32, 1f32);
+            if let Some(index) = info.hit_link(Vec2::new(local.x, local.y)) {
+                widget_events.send(WidgetEvent::LinkClick { text:ev.entity,index,link:info.links[index].link.clone() });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glyph_brush::{SectionGlyph, FontId, ab_glyph::{FontArc, Font, ScaleFont, Glyph, point}};
+    use seija_core::math::{Vec2, Vec4};
+    use super::{parse_rich_text, layout_rich_text, RichSpan, RichStyle, RichTextInfo};
+
+    const FONT:&[u8] = include_bytes!("../../../../seija-examples/res/ui/FiraMono-Medium.ttf");
+
+    fn glyph(font:&FontArc,section_index:usize,chr:char,x:f32,y:f32) -> SectionGlyph {
+        let glyph = Glyph { id:font.glyph_id(chr),scale:20f32.into(),position:point(x, y) };
+        SectionGlyph { section_index,byte_index:0,glyph,font_id:FontId(0) }
+    }
+
+    #[test]
+    fn layout_links_and_sprites() {
+        let font = FontArc::try_from_slice(FONT).unwrap();
+        let scaled = font.as_scaled(20f32);
+        let (advance,ascent,descent) = (scaled.h_advance(font.glyph_id('a')),scaled.ascent(),scaled.descent());
+        let link = |text:&str,link:&str| RichSpan::Text { text:text.into(),style:RichStyle { link:Some(link.into()),..Default::default() } };
+        let red = Some(Vec4::new(1f32, 0f32, 0f32, 1f32));
+        let spans = [link("ab","home"),
+                         RichSpan::Text { text:"c".into(),style:RichStyle::default() },
+                         RichSpan::Sprite { name:"icon".into(),style:RichStyle { color:red,..Default::default() } },
+                         link("de","more")];
+        //第二个链接从第一行末尾换行到第二行
+        let glyphs = [glyph(&font, 0, 'a', 0f32, 20f32),glyph(&font, 0, 'b', advance, 20f32),
+                          glyph(&font, 1, 'c', advance * 2f32, 20f32),glyph(&font, 2, ' ', advance * 3f32, 20f32),
+                          glyph(&font, 3, 'd', advance * 4f32, 20f32),glyph(&font, 3, 'e', 0f32, 45f32)];
+        let (links,sprites) = layout_rich_text(&spans, glyphs.iter(), std::slice::from_ref(&font));
+
+        assert_eq!(links.len(), 2);
+        assert_eq!(links[0].link, "home");
+        assert_eq!(links[0].rects.len(), 1);
+        let rect = &links[0].rects[0];
+        assert_eq!((rect.x,rect.y,rect.width,rect.height), (0f32,-20f32 + descent,advance * 2f32,ascent - descent));
+        assert_eq!(links[1].rects.len(), 2);
+        assert_eq!((links[1].rects[0].x,links[1].rects[1].x,links[1].rects[1].y), (advance * 4f32,0f32,-45f32 + descent));
+
+        assert_eq!(sprites.len(), 1);
+        assert_eq!((sprites[0].name.as_str(),sprites[0].color), ("icon",red));
+        let rect = &sprites[0].rect;
+        assert_eq!((rect.x,rect.y,rect.height), (advance * 3f32,-20f32 + descent,20f32));
+
+        let info = RichTextInfo { links,sprites:vec![] };
+        assert_eq!(info.hit_link(Vec2::new(advance, -15f32)), Some(0));
+        assert_eq!(info.hit_link(Vec2::new(advance * 0.5f32, -40f32)), Some(1));
+        assert_eq!(info.hit_link(Vec2::new(advance * 2.5f32, -15f32)), None);
+        assert_eq!(info.hit_link(Vec2::new(advance * 3f32, -40f32)), None);
+    }
+
+    #[test]
+    fn parse_markup() {
+        let spans = parse_rich_text("a<color=#f00>b<b>c</b></color><size=20><sprite name=icon></size><link=\"home\">d</link> 1<2");
+        let red = Some(Vec4::new(1f32, 0f32, 0f32, 1f32));
+        assert_eq!(spans, vec![
+            RichSpan::Text { text:"a".into(),style:RichStyle::default() },
+            RichSpan::Text { text:"b".into(),style:RichStyle { color:red,..Default::default() } },
+            RichSpan::Text { text:"c".into(),style:RichStyle { color:red,bold:true,..Default::default() } },
+            RichSpan::Sprite { name:"icon".into(),style:RichStyle { size:Some(20f32),..Default::default() } },
+            RichSpan::Text { text:"d".into(),style:RichStyle { link:Some("home".into()),..Default::default() } },
+            RichSpan::Text { text:" 1<2".into(),style:RichStyle::default() },
+        ]);
+    }
+}
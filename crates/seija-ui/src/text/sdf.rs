@@ -0,0 +1,363 @@
+use std::collections::HashMap;
+use glyph_brush::{FontId, SectionGlyph, ab_glyph::{FontArc, Font as ABFont, GlyphId, OutlinedGlyph, PxScale, point}};
+use seija_core::{math::{Vec2, Vec4}, Rect};
+use seija_2d::common::{Mesh2D, Vertex2D};
+use super::RichSpan;
+
+//SDF字形统一按这个字号生成,显示时按实际字号缩放
+pub const SDF_BASE_SIZE:f32 = 48f32;
+//字形四周留出的距离场范围,描边,发光和阴影都不能超过这个范围
+pub const SDF_SPREAD:u32 = 6;
+pub const SDF_ATLAS_SIZE:u32 = 1024;
+
+//描边,发光和阴影参数,长度单位都是当前字号下的像素
+#[derive(Debug,Clone,Copy,Default)]
+#[repr(C)]
+pub struct TextEffect {
+    pub outline_width:f32,
+    pub outline_color:Vec4,
+    pub glow_width:f32,
+    pub glow_color:Vec4,
+    //x向右,y向上
+    pub shadow_offset:Vec2,
+    pub shadow_softness:f32,
+    pub shadow_color:Vec4
+}
+
+impl TextEffect {
+    //转换成距离场中的宽度(0.5是字形边缘)
+    pub fn to_sdf_params(&self,font_size:f32) -> (Vec4,Vec4) {
+        let k = SDF_BASE_SIZE / font_size.max(1f32);
+        let to_dist = |v:f32| (v * k / (2f32 * SDF_SPREAD as f32)).clamp(0f32, 0.5f32);
+        let params = Vec4::new(to_dist(self.outline_width), to_dist(self.glow_width), to_dist(self.shadow_softness), 0f32);
+        //阴影偏移超过留边时会采样到相邻的字形,限制在SDF_SPREAD以内,贴图v轴向下
+        let spread = SDF_SPREAD as f32;
+        let offset = (self.shadow_offset * k).clamp(Vec2::splat(-spread), Vec2::splat(spread)) / SDF_ATLAS_SIZE as f32;
+        (params,Vec4::new(offset.x, -offset.y, 0f32, 0f32))
+    }
+}
+
+//SDF文字材质的参数,放在UIRender2D上,参数相同的文字合并到同一个drawcall
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct SdfMaterialParams {
+    pub outline_color:Vec4,
+    pub glow_color:Vec4,
+    pub shadow_color:Vec4,
+    pub effect_params:Vec4,
+    pub shadow_offset:Vec4
+}
+
+impl SdfMaterialParams {
+    pub fn new(effect:&TextEffect,font_size:f32) -> Self {
+        let (effect_params,shadow_offset) = effect.to_sdf_params(font_size);
+        SdfMaterialParams {
+            outline_color:effect.outline_color,
+            glow_color:effect.glow_color,
+            shadow_color:effect.shadow_color,
+            effect_params,
+            shadow_offset
+        }
+    }
+}
+
+#[derive(Debug,Clone)]
+pub struct SdfGlyph {
+    pub uv:Rect<f32>,
+    //基准字号下相对字形原点的左上角偏移(y向下)和包含留边的大小
+    pub offset:Vec2,
+    pub size:Vec2
+}
+
+struct Shelf {
+    y:u32,
+    height:u32,
+    x:u32
+}
+
+//SDF字形图集,字形和字号无关,所以同一个字只生成一次
+//满了之后清空重建,重建后generation加1,之前生成的SDF网格都需要重新生成
+#[derive(Default)]
+pub struct SdfAtlas {
+    shelves:Vec<Shelf>,
+    //没有轮廓的字形(空格)记为None
+    glyphs:HashMap<(FontId,GlyphId),Option<SdfGlyph>>,
+    pub(crate) is_dirty:bool,
+    generation:u32
+}
+
+impl SdfAtlas {
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+
+    //data是SDF_ATLAS_SIZE大小的R8图集数据
+    pub fn clear(&mut self,data:&mut [u8]) {
+        data.fill(0);
+        self.shelves.clear();
+        self.glyphs.clear();
+        self.is_dirty = true;
+        self.generation += 1;
+    }
+
+    //放不下的字形不缓存,下次还会再尝试
+    pub fn get_glyph(&mut self,font:&FontArc,font_id:FontId,glyph_id:GlyphId,data:&mut [u8]) -> Option<SdfGlyph> {
+        if let Some(glyph) = self.glyphs.get(&(font_id,glyph_id)) {
+            return glyph.clone();
+        }
+        let outlined = match font.outline_glyph(glyph_id.with_scale_and_position(PxScale::from(SDF_BASE_SIZE), point(0f32, 0f32))) {
+            Some(v) => v,
+            None => {
+                self.glyphs.insert((font_id,glyph_id), None);
+                return None;
+            }
+        };
+        let glyph = self.create_glyph(outlined, data)?;
+        self.glyphs.insert((font_id,glyph_id), Some(glyph.clone()));
+        Some(glyph)
+    }
+
+    fn create_glyph(&mut self,outlined:OutlinedGlyph,data:&mut [u8]) -> Option<SdfGlyph> {
+        let bounds = outlined.px_bounds();
+        let width = bounds.width() as u32 + SDF_SPREAD * 2;
+        let height = bounds.height() as u32 + SDF_SPREAD * 2;
+        let mut coverage = vec![0f32;(width * height) as usize];
+        outlined.draw(|x,y,c| {
+            let index = ((y + SDF_SPREAD) * width + x + SDF_SPREAD) as usize;
+            if index < coverage.len() {
+                coverage[index] = c;
+            }
+        });
+        let sdf = coverage_to_sdf(&coverage, width, height, SDF_SPREAD);
+        let (x,y) = match self.allocate_or_evict(width, height, data) {
+            Some(v) => v,
+            None => { log::warn!("sdf glyph is larger than atlas {}x{}",width,height); return None; }
+        };
+        for (row,bytes) in sdf.chunks_exact(width as usize).enumerate() {
+            let offset = (y as usize + row) * SDF_ATLAS_SIZE as usize + x as usize;
+            data[offset..offset + width as usize].copy_from_slice(bytes);
+        }
+        self.is_dirty = true;
+        let atlas_size = SDF_ATLAS_SIZE as f32;
+        Some(SdfGlyph {
+            uv:Rect { x:x as f32 / atlas_size,y:y as f32 / atlas_size,width:width as f32 / atlas_size,height:height as f32 / atlas_size },
+            offset:Vec2::new(bounds.min.x - SDF_SPREAD as f32, bounds.min.y - SDF_SPREAD as f32),
+            size:Vec2::new(width as f32, height as f32)
+        })
+    }
+
+    fn allocate_or_evict(&mut self,width:u32,height:u32,data:&mut [u8]) -> Option<(u32,u32)> {
+        if let Some(pos) = self.allocate(width, height) {
+            return Some(pos);
+        }
+        if self.shelves.is_empty() {
+            return None;
+        }
+        log::info!("sdf atlas is full, rebuild");
+        self.clear(data);
+        self.allocate(width, height)
+    }
+
+    fn allocate(&mut self,width:u32,height:u32) -> Option<(u32,u32)> {
+        let w = width + 1;
+        let h = height + 1;
+        if let Some(shelf) = self.shelves.iter_mut().filter(|s| s.height >= h && s.x + w <= SDF_ATLAS_SIZE).min_by_key(|s| s.height) {
+            let x = shelf.x;
+            shelf.x += w;
+            return Some((x,shelf.y));
+        }
+        let y = self.shelves.last().map(|s| s.y + s.height).unwrap_or(0);
+        if y + h > SDF_ATLAS_SIZE || w > SDF_ATLAS_SIZE {
+            return None;
+        }
+        self.shelves.push(Shelf { y,height:h,x:w });
+        Some((0,y))
+    }
+}
+
+//覆盖率转有符号距离场,0.5是边缘,大于0.5在字形内部,spread像素外截断
+pub fn coverage_to_sdf(coverage:&[f32],width:u32,height:u32,spread:u32) -> Vec<u8> {
+    let (w,h,r) = (width as i32,height as i32,spread as i32);
+    let inside = |x:i32,y:i32| coverage[(y * w + x) as usize] >= 0.5f32;
+    let mut out = vec![0u8;coverage.len()];
+    for y in 0..h {
+        for x in 0..w {
+            let is_in = inside(x, y);
+            let mut min_dist = spread as f32;
+            for dy in -r..=r {
+                for dx in -r..=r {
+                    let (sx,sy) = (x + dx,y + dy);
+                    if sx < 0 || sy < 0 || sx >= w || sy >= h { continue; }
+                    if inside(sx, sy) != is_in {
+                        let d = ((dx * dx + dy * dy) as f32).sqrt() - 0.5f32;
+                        min_dist = min_dist.min(d);
+                    }
+                }
+            }
+            //半覆盖的像素直接用覆盖率估计到边缘的距离
+            let cov = coverage[(y * w + x) as usize];
+            if cov > 0f32 && cov < 1f32 {
+                min_dist = min_dist.min((cov - 0.5f32).abs());
+            }
+            let signed = if is_in { min_dist } else { -min_dist };
+            let value = 0.5f32 + signed / (2f32 * spread as f32);
+            out[(y * w + x) as usize] = (value.clamp(0f32, 1f32) * 255f32) as u8;
+        }
+    }
+    out
+}
+
+//用排版好的字形生成SDF网格,富文本时按span取颜色,没有设置颜色的使用text_color
+pub(crate) fn build_sdf_mesh(text_color:Vec4,spans:Option<&[RichSpan]>,glyphs:&[SectionGlyph],fonts:&[FontArc],atlas:&mut SdfAtlas,data:&mut [u8]) -> Mesh2D {
+    let generation = atlas.generation();
+    let mesh = build_sdf_quads(text_color, spans, glyphs, fonts, atlas, data);
+    //生成过程中图集重建了,前面字形的uv已经失效,重新生成一次
+    if atlas.generation() != generation {
+        return build_sdf_quads(text_color, spans, glyphs, fonts, atlas, data);
+    }
+    mesh
+}
+
+fn build_sdf_quads(text_color:Vec4,spans:Option<&[RichSpan]>,glyphs:&[SectionGlyph],fonts:&[FontArc],atlas:&mut SdfAtlas,data:&mut [u8]) -> Mesh2D {
+    let mut points:Vec<Vertex2D> = vec![];
+    let mut colors:Vec<Vec4> = vec![];
+    let mut indexs:Vec<u32> = vec![];
+    for glyph in glyphs.iter() {
+        let font = match fonts.get(glyph.font_id.0) {
+            Some(v) => v,
+            None => continue
+        };
+        let sdf_glyph = match atlas.get_glyph(font, glyph.font_id, glyph.glyph.id, data) {
+            Some(v) => v,
+            None => continue
+        };
+        let color = match spans.and_then(|s| s.get(glyph.section_index)) {
+            Some(RichSpan::Text { style, .. }) => style.color.unwrap_or(text_color),
+            Some(RichSpan::Sprite { .. }) => continue,
+            None => text_color
+        };
+        let scale = glyph.glyph.scale.y / SDF_BASE_SIZE;
+        let left = glyph.glyph.position.x + sdf_glyph.offset.x * scale;
+        let right = left + sdf_glyph.size.x * scale;
+        let top = -(glyph.glyph.position.y + sdf_glyph.offset.y * scale);
+        let bottom = top - sdf_glyph.size.y * scale;
+        let uv = &sdf_glyph.uv;
+        let index_offset = points.len() as u32;
+        indexs.extend_from_slice(&[2,1,0,2,3,1].map(|v| v + index_offset));
+        points.extend_from_slice(&[
+            Vertex2D { pos:[left,top,0f32].into(),uv:[uv.x,uv.y].into() },
+            Vertex2D { pos:[right,top,0f32].into(),uv:[uv.x + uv.width,uv.y].into() },
+            Vertex2D { pos:[left,bottom,0f32].into(),uv:[uv.x,uv.y + uv.height].into() },
+            Vertex2D { pos:[right,bottom,0f32].into(),uv:[uv.x + uv.width,uv.y + uv.height].into() }
+        ]);
+        colors.extend_from_slice(&[color;4]);
+    }
+    Mesh2D { color:Vec4::ONE,colors,points,indexs }
+}
+
+#[cfg(test)]
+mod tests {
+    use glyph_brush::{SectionGlyph, FontId, ab_glyph::{FontArc, Font, Glyph, point}};
+    use seija_core::math::{Vec2, Vec4};
+    use crate::text::{RichSpan, RichStyle};
+    use super::{coverage_to_sdf, build_sdf_mesh, TextEffect, SdfAtlas, SdfMaterialParams, SDF_ATLAS_SIZE};
+
+    const FONT:&[u8] = include_bytes!("../../../../seija-examples/res/ui/FiraMono-Medium.ttf");
+
+    fn near(a:Vec4,b:Vec4) -> bool {
+        (a - b).abs().max_element() < 0.0001f32
+    }
+
+    #[test]
+    fn effect_params() {
+        let effect = TextEffect { outline_width:2f32,glow_width:4f32,shadow_softness:1f32,shadow_offset:Vec2::new(3f32, -3f32),..Default::default() };
+        //24号字放大到48的基准字号,宽度乘2后除以距离场的总范围12
+        let (params,offset) = effect.to_sdf_params(24f32);
+        assert!(near(params, Vec4::new(4f32 / 12f32, 0.5f32, 2f32 / 12f32, 0f32)));
+        assert!(near(offset, Vec4::new(6f32 / 1024f32, 6f32 / 1024f32, 0f32, 0f32)));
+        let (params,_) = effect.to_sdf_params(48f32);
+        assert!(near(params, Vec4::new(2f32 / 12f32, 4f32 / 12f32, 1f32 / 12f32, 0f32)));
+        assert_eq!(SdfMaterialParams::new(&effect, 24f32), SdfMaterialParams::new(&effect, 24f32));
+        //阴影偏移不超过距离场留边
+        let far = TextEffect { shadow_offset:Vec2::new(10f32, 2f32),..effect };
+        let (_,offset) = far.to_sdf_params(24f32);
+        assert!(near(offset, Vec4::new(6f32 / 1024f32, -4f32 / 1024f32, 0f32, 0f32)));
+        assert_ne!(SdfMaterialParams::new(&effect, 24f32), SdfMaterialParams::new(&effect, 12f32));
+    }
+
+    #[test]
+    fn sdf_mesh() {
+        let font = FontArc::try_from_slice(FONT).unwrap();
+        let glyph = |section_index:usize,chr:char,x:f32| {
+            let glyph = Glyph { id:font.glyph_id(chr),scale:24f32.into(),position:point(x, 20f32) };
+            SectionGlyph { section_index,byte_index:0,glyph,font_id:FontId(0) }
+        };
+        let red = Vec4::new(1f32, 0f32, 0f32, 1f32);
+        let spans = [RichSpan::Text { text:"a ".into(),style:RichStyle::default() },
+                     RichSpan::Text { text:"b".into(),style:RichStyle { color:Some(red),..Default::default() } },
+                     RichSpan::Sprite { name:"icon".into(),style:RichStyle::default() }];
+        //空格没有轮廓,图片由子节点绘制,都不生成网格
+        let glyphs = [glyph(0, 'a', 0f32),glyph(0, ' ', 14f32),glyph(1, 'b', 28f32),glyph(2, ' ', 42f32)];
+        let mut atlas = SdfAtlas::default();
+        let mut data = vec![0u8;(SDF_ATLAS_SIZE * SDF_ATLAS_SIZE) as usize];
+        let fonts = std::slice::from_ref(&font);
+        let mesh = build_sdf_mesh(Vec4::ONE, Some(&spans), &glyphs, fonts, &mut atlas, &mut data);
+        assert!(atlas.is_dirty);
+        assert!(data.iter().any(|v| *v > 128));
+        assert_eq!((mesh.points.len(),mesh.indexs.len()), (8,12));
+        assert_eq!(mesh.colors, vec![Vec4::ONE,Vec4::ONE,Vec4::ONE,Vec4::ONE,red,red,red,red]);
+        assert_eq!(&mesh.indexs[6..], &[6,5,4,6,7,5]);
+        //包含留边的四边形包住字形,y向上
+        let (lt,rb) = (mesh.points[0].pos,mesh.points[3].pos);
+        assert!(lt.x < 0f32 && rb.x > 0f32 && lt.y > -20f32 && rb.y < -20f32);
+        assert!(mesh.points.iter().all(|p| p.uv.x >= 0f32 && p.uv.x <= 1f32 && p.uv.y >= 0f32 && p.uv.y <= 1f32));
+
+        //同一个字形只生成一次,不使用富文本时都用文字颜色
+        atlas.is_dirty = false;
+        let mesh2 = build_sdf_mesh(red, None, &glyphs[..1], fonts, &mut atlas, &mut data);
+        assert!(!atlas.is_dirty);
+        assert_eq!(mesh2.points[0].uv, mesh.points[0].uv);
+        assert_eq!(mesh2.colors[0], red);
+    }
+
+    #[test]
+    fn rebuild_when_full() {
+        let font = FontArc::try_from_slice(FONT).unwrap();
+        let mut atlas = SdfAtlas::default();
+        let mut data = vec![0u8;(SDF_ATLAS_SIZE * SDF_ATLAS_SIZE) as usize];
+        //只剩下不到字形高度的空间
+        assert!(atlas.allocate(1000, 1000).is_some());
+        *data.last_mut().unwrap() = 255;
+        atlas.is_dirty = false;
+        let a = atlas.get_glyph(&font, FontId(0), font.glyph_id('a'), &mut data).unwrap();
+        assert_eq!(atlas.generation(), 1);
+        assert!(atlas.is_dirty);
+        assert_eq!((a.uv.x,a.uv.y), (0f32,0f32));
+        assert_eq!(*data.last().unwrap(), 0);
+
+        //生成网格的中途重建时,前面字形的uv失效,整段文字重新生成
+        while atlas.allocate(1000, 10).is_some() {}
+        let glyph = |chr:char,x:f32| SectionGlyph { section_index:0,byte_index:0,glyph:Glyph { id:font.glyph_id(chr),scale:24f32.into(),position:point(x, 20f32) },font_id:FontId(0) };
+        let mesh = build_sdf_mesh(Vec4::ONE, None, &[glyph('a', 0f32),glyph('b', 14f32)], std::slice::from_ref(&font), &mut atlas, &mut data);
+        assert_eq!(atlas.generation(), 2);
+        assert_eq!(mesh.points.len(), 8);
+        //b重建后放在图集开头,a重新生成在b后面,而不是沿用旧图集里同样位置的uv
+        assert_eq!((mesh.points[4].uv.x,mesh.points[4].uv.y), (0f32,0f32));
+        assert!(mesh.points[0].uv.x > mesh.points[5].uv.x);
+    }
+
+    #[test]
+    fn square_sdf() {
+        //8x8中间4x4是实心的
+        let mut coverage = vec![0f32;64];
+        for y in 2..6 {
+            for x in 2..6 {
+                coverage[y * 8 + x] = 1f32;
+            }
+        }
+        let sdf = coverage_to_sdf(&coverage, 8, 8, 2);
+        assert!(sdf[3 * 8 + 3] > 128);
+        assert!(sdf[2 * 8 + 2] > 128);
+        assert!(sdf[2 * 8 + 1] < 128);
+        assert_eq!(sdf[0], 0);
+    }
+}
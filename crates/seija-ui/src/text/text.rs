@@ -1,11 +1,12 @@
 use bevy_ecs::prelude::*;
-use glyph_brush::{Section, ab_glyph::PxScale, Layout,VerticalAlign,HorizontalAlign,BuiltInLineBreaker, LineBreaker};
+use glyph_brush::{Section, ab_glyph::PxScale, Layout,VerticalAlign,HorizontalAlign,BuiltInLineBreaker, LineBreaker, FontId};
 use seija_asset::Handle;
-use seija_core::math::{Vec4, Vec2};
+use seija_core::math::{Vec4, Vec2, Vec3};
 use seija_render::resource::{Texture, TextureType};
+use spritesheet::SpriteSheet;
 use crate::types::AnchorAlign;
 use seija_2d::common::{Rect2D,Mesh2D,Vertex2D};
-use super::Font;
+use super::{Font, RichSpan, TextEffect};
 use num_enum::{TryFromPrimitive,IntoPrimitive};
 #[derive(Debug, Clone, Eq, PartialEq,Copy,TryFromPrimitive,IntoPrimitive)]
 #[repr(u8)]
@@ -14,6 +15,8 @@ pub enum LineMode {
     Wrap,
 }
 
+seija_core::impl_reflect_enum!(LineMode,Single,Wrap);
+
 impl Into<(HorizontalAlign,VerticalAlign)> for AnchorAlign {
     fn into(self) -> (HorizontalAlign,VerticalAlign) {
         match self {
@@ -41,7 +44,34 @@ pub struct Text {
     pub font_size:u32,
     pub font:Option<Handle<Font>>,
     pub text:String,
-    
+    //为true时解析text中的富文本标签
+    pub rich:bool,
+    //<b>使用的字体,没有时把字形加粗绘制
+    pub bold_font:Option<Handle<Font>>,
+    //<sprite name=xxx>使用的图集
+    pub sprite_sheet:Option<Handle<SpriteSheet>>,
+    //使用距离场字形,缩放后依然清晰,并支持描边,发光和阴影
+    pub sdf:bool,
+    //只在sdf为true时生效
+    pub effect:TextEffect,
+    //放在3D场景中的文字标签,SDF文字会做深度测试并且不剔除背面
+    pub world_space:bool
+}
+
+//富文本排版需要的字体信息
+pub struct RichFonts {
+    pub font_id:FontId,
+    pub bold_id:Option<FontId>,
+    //1像素字号下空格的宽度,用来给内嵌图片占位
+    pub space_advance:f32
+}
+
+//一个字形的四个顶点和颜色
+#[derive(Clone)]
+pub struct GlyphQuad {
+    pub verts:Vec<Vertex2D>,
+    pub color:Vec4,
+    pub fake_bold:bool
 }
 
 impl Text {
@@ -54,29 +84,73 @@ impl Text {
             anchor:AnchorAlign::Center,
             line_mode:LineMode::Single,
             color:Vec4::new(1.0,1.0,1.0,1.0),
+            rich:false,
+            bold_font:None,
+            sprite_sheet:None,
+            sdf:false,
+            effect:TextEffect::default(),
+            world_space:false
         }
     }
 
 
-    pub fn build_mesh(verts:Vec<Vec<Vertex2D>>,color:Vec4) -> Mesh2D {
+    pub fn build_mesh(quads:Vec<GlyphQuad>) -> Mesh2D {
         let mut points:Vec<Vertex2D> = vec![];
+        let mut colors:Vec<Vec4> = vec![];
         let mut indexs:Vec<u32> = vec![];
         let mut index_offset:usize = 0;
-        for char_mesh in verts {
-            indexs.extend_from_slice(&[2,1,0,2,3,1].map(|v| v + index_offset as u32));
-            index_offset += char_mesh.len();
-            points.extend(char_mesh);
+        for quad in quads {
+            let count = if quad.fake_bold { 2 } else { 1 };
+            for i in 0..count {
+                indexs.extend_from_slice(&[2,1,0,2,3,1].map(|v| v + index_offset as u32));
+                index_offset += quad.verts.len();
+                colors.extend(std::iter::repeat(quad.color).take(quad.verts.len()));
+                //没有粗体字体时偏移1像素再画一次
+                points.extend(quad.verts.iter().map(|v| Vertex2D { pos:v.pos + Vec3::new(i as f32, 0f32, 0f32),uv:v.uv }));
+            }
         }
 
         Mesh2D {
             points,
-            color,
+            color:Vec4::ONE,
+            colors,
             indexs
         }
     }
 
     pub fn build_section(&self,rect:&Rect2D) -> Section {
-      let text = glyph_brush::Text::new(&self.text).with_scale(PxScale::from(self.font_size as f32));
+        let text = glyph_brush::Text::new(&self.text).with_scale(PxScale::from(self.font_size as f32)).with_color(self.color);
+        self.new_section(rect).add_text(text)
+    }
+
+    //每个span对应section里的一个Text,排版后按section_index找回span
+    pub fn build_rich_section<'a>(&self,rect:&Rect2D,spans:&'a [RichSpan],fonts:&RichFonts) -> Section<'a> {
+        let mut section = self.new_section(rect);
+        for span in spans.iter() {
+            let text = match span {
+                RichSpan::Text { text, style } => {
+                    let size = style.size.unwrap_or(self.font_size as f32);
+                    let (font_id,fake_bold) = match fonts.bold_id {
+                        Some(bold_id) if style.bold => (bold_id,false),
+                        _ => (fonts.font_id,style.bold)
+                    };
+                    glyph_brush::Text::new(text).with_scale(PxScale::from(size)).with_font_id(font_id)
+                                                .with_color(style.color.unwrap_or(self.color))
+                                                .with_z(if fake_bold { 1f32 } else { 0f32 })
+                },
+                RichSpan::Sprite { style, .. } => {
+                    //用一个拉宽的空格占位,宽高都是字号
+                    let size = style.size.unwrap_or(self.font_size as f32);
+                    let scale = PxScale { x:size / fonts.space_advance.max(f32::EPSILON),y:size };
+                    glyph_brush::Text::new(" ").with_scale(scale).with_font_id(fonts.font_id)
+                }
+            };
+            section = section.add_text(text);
+        }
+        section
+    }
+
+    fn new_section<'a>(&self,rect:&Rect2D) -> Section<'a> {
       let (h_align,v_align) = self.anchor.into();
       let section = match self.line_mode {
           LineMode::Single => {
@@ -84,14 +158,14 @@ impl Text {
                   h_align,
                   v_align,
                   line_breaker: BuiltInLineBreaker::UnicodeLineBreaker,
-              }).add_text(text)
+              })
           },
           LineMode::Wrap => {
               Section::default().with_layout(Layout::Wrap {
                   h_align,
                   v_align,
                   line_breaker: BuiltInLineBreaker::UnicodeLineBreaker,
-              }).add_text(text)
+              })
           }
       };
       if !self.is_auto_size {
@@ -109,7 +183,8 @@ impl Text {
     }
 }
 
-pub fn glyph_to_mesh(vert:glyph_brush::GlyphVertex) -> Vec<Vertex2D> {
+//颜色来自Text的extra,extra.z为1时表示需要模拟粗体
+pub fn glyph_to_mesh(vert:glyph_brush::GlyphVertex) -> GlyphQuad {
     let left = vert.pixel_coords.min.x as f32;
     let right = vert.pixel_coords.max.x as f32;
     let top = -vert.pixel_coords.min.y as f32;
@@ -132,7 +207,7 @@ pub fn glyph_to_mesh(vert:glyph_brush::GlyphVertex) -> Vec<Vertex2D> {
         pos:[right,bottom,0f32].into(),
         uv:[uv.max.x,uv.max.y].into(),
       }];
-    verts
+    GlyphQuad { verts,color:vert.extra.color.into(),fake_bold:vert.extra.z > 0f32 }
 }
 
 
@@ -1,5 +1,5 @@
 use bevy_ecs::prelude::Component;
-use seija_core::math::Vec2;
+use seija_core::{math::Vec2, impl_reflect_enum};
 use num_enum::{TryFromPrimitive, IntoPrimitive};
 
 
@@ -26,6 +26,8 @@ pub enum AnchorAlign {
     BottomRight
 }
 
+impl_reflect_enum!(AnchorAlign,TopLeft,Top,TopRight,Left,Center,Right,BottomLeft,Bottom,BottomRight);
+
 #[derive(Debug,Default,Clone,Copy)]
 pub struct Box2D {
   pub lt:Vec2,
@@ -1,5 +1,6 @@
-use winit::event::{KeyboardInput, ElementState, MouseButton};
-use seija_input::{event::{KeyboardInput as IKeyboardInput,InputState, MouseInput,MouseButton as IMouseButton}, keycode::KeyCode};
+use seija_core::math::Vec2;
+use winit::event::{KeyboardInput, ElementState, MouseButton, Touch, TouchPhase};
+use seija_input::{event::{KeyboardInput as IKeyboardInput,InputState, MouseInput,MouseButton as IMouseButton,TouchInput,TouchPhase as ITouchPhase}, keycode::KeyCode};
 
 #[derive(Debug, Clone,Copy)]
 pub struct WindowResized {
@@ -47,4 +48,16 @@ pub(crate) fn conv_mouse_input(state:ElementState,button:MouseButton) -> MouseIn
             _ => IMouseButton::Other(3)
         }
     }
-}
\ No newline at end of file
+}
+pub(crate) fn conv_touch_input(touch:Touch) -> TouchInput {
+    TouchInput {
+        id:touch.id,
+        phase:match touch.phase {
+            TouchPhase::Started => ITouchPhase::Started,
+            TouchPhase::Moved => ITouchPhase::Moved,
+            TouchPhase::Ended => ITouchPhase::Ended,
+            TouchPhase::Cancelled => ITouchPhase::Cancelled
+        },
+        position:Vec2::new(touch.location.x as f32, touch.location.y as f32)
+    }
+}
@@ -4,6 +4,7 @@ use std::os::raw::c_char;
 use seija_app::App;
 use seija_app::ecs::world::World;
 use seija_core::window::{WindowConfig, AppWindow};
+use seija_core::ResultExt;
 use crate::WinitModule;
 
 #[no_mangle]
@@ -44,4 +45,36 @@ pub unsafe extern "C" fn set_inner_size(world:&World,w:f32,h:f32) {
     if let Some(window) = world.get_resource::<AppWindow>() {
         window.set_inner_size(w,h);
     }
-}
\ No newline at end of file
+}
+#[no_mangle]
+pub unsafe extern "C" fn winit_add_headless_module(app_ptr:*mut App,width:u32,height:u32) {
+    (&mut *app_ptr).add_module(crate::headless::HeadlessModule { width,height });
+}
+
+//fixed_delta是回放时使用的帧间隔,小于等于0时使用1/60秒
+#[no_mangle]
+pub unsafe extern "C" fn winit_start_record(world:&mut World,fixed_delta:f32) {
+    crate::record::start_record(world, fixed_delta);
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn winit_stop_record(world:&mut World,path:*const c_char) -> bool {
+    let path = CStr::from_ptr(path).to_str().unwrap_or_default();
+    match crate::record::stop_record(world) {
+        Some(record) => record.save(path).log_err().is_ok(),
+        None => false
+    }
+}
+
+//在无头App上同步回放整个录制文件
+#[no_mangle]
+pub unsafe extern "C" fn winit_replay_file(app_ptr:*mut App,path:*const c_char) -> bool {
+    let path = CStr::from_ptr(path).to_str().unwrap_or_default();
+    match crate::record::InputRecord::load(path).log_err() {
+        Ok(record) => {
+            crate::record::InputReplayer::new(record).run(&mut *app_ptr);
+            true
+        },
+        Err(_) => false
+    }
+}
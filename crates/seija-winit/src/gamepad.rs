@@ -0,0 +1,125 @@
+use std::collections::HashMap;
+use gilrs::{Gilrs, GamepadId, EventType, Button, Axis, ff};
+use seija_app::App;
+use seija_input::{event::{GamepadEvent, GamepadEventKind, GamepadButton, GamepadAxis, GamepadRumble, InputState}, gamepad::{GamepadBackend, GamepadBackendRes}};
+
+pub(crate) fn add_gilrs_backend(app:&mut App) {
+    match Gilrs::new() {
+        Ok(gilrs) => {
+            app.world.insert_non_send_resource(GamepadBackendRes::new(GilrsBackend { gilrs,ids:HashMap::default(),effects:HashMap::default() }));
+        },
+        Err(err) => log::error!("gilrs init error:{:?}",err)
+    }
+}
+
+struct GilrsBackend {
+    gilrs:Gilrs,
+    ids:HashMap<u32,GamepadId>,
+    //正在播放的震动,新的震动会替换旧的
+    effects:HashMap<u32,ff::Effect>
+}
+
+impl GamepadBackend for GilrsBackend {
+    fn poll(&mut self) -> Vec<GamepadEvent> {
+        let mut events = vec![];
+        while let Some(ev) = self.gilrs.next_event() {
+            let gamepad = usize::from(ev.id) as u32;
+            let kind = match ev.event {
+                EventType::Connected => {
+                    self.ids.insert(gamepad, ev.id);
+                    GamepadEventKind::Connected(self.gilrs.gamepad(ev.id).name().to_string())
+                },
+                EventType::Disconnected => {
+                    self.ids.remove(&gamepad);
+                    self.effects.remove(&gamepad);
+                    GamepadEventKind::Disconnected
+                },
+                EventType::ButtonPressed(button,_) => match conv_button(button) {
+                    Some(button) => GamepadEventKind::Button(button, InputState::Pressed),
+                    None => continue
+                },
+                EventType::ButtonReleased(button,_) => match conv_button(button) {
+                    Some(button) => GamepadEventKind::Button(button, InputState::Released),
+                    None => continue
+                },
+                //模拟扳机按轴处理
+                EventType::ButtonChanged(Button::LeftTrigger2,value,_) => GamepadEventKind::Axis(GamepadAxis::LeftTrigger, value),
+                EventType::ButtonChanged(Button::RightTrigger2,value,_) => GamepadEventKind::Axis(GamepadAxis::RightTrigger, value),
+                EventType::AxisChanged(axis,value,_) => match conv_axis(axis) {
+                    Some(axis) => GamepadEventKind::Axis(axis, value),
+                    None => continue
+                },
+                _ => continue
+            };
+            events.push(GamepadEvent { gamepad,kind });
+        }
+        events
+    }
+
+    fn rumble(&mut self,rumble:&GamepadRumble) {
+        let id = match self.ids.get(&rumble.gamepad) {
+            Some(id) => *id,
+            None => return
+        };
+        let play_for = ff::Ticks::from_ms((rumble.duration * 1000f32) as u32);
+        let mut builder = ff::EffectBuilder::new();
+        for (kind,magnitude) in [(true,rumble.strong),(false,rumble.weak)] {
+            let magnitude = (magnitude.clamp(0f32, 1f32) * u16::MAX as f32) as u16;
+            let kind = if kind { ff::BaseEffectType::Strong { magnitude } } else { ff::BaseEffectType::Weak { magnitude } };
+            builder.add_effect(ff::BaseEffect { kind,scheduling:ff::Replay { play_for,..Default::default() },..Default::default() });
+        }
+        match builder.gamepads(&[id]).finish(&mut self.gilrs).and_then(|effect| effect.play().map(|_| effect)) {
+            Ok(effect) => { self.effects.insert(rumble.gamepad, effect); },
+            Err(err) => log::warn!("gamepad rumble error:{:?}",err)
+        }
+    }
+}
+
+fn conv_button(button:Button) -> Option<GamepadButton> {
+    Some(match button {
+        Button::South => GamepadButton::South,
+        Button::East => GamepadButton::East,
+        Button::North => GamepadButton::North,
+        Button::West => GamepadButton::West,
+        Button::LeftTrigger => GamepadButton::LeftBumper,
+        Button::RightTrigger => GamepadButton::RightBumper,
+        Button::Select => GamepadButton::Select,
+        Button::Start => GamepadButton::Start,
+        Button::Mode => GamepadButton::Mode,
+        Button::LeftThumb => GamepadButton::LeftThumb,
+        Button::RightThumb => GamepadButton::RightThumb,
+        Button::DPadUp => GamepadButton::DPadUp,
+        Button::DPadDown => GamepadButton::DPadDown,
+        Button::DPadLeft => GamepadButton::DPadLeft,
+        Button::DPadRight => GamepadButton::DPadRight,
+        _ => return None
+    })
+}
+
+fn conv_axis(axis:Axis) -> Option<GamepadAxis> {
+    Some(match axis {
+        Axis::LeftStickX => GamepadAxis::LeftStickX,
+        Axis::LeftStickY => GamepadAxis::LeftStickY,
+        Axis::RightStickX => GamepadAxis::RightStickX,
+        Axis::RightStickY => GamepadAxis::RightStickY,
+        _ => return None
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use gilrs::{Button, Axis};
+    use seija_input::event::{GamepadButton, GamepadAxis};
+    use super::{conv_button, conv_axis};
+
+    #[test]
+    fn conv_gilrs_input() {
+        assert_eq!(conv_button(Button::South), Some(GamepadButton::South));
+        assert_eq!(conv_button(Button::LeftTrigger), Some(GamepadButton::LeftBumper));
+        assert_eq!(conv_button(Button::DPadRight), Some(GamepadButton::DPadRight));
+        //模拟扳机在poll里作为轴处理
+        assert_eq!(conv_button(Button::LeftTrigger2), None);
+        assert_eq!(conv_axis(Axis::RightStickY), Some(GamepadAxis::RightStickY));
+        assert_eq!(conv_axis(Axis::LeftZ), None);
+    }
+}
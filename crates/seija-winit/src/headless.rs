@@ -0,0 +1,69 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+use raw_window_handle::{HasRawWindowHandle, RawWindowHandle, HasRawDisplayHandle, RawDisplayHandle, WebWindowHandle, WebDisplayHandle};
+use seija_app::{IModule, App, ecs::{event::EventReader, system::Res}};
+use seija_core::{window::{IWindow, AppWindow}, math::Vec2, AddCore, CoreStage};
+use seija_input::event::ImeEvent;
+use crate::event::{WindowCreated, WindowResized};
+
+//没有真实窗口的App使用,大小跟随WindowResized事件,不能用来创建渲染表面
+pub struct HeadlessWindow {
+    title:String,
+    width:AtomicU32,
+    height:AtomicU32
+}
+
+impl HeadlessWindow {
+    pub fn new(width:u32,height:u32) -> Self {
+        HeadlessWindow { title:String::default(),width:AtomicU32::new(width),height:AtomicU32::new(height) }
+    }
+}
+
+unsafe impl HasRawWindowHandle for HeadlessWindow {
+    fn raw_window_handle(&self) -> RawWindowHandle {
+        RawWindowHandle::Web(WebWindowHandle::empty())
+    }
+}
+
+unsafe impl HasRawDisplayHandle for HeadlessWindow {
+    fn raw_display_handle(&self) -> RawDisplayHandle {
+        RawDisplayHandle::Web(WebDisplayHandle::empty())
+    }
+}
+
+impl IWindow for HeadlessWindow {
+    fn set_title(&mut self,str:&str) { self.title = str.into(); }
+    fn title(&self) -> &str { self.title.as_str() }
+    fn width(&self) -> u32 { self.width.load(Ordering::Relaxed) }
+    fn height(&self) -> u32 { self.height.load(Ordering::Relaxed) }
+    fn vsync(&self) -> bool { false }
+    fn set_ime_position(&self,_pos:Vec2) {}
+    fn set_ime_allowed(&self,_value:bool) {}
+    fn set_fullscreen(&self) {}
+    fn set_maximized(&self,_value:bool) {}
+    fn set_inner_size(&self,w:f32,h:f32) {
+        self.width.store(w as u32, Ordering::Relaxed);
+        self.height.store(h as u32, Ordering::Relaxed);
+    }
+}
+
+//替代WinitModule,用于测试和输入回放
+pub struct HeadlessModule {
+    pub width:u32,
+    pub height:u32
+}
+
+impl IModule for HeadlessModule {
+    fn init(&mut self,app:&mut App) {
+        app.add_event::<WindowCreated>();
+        app.add_event::<WindowResized>();
+        app.add_event::<ImeEvent>();
+        app.add_resource(AppWindow::new(HeadlessWindow::new(self.width, self.height)));
+        app.add_system(CoreStage::First, headless_resize_system);
+    }
+}
+
+fn headless_resize_system(window:Res<AppWindow>,mut resize_events:EventReader<WindowResized>) {
+    for ev in resize_events.iter() {
+        window.set_inner_size(ev.width, ev.height);
+    }
+}
@@ -1,12 +1,17 @@
 mod window;
 pub mod event;
 pub mod ffi;
-use event::{WindowCreated, WindowResized, conv_mouse_input};
+pub mod record;
+pub mod headless;
+#[cfg(feature = "gilrs")]
+mod gamepad;
+use event::{WindowCreated, WindowResized, conv_mouse_input, conv_touch_input};
 use seija_app::{IModule,App};
 use seija_core::{ window::{AppWindow, WindowConfig},AddCore, math::Vec2};
 use seija_core::bevy_ecs::event::Events;
 use window::WinitWindow;
-use seija_input::{event::{KeyboardInput as IKeyboardInput, MouseInput, MouseWheelInput, ImeEvent}, Input};
+use seija_input::event::ImeEvent;
+use record::RecordedEvent;
 use winit::{event::{Event,WindowEvent,  MouseScrollDelta, Ime}, event_loop::{ControlFlow, EventLoop, EventLoopWindowTarget}, window::ImePurpose};
 
 use crate::event::conv_keyboard_input;
@@ -27,6 +32,8 @@ impl IModule for WinitModule {
         window_created_events.send(WindowCreated);
  
         app.add_resource(app_window);
+        #[cfg(feature = "gilrs")]
+        gamepad::add_gilrs_backend(app);
         app.set_runner(|app| { winit_runner(event_loop,app); });
     }
 }
@@ -48,68 +55,29 @@ fn winit_runner(event_loop:EventLoop<()>,mut app:App) {
         }
         match event {
             Event::WindowEvent {event,..} => {
-                match event {
+                let recorded = match event {
                     WindowEvent::CloseRequested => {
-                        *control_flow = ControlFlow::Exit
+                        *control_flow = ControlFlow::Exit;
+                        None
                     },
-                    WindowEvent::Resized(new_size) => {
-                        let mut resize_events = app.world.get_resource_mut::<Events<WindowResized>>().unwrap();
-                        resize_events.send(WindowResized {
-                            width: new_size.width as f32,
-                            height: new_size.height as f32,
-                        });
-                    },
-                    WindowEvent::KeyboardInput { device_id:_, input, is_synthetic:_ } => {
-                        if let Some(mut events) = app.world.get_resource_mut::<Events<IKeyboardInput>>() {
-                            events.send(conv_keyboard_input(input));
-                        }
-                    }
-                    WindowEvent::MouseInput {  state, button, .. } => {
-                        if let Some(mut events) = app.world.get_resource_mut::<Events<MouseInput>>() {
-                            let mouse_input = conv_mouse_input(state, button);
-                            events.send(mouse_input);
-                        }
-                    }
-                    WindowEvent::CursorMoved { position,.. } => {
-                        if let Some(mut input) = app.world.get_resource_mut::<Input>() {
-                            input.is_mouse_move = true;
-                            let new_pos = Vec2::new(position.x as f32, position.y as f32);
-                            input.mouse_position = new_pos;
-                        }
-                    }
+                    WindowEvent::Resized(new_size) => Some(RecordedEvent::Resized(new_size.width as f32, new_size.height as f32)),
+                    WindowEvent::KeyboardInput { device_id:_, input, is_synthetic:_ } => Some(RecordedEvent::Keyboard(conv_keyboard_input(input))),
+                    WindowEvent::MouseInput {  state, button, .. } => Some(RecordedEvent::Mouse(conv_mouse_input(state, button))),
+                    WindowEvent::CursorMoved { position,.. } => Some(RecordedEvent::CursorMoved(Vec2::new(position.x as f32, position.y as f32))),
                     WindowEvent::MouseWheel { delta, .. } => {
-                        if let Some(mut events) = app.world.get_resource_mut::<Events<MouseWheelInput>>() {
-                            let mut mouse_wheel = MouseWheelInput::default();
-                             match delta {
-                                MouseScrollDelta::LineDelta(x,y) => {
-                                    mouse_wheel.delta.x = x;
-                                    mouse_wheel.delta.y = y;
-                                },
-                                MouseScrollDelta::PixelDelta(v) => {
-                                    mouse_wheel.delta.x = v.x as f32;
-                                    mouse_wheel.delta.y = v.y as f32;
-                                }
-                            }
-                            events.send(mouse_wheel);
-                        }
-                    }
-                    WindowEvent::Ime(ev) => {
-                       if let Some(mut events) = app.world.get_resource_mut::<Events<ImeEvent>>() {
-                            match ev {
-                                Ime::Commit(s) => {
-                                    events.send(ImeEvent::Commit(s));
-                                },
-                                _ => {}
-                            }    
-                       }
-                    },
-                    WindowEvent::ReceivedCharacter(chr) => {
-                        if let Some(mut events) = app.world.get_resource_mut::<Events<ImeEvent>>() {
-                            events.send(ImeEvent::ReceivedCharacter(chr));
-                        }
+                        let delta = match delta {
+                            MouseScrollDelta::LineDelta(x,y) => Vec2::new(x, y),
+                            MouseScrollDelta::PixelDelta(v) => Vec2::new(v.x as f32, v.y as f32)
+                        };
+                        Some(RecordedEvent::MouseWheel(delta))
                     }
-                
-                    _ => {}
+                    WindowEvent::Touch(touch) => Some(RecordedEvent::Touch(conv_touch_input(touch))),
+                    WindowEvent::Ime(Ime::Commit(s)) => Some(RecordedEvent::Ime(ImeEvent::Commit(s))),
+                    WindowEvent::ReceivedCharacter(chr) => Some(RecordedEvent::Ime(ImeEvent::ReceivedCharacter(chr))),
+                    _ => None
+                };
+                if let Some(recorded) = recorded {
+                    record::push_event(&mut app.world, recorded);
                 }
             },
             Event::MainEventsCleared => {
@@ -0,0 +1,395 @@
+use std::path::Path;
+use seija_app::{App, ecs::{world::World, system::Resource, event::Events}};
+use seija_core::{anyhow::{Result, anyhow, bail}, math::Vec2, time::Time};
+use seija_input::{Input, keycode::KeyCode, event::{KeyboardInput, MouseInput, MouseWheelInput, TouchInput, TouchPhase, ImeEvent, InputState, MouseButton}};
+use crate::event::WindowResized;
+
+const RECORD_VERSION:&str = "seija-input-record 1";
+
+//winit推给seija-input的事件,录制和实时运行走同一个apply_event
+#[derive(Debug,Clone,PartialEq)]
+pub enum RecordedEvent {
+    Keyboard(KeyboardInput),
+    Mouse(MouseInput),
+    MouseWheel(Vec2),
+    CursorMoved(Vec2),
+    Touch(TouchInput),
+    Ime(ImeEvent),
+    Resized(f32,f32)
+}
+
+pub fn apply_event(world:&mut World,ev:RecordedEvent) {
+    match ev {
+        RecordedEvent::Keyboard(key) => {
+            if let Some(mut events) = world.get_resource_mut::<Events<KeyboardInput>>() {
+                events.send(key);
+            }
+        },
+        RecordedEvent::Mouse(mouse) => {
+            if let Some(mut events) = world.get_resource_mut::<Events<MouseInput>>() {
+                events.send(mouse);
+            }
+        },
+        RecordedEvent::MouseWheel(delta) => {
+            if let Some(mut events) = world.get_resource_mut::<Events<MouseWheelInput>>() {
+                events.send(MouseWheelInput { delta });
+            }
+        },
+        RecordedEvent::CursorMoved(pos) => {
+            if let Some(mut input) = world.get_resource_mut::<Input>() {
+                input.is_mouse_move = true;
+                input.mouse_position = pos;
+            }
+        },
+        RecordedEvent::Touch(touch) => {
+            if let Some(mut events) = world.get_resource_mut::<Events<TouchInput>>() {
+                events.send(touch);
+            }
+        },
+        RecordedEvent::Ime(ime) => {
+            if let Some(mut events) = world.get_resource_mut::<Events<ImeEvent>>() {
+                events.send(ime);
+            }
+        },
+        RecordedEvent::Resized(width,height) => {
+            if let Some(mut events) = world.get_resource_mut::<Events<WindowResized>>() {
+                events.send(WindowResized { width,height });
+            }
+        }
+    }
+}
+
+//录制中时记录事件,然后应用到world
+pub(crate) fn push_event(world:&mut World,ev:RecordedEvent) {
+    let frame = world.get_resource::<Time>().map(|t| t.frame()).unwrap_or(0);
+    if let Some(mut recorder) = world.get_resource_mut::<InputRecorder>() {
+        recorder.record(frame, &ev);
+    }
+    apply_event(world, ev);
+}
+
+//frame是从开始录制后第几次App::update时处理这个事件
+#[derive(Debug,Clone,Default,PartialEq)]
+pub struct InputRecord {
+    pub width:f32,
+    pub height:f32,
+    pub fixed_delta:f32,
+    pub frame_count:u64,
+    pub events:Vec<(u64,RecordedEvent)>
+}
+
+impl InputRecord {
+    pub fn load(path:impl AsRef<Path>) -> Result<InputRecord> {
+        InputRecord::parse(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn save(&self,path:impl AsRef<Path>) -> Result<()> {
+        std::fs::write(path, self.to_text())?;
+        Ok(())
+    }
+
+    /*
+    seija-input-record 1
+    window 1024 768
+    delta 0.016666668
+    frames 120
+    3 key 36 0 pressed
+    5 cursor 100 200
+    */
+    pub fn to_text(&self) -> String {
+        let mut lines = vec![RECORD_VERSION.to_string()];
+        lines.push(format!("window {} {}",self.width,self.height));
+        lines.push(format!("delta {}",self.fixed_delta));
+        lines.push(format!("frames {}",self.frame_count));
+        for (frame,ev) in self.events.iter() {
+            let line = match ev {
+                RecordedEvent::Keyboard(key) => format!("key {} {} {}",key.key_code as u32,key.scan_code,state_name(&key.state)),
+                RecordedEvent::Mouse(mouse) => format!("mouse {} {}",Into::<u32>::into(mouse.button),state_name(&mouse.state)),
+                RecordedEvent::MouseWheel(delta) => format!("wheel {} {}",delta.x,delta.y),
+                RecordedEvent::CursorMoved(pos) => format!("cursor {} {}",pos.x,pos.y),
+                RecordedEvent::Touch(touch) => format!("touch {} {} {} {}",touch.id,touch.phase as u8,touch.position.x,touch.position.y),
+                RecordedEvent::Ime(ImeEvent::ReceivedCharacter(chr)) => format!("char {}",*chr as u32),
+                RecordedEvent::Ime(ImeEvent::Commit(text)) => format!("commit {}",escape(text)),
+                RecordedEvent::Resized(w,h) => format!("resize {} {}",w,h)
+            };
+            lines.push(format!("{} {}",frame,line));
+        }
+        lines.join("\n")
+    }
+
+    pub fn parse(text:&str) -> Result<InputRecord> {
+        let mut lines = text.lines();
+        if lines.next().map(|v| v.trim()) != Some(RECORD_VERSION) {
+            bail!("not a input record file");
+        }
+        let mut record = InputRecord::default();
+        for line in lines {
+            let line = line.trim_end_matches('\r');
+            if line.is_empty() { continue; }
+            let mut args = line.split(' ');
+            let first = args.next().unwrap_or_default();
+            match first {
+                "window" => {
+                    record.width = next_arg(&mut args)?;
+                    record.height = next_arg(&mut args)?;
+                },
+                "delta" => record.fixed_delta = next_arg(&mut args)?,
+                "frames" => record.frame_count = next_arg(&mut args)?,
+                _ => {
+                    let frame:u64 = first.parse()?;
+                    let kind = args.next().unwrap_or_default();
+                    let ev = match kind {
+                        "key" => {
+                            let code:u32 = next_arg(&mut args)?;
+                            if code > KeyCode::Unknow as u32 { bail!("error key code {}",code); }
+                            let key_code = unsafe { KeyCode::from_u32(code) };
+                            let scan_code = next_arg(&mut args)?;
+                            RecordedEvent::Keyboard(KeyboardInput { key_code,scan_code,state:read_state(args.next())? })
+                        },
+                        "mouse" => {
+                            let button:u32 = next_arg(&mut args)?;
+                            RecordedEvent::Mouse(MouseInput { button:MouseButton::from(button),state:read_state(args.next())? })
+                        },
+                        "wheel" => RecordedEvent::MouseWheel(Vec2::new(next_arg(&mut args)?, next_arg(&mut args)?)),
+                        "cursor" => RecordedEvent::CursorMoved(Vec2::new(next_arg(&mut args)?, next_arg(&mut args)?)),
+                        "touch" => {
+                            let id = next_arg(&mut args)?;
+                            let phase = match next_arg::<u8>(&mut args)? {
+                                0 => TouchPhase::Started,
+                                1 => TouchPhase::Moved,
+                                2 => TouchPhase::Stationary,
+                                3 => TouchPhase::Ended,
+                                _ => TouchPhase::Cancelled
+                            };
+                            let position = Vec2::new(next_arg(&mut args)?, next_arg(&mut args)?);
+                            RecordedEvent::Touch(TouchInput { id,phase,position })
+                        },
+                        "char" => {
+                            let chr = char::from_u32(next_arg(&mut args)?).ok_or_else(|| anyhow!("error char in {}",line))?;
+                            RecordedEvent::Ime(ImeEvent::ReceivedCharacter(chr))
+                        },
+                        "commit" => {
+                            let text = line.splitn(3, ' ').nth(2).unwrap_or_default();
+                            RecordedEvent::Ime(ImeEvent::Commit(unescape(text)))
+                        },
+                        "resize" => RecordedEvent::Resized(next_arg(&mut args)?, next_arg(&mut args)?),
+                        _ => bail!("unknown record event {}",line)
+                    };
+                    record.events.push((frame,ev));
+                }
+            }
+        }
+        Ok(record)
+    }
+}
+
+fn next_arg<'a,T:std::str::FromStr>(args:&mut impl Iterator<Item = &'a str>) -> Result<T> {
+    let arg = args.next().ok_or_else(|| anyhow!("record miss arg"))?;
+    arg.parse::<T>().map_err(|_| anyhow!("record error arg {}",arg))
+}
+
+fn state_name(state:&InputState) -> &'static str {
+    match state {
+        InputState::Pressed => "pressed",
+        InputState::Released => "released"
+    }
+}
+
+fn read_state(name:Option<&str>) -> Result<InputState> {
+    match name {
+        Some("pressed") => Ok(InputState::Pressed),
+        Some("released") => Ok(InputState::Released),
+        _ => Err(anyhow!("error input state {:?}",name))
+    }
+}
+
+fn escape(text:&str) -> String {
+    text.replace('\\', "\\\\").replace('\n', "\\n").replace('\r', "\\r")
+}
+
+fn unescape(text:&str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(chr) = chars.next() {
+        if chr != '\\' {
+            result.push(chr);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => result.push('\n'),
+            Some('r') => result.push('\r'),
+            Some(other) => result.push(other),
+            None => {}
+        }
+    }
+    result
+}
+
+//插入这个资源后winit的事件会被录制,stop取出录制结果
+#[derive(Resource,Default)]
+pub struct InputRecorder {
+    start_frame:Option<u64>,
+    //开始录制前Time的固定步长,停止时恢复
+    last_fixed_delta:Option<f32>,
+    record:InputRecord
+}
+
+impl InputRecorder {
+    pub fn is_recording(&self) -> bool {
+        self.start_frame.is_some()
+    }
+
+    pub fn start(&mut self,world_frame:u64,width:f32,height:f32,fixed_delta:f32) {
+        self.start_frame = Some(world_frame);
+        self.record = InputRecord { width,height,fixed_delta,frame_count:0,events:vec![] };
+    }
+
+    //world_frame是当前的Time::frame
+    pub fn stop(&mut self,world_frame:u64) -> Option<InputRecord> {
+        let start_frame = self.start_frame.take()?;
+        let mut record = std::mem::take(&mut self.record);
+        record.frame_count = world_frame - start_frame;
+        Some(record)
+    }
+
+    fn record(&mut self,world_frame:u64,ev:&RecordedEvent) {
+        //两次update之间收到的事件在下一次update中处理,Time::frame在下一次update开始时才加1
+        if let Some(start_frame) = self.start_frame {
+            self.record.events.push((world_frame - start_frame,ev.clone()));
+        }
+    }
+}
+
+//录制期间Time使用fixed_delta作为固定步长,回放时每帧的时间才和录制时一致
+pub fn start_record(world:&mut World,fixed_delta:f32) {
+    let mut frame = 0;
+    let mut last_fixed_delta = None;
+    if let Some(mut time) = world.get_resource_mut::<Time>() {
+        frame = time.frame();
+        last_fixed_delta = time.fixed_delta();
+        time.set_fixed_delta(Some(fixed_delta));
+    }
+    let (width,height) = world.get_resource::<seija_core::window::AppWindow>().map(|w| (w.width() as f32,w.height() as f32)).unwrap_or((0f32,0f32));
+    let mut recorder = world.get_resource_or_insert_with(InputRecorder::default);
+    if !recorder.is_recording() {
+        recorder.last_fixed_delta = last_fixed_delta;
+    }
+    recorder.start(frame, width, height, fixed_delta);
+}
+
+pub fn stop_record(world:&mut World) -> Option<InputRecord> {
+    let frame = world.get_resource::<Time>().map(|t| t.frame()).unwrap_or(0);
+    let mut recorder = world.get_resource_mut::<InputRecorder>()?;
+    let record = recorder.stop(frame)?;
+    let last_fixed_delta = recorder.last_fixed_delta.take();
+    if let Some(mut time) = world.get_resource_mut::<Time>() {
+        time.set_fixed_delta(last_fixed_delta);
+    }
+    Some(record)
+}
+
+//在没有窗口的App上按帧回放,每次step应用一帧的事件并执行一次update
+pub struct InputReplayer {
+    record:InputRecord,
+    cursor:usize,
+    frame:u64
+}
+
+impl InputReplayer {
+    pub fn new(record:InputRecord) -> Self {
+        InputReplayer { record,cursor:0,frame:0 }
+    }
+
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.frame >= self.record.frame_count && self.cursor >= self.record.events.len()
+    }
+
+    //开始回放前调用,使用录制时的固定时间步长,并发送录制开始时的窗口大小
+    pub fn setup(&self,app:&mut App) {
+        if let Some(mut time) = app.world.get_resource_mut::<Time>() {
+            let delta = if self.record.fixed_delta > 0f32 { self.record.fixed_delta } else { 1f32 / 60f32 };
+            time.set_fixed_delta(Some(delta));
+        }
+        if self.record.width > 0f32 && self.record.height > 0f32 {
+            apply_event(&mut app.world, RecordedEvent::Resized(self.record.width, self.record.height));
+        }
+    }
+
+    pub fn step(&mut self,app:&mut App) -> bool {
+        if self.is_finished() { return false; }
+        while let Some((frame,ev)) = self.record.events.get(self.cursor) {
+            if *frame > self.frame { break; }
+            apply_event(&mut app.world, ev.clone());
+            self.cursor += 1;
+        }
+        app.update();
+        self.frame += 1;
+        true
+    }
+
+    pub fn run(&mut self,app:&mut App) {
+        self.setup(app);
+        while self.step(app) {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use seija_app::App;
+    use seija_core::{CoreModule, math::Vec2, time::Time, window::AppWindow};
+    use seija_input::{InputModule, Input, keycode::KeyCode, event::{KeyboardInput, InputState, ImeEvent}};
+    use crate::headless::HeadlessModule;
+    use super::{RecordedEvent, InputRecord, InputReplayer, push_event, start_record, stop_record};
+
+    fn new_app() -> App {
+        let mut app = App::new();
+        app.add_module(CoreModule);
+        app.add_module(InputModule);
+        app.add_module(HeadlessModule { width:800,height:600 });
+        app
+    }
+
+    fn key(key_code:KeyCode,state:InputState) -> RecordedEvent {
+        RecordedEvent::Keyboard(KeyboardInput { scan_code:0,key_code,state })
+    }
+
+    #[test]
+    fn record_and_replay() {
+        let mut app = new_app();
+        app.update();
+        start_record(&mut app.world, 0.02f32);
+        app.update();
+        assert_eq!(app.world.resource::<Time>().delta_seconds(), 0.02f32);
+        push_event(&mut app.world, key(KeyCode::A, InputState::Pressed));
+        push_event(&mut app.world, RecordedEvent::CursorMoved(Vec2::new(10f32, 20f32)));
+        push_event(&mut app.world, RecordedEvent::Ime(ImeEvent::Commit("a b\nc".into())));
+        app.update();
+        push_event(&mut app.world, key(KeyCode::A, InputState::Released));
+        push_event(&mut app.world, RecordedEvent::Resized(400f32, 300f32));
+        app.update();
+        app.update();
+        let record = stop_record(&mut app.world).unwrap();
+        assert_eq!(app.world.resource::<Time>().fixed_delta(), None);
+        assert_eq!(record.frame_count, 4);
+        assert_eq!(record.events[0].0, 1);
+        assert_eq!(record.events[3].0, 2);
+        let record = InputRecord::parse(&record.to_text()).unwrap();
+        assert_eq!(record.events[2].1, RecordedEvent::Ime(ImeEvent::Commit("a b\nc".into())));
+
+        let mut app = new_app();
+        let mut replayer = InputReplayer::new(record);
+        replayer.setup(&mut app);
+        let mut pressed = vec![];
+        while replayer.step(&mut app) {
+            pressed.push(app.world.resource::<Input>().get_key(KeyCode::A));
+            assert_eq!(app.world.resource::<Time>().delta_seconds(), 0.02f32);
+        }
+        assert_eq!(pressed, vec![false,true,false,false]);
+        assert_eq!(app.world.resource::<Input>().mouse_position, Vec2::new(10f32, 20f32));
+        assert_eq!(app.world.resource::<AppWindow>().width(), 400);
+    }
+}
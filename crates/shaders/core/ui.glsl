@@ -47,6 +47,45 @@ vec4 text_fs_main(VSInput inv) {
     return vec4(inv.color.r,inv.color.g,inv.color.b,textureColor.r);
 }
 
+//top叠加在bottom上
+vec4 sdf_blend(vec4 top,vec4 bottom) {
+    float a = top.a + bottom.a * (1.0 - top.a);
+    vec3 rgb = (top.rgb * top.a + bottom.rgb * bottom.a * (1.0 - top.a)) / max(a, 0.0001);
+    return vec4(rgb, a);
+}
+
+//距离场0.5是字形边缘,effectParams:x描边宽度,y发光宽度,z阴影柔和度
+vec4 sdf_text_fs_main(VSInput inv) {
+    if (material.isClip > 0 && 
+        (inv.outPos.x < material.clipRect.x 
+        || inv.outPos.x > material.clipRect.z
+        || inv.outPos.y > material.clipRect.y
+        || inv.outPos.y < material.clipRect.w)) {
+        discard;
+    }
+    float dist = texture(sampler2D(tex_mainTexture,tex_mainTextureSampler),inv.uv).r;
+    float aa = max(fwidth(dist) * 0.5, 0.0001);
+    float outlineEdge = 0.5 - material.effectParams.x;
+    float face = smoothstep(0.5 - aa, 0.5 + aa, dist);
+    float outline = smoothstep(outlineEdge - aa, outlineEdge + aa, dist);
+    float glowWidth = material.effectParams.y;
+    float glow = glowWidth > 0.0 ? smoothstep(outlineEdge - glowWidth, outlineEdge, dist) : 0.0;
+    float softness = material.effectParams.z + aa;
+    float shadowDist = texture(sampler2D(tex_mainTexture,tex_mainTextureSampler),inv.uv - material.shadowOffset.xy).r;
+    float shadow = smoothstep(outlineEdge - softness, outlineEdge + softness, shadowDist);
+
+    vec4 color = vec4(material.shadowColor.rgb, material.shadowColor.a * shadow);
+    color = sdf_blend(vec4(material.glowColor.rgb, material.glowColor.a * glow), color);
+    if (material.effectParams.x > 0.0) {
+        color = sdf_blend(vec4(material.outlineColor.rgb, material.outlineColor.a * outline), color);
+    }
+    color = sdf_blend(vec4(inv.color.rgb, inv.color.a * face), color);
+    if (color.a < 0.001) {
+        discard;
+    }
+    return color;
+}
+
 struct VSCaret {
   vec3 outPos;
 };
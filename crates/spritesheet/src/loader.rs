@@ -6,7 +6,6 @@ use seija_core::bevy_ecs::prelude::*;
 use seija_core::anyhow;
 use seija_asset::async_trait::async_trait;
 use seija_core::smol_str::SmolStr;
-use seija_core::smol;
 use seija_render::resource::Texture;
 use crate::{SpriteSheet, MetaData, SpriteInfo, Rect};
 use serde::{Deserialize};
@@ -67,12 +66,12 @@ impl IAssetLoader for SpriteSheetLoader {
 
     fn sync_load(&self,world:&mut World,path:&str,server:&AssetServer,_:Option<Box<dyn AssetLoaderParams>>) -> anyhow::Result<Box<dyn AssetDynamic>> {
         let file_path = RelativePath::new(path).parent().get()?;
-        let full_path = server.full_path(path)?;
-        let bytes = std::fs::read(&full_path)?;
+        let bytes = server.read_bytes(path)?;
         let serde_data:SerdeData = serde_json::from_slice::<SerdeData>(bytes.as_slice())?;
         let server = world.get_resource::<AssetServer>().unwrap().clone();
         let texture_path = this_asset_path(file_path, serde_data.meta.texture.as_str());
         let h_texture = server.load_sync::<Texture>(world,texture_path.as_str(),None)?;
+        server.add_dependency(path, h_texture.id);
         Ok(Box::new(SpriteSheetLoader::create(h_texture, serde_data)))
     }
 
@@ -80,12 +79,12 @@ impl IAssetLoader for SpriteSheetLoader {
         _:Option<Box<dyn seija_asset::downcast_rs::DowncastSync>>,
         _:Option<Box<dyn AssetLoaderParams>>) -> anyhow::Result<Box<dyn AssetDynamic>> {
             let file_path = RelativePath::new(path.as_str()).parent().get()?;
-            let full_path = server.full_path(path.as_str())?;
-            let bytes = smol::fs::read(&full_path).await?;
+            let bytes = server.read_bytes_async(path.as_str()).await?;
             let serde_data:SerdeData = serde_json::from_slice::<SerdeData>(bytes.as_slice())?;
             let texture_path = this_asset_path(file_path, serde_data.meta.texture.as_str());
             let req = server.load_async::<Texture>(texture_path.as_str(),None)?;
             let h_texture = req.wait_handle().await.get()?.typed::<Texture>();
+            server.add_dependency(path.as_str(), h_texture.id);
             Ok(Box::new(SpriteSheetLoader::create(h_texture, serde_data)))
     }
 
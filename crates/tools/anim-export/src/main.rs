@@ -0,0 +1,72 @@
+use std::{path::{Path, PathBuf}, fs::File, io::BufWriter, collections::HashMap};
+use anyhow::{Result, anyhow};
+use clap::Parser;
+use seija_gltf::{gltf::{self, Gltf}, loader::{load_skeleton, import_raw_animation}};
+use seija_skeleton3d::{AnimationSet, binary::{write_skeleton, write_animation_set},
+                       offine::{animation_builder::AnimationBuilder, animation_optimizer::AnimationOptimizer,
+                                raw_animation::{RawAnimation, parse_event_file}}};
+
+//把glTF里的骨骼和动画导出成<name>.skel和<name>.anim
+#[derive(Debug,Parser)]
+#[command(author, version, about, long_about = None)]
+struct ARGS {
+    //glTF文件
+    input:String,
+    //输出目录
+    output:String,
+    //位移和缩放的误差容差
+    #[arg(short, long, default_value_t = 0.001)]
+    tolerance:f32,
+    //旋转的误差容差,弧度
+    #[arg(short, long, default_value_t = 0.001)]
+    rotation_tolerance:f32
+}
+
+fn main() {
+    let mut builder = env_logger::builder();
+    builder.filter_level(log::LevelFilter::Info);
+    builder.init();
+    let args = ARGS::parse();
+    if let Err(err) = run(&args) {
+        log::error!("export error:{:?}",err);
+        std::process::exit(1);
+    }
+}
+
+fn run(args:&ARGS) -> Result<()> {
+    let path = Path::new(&args.input);
+    let gltf_data = Gltf::open(path)?;
+    let buffers = gltf::import_buffers(&gltf_data.document, path.parent(), gltf_data.blob.clone())?;
+    let skeleton = load_skeleton(&gltf_data)?.ok_or_else(|| anyhow!("{} has no skeleton",args.input))?;
+
+    //和运行时加载glTF一样读取同名的事件文件
+    let event_path = path.with_extension("events.json");
+    let events = if event_path.exists() { parse_event_file(&std::fs::read_to_string(&event_path)?)? } else { HashMap::default() };
+
+    let optimizer = AnimationOptimizer {
+        translation_tolerance:args.tolerance,
+        rotation_tolerance:args.rotation_tolerance,
+        scale_tolerance:args.tolerance
+    };
+    let mut anim_set = AnimationSet::default();
+    for animation in gltf_data.animations() {
+        let raw_animation = import_raw_animation(&buffers, &animation, &skeleton, &events)?;
+        let optimized = optimizer.optimize(&raw_animation);
+        log::info!("animation {} keys {} -> {}",raw_animation.name,key_count(&raw_animation),key_count(&optimized));
+        anim_set.add(AnimationBuilder::build(&optimized));
+    }
+
+    let name = path.file_stem().and_then(|v| v.to_str()).ok_or_else(|| anyhow!("invalid path {}",args.input))?;
+    let out_dir = PathBuf::from(&args.output);
+    std::fs::create_dir_all(&out_dir)?;
+    let skeleton_path = out_dir.join(format!("{}.skel",name));
+    write_skeleton(&skeleton, &mut BufWriter::new(File::create(&skeleton_path)?))?;
+    let anim_path = out_dir.join(format!("{}.anim",name));
+    write_animation_set(&anim_set, &mut BufWriter::new(File::create(&anim_path)?))?;
+    log::info!("export {} joints to {:?}, {} animations to {:?}",skeleton.num_joints(),skeleton_path,anim_set.len(),anim_path);
+    Ok(())
+}
+
+fn key_count(raw_animation:&RawAnimation) -> usize {
+    raw_animation.tracks.iter().map(|t| t.translations.len() + t.rotations.len() + t.scales.len()).sum()
+}
@@ -0,0 +1,66 @@
+use std::{path::{Path, PathBuf}, fs::File, io::BufWriter};
+use anyhow::Result;
+use clap::Parser;
+use seija_asset::ArchiveWriter;
+
+#[derive(Debug,Parser)]
+#[command(author, version, about, long_about = None)]
+struct ARGS {
+    //资源目录,例如seija-examples/res
+    input:String,
+    //输出的打包文件
+    output:String,
+    //使用deflate压缩每个资源
+    #[arg(short, long)]
+    compress:bool,
+    //不打包的文件扩展名
+    #[arg(short, long)]
+    exclude:Vec<String>
+}
+
+fn main() {
+    let mut builder = env_logger::builder();
+    builder.filter_level(log::LevelFilter::Info);
+    builder.init();
+    let args = ARGS::parse();
+    if let Err(err) = run(&args) {
+        log::error!("pack error:{:?}",err);
+        std::process::exit(1);
+    }
+}
+
+fn run(args:&ARGS) -> Result<()> {
+    let root = PathBuf::from(&args.input);
+    let mut files:Vec<PathBuf> = vec![];
+    collect_files(&root, &mut files)?;
+    files.sort();
+    let mut writer = ArchiveWriter::default();
+    for file in files.iter() {
+        let ext = file.extension().and_then(|v| v.to_str()).unwrap_or_default();
+        if args.exclude.iter().any(|v| v.trim_start_matches('.') == ext) {
+            continue;
+        }
+        let rel_path = file.strip_prefix(&root)?.components()
+                           .map(|c| c.as_os_str().to_string_lossy().to_string())
+                           .collect::<Vec<_>>().join("/");
+        let bytes = std::fs::read(file)?;
+        log::info!("pack {} {}",rel_path,bytes.len());
+        writer.add(&rel_path, bytes, args.compress)?;
+    }
+    let mut out = BufWriter::new(File::create(&args.output)?);
+    writer.write(&mut out)?;
+    log::info!("pack {} files to {}",writer.len(),&args.output);
+    Ok(())
+}
+
+fn collect_files(dir:&Path,files:&mut Vec<PathBuf>) -> Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_files(&path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
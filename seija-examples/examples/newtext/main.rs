@@ -22,7 +22,7 @@ fn start(world: &mut World) {
     let t = Transform::default();
     let rect2d = Rect2D::default();
     let mut font_system = world.get_resource_mut::<FontSystem>().unwrap();
-    font_system.db.load_system_fonts();
+    font_system.load_system_fonts();
     let face_id = font_system.query_family("微软雅黑").unwrap();
     let mut text_2d = Text::default();
     text_2d.text = "我.".into();
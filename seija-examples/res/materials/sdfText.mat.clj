@@ -0,0 +1,25 @@
+{
+    :name "sdfText"
+    :order "Transparent"
+    :props [
+        {:name "mainTexture"  :type "texture2D" :default "white"}
+        {:name "clipRect"     :type "float4"}
+        {:name "isClip"       :type "int"}
+        {:name "outlineColor" :type "float4" :default [0,0,0,1]}
+        {:name "glowColor"    :type "float4" :default [1,1,1,0]}
+        {:name "shadowColor"  :type "float4" :default [0,0,0,0.5]}
+        {:name "effectParams" :type "float4" :default [0,0,0,0]}
+        {:name "shadowOffset" :type "float4" :default [0,0,0,0]}
+    ]
+    :pass [
+        { 
+            :z-test "always"
+            :z-write false
+            :shader { 
+                :name "core.sdfText" 
+                
+            } 
+            
+        }
+    ]
+}
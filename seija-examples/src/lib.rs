@@ -13,7 +13,7 @@ use seija_render::{camera::{camera::Perspective,camera::Camera},
                    material::MaterialDefineAsset, resource::{Texture, TextureDescInfo}
                   ,RenderConfig, GraphSetting, RenderModule, RenderContext};
 
-use seija_render_template::add_render_templates;
+use seija_render_template::{add_render_templates, add_ui_templates};
 use seija_template::TemplateModule;
 use seija_transform::{Transform, TransformModule};
 use seija_ui::UIModule;
@@ -39,9 +39,10 @@ pub fn init_core_app(render_file:&str,pre_renders:Vec<fn(world:&mut World,ctx:&m
     app.add_module(win);
     app.add_module(TransformModule);
     app.add_module(TemplateModule);
-    app.add_module(TextModule);
+    app.add_module(TextModule::default());
    
     add_render_templates(&mut app);
+    add_ui_templates(&mut app);
     app.add_module(GLTFModule);
     let render_config = RenderConfig {
         config_path:".render/shaders".into(),